@@ -1,6 +1,6 @@
 use super::set_account_webhook::{SetAccountWebhookUseCase, UseCaseErrors};
 use crate::error::NettuError;
-use crate::shared::auth::protect_account_route;
+use crate::shared::auth::protect_account_update_route;
 use crate::shared::usecase::execute;
 use actix_web::{web, HttpResponse};
 use nettu_scheduler_api_structs::delete_account_webhook::APIResponse;
@@ -10,7 +10,7 @@ pub async fn delete_account_webhook_controller(
     http_req: web::HttpRequest,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
-    let account = protect_account_route(&http_req, &ctx).await?;
+    let account = protect_account_update_route(&http_req, &ctx).await?;
 
     let usecase = SetAccountWebhookUseCase {
         account,
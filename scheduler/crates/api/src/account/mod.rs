@@ -1,6 +1,8 @@
 mod create_account;
 mod delete_account_webhook;
 mod get_account;
+mod set_account_calendar_limit;
+mod set_account_default_timezone;
 mod set_account_pub_key;
 mod set_account_webhook;
 
@@ -8,6 +10,8 @@ use actix_web::web;
 use create_account::create_account_controller;
 use delete_account_webhook::delete_account_webhook_controller;
 use get_account::get_account_controller;
+use set_account_calendar_limit::set_account_calendar_limit_controller;
+use set_account_default_timezone::set_account_default_timezone_controller;
 use set_account_pub_key::set_account_pub_key_controller;
 use set_account_webhook::set_account_webhook_controller;
 
@@ -18,6 +22,14 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/account/pubkey",
         web::put().to(set_account_pub_key_controller),
     );
+    cfg.route(
+        "/account/timezone",
+        web::put().to(set_account_default_timezone_controller),
+    );
+    cfg.route(
+        "/account/calendar-limit",
+        web::put().to(set_account_calendar_limit_controller),
+    );
     cfg.route(
         "/account/webhook",
         web::put().to(set_account_webhook_controller),
@@ -0,0 +1,100 @@
+use crate::shared::usecase::{execute, UseCase};
+use crate::{error::NettuError, shared::auth::protect_account_update_route};
+use actix_web::{web, HttpResponse};
+use nettu_scheduler_api_structs::set_account_calendar_limit::{APIResponse, RequestBody};
+use nettu_scheduler_domain::{Account, MAX_CALENDARS_PER_USER_LIMIT};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn set_account_calendar_limit_controller(
+    http_req: web::HttpRequest,
+    ctx: web::Data<NettuContext>,
+    body: web::Json<RequestBody>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_update_route(&http_req, &ctx).await?;
+
+    let usecase = SetAccountCalendarLimitUseCase {
+        account,
+        max_calendars_per_user: body.max_calendars_per_user,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|account| HttpResponse::Ok().json(APIResponse::new(account)))
+        .map_err(|e| match e {
+            UseCaseErrors::InvalidLimit => NettuError::BadClientData(format!(
+                "The calendar limit must be between 1 and {}",
+                MAX_CALENDARS_PER_USER_LIMIT
+            )),
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+#[derive(Debug)]
+struct SetAccountCalendarLimitUseCase {
+    pub account: Account,
+    pub max_calendars_per_user: usize,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    InvalidLimit,
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for SetAccountCalendarLimitUseCase {
+    type Response = Account;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "SetAccountCalendarLimit";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        if !self
+            .account
+            .settings
+            .set_max_calendars_per_user(self.max_calendars_per_user)
+        {
+            return Err(UseCaseErrors::InvalidLimit);
+        }
+
+        match ctx.repos.account_repo.save(&self.account).await {
+            Ok(_) => {
+                ctx.account_cache.invalidate_account(&self.account.id);
+                Ok(self.account.clone())
+            }
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nettu_scheduler_infra::setup_context;
+
+    use super::*;
+
+    #[actix_web::main]
+    #[test]
+    async fn updates_the_calendar_limit_of_the_account() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+
+        let usecase = |max_calendars_per_user: usize| SetAccountCalendarLimitUseCase {
+            account: account.clone(),
+            max_calendars_per_user,
+        };
+
+        let res = usecase(0).execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::InvalidLimit)));
+        let res = usecase(MAX_CALENDARS_PER_USER_LIMIT + 1)
+            .execute(&ctx)
+            .await;
+        assert!(matches!(res, Err(UseCaseErrors::InvalidLimit)));
+
+        assert!(usecase(3).execute(&ctx).await.is_ok());
+        let account = ctx.repos.account_repo.find(&account.id).await.unwrap();
+        assert_eq!(account.settings.max_calendars_per_user, 3);
+    }
+}
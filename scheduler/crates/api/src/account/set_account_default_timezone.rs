@@ -0,0 +1,68 @@
+use crate::shared::usecase::{execute, UseCase};
+use crate::{error::NettuError, shared::auth::protect_account_update_route};
+use actix_web::{web, HttpResponse};
+use nettu_scheduler_api_structs::set_account_default_timezone::{APIResponse, RequestBody};
+use nettu_scheduler_domain::Account;
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn set_account_default_timezone_controller(
+    http_req: web::HttpRequest,
+    ctx: web::Data<NettuContext>,
+    body: web::Json<RequestBody>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_update_route(&http_req, &ctx).await?;
+
+    let usecase = SetAccountDefaultTimezoneUseCase {
+        account,
+        default_timezone: body.default_timezone.clone(),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|account| HttpResponse::Ok().json(APIResponse::new(account)))
+        .map_err(|e| match e {
+            UseCaseErrors::InvalidTimezone(timezone) => NettuError::BadClientData(format!(
+                "Invalid timezone: {}, must be a valid IANA Timezone string",
+                timezone
+            )),
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+#[derive(Debug)]
+struct SetAccountDefaultTimezoneUseCase {
+    pub account: Account,
+    pub default_timezone: Option<String>,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    InvalidTimezone(String),
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for SetAccountDefaultTimezoneUseCase {
+    type Response = Account;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "SetAccountDefaultTimezone";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let timezone = self.default_timezone.as_deref();
+        if !self.account.settings.set_default_timezone(timezone) {
+            return Err(UseCaseErrors::InvalidTimezone(
+                timezone.unwrap_or_default().to_string(),
+            ));
+        }
+
+        match ctx.repos.account_repo.save(&self.account).await {
+            Ok(_) => {
+                ctx.account_cache.invalidate_account(&self.account.id);
+                Ok(self.account.clone())
+            }
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+}
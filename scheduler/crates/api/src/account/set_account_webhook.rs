@@ -1,5 +1,5 @@
 use crate::shared::usecase::{execute, UseCase};
-use crate::{error::NettuError, shared::auth::protect_account_route};
+use crate::{error::NettuError, shared::auth::protect_account_update_route};
 use actix_web::{web, HttpResponse};
 use nettu_scheduler_api_structs::set_account_webhook::{APIResponse, RequestBody};
 use nettu_scheduler_domain::Account;
@@ -10,7 +10,7 @@ pub async fn set_account_webhook_controller(
     ctx: web::Data<NettuContext>,
     body: web::Json<RequestBody>,
 ) -> Result<HttpResponse, NettuError> {
-    let account = protect_account_route(&http_req, &ctx).await?;
+    let account = protect_account_update_route(&http_req, &ctx).await?;
 
     let usecase = SetAccountWebhookUseCase {
         account,
@@ -74,7 +74,10 @@ impl UseCase for SetAccountWebhookUseCase {
         }
 
         match ctx.repos.account_repo.save(&self.account).await {
-            Ok(_) => Ok(self.account.clone()),
+            Ok(_) => {
+                ctx.account_cache.invalidate_account(&self.account.id);
+                Ok(self.account.clone())
+            }
             Err(_) => Err(UseCaseErrors::StorageError),
         }
     }
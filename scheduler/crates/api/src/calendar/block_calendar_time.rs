@@ -0,0 +1,138 @@
+use crate::event::create_event::{handle_error, CreateEventUseCase};
+use crate::shared::{
+    auth::{account_can_modify_calendar, protect_account_route},
+    usecase::{execute, execute_with_policy, UseCaseErrorContainer},
+};
+use crate::{error::NettuError, shared::auth::protect_route};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::block_calendar_time::{APIResponse, PathParams, RequestBody};
+use nettu_scheduler_domain::{Metadata, ID};
+use nettu_scheduler_infra::NettuContext;
+
+/// Blocking time is sugar over creating a busy and non-recurring `CalendarEvent`,
+/// which is tagged with this metadata value for the `kind` key
+pub const BLOCK_EVENT_KIND: &str = "block";
+
+fn block_usecase(
+    account_id: ID,
+    user_id: ID,
+    calendar_id: ID,
+    body: RequestBody,
+) -> CreateEventUseCase {
+    let mut metadata = Metadata::new();
+    metadata.insert("kind".into(), BLOCK_EVENT_KIND.into());
+    if let Some(reason) = body.reason {
+        metadata.insert("reason".into(), reason);
+    }
+
+    CreateEventUseCase {
+        account_id,
+        calendar_id,
+        user_id,
+        start_ts: body.start_ts,
+        duration: body.duration,
+        busy: true,
+        status: None,
+        recurrence: None,
+        reminder: None,
+        is_service: false,
+        service_id: None,
+        metadata,
+        external_id: None,
+    }
+}
+
+pub async fn block_calendar_time_admin_controller(
+    http_req: HttpRequest,
+    path: web::Path<PathParams>,
+    body: web::Json<RequestBody>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let cal = account_can_modify_calendar(&account, &path.calendar_id, &ctx).await?;
+
+    let usecase = block_usecase(account.id, cal.user_id, cal.id, body.0);
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Created().json(APIResponse::new(event.id)))
+        .map_err(handle_error)
+}
+
+pub async fn block_calendar_time_controller(
+    http_req: HttpRequest,
+    path: web::Path<PathParams>,
+    body: web::Json<RequestBody>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = block_usecase(user.account_id, user.id, path.calendar_id.clone(), body.0);
+
+    execute_with_policy(usecase, &policy, &ctx)
+        .await
+        .map(|event| HttpResponse::Created().json(APIResponse::new(event.id)))
+        .map_err(|e| match e {
+            UseCaseErrorContainer::Unauthorized(e) => NettuError::Unauthorized(e),
+            UseCaseErrorContainer::UseCase(e) => handle_error(e),
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::get_user_freebusy::GetFreeBusyUseCase;
+    use nettu_scheduler_domain::{Calendar, EventInstance, User};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn blocked_time_is_busy_in_freebusy() {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let hour = 1000 * 60 * 60;
+        let usecase = block_usecase(
+            user.account_id.clone(),
+            user.id.clone(),
+            calendar.id.clone(),
+            RequestBody {
+                start_ts: 14 * hour,
+                duration: 2 * hour,
+                reason: Some("Dentist".into()),
+            },
+        );
+        let event = execute(usecase, &ctx).await.unwrap();
+        assert!(event.busy);
+        assert!(event.recurrence.is_none());
+        assert_eq!(event.metadata.get("kind").unwrap(), BLOCK_EVENT_KIND);
+        assert_eq!(event.metadata.get("reason").unwrap(), "Dentist");
+
+        let usecase = GetFreeBusyUseCase {
+            user_id: user.id.clone(),
+            calendar_ids: Some(vec![calendar.id.clone()]),
+            start_ts: 0,
+            end_ts: 24 * hour,
+            include_free: true,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: true,
+        };
+        let res = execute(usecase, &ctx).await.unwrap();
+        assert_eq!(
+            res.busy.inner().into_iter().collect::<Vec<_>>(),
+            vec![EventInstance {
+                start_ts: 14 * hour,
+                end_ts: 16 * hour,
+                busy: true,
+                event_id: None,
+            }]
+        );
+        let free = res.free.expect("Free instances to be included").inner();
+        assert_eq!(free.len(), 2);
+        assert_eq!(free[0].end_ts, 14 * hour);
+        assert_eq!(free[1].start_ts, 16 * hour);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::event::create_event::{handle_error, CreateEventUseCase, UseCaseErrors};
+use crate::shared::auth::{account_can_modify_calendar, protect_route};
+use crate::shared::usecase::{execute, UseCase};
+use crate::{error::NettuError, shared::auth::protect_account_route};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::check_calendar_conflict::{APIResponse, PathParams, RequestBody};
+use nettu_scheduler_domain::{
+    find_conflicting_instances, CompatibleInstances, EventInstance, RRuleOptions, TimeSpan, ID,
+};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn check_calendar_conflict_admin_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let cal = account_can_modify_calendar(&account, &path.calendar_id, &ctx).await?;
+
+    let body = body.0;
+    let usecase = CheckCalendarConflictUseCase {
+        user_id: cal.user_id,
+        calendar_id: cal.id,
+        start_ts: body.start_ts,
+        duration: body.duration,
+        recurrence: body.recurrence,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|conflicts| HttpResponse::Ok().json(APIResponse::new(conflicts)))
+        .map_err(handle_error)
+}
+
+pub async fn check_calendar_conflict_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, _policy) = protect_route(&http_req, &ctx).await?;
+
+    let body = body.0;
+    let usecase = CheckCalendarConflictUseCase {
+        user_id: user.id,
+        calendar_id: path.calendar_id.clone(),
+        start_ts: body.start_ts,
+        duration: body.duration,
+        recurrence: body.recurrence,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|conflicts| HttpResponse::Ok().json(APIResponse::new(conflicts)))
+        .map_err(handle_error)
+}
+
+/// Finds the busy instances in a `Calendar` that a proposed busy `CalendarEvent`
+/// would overlap with, without creating the event. The proposal is validated
+/// the same way as when creating an event.
+#[derive(Debug)]
+struct CheckCalendarConflictUseCase {
+    pub calendar_id: ID,
+    pub user_id: ID,
+    pub start_ts: i64,
+    pub duration: i64,
+    pub recurrence: Option<RRuleOptions>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for CheckCalendarConflictUseCase {
+    type Response = Vec<EventInstance>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "CheckCalendarConflict";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let calendar = match ctx.repos.calendar_repo.find(&self.calendar_id).await {
+            Some(calendar) if calendar.user_id == self.user_id => calendar,
+            _ => return Err(UseCaseErrors::NotFound(self.calendar_id.clone())),
+        };
+
+        let proposal = CreateEventUseCase {
+            account_id: calendar.account_id.clone(),
+            calendar_id: calendar.id.clone(),
+            user_id: self.user_id.clone(),
+            start_ts: self.start_ts,
+            duration: self.duration,
+            busy: true,
+            status: None,
+            recurrence: self.recurrence.clone(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        }
+        .build_event(&calendar, ctx.sys.get_timestamp_millis())?;
+
+        // Recurring proposals are only checked as far ahead as event instances can be queried
+        let end_ts = std::cmp::min(
+            proposal.end_ts,
+            proposal.start_ts + ctx.config.event_instances_query_duration_limit,
+        );
+        let timespan = TimeSpan::new(proposal.start_ts, end_ts);
+        let proposed =
+            CompatibleInstances::new(proposal.expand(Some(&timespan), &calendar.settings));
+
+        let instances = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&calendar.id, Some(&timespan))
+            .await
+            .map_err(|_| UseCaseErrors::StorageError)?
+            .into_iter()
+            .flat_map(|event| event.expand(Some(&timespan), &calendar.settings))
+            .collect();
+
+        Ok(find_conflicting_instances(&proposed, instances))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_infra::SeededContext;
+
+    #[actix_web::main]
+    #[test]
+    async fn returns_the_busy_instances_a_proposal_overlaps() {
+        let hour = 1000 * 60 * 60;
+        let SeededContext {
+            ctx,
+            account,
+            calendar,
+            events,
+            ..
+        } = NettuContext::seeded()
+            .with_calendar_events(&[(2 * hour, 3 * hour), (5 * hour, 6 * hour)])
+            .build()
+            .await;
+        let (account, calendar) = (account.unwrap(), calendar.unwrap());
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::calendar::configure_routes),
+        )
+        .await;
+        let check = |body: RequestBody| {
+            test::TestRequest::post()
+                .uri(&format!("/user/calendar/{}/check-conflict", calendar.id))
+                .header("x-api-key", account.secret_api_key.clone())
+                .set_json(&body)
+                .to_request()
+        };
+
+        let res: APIResponse = test::read_response_json(
+            &mut app,
+            check(RequestBody {
+                start_ts: 2 * hour + hour / 2,
+                duration: 3 * hour,
+                recurrence: None,
+            }),
+        )
+        .await;
+        let conflicts = res
+            .conflicts
+            .iter()
+            .map(|c| (c.start_ts, c.end_ts, c.event_id.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![
+                (2 * hour, 3 * hour, Some(events[0].id.clone())),
+                (5 * hour, 6 * hour, Some(events[1].id.clone())),
+            ]
+        );
+
+        // Touching the busy events is not a conflict
+        let res: APIResponse = test::read_response_json(
+            &mut app,
+            check(RequestBody {
+                start_ts: 3 * hour,
+                duration: 2 * hour,
+                recurrence: None,
+            }),
+        )
+        .await;
+        assert!(res.conflicts.is_empty());
+
+        // Nothing is stored by the check
+        let stored = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&calendar.id, None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+
+        let req = check(RequestBody {
+            start_ts: 0,
+            duration: -hour,
+            recurrence: None,
+        });
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
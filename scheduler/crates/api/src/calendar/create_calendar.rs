@@ -16,6 +16,10 @@ fn error_handler(e: UseCaseErrors) -> NettuError {
     match e {
         UseCaseErrors::StorageError => NettuError::InternalError,
         UseCaseErrors::InvalidCalendarSetting(e) => NettuError::BadClientData(e),
+        UseCaseErrors::CalendarLimitReached(limit) => NettuError::BadClientData(format!(
+            "The user already has the maximum number of calendars allowed: {}",
+            limit
+        )),
         UseCaseErrors::UserNotFound => {
             NettuError::NotFound("The requested user was not found.".to_string())
         }
@@ -82,6 +86,7 @@ struct CreateCalendarUseCase {
 enum UseCaseErrors {
     UserNotFound,
     InvalidCalendarSetting(String),
+    CalendarLimitReached(usize),
     StorageError,
 }
 
@@ -94,11 +99,29 @@ impl UseCase for CreateCalendarUseCase {
     const NAME: &'static str = "CreateCalendar";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let user = match ctx.repos.user_repo.find(&self.user_id).await {
-            Some(user) if user.account_id == self.account_id => user,
-            _ => return Err(UseCaseErrors::UserNotFound),
+        let user = match ctx
+            .repos
+            .user_repo
+            .find_for_account(&self.user_id, &self.account_id)
+            .await
+        {
+            Some(user) => user,
+            None => return Err(UseCaseErrors::UserNotFound),
+        };
+        let account = match ctx.repos.account_repo.find(&user.account_id).await {
+            Some(account) => account,
+            None => return Err(UseCaseErrors::StorageError),
         };
 
+        let limit = account.settings.max_calendars_per_user;
+        let calendar_count = match ctx.repos.calendar_repo.count_by_user(&user.id).await {
+            Ok(count) => count,
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        };
+        if calendar_count >= limit {
+            return Err(UseCaseErrors::CalendarLimitReached(limit));
+        }
+
         let mut settings = CalendarSettings::default();
         if !settings.set_timezone(&self.timezone) {
             return Err(UseCaseErrors::InvalidCalendarSetting(format!(
@@ -116,6 +139,8 @@ impl UseCase for CreateCalendarUseCase {
         let mut calendar = Calendar::new(&self.user_id, &user.account_id);
         calendar.settings = settings;
         calendar.metadata = self.metadata.clone();
+        calendar.created = ctx.sys.get_timestamp_millis();
+        calendar.updated = calendar.created;
 
         let res = ctx.repos.calendar_repo.insert(&calendar).await;
         match res {
@@ -130,3 +155,40 @@ impl PermissionBoundary for CreateCalendarUseCase {
         vec![Permission::CreateCalendar]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use nettu_scheduler_domain::{Account, User};
+    use nettu_scheduler_infra::setup_context;
+
+    use super::*;
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_calendar_when_limit_is_reached() {
+        let ctx = setup_context().await;
+        let mut account = Account::default();
+        account.settings.max_calendars_per_user = 2;
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+
+        let usecase = || CreateCalendarUseCase {
+            user_id: user.id.clone(),
+            account_id: account.id.clone(),
+            week_start: 0,
+            timezone: "UTC".into(),
+            metadata: Default::default(),
+        };
+
+        for _ in 0..2 {
+            assert!(usecase().execute(&ctx).await.is_ok());
+        }
+        let res = usecase().execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::CalendarLimitReached(2))));
+        assert_eq!(
+            ctx.repos.calendar_repo.find_by_user(&user.id).await.len(),
+            2
+        );
+    }
+}
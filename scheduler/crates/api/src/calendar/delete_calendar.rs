@@ -82,7 +82,11 @@ impl UseCase for DeleteCalendarUseCase {
         let calendar = ctx.repos.calendar_repo.find(&self.calendar_id).await;
         match calendar {
             Some(calendar) if calendar.user_id == self.user_id => {
-                ctx.repos.calendar_repo.delete(&calendar.id).await;
+                match ctx.repos.calendar_repo.delete(&calendar.id).await {
+                    Ok(Some(_)) => (),
+                    Ok(None) => return Err(UseCaseErrors::NotFound(calendar.id)),
+                    Err(_) => return Err(UseCaseErrors::UnableToDelete),
+                }
                 let repo_res = ctx.repos.event_repo.delete_by_calendar(&calendar.id).await;
                 if repo_res.is_err() {
                     return Err(UseCaseErrors::UnableToDelete);
@@ -95,6 +99,7 @@ impl UseCase for DeleteCalendarUseCase {
                 if repo_res.is_err() {
                     return Err(UseCaseErrors::UnableToDelete);
                 }
+                ctx.booking_slots_cache.invalidate_user(&calendar.user_id);
 
                 Ok(calendar)
             }
@@ -108,3 +113,40 @@ impl PermissionBoundary for DeleteCalendarUseCase {
         vec![Permission::DeleteCalendar]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::{Service, ServiceResource, TimePlan, User};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn removes_deleted_calendar_from_service_resources() {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let other = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&other).await.unwrap();
+
+        let mut service = Service::new(user.account_id.clone());
+        service.add_user(ServiceResource::new(
+            user.id.clone(),
+            TimePlan::Calendar(calendar.id.clone()),
+            vec![calendar.id.clone(), other.id.clone()],
+        ));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let mut usecase = DeleteCalendarUseCase {
+            user_id: user.id.clone(),
+            calendar_id: calendar.id.clone(),
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let service = ctx.repos.service_repo.find(&service.id).await.unwrap();
+        assert_eq!(service.users.len(), 1);
+        assert_eq!(service.users[0].busy, vec![other.id]);
+        assert_eq!(service.users[0].availibility, TimePlan::Empty);
+    }
+}
@@ -4,7 +4,8 @@ use crate::shared::{
 };
 use crate::{error::NettuError, shared::auth::protect_route};
 use actix_web::{web, HttpRequest, HttpResponse};
-use nettu_scheduler_api_structs::get_calendar::{APIResponse, PathParams};
+use futures::join;
+use nettu_scheduler_api_structs::get_calendar::{APIResponse, PathParams, QueryParams};
 use nettu_scheduler_domain::{Calendar, ID};
 use nettu_scheduler_infra::NettuContext;
 
@@ -14,12 +15,26 @@ fn handle_errors(e: UseCaseErrors) -> NettuError {
             "The calendar with id: {}, was not found.",
             calendar_id
         )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
     }
 }
 
+fn to_response(usecase_res: UseCaseRes) -> HttpResponse {
+    let res = match usecase_res.stats {
+        Some(stats) => APIResponse::with_stats(
+            usecase_res.calendar,
+            stats.event_count,
+            stats.events_last_updated,
+        ),
+        None => APIResponse::new(usecase_res.calendar),
+    };
+    HttpResponse::Ok().json(res)
+}
+
 pub async fn get_calendar_admin_controller(
     http_req: web::HttpRequest,
     path: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
@@ -28,17 +43,19 @@ pub async fn get_calendar_admin_controller(
     let usecase = GetCalendarUseCase {
         user_id: cal.user_id,
         calendar_id: cal.id,
+        include_stats: query_params.include_stats,
     };
 
     execute(usecase, &ctx)
         .await
-        .map(|calendar| HttpResponse::Ok().json(APIResponse::new(calendar)))
+        .map(to_response)
         .map_err(handle_errors)
 }
 
 pub async fn get_calendar_controller(
     http_req: HttpRequest,
     path: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
     let (user, _policy) = protect_route(&http_req, &ctx).await?;
@@ -46,11 +63,12 @@ pub async fn get_calendar_controller(
     let usecase = GetCalendarUseCase {
         user_id: user.id.clone(),
         calendar_id: path.calendar_id.clone(),
+        include_stats: query_params.include_stats,
     };
 
     execute(usecase, &ctx)
         .await
-        .map(|calendar| HttpResponse::Ok().json(APIResponse::new(calendar)))
+        .map(to_response)
         .map_err(handle_errors)
 }
 
@@ -58,26 +76,121 @@ pub async fn get_calendar_controller(
 struct GetCalendarUseCase {
     pub user_id: ID,
     pub calendar_id: ID,
+    pub include_stats: bool,
+}
+
+#[derive(Debug)]
+struct CalendarStats {
+    event_count: usize,
+    events_last_updated: Option<i64>,
+}
+
+#[derive(Debug)]
+struct UseCaseRes {
+    pub calendar: Calendar,
+    pub stats: Option<CalendarStats>,
 }
 
 #[derive(Debug)]
 enum UseCaseErrors {
     NotFound(ID),
+    StorageError,
 }
 
 #[async_trait::async_trait(?Send)]
 impl UseCase for GetCalendarUseCase {
-    type Response = Calendar;
+    type Response = UseCaseRes;
 
     type Errors = UseCaseErrors;
 
     const NAME: &'static str = "GetCalendar";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let cal = ctx.repos.calendar_repo.find(&self.calendar_id).await;
-        match cal {
-            Some(cal) if cal.user_id == self.user_id => Ok(cal),
-            _ => Err(UseCaseErrors::NotFound(self.calendar_id.clone())),
+        let calendar = match ctx.repos.calendar_repo.find(&self.calendar_id).await {
+            Some(cal) if cal.user_id == self.user_id => cal,
+            _ => return Err(UseCaseErrors::NotFound(self.calendar_id.clone())),
+        };
+
+        if !self.include_stats {
+            return Ok(UseCaseRes {
+                calendar,
+                stats: None,
+            });
         }
+
+        let (event_count, events_last_updated) = join!(
+            ctx.repos.event_repo.count_by_calendar(&calendar.id),
+            ctx.repos
+                .event_repo
+                .find_last_updated_by_calendar(&calendar.id)
+        );
+        match (event_count, events_last_updated) {
+            (Ok(event_count), Ok(events_last_updated)) => Ok(UseCaseRes {
+                calendar,
+                stats: Some(CalendarStats {
+                    event_count,
+                    events_last_updated,
+                }),
+            }),
+            _ => Err(UseCaseErrors::StorageError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::{CalendarEvent, EventStatus};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn includes_event_stats_when_requested() {
+        let ctx = setup_context().await;
+        let user_id = ID::default();
+        let calendar = Calendar::new(&user_id, &ID::default());
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        for updated in &[20, 50, 30] {
+            let event = CalendarEvent {
+                id: Default::default(),
+                start_ts: 0,
+                duration: 1000,
+                end_ts: 1000,
+                busy: false,
+                status: EventStatus::Free,
+                recurrence: None,
+                exdates: vec![],
+                calendar_id: calendar.id.clone(),
+                user_id: user_id.clone(),
+                account_id: calendar.account_id.clone(),
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+                created: 10,
+                updated: *updated,
+                version: 0,
+            };
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+        }
+
+        let mut usecase = GetCalendarUseCase {
+            user_id: user_id.clone(),
+            calendar_id: calendar.id.clone(),
+            include_stats: true,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        let stats = res.stats.expect("Stats to be included");
+        assert_eq!(stats.event_count, 3);
+        assert_eq!(stats.events_last_updated, Some(50));
+
+        let mut usecase = GetCalendarUseCase {
+            user_id,
+            calendar_id: calendar.id.clone(),
+            include_stats: false,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert!(res.stats.is_none());
     }
 }
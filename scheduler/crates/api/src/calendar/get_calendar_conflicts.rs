@@ -0,0 +1,181 @@
+use crate::shared::auth::{account_can_modify_calendar, protect_route};
+use crate::shared::usecase::{execute, UseCase};
+use crate::{error::NettuError, shared::auth::protect_account_route};
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_calendar_conflicts::{APIResponse, PathParams, QueryParams};
+use nettu_scheduler_domain::{find_conflicts, EventConflict, TimeSpan, ID};
+use nettu_scheduler_infra::NettuContext;
+
+fn handle_errors(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::InvalidTimespan => {
+            NettuError::BadClientData("The start and end timespan is invalid".into())
+        }
+        UseCaseErrors::NotFound(calendar_id) => NettuError::NotFound(format!(
+            "The calendar with id: {}, was not found.",
+            calendar_id
+        )),
+    }
+}
+
+pub async fn get_calendar_conflicts_admin_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    path: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let cal = account_can_modify_calendar(&account, &path.calendar_id, &ctx).await?;
+
+    let usecase = GetCalendarConflictsUseCase {
+        user_id: cal.user_id,
+        calendar_id: cal.id,
+        start_ts: query_params.start_ts,
+        end_ts: query_params.end_ts,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|conflicts| HttpResponse::Ok().json(APIResponse::new(conflicts)))
+        .map_err(handle_errors)
+}
+
+pub async fn get_calendar_conflicts_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    path: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, _policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = GetCalendarConflictsUseCase {
+        user_id: user.id,
+        calendar_id: path.calendar_id.clone(),
+        start_ts: query_params.start_ts,
+        end_ts: query_params.end_ts,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|conflicts| HttpResponse::Ok().json(APIResponse::new(conflicts)))
+        .map_err(handle_errors)
+}
+
+/// Finds the busy `CalendarEvent`s in a `Calendar` that overlap each other
+/// within the timespan, like double-bookings
+#[derive(Debug)]
+struct GetCalendarConflictsUseCase {
+    pub calendar_id: ID,
+    pub user_id: ID,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    NotFound(ID),
+    InvalidTimespan,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetCalendarConflictsUseCase {
+    type Response = Vec<EventConflict>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetCalendarConflicts";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let timespan = TimeSpan::new(self.start_ts, self.end_ts);
+        if timespan.greater_than(ctx.config.event_instances_query_duration_limit) {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
+
+        let calendar = match ctx.repos.calendar_repo.find(&self.calendar_id).await {
+            Some(calendar) if calendar.user_id == self.user_id => calendar,
+            _ => return Err(UseCaseErrors::NotFound(self.calendar_id.clone())),
+        };
+
+        let instances = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&calendar.id, Some(&timespan))
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|event| event.expand(Some(&timespan), &calendar.settings))
+            .collect();
+
+        Ok(find_conflicts(instances))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{test, App};
+    use nettu_scheduler_infra::SeededContext;
+
+    #[actix_web::main]
+    #[test]
+    async fn reports_overlapping_busy_events() {
+        let hour = 1000 * 60 * 60;
+        let SeededContext {
+            ctx,
+            account,
+            calendar,
+            events,
+            ..
+        } = NettuContext::seeded()
+            .with_calendar_events(&[
+                (0, 2 * hour),
+                (hour, 3 * hour),
+                // Starts right when the second event ends
+                (3 * hour, 4 * hour),
+                (10 * hour, 11 * hour),
+            ])
+            .build()
+            .await;
+        let (account, calendar) = (account.unwrap(), calendar.unwrap());
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::calendar::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/calendar/{}/conflicts?startTs=0&endTs={}",
+                calendar.id,
+                12 * hour
+            ))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(
+            res.conflicts,
+            vec![EventConflict {
+                first_event_id: events[0].id.clone(),
+                second_event_id: events[1].id.clone(),
+                start_ts: hour,
+                end_ts: 2 * hour,
+            }]
+        );
+
+        // The first event is outside of this timespan, so the remaining events do not overlap
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/calendar/{}/conflicts?startTs={}&endTs={}",
+                calendar.id,
+                3 * hour,
+                12 * hour
+            ))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert!(res.conflicts.is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::shared::{
+    auth::{account_can_modify_user, protect_account_route},
+    pagination::page_limit,
+    usecase::{execute, UseCase},
+};
+use crate::{error::NettuError, shared::auth::protect_route};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_user_calendars::{APIResponse, PathParams, QueryParams};
+use nettu_scheduler_domain::{Calendar, ID};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn get_user_calendars_admin_controller(
+    http_req: HttpRequest,
+    path: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let user = account_can_modify_user(&account, &path.user_id, &ctx).await?;
+
+    let usecase = GetUserCalendarsUseCase {
+        user_id: user.id,
+        skip: query_params.skip.unwrap_or(0),
+        limit: page_limit(query_params.limit),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|calendars| HttpResponse::Ok().json(APIResponse::new(calendars)))
+        .map_err(|e| match e {})
+}
+
+pub async fn get_user_calendars_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, _policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = GetUserCalendarsUseCase {
+        user_id: user.id,
+        skip: query_params.skip.unwrap_or(0),
+        limit: page_limit(query_params.limit),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|calendars| HttpResponse::Ok().json(APIResponse::new(calendars)))
+        .map_err(|e| match e {})
+}
+
+/// Lists the `Calendar`s of a `User` ordered by id, which is the order they
+/// were created in, so that paging through them with `skip` is stable
+#[derive(Debug)]
+struct GetUserCalendarsUseCase {
+    pub user_id: ID,
+    pub skip: usize,
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetUserCalendarsUseCase {
+    type Response = Vec<Calendar>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetUserCalendars";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let mut calendars = ctx.repos.calendar_repo.find_by_user(&self.user_id).await;
+        // Ids are hex encoded `ObjectId`s of the same length, so they sort the same as strings
+        calendars.sort_by_key(|calendar| calendar.id.as_string());
+
+        Ok(calendars
+            .into_iter()
+            .skip(self.skip)
+            .take(self.limit)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn lists_calendars_of_user_in_stable_order() {
+        let ctx = setup_context().await;
+        let user_id = ID::default();
+        let account_id = ID::default();
+        let calendar1 = Calendar::new(&user_id, &account_id);
+        let calendar2 = Calendar::new(&user_id, &account_id);
+        // Inserted in reverse order to check that the ordering does not depend on it
+        ctx.repos.calendar_repo.insert(&calendar2).await.unwrap();
+        ctx.repos.calendar_repo.insert(&calendar1).await.unwrap();
+        // A calendar of another user is not listed
+        let other_calendar = Calendar::new(&ID::default(), &account_id);
+        ctx.repos
+            .calendar_repo
+            .insert(&other_calendar)
+            .await
+            .unwrap();
+
+        let mut usecase = GetUserCalendarsUseCase {
+            user_id: user_id.clone(),
+            skip: 0,
+            limit: 20,
+        };
+        let calendars = usecase.execute(&ctx).await.unwrap();
+        let ids = calendars.into_iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![calendar1.id.clone(), calendar2.id.clone()]);
+
+        let mut usecase = GetUserCalendarsUseCase {
+            user_id,
+            skip: 1,
+            limit: 20,
+        };
+        let calendars = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(calendars.len(), 1);
+        assert_eq!(calendars[0].id, calendar2.id);
+    }
+}
@@ -1,17 +1,29 @@
 use actix_web::web;
 
+mod block_calendar_time;
+mod check_calendar_conflict;
 mod create_calendar;
 mod delete_calendar;
 mod get_calendar;
+mod get_calendar_conflicts;
 mod get_calendar_events;
 mod get_calendars_by_meta;
+mod get_user_calendars;
 mod update_calendar;
 
+use block_calendar_time::{block_calendar_time_admin_controller, block_calendar_time_controller};
+use check_calendar_conflict::{
+    check_calendar_conflict_admin_controller, check_calendar_conflict_controller,
+};
 use create_calendar::{create_calendar_admin_controller, create_calendar_controller};
 use delete_calendar::{delete_calendar_admin_controller, delete_calendar_controller};
 use get_calendar::{get_calendar_admin_controller, get_calendar_controller};
+use get_calendar_conflicts::{
+    get_calendar_conflicts_admin_controller, get_calendar_conflicts_controller,
+};
 use get_calendar_events::{get_calendar_events_admin_controller, get_calendar_events_controller};
 use get_calendars_by_meta::get_calendars_by_meta_controller;
+use get_user_calendars::{get_user_calendars_admin_controller, get_user_calendars_controller};
 use update_calendar::{update_calendar_admin_controller, update_calendar_controller};
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
@@ -26,6 +38,12 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         web::get().to(get_calendars_by_meta_controller),
     );
 
+    cfg.route("/calendars", web::get().to(get_user_calendars_controller));
+    cfg.route(
+        "/user/{user_id}/calendars",
+        web::get().to(get_user_calendars_admin_controller),
+    );
+
     cfg.route(
         "/calendar/{calendar_id}",
         web::get().to(get_calendar_controller),
@@ -53,6 +71,15 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         web::put().to(update_calendar_admin_controller),
     );
 
+    cfg.route(
+        "/calendar/{calendar_id}/block",
+        web::post().to(block_calendar_time_controller),
+    );
+    cfg.route(
+        "/user/calendar/{calendar_id}/block",
+        web::post().to(block_calendar_time_admin_controller),
+    );
+
     cfg.route(
         "/calendar/{calendar_id}/events",
         web::get().to(get_calendar_events_controller),
@@ -61,4 +88,22 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/user/calendar/{calendar_id}/events",
         web::get().to(get_calendar_events_admin_controller),
     );
+
+    cfg.route(
+        "/calendar/{calendar_id}/conflicts",
+        web::get().to(get_calendar_conflicts_controller),
+    );
+    cfg.route(
+        "/user/calendar/{calendar_id}/conflicts",
+        web::get().to(get_calendar_conflicts_admin_controller),
+    );
+
+    cfg.route(
+        "/calendar/{calendar_id}/check-conflict",
+        web::post().to(check_calendar_conflict_controller),
+    );
+    cfg.route(
+        "/user/calendar/{calendar_id}/check-conflict",
+        web::post().to(check_calendar_conflict_admin_controller),
+    );
 }
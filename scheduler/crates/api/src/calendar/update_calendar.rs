@@ -120,10 +120,14 @@ impl UseCase for UpdateCalendarUseCase {
         if let Some(metadata) = &self.metadata {
             calendar.metadata = metadata.clone();
         }
+        calendar.updated = ctx.sys.get_timestamp_millis();
 
         let repo_res = ctx.repos.calendar_repo.save(&calendar).await;
         match repo_res {
-            Ok(_) => Ok(calendar),
+            Ok(_) => {
+                ctx.booking_slots_cache.invalidate_user(&calendar.user_id);
+                Ok(calendar)
+            }
             Err(_) => Err(UseCaseErrors::StorageError),
         }
     }
@@ -140,7 +144,8 @@ mod test {
     use std::collections::HashMap;
 
     use nettu_scheduler_domain::Calendar;
-    use nettu_scheduler_infra::setup_context;
+    use nettu_scheduler_infra::{setup_context, ISys};
+    use std::sync::Arc;
 
     use super::*;
 
@@ -189,4 +194,36 @@ mod test {
         let calendar = ctx.repos.calendar_repo.find(&calendar.id).await.unwrap();
         assert_eq!(calendar.settings.week_start, new_wkst);
     }
+
+    struct StaticTimeSys;
+    impl ISys for StaticTimeSys {
+        fn get_timestamp_millis(&self) -> i64 {
+            1000
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn it_bumps_updated_and_keeps_created() {
+        let mut ctx = setup_context().await;
+        ctx.sys = Arc::new(StaticTimeSys);
+        let user_id = ID::default();
+        let mut calendar = Calendar::new(&user_id, &ID::default());
+        calendar.created = 10;
+        calendar.updated = 10;
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut usecase = UpdateCalendarUseCase {
+            calendar_id: calendar.id.clone(),
+            user_id,
+            week_start: Some(1),
+            timezone: None,
+            metadata: None,
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let calendar = ctx.repos.calendar_repo.find(&calendar.id).await.unwrap();
+        assert_eq!(calendar.created, 10);
+        assert_eq!(calendar.updated, 1000);
+    }
 }
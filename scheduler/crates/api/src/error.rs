@@ -1,10 +1,13 @@
 use actix_web::{
     dev::HttpResponseBuilder,
+    error::PathError,
     http::{header, StatusCode},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use thiserror::Error;
 
+use crate::shared::locale::Locale;
+
 #[derive(Error, Debug)]
 pub enum NettuError {
     #[error("data store disconnected")]
@@ -21,23 +24,127 @@ pub enum NettuError {
     UnidentifiableClient(String),
     #[error("404 Not found. Error message: `{0}`")]
     NotFound(String),
+    #[error("A precondition of the request failed. Error message: `{0}`")]
+    PreconditionFailed(String),
+    /// An error that is rendered to the client in the given language
+    #[error("{0}")]
+    Localized(Box<NettuError>, Locale),
+}
+
+impl NettuError {
+    /// Renders this error in the given `Locale` instead of English. Only the messages
+    /// from the `locale` catalog are translated, other messages are kept as they are.
+    pub fn localize(self, locale: Locale) -> Self {
+        match self {
+            NettuError::Localized(error, _) => NettuError::Localized(error, locale),
+            error => NettuError::Localized(Box::new(error), locale),
+        }
+    }
+
+    fn render(&self, locale: Locale) -> String {
+        match (self, locale) {
+            (NettuError::Localized(error, locale), _) => error.render(*locale),
+            (_, Locale::English) => self.to_string(),
+            (NettuError::InternalError, Locale::Norwegian) => "Intern feil".into(),
+            (NettuError::BadClientData(msg), Locale::Norwegian) => {
+                format!("Ugyldige data. Feilmelding: `{}`", msg)
+            }
+            (NettuError::Conflict(msg), Locale::Norwegian) => {
+                format!("Forespørselen er i konflikt. Feilmelding: `{}`", msg)
+            }
+            (NettuError::Unauthorized(msg), Locale::Norwegian) => {
+                format!("Uautorisert forespørsel. Feilmelding: `{}`", msg)
+            }
+            (NettuError::UnidentifiableClient(msg), Locale::Norwegian) => format!(
+                "Uidentifiserbar klient. Må inkludere `nettu-account` headeren. Feilmelding: `{}`",
+                msg
+            ),
+            (NettuError::NotFound(msg), Locale::Norwegian) => {
+                format!("404 Ikke funnet. Feilmelding: `{}`", msg)
+            }
+            (NettuError::PreconditionFailed(msg), Locale::Norwegian) => {
+                format!(
+                    "En forutsetning for forespørselen feilet. Feilmelding: `{}`",
+                    msg
+                )
+            }
+        }
+    }
 }
 
 impl actix_web::error::ResponseError for NettuError {
     fn error_response(&self) -> HttpResponse {
         HttpResponseBuilder::new(self.status_code())
             .set_header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-            .body(self.to_string())
+            .body(self.render(Locale::default()))
     }
 
     fn status_code(&self) -> StatusCode {
         match *self {
+            NettuError::Localized(ref error, _) => error.status_code(),
             NettuError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
             NettuError::BadClientData(_) => StatusCode::BAD_REQUEST,
             NettuError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
             NettuError::Conflict(_) => StatusCode::CONFLICT,
             NettuError::NotFound(_) => StatusCode::NOT_FOUND,
             NettuError::UnidentifiableClient(_) => StatusCode::UNAUTHORIZED,
+            NettuError::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
         }
     }
 }
+
+/// Malformed path params, like an invalid `ID`, are rejected by actix with a 404
+/// by default. Reject them as bad client data instead, before they reach any controller.
+pub fn path_params_error_handler(err: PathError, _req: &HttpRequest) -> actix_web::Error {
+    let msg = match err {
+        PathError::Deserialize(e) => e.to_string(),
+    };
+    NettuError::BadClientData(msg).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::configure_server_api;
+    use actix_web::{error::ResponseError, test, web, App};
+    use nettu_scheduler_infra::setup_context;
+
+    #[test]
+    fn renders_localized_errors_in_their_locale() {
+        let error = NettuError::BadClientData("duration".into());
+        assert_eq!(
+            error.render(Locale::Norwegian),
+            "Ugyldige data. Feilmelding: `duration`"
+        );
+
+        let error = NettuError::NotFound("event".into()).localize(Locale::Norwegian);
+        assert_eq!(error.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            error.render(Locale::default()),
+            "404 Ikke funnet. Feilmelding: `event`"
+        );
+        assert_eq!(
+            error.localize(Locale::English).render(Locale::default()),
+            "404 Not found. Error message: `event`"
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_malformed_id_in_path_with_bad_request() {
+        let ctx = setup_context().await;
+        let mut app = test::init_service(
+            App::new()
+                .app_data(web::PathConfig::default().error_handler(path_params_error_handler))
+                .data(ctx)
+                .configure(configure_server_api),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/events/not-an-id")
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,255 @@
+use crate::shared::{
+    auth::{account_can_modify_event, protect_account_route, protect_route, Permission},
+    usecase::{execute_with_policy, PermissionBoundary, Subscriber, UseCaseErrorContainer},
+};
+use crate::{
+    error::NettuError,
+    shared::usecase::{execute, UseCase},
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::add_event_exdate::*;
+use nettu_scheduler_domain::{CalendarEvent, ID};
+use nettu_scheduler_infra::NettuContext;
+
+use super::subscribers::{InvalidateBookingSlotsOnEventModified, SyncRemindersOnEventUpdated};
+
+fn handle_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::NotFound(entity, id) => {
+            NettuError::NotFound(format!("The {} with id: {}, was not found.", entity, id))
+        }
+        UseCaseErrors::NotAnOccurrence(ts) => NettuError::BadClientData(format!(
+            "The calendar event does not have an instance starting at: {}",
+            ts
+        )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
+    }
+}
+
+pub async fn add_event_exdate_admin_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let e = account_can_modify_event(&account, &path_params.event_id, &ctx).await?;
+
+    let usecase = AddEventExdateUseCase {
+        user_id: e.user_id,
+        event_id: e.id,
+        ts: body.ts,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(handle_error)
+}
+
+pub async fn add_event_exdate_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = AddEventExdateUseCase {
+        user_id: user.id.clone(),
+        event_id: path_params.event_id.clone(),
+        ts: body.ts,
+    };
+
+    execute_with_policy(usecase, &policy, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(|e| match e {
+            UseCaseErrorContainer::Unauthorized(e) => NettuError::Unauthorized(e),
+            UseCaseErrorContainer::UseCase(e) => handle_error(e),
+        })
+}
+
+/// Excludes a single instance of a `CalendarEvent` without having to send
+/// the full list of exdates, which would overwrite concurrent changes to it
+#[derive(Debug)]
+pub struct AddEventExdateUseCase {
+    pub user_id: ID,
+    pub event_id: ID,
+    pub ts: i64,
+}
+
+#[derive(Debug)]
+pub enum UseCaseErrors {
+    NotFound(String, ID),
+    NotAnOccurrence(i64),
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for AddEventExdateUseCase {
+    type Response = CalendarEvent;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "AddEventExdate";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let event = match ctx.repos.event_repo.find(&self.event_id).await {
+            Some(event) if event.user_id == self.user_id => event,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar Event".into(),
+                    self.event_id.clone(),
+                ))
+            }
+        };
+
+        // Already excluded instances are not expanded anymore, so only validate new exdates
+        if !event.exdates.contains(&self.ts) {
+            let calendar = match ctx.repos.calendar_repo.find(&event.calendar_id).await {
+                Some(calendar) => calendar,
+                None => {
+                    return Err(UseCaseErrors::NotFound(
+                        "Calendar".into(),
+                        event.calendar_id.clone(),
+                    ))
+                }
+            };
+            if !event.is_occurrence(self.ts, &calendar.settings) {
+                return Err(UseCaseErrors::NotAnOccurrence(self.ts));
+            }
+        }
+
+        let updated = ctx.sys.get_timestamp_millis();
+        match ctx
+            .repos
+            .event_repo
+            .add_exdate(&event.id, self.ts, updated)
+            .await
+        {
+            Ok(Some(event)) => Ok(event),
+            Ok(None) => Err(UseCaseErrors::NotFound(
+                "Calendar Event".into(),
+                self.event_id.clone(),
+            )),
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(SyncRemindersOnEventUpdated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+impl PermissionBoundary for AddEventExdateUseCase {
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::UpdateCalendarEvent]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::remove_event_exdate::RemoveEventExdateUseCase;
+    use nettu_scheduler_domain::{
+        Calendar, EventStatus, RRuleFrequenzy, RRuleOptions, TimeSpan, User,
+    };
+    use nettu_scheduler_infra::setup_context;
+
+    const DAY: i64 = 1000 * 60 * 60 * 24;
+
+    async fn setup_daily_event(ctx: &NettuContext) -> (Calendar, CalendarEvent) {
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut event = CalendarEvent {
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id.clone(),
+            busy: false,
+            status: EventStatus::Free,
+            duration: 1000 * 60 * 60,
+            end_ts: 0,
+            exdates: vec![],
+            id: Default::default(),
+            start_ts: 0,
+            recurrence: None,
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            updated: Default::default(),
+            version: Default::default(),
+            created: Default::default(),
+        };
+        let recurrence = RRuleOptions {
+            freq: RRuleFrequenzy::Daily,
+            interval: 1,
+            count: Some(3),
+            ..Default::default()
+        };
+        assert!(event.set_recurrence(recurrence, &calendar.settings, true));
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+
+        (calendar, event)
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn excluded_occurrence_disappears_from_expansion() {
+        let ctx = setup_context().await;
+        let (calendar, event) = setup_daily_event(&ctx).await;
+
+        let mut usecase = AddEventExdateUseCase {
+            user_id: event.user_id.clone(),
+            event_id: event.id.clone(),
+            ts: DAY,
+        };
+        let event = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(event.exdates, vec![DAY]);
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        let instances = event.expand(Some(&TimeSpan::new(0, 3 * DAY)), &calendar.settings);
+        assert_eq!(
+            instances.iter().map(|i| i.start_ts).collect::<Vec<_>>(),
+            vec![0, 2 * DAY]
+        );
+
+        // Removing the exdate brings the occurrence back
+        let mut usecase = RemoveEventExdateUseCase {
+            user_id: event.user_id.clone(),
+            event_id: event.id.clone(),
+            ts: DAY,
+        };
+        let event = usecase.execute(&ctx).await.unwrap();
+        assert!(event.exdates.is_empty());
+        let instances = event.expand(Some(&TimeSpan::new(0, 3 * DAY)), &calendar.settings);
+        assert_eq!(instances.len(), 3);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_exdate_that_is_not_an_occurrence() {
+        let ctx = setup_context().await;
+        let (_, event) = setup_daily_event(&ctx).await;
+
+        for ts in &[DAY + 1, 3 * DAY] {
+            let mut usecase = AddEventExdateUseCase {
+                user_id: event.user_id.clone(),
+                event_id: event.id.clone(),
+                ts: *ts,
+            };
+            let res = usecase.execute(&ctx).await;
+            assert!(matches!(res, Err(UseCaseErrors::NotAnOccurrence(_))));
+        }
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert!(event.exdates.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use super::subscribers::CreateRemindersOnEventCreated;
+use super::subscribers::{CreateRemindersOnEventCreated, InvalidateBookingSlotsOnEventModified};
 use crate::error::NettuError;
 use crate::shared::{
     auth::{account_can_modify_user, protect_account_route, protect_route, Permission},
@@ -9,10 +9,65 @@ use crate::shared::{
 };
 use actix_web::{web, HttpResponse};
 use nettu_scheduler_api_structs::create_event::*;
-use nettu_scheduler_domain::{CalendarEvent, CalendarEventReminder, Metadata, RRuleOptions, ID};
+use nettu_scheduler_domain::{
+    Calendar, CalendarEvent, CalendarEventReminder, EventStatus, Metadata, RRuleOptions, ID,
+};
 use nettu_scheduler_infra::NettuContext;
 
-fn handle_error(e: UseCaseErrors) -> NettuError {
+pub(crate) const INCONSISTENT_DURATION_MESSAGE: &str =
+    "The provided duration and end_ts is inconsistent, the duration has to equal end_ts - start_ts";
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum ResolveDurationError {
+    /// Both `duration` and `end_ts` are given but do not agree
+    Inconsistent,
+    /// The timestamps are so large that the duration or end can not be computed
+    InvalidTimespan,
+}
+
+/// Clients coming from iCalendar describe the end of an event with `end_ts` (`DTEND`)
+/// instead of a `duration`. Resolves the duration from whichever of them is given,
+/// and fails if both are given but do not agree.
+pub(crate) fn resolve_duration(
+    start_ts: i64,
+    duration: Option<i64>,
+    end_ts: Option<i64>,
+) -> Result<Option<i64>, ResolveDurationError> {
+    match (duration, end_ts) {
+        (Some(duration), Some(end_ts)) => match start_ts.checked_add(duration) {
+            Some(resolved_end_ts) if resolved_end_ts == end_ts => Ok(Some(duration)),
+            Some(_) => Err(ResolveDurationError::Inconsistent),
+            None => Err(ResolveDurationError::InvalidTimespan),
+        },
+        (Some(duration), None) => Ok(Some(duration)),
+        (None, Some(end_ts)) => end_ts
+            .checked_sub(start_ts)
+            .map(Some)
+            .ok_or(ResolveDurationError::InvalidTimespan),
+        (None, None) => Ok(None),
+    }
+}
+
+pub(crate) fn parse_duration(
+    start_ts: i64,
+    duration: Option<i64>,
+    end_ts: Option<i64>,
+) -> Result<i64, NettuError> {
+    match resolve_duration(start_ts, duration, end_ts) {
+        Ok(Some(duration)) => Ok(duration),
+        Ok(None) => Err(NettuError::BadClientData(
+            "Either duration or end_ts has to be provided for the event".into(),
+        )),
+        Err(ResolveDurationError::Inconsistent) => Err(NettuError::BadClientData(
+            INCONSISTENT_DURATION_MESSAGE.into(),
+        )),
+        Err(ResolveDurationError::InvalidTimespan) => {
+            Err(handle_error(UseCaseErrors::InvalidTimespan))
+        }
+    }
+}
+
+pub(crate) fn handle_error(e: UseCaseErrors) -> NettuError {
     match e {
         UseCaseErrors::NotFound(calendar_id) => NettuError::NotFound(format!(
             "The calendar with id: {}, was not found.",
@@ -24,6 +79,13 @@ fn handle_error(e: UseCaseErrors) -> NettuError {
         UseCaseErrors::InvalidReminder => {
             NettuError::BadClientData("Invalid reminder specified for the event".into())
         }
+        UseCaseErrors::InvalidTimespan => NettuError::BadClientData(
+            "The provided start_ts and duration is invalid for the event".into(),
+        ),
+        UseCaseErrors::ServiceNotFound(service_id) => NettuError::NotFound(format!(
+            "The service with id: {}, was not found.",
+            service_id
+        )),
         UseCaseErrors::StorageError => NettuError::InternalError,
     }
 }
@@ -38,17 +100,21 @@ pub async fn create_event_admin_controller(
     let user = account_can_modify_user(&account, &path_params.user_id, &ctx).await?;
 
     let body = body.0;
+    let duration = parse_duration(body.start_ts, body.duration, body.end_ts)?;
     let usecase = CreateEventUseCase {
         busy: body.busy.unwrap_or(false),
+        status: body.status,
         start_ts: body.start_ts,
-        duration: body.duration,
+        duration,
         user_id: user.id,
         calendar_id: body.calendar_id,
         recurrence: body.recurrence,
         account_id: account.id,
         reminder: body.reminder,
         is_service: body.is_service.unwrap_or(false),
+        service_id: body.service_id,
         metadata: body.metadata.unwrap_or_default(),
+        external_id: body.external_id,
     };
 
     execute(usecase, &ctx)
@@ -65,17 +131,21 @@ pub async fn create_event_controller(
     let (user, policy) = protect_route(&http_req, &ctx).await?;
 
     let body = body.0;
+    let duration = parse_duration(body.start_ts, body.duration, body.end_ts)?;
     let usecase = CreateEventUseCase {
         busy: body.busy.unwrap_or(false),
+        status: body.status,
         start_ts: body.start_ts,
-        duration: body.duration,
+        duration,
         calendar_id: body.calendar_id,
         recurrence: body.recurrence,
         user_id: user.id,
         account_id: user.account_id,
         reminder: body.reminder,
         is_service: body.is_service.unwrap_or(false),
+        service_id: body.service_id,
         metadata: body.metadata.unwrap_or_default(),
+        external_id: body.external_id,
     };
 
     execute_with_policy(usecase, &policy, &ctx)
@@ -95,17 +165,24 @@ pub struct CreateEventUseCase {
     pub start_ts: i64,
     pub duration: i64,
     pub busy: bool,
+    /// Overrides `busy` when set
+    pub status: Option<EventStatus>,
     pub recurrence: Option<RRuleOptions>,
     pub reminder: Option<CalendarEventReminder>,
     pub is_service: bool,
+    /// The `Service` that the `CalendarEvent` is a booking of
+    pub service_id: Option<ID>,
     pub metadata: Metadata,
+    pub external_id: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum UseCaseErrors {
     InvalidRecurrenceRule,
     InvalidReminder,
+    InvalidTimespan,
     NotFound(ID),
+    ServiceNotFound(ID),
     StorageError,
 }
 
@@ -122,24 +199,82 @@ impl UseCase for CreateEventUseCase {
             Some(calendar) if calendar.user_id == self.user_id => calendar,
             _ => return Err(UseCaseErrors::NotFound(self.calendar_id.clone())),
         };
+        if let Some(service_id) = &self.service_id {
+            if !service_exists_in_account(service_id, &self.account_id, ctx).await {
+                return Err(UseCaseErrors::ServiceNotFound(service_id.clone()));
+            }
+        }
+
+        let e = self.build_event(&calendar, ctx.sys.get_timestamp_millis())?;
 
+        let repo_res = ctx.repos.event_repo.insert(&e).await;
+        if repo_res.is_err() {
+            return Err(UseCaseErrors::StorageError);
+        }
+
+        Ok(e)
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(CreateRemindersOnEventCreated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+/// Bookings can only reference the `Service`s of the `Account` the `CalendarEvent` is in
+pub(crate) async fn service_exists_in_account(
+    service_id: &ID,
+    account_id: &ID,
+    ctx: &NettuContext,
+) -> bool {
+    ctx.repos
+        .service_repo
+        .find_for_account(service_id, account_id)
+        .await
+        .is_some()
+}
+
+impl CreateEventUseCase {
+    /// Creates and validates the `CalendarEvent` in the given `Calendar` of the `User`,
+    /// without storing it
+    pub(crate) fn build_event(
+        &self,
+        calendar: &Calendar,
+        now: i64,
+    ) -> Result<CalendarEvent, UseCaseErrors> {
+        let end_ts = match self.start_ts.checked_add(self.duration) {
+            Some(end_ts) => end_ts,
+            None => return Err(UseCaseErrors::InvalidTimespan),
+        };
         let mut e = CalendarEvent {
             id: Default::default(),
             busy: self.busy,
+            status: EventStatus::from_busy(self.busy),
             start_ts: self.start_ts,
             duration: self.duration,
-            created: ctx.sys.get_timestamp_millis(),
-            updated: ctx.sys.get_timestamp_millis(),
+            created: now,
+            updated: now,
+            version: 0,
             recurrence: None,
-            end_ts: self.start_ts + self.duration, // default, if recurrence changes, this will be updated
+            end_ts, // default, if recurrence changes, this will be updated
             exdates: vec![],
             calendar_id: calendar.id.clone(),
             user_id: self.user_id.clone(),
             account_id: self.account_id.clone(),
             reminder: self.reminder.clone(),
-            is_service: self.is_service,
+            is_service: self.is_service || self.service_id.is_some(),
+            service_id: self.service_id.clone(),
             metadata: self.metadata.clone(),
+            external_id: self.external_id.clone(),
         };
+        if let Some(status) = self.status {
+            e.set_status(status);
+        }
+        if !e.has_valid_timespan() {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
         if let Some(rrule_opts) = self.recurrence.clone() {
             if !e.set_recurrence(rrule_opts, &calendar.settings, true) {
                 return Err(UseCaseErrors::InvalidRecurrenceRule);
@@ -152,17 +287,8 @@ impl UseCase for CreateEventUseCase {
             }
         }
 
-        let repo_res = ctx.repos.event_repo.insert(&e).await;
-        if repo_res.is_err() {
-            return Err(UseCaseErrors::StorageError);
-        }
-
         Ok(e)
     }
-
-    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
-        vec![Box::new(CreateRemindersOnEventCreated)]
-    }
 }
 
 impl PermissionBoundary for CreateEventUseCase {
@@ -174,9 +300,10 @@ impl PermissionBoundary for CreateEventUseCase {
 #[cfg(test)]
 mod test {
     use super::*;
+    use actix_web::{http::StatusCode, test, App};
     use chrono::prelude::*;
     use chrono::Utc;
-    use nettu_scheduler_domain::{Calendar, User};
+    use nettu_scheduler_domain::{Account, Service, User};
     use nettu_scheduler_infra::setup_context;
 
     struct TestContext {
@@ -214,12 +341,15 @@ mod test {
             duration: 800,
             recurrence: None,
             busy: false,
+            status: None,
             calendar_id: calendar.id.clone(),
             user_id: user.id.clone(),
             account_id: user.account_id,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -241,12 +371,15 @@ mod test {
             duration: 800,
             recurrence: Some(Default::default()),
             busy: false,
+            status: None,
             calendar_id: calendar.id.clone(),
             user_id: user.id.clone(),
             account_id: user.account_id,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -268,12 +401,15 @@ mod test {
             duration: 800,
             recurrence: Some(Default::default()),
             busy: false,
+            status: None,
             calendar_id: ID::default(),
             user_id: user.id.clone(),
             account_id: user.account_id,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -284,6 +420,46 @@ mod test {
         );
     }
 
+    #[actix_web::main]
+    #[test]
+    async fn creates_booking_of_service_in_the_account() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+        let service = Service::new(user.account_id.clone());
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+        let other_service = Service::new(ID::default());
+        ctx.repos.service_repo.insert(&other_service).await.unwrap();
+
+        let usecase = |service_id: &ID| CreateEventUseCase {
+            start_ts: 500,
+            duration: 800,
+            recurrence: None,
+            busy: true,
+            status: None,
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id.clone(),
+            reminder: None,
+            is_service: false,
+            service_id: Some(service_id.clone()),
+            metadata: Default::default(),
+            external_id: None,
+        };
+
+        let booking = usecase(&service.id).execute(&ctx).await.unwrap();
+        assert!(booking.is_service);
+        assert_eq!(booking.service_id, Some(service.id.clone()));
+
+        let res = usecase(&other_service.id).execute(&ctx).await;
+        assert_eq!(
+            res.unwrap_err(),
+            UseCaseErrors::ServiceNotFound(other_service.id)
+        );
+    }
+
     #[actix_web::main]
     #[test]
     async fn rejects_event_with_invalid_recurrence() {
@@ -308,12 +484,15 @@ mod test {
                 duration: 800,
                 recurrence: Some(rrule),
                 busy: false,
+                status: None,
                 calendar_id: calendar.id.clone(),
                 user_id: user.id.clone(),
                 account_id: user.account_id.to_owned(),
                 reminder: None,
                 is_service: false,
+                service_id: None,
                 metadata: Default::default(),
+                external_id: None,
             };
 
             let res = usecase.execute(&ctx).await;
@@ -321,4 +500,184 @@ mod test {
             assert!(res.is_err());
         }
     }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_event_with_negative_duration() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+
+        let mut usecase = CreateEventUseCase {
+            start_ts: 500,
+            duration: -800,
+            recurrence: None,
+            busy: false,
+            status: None,
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id,
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+
+        let res = usecase.execute(&ctx).await;
+        assert_eq!(res.unwrap_err(), UseCaseErrors::InvalidTimespan);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_event_with_implausible_start_ts() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+
+        for start_ts in vec![-500, CalendarEvent::get_max_timestamp() + 500] {
+            let mut usecase = CreateEventUseCase {
+                start_ts,
+                duration: 800,
+                recurrence: None,
+                busy: false,
+                status: None,
+                calendar_id: calendar.id.clone(),
+                user_id: user.id.clone(),
+                account_id: user.account_id.to_owned(),
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+            };
+
+            let res = usecase.execute(&ctx).await;
+            assert_eq!(res.unwrap_err(), UseCaseErrors::InvalidTimespan);
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_event_with_overflowing_timespan() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+
+        for (start_ts, duration) in vec![(i64::MAX - 500, 800), (500, i64::MAX)] {
+            let mut usecase = CreateEventUseCase {
+                start_ts,
+                duration,
+                recurrence: None,
+                busy: false,
+                status: None,
+                calendar_id: calendar.id.clone(),
+                user_id: user.id.clone(),
+                account_id: user.account_id.to_owned(),
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+            };
+
+            let res = usecase.execute(&ctx).await;
+            assert_eq!(res.unwrap_err(), UseCaseErrors::InvalidTimespan);
+        }
+    }
+
+    #[test]
+    fn resolves_duration_from_duration_or_end_ts() {
+        // Only duration
+        assert_eq!(resolve_duration(500, Some(800), None), Ok(Some(800)));
+        // Only end_ts
+        assert_eq!(resolve_duration(500, None, Some(1300)), Ok(Some(800)));
+        // Both and consistent
+        assert_eq!(resolve_duration(500, Some(800), Some(1300)), Ok(Some(800)));
+        // Neither
+        assert_eq!(resolve_duration(500, None, None), Ok(None));
+    }
+
+    #[test]
+    fn rejects_duration_overflowing_the_timestamps() {
+        assert_eq!(
+            resolve_duration(i64::MAX, Some(800), Some(1000)),
+            Err(ResolveDurationError::InvalidTimespan)
+        );
+        assert_eq!(
+            resolve_duration(i64::MIN, None, Some(i64::MAX)),
+            Err(ResolveDurationError::InvalidTimespan)
+        );
+        assert!(matches!(
+            parse_duration(-1, None, Some(i64::MAX)),
+            Err(NettuError::BadClientData(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_conflicting_duration_and_end_ts() {
+        assert_eq!(
+            resolve_duration(500, Some(800), Some(1000)),
+            Err(ResolveDurationError::Inconsistent)
+        );
+
+        let body = RequestBody {
+            calendar_id: Default::default(),
+            start_ts: 500,
+            duration: Some(800),
+            end_ts: Some(1000),
+            busy: None,
+            status: None,
+            recurrence: None,
+            reminder: None,
+            is_service: None,
+            service_id: None,
+            metadata: None,
+            external_id: None,
+        };
+        assert!(matches!(
+            parse_duration(body.start_ts, body.duration, body.end_ts),
+            Err(NettuError::BadClientData(_))
+        ));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn admin_creates_event_by_acting_as_user() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let calendar = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/events")
+            .header("x-api-key", account.secret_api_key.clone())
+            .header("x-act-as-user", user.id.to_string())
+            .set_json(&serde_json::json!({
+                "calendarId": calendar.id,
+                "startTs": 500,
+                "duration": 800,
+            }))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        let res: APIResponse = test::read_body_json(res).await;
+        assert_eq!(res.event.user_id, user.id);
+        assert_eq!(res.event.calendar_id, calendar.id);
+    }
 }
@@ -0,0 +1,362 @@
+use super::create_event::{
+    self, handle_error, resolve_duration, service_exists_in_account, CreateEventUseCase,
+    ResolveDurationError, INCONSISTENT_DURATION_MESSAGE,
+};
+use super::subscribers::{CreateRemindersOnEventCreated, InvalidateBookingSlotsOnEventModified};
+use crate::error::NettuError;
+use crate::shared::{
+    auth::{account_can_modify_user, protect_account_route, protect_route, Permission},
+    usecase::{
+        execute, execute_with_policy, PermissionBoundary, Subscriber, UseCase,
+        UseCaseErrorContainer,
+    },
+};
+use actix_web::{web, HttpResponse};
+use nettu_scheduler_api_structs::create_event::RequestBody as EventBody;
+use nettu_scheduler_api_structs::create_events_batch::*;
+use nettu_scheduler_domain::{CalendarEvent, ID};
+use nettu_scheduler_infra::NettuContext;
+use std::collections::HashMap;
+
+/// Upper bound for how many `CalendarEvent`s can be created in a single batch
+const MAX_BATCH_SIZE: usize = 100;
+
+fn invalid_event_message(e: InvalidEvent) -> String {
+    match e {
+        InvalidEvent::MissingDuration => {
+            "Either duration or end_ts has to be provided for the event".into()
+        }
+        InvalidEvent::InconsistentDuration => INCONSISTENT_DURATION_MESSAGE.into(),
+        InvalidEvent::Event(e) => match handle_error(e) {
+            NettuError::BadClientData(msg) | NettuError::NotFound(msg) => msg,
+            e => e.to_string(),
+        },
+    }
+}
+
+fn handle_batch_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::InvalidBatchSize => NettuError::BadClientData(format!(
+            "The batch has to contain between 1 and {} events",
+            MAX_BATCH_SIZE
+        )),
+        UseCaseErrors::InvalidEvents(errors) => NettuError::BadClientData(format!(
+            "No events were created, because some of them are invalid. {}",
+            errors
+                .into_iter()
+                .map(|(index, e)| format!("Event {}: {}", index, invalid_event_message(e)))
+                .collect::<Vec<_>>()
+                .join(". ")
+        )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
+    }
+}
+
+pub async fn create_events_batch_admin_controller(
+    http_req: web::HttpRequest,
+    path_params: web::Path<PathParams>,
+    body: web::Json<RequestBody>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let user = account_can_modify_user(&account, &path_params.user_id, &ctx).await?;
+
+    let usecase = CreateEventsBatchUseCase {
+        account_id: account.id,
+        user_id: user.id,
+        events: body.0.events,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|events| HttpResponse::Created().json(APIResponse::new(events)))
+        .map_err(handle_batch_error)
+}
+
+pub async fn create_events_batch_controller(
+    http_req: web::HttpRequest,
+    body: web::Json<RequestBody>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = CreateEventsBatchUseCase {
+        account_id: user.account_id,
+        user_id: user.id,
+        events: body.0.events,
+    };
+
+    execute_with_policy(usecase, &policy, &ctx)
+        .await
+        .map(|events| HttpResponse::Created().json(APIResponse::new(events)))
+        .map_err(|e| match e {
+            UseCaseErrorContainer::Unauthorized(e) => NettuError::Unauthorized(e),
+            UseCaseErrorContainer::UseCase(e) => handle_batch_error(e),
+        })
+}
+
+/// Creates all the `CalendarEvent`s in a single insert after every one of them
+/// has been validated, so that either all of them are created or none of them
+#[derive(Debug)]
+pub struct CreateEventsBatchUseCase {
+    pub account_id: ID,
+    pub user_id: ID,
+    pub events: Vec<EventBody>,
+}
+
+/// Why a single `CalendarEvent` of the batch is invalid
+#[derive(Debug, PartialEq)]
+pub enum InvalidEvent {
+    MissingDuration,
+    InconsistentDuration,
+    Event(create_event::UseCaseErrors),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UseCaseErrors {
+    InvalidBatchSize,
+    /// The invalid events together with their index in the batch
+    InvalidEvents(Vec<(usize, InvalidEvent)>),
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for CreateEventsBatchUseCase {
+    type Response = Vec<CalendarEvent>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "CreateEventsBatch";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        if self.events.is_empty() || self.events.len() > MAX_BATCH_SIZE {
+            return Err(UseCaseErrors::InvalidBatchSize);
+        }
+
+        let calendars = ctx
+            .repos
+            .calendar_repo
+            .find_by_user(&self.user_id)
+            .await
+            .into_iter()
+            .map(|calendar| (calendar.id.to_string(), calendar))
+            .collect::<HashMap<_, _>>();
+
+        let now = ctx.sys.get_timestamp_millis();
+        let mut events = Vec::with_capacity(self.events.len());
+        let mut errors = Vec::new();
+        for (index, body) in self.events.iter().enumerate() {
+            let duration = match resolve_duration(body.start_ts, body.duration, body.end_ts) {
+                Ok(Some(duration)) => duration,
+                Ok(None) => {
+                    errors.push((index, InvalidEvent::MissingDuration));
+                    continue;
+                }
+                Err(ResolveDurationError::Inconsistent) => {
+                    errors.push((index, InvalidEvent::InconsistentDuration));
+                    continue;
+                }
+                Err(ResolveDurationError::InvalidTimespan) => {
+                    let e = create_event::UseCaseErrors::InvalidTimespan;
+                    errors.push((index, InvalidEvent::Event(e)));
+                    continue;
+                }
+            };
+            let calendar = match calendars.get(&body.calendar_id.to_string()) {
+                Some(calendar) => calendar,
+                None => {
+                    let e = create_event::UseCaseErrors::NotFound(body.calendar_id.clone());
+                    errors.push((index, InvalidEvent::Event(e)));
+                    continue;
+                }
+            };
+            if let Some(service_id) = &body.service_id {
+                if !service_exists_in_account(service_id, &self.account_id, ctx).await {
+                    let e = create_event::UseCaseErrors::ServiceNotFound(service_id.clone());
+                    errors.push((index, InvalidEvent::Event(e)));
+                    continue;
+                }
+            }
+
+            let usecase = CreateEventUseCase {
+                account_id: self.account_id.clone(),
+                calendar_id: body.calendar_id.clone(),
+                user_id: self.user_id.clone(),
+                start_ts: body.start_ts,
+                duration,
+                busy: body.busy.unwrap_or(false),
+                status: body.status,
+                recurrence: body.recurrence.clone(),
+                reminder: body.reminder.clone(),
+                is_service: body.is_service.unwrap_or(false),
+                service_id: body.service_id.clone(),
+                metadata: body.metadata.clone().unwrap_or_default(),
+                external_id: body.external_id.clone(),
+            };
+            match usecase.build_event(calendar, now) {
+                Ok(event) => events.push(event),
+                Err(e) => errors.push((index, InvalidEvent::Event(e))),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(UseCaseErrors::InvalidEvents(errors));
+        }
+
+        match ctx.repos.event_repo.insert_many(&events).await {
+            Ok(_) => Ok(events),
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(CreateRemindersOnEventCreated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+impl PermissionBoundary for CreateEventsBatchUseCase {
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::CreateCalendarEvent]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::{Calendar, CalendarEventReminder, User};
+    use nettu_scheduler_infra::setup_context;
+
+    struct TestContext {
+        ctx: NettuContext,
+        calendar: Calendar,
+        user: User,
+    }
+
+    async fn setup() -> TestContext {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        TestContext {
+            ctx,
+            calendar,
+            user,
+        }
+    }
+
+    fn event_body(calendar: &Calendar, start_ts: i64) -> EventBody {
+        EventBody {
+            calendar_id: calendar.id.clone(),
+            start_ts,
+            duration: Some(1000),
+            end_ts: None,
+            busy: Some(true),
+            status: None,
+            recurrence: None,
+            reminder: None,
+            is_service: None,
+            service_id: None,
+            metadata: None,
+            external_id: None,
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn creates_all_events_of_valid_batch() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+
+        let mut usecase = CreateEventsBatchUseCase {
+            account_id: user.account_id.clone(),
+            user_id: user.id.clone(),
+            events: vec![
+                event_body(&calendar, 0),
+                EventBody {
+                    duration: None,
+                    end_ts: Some(6000),
+                    ..event_body(&calendar, 5000)
+                },
+            ],
+        };
+        let events = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].duration, 1000);
+
+        let stored = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&calendar.id, None)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_whole_batch_with_invalid_events() {
+        let TestContext {
+            ctx,
+            calendar,
+            user,
+        } = setup().await;
+        let other_calendar = Calendar::new(&ID::default(), &user.account_id);
+        ctx.repos
+            .calendar_repo
+            .insert(&other_calendar)
+            .await
+            .unwrap();
+
+        let mut usecase = CreateEventsBatchUseCase {
+            account_id: user.account_id.clone(),
+            user_id: user.id.clone(),
+            events: vec![
+                event_body(&calendar, 0),
+                EventBody {
+                    reminder: Some(CalendarEventReminder {
+                        minutes_before: -10,
+                    }),
+                    ..event_body(&calendar, 1000)
+                },
+                EventBody {
+                    duration: None,
+                    ..event_body(&calendar, 2000)
+                },
+                event_body(&other_calendar, 3000),
+            ],
+        };
+        let res = usecase.execute(&ctx).await;
+        assert_eq!(
+            res.unwrap_err(),
+            UseCaseErrors::InvalidEvents(vec![
+                (
+                    1,
+                    InvalidEvent::Event(create_event::UseCaseErrors::InvalidReminder)
+                ),
+                (2, InvalidEvent::MissingDuration),
+                (
+                    3,
+                    InvalidEvent::Event(create_event::UseCaseErrors::NotFound(
+                        other_calendar.id.clone()
+                    ))
+                ),
+            ])
+        );
+
+        // Not even the valid event was created
+        let stored = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&calendar.id, None)
+            .await
+            .unwrap();
+        assert!(stored.is_empty());
+    }
+}
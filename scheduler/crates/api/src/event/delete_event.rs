@@ -11,7 +11,7 @@ use nettu_scheduler_api_structs::delete_event::*;
 use nettu_scheduler_domain::{CalendarEvent, ID};
 use nettu_scheduler_infra::NettuContext;
 
-use super::subscribers::DeleteRemindersOnEventDeleted;
+use super::subscribers::{DeleteRemindersOnEventDeleted, InvalidateBookingSlotsOnEventModified};
 
 fn handle_error(e: UseCaseErrors) -> NettuError {
     match e {
@@ -19,6 +19,7 @@ fn handle_error(e: UseCaseErrors) -> NettuError {
             "The calendar event with id: {}, was not found.",
             event_id
         )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
     }
 }
 
@@ -71,6 +72,7 @@ pub struct DeleteEventUseCase {
 #[derive(Debug)]
 pub enum UseCaseErrors {
     NotFound(ID),
+    StorageError,
 }
 
 #[async_trait::async_trait(?Send)]
@@ -86,16 +88,21 @@ impl UseCase for DeleteEventUseCase {
         let e = ctx.repos.event_repo.find(&self.event_id).await;
         match e {
             Some(event) if event.user_id == self.user_id => {
-                ctx.repos.event_repo.delete(&event.id).await;
-
-                Ok(event)
+                match ctx.repos.event_repo.delete(&event.id).await {
+                    Ok(Some(_)) => Ok(event),
+                    Ok(None) => Err(UseCaseErrors::NotFound(self.event_id.clone())),
+                    Err(_) => Err(UseCaseErrors::StorageError),
+                }
             }
             _ => Err(UseCaseErrors::NotFound(self.event_id.clone())),
         }
     }
 
     fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
-        vec![Box::new(DeleteRemindersOnEventDeleted)]
+        vec![
+            Box::new(DeleteRemindersOnEventDeleted),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
     }
 }
 
@@ -0,0 +1,152 @@
+use super::get_upcoming_reminders::remove_old_reminders;
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_account_route,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_account_upcoming_reminders::*;
+use nettu_scheduler_domain::{Reminder, ID};
+use nettu_scheduler_infra::NettuContext;
+
+/// Upper bound for how far ahead the upcoming `Reminder`s can be previewed
+const MAX_WITHIN_MS: i64 = 1000 * 60 * 60 * 24;
+
+pub async fn get_account_upcoming_reminders_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+
+    let usecase = GetAccountUpcomingRemindersUseCase {
+        account_id: account.id,
+        within_ms: query_params.within_ms,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|reminders| HttpResponse::Ok().json(APIResponse::new(reminders)))
+        .map_err(|e| match e {
+            UseCaseErrors::InvalidWithin => NettuError::BadClientData(format!(
+                "The provided within_ms has to be between 1 and {}",
+                MAX_WITHIN_MS
+            )),
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+/// Lists the `Reminder`s of an `Account` that are going to be sent within the
+/// given interval, without consuming them like `GetUpcomingRemindersUseCase` does
+#[derive(Debug)]
+struct GetAccountUpcomingRemindersUseCase {
+    account_id: ID,
+    within_ms: i64,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    InvalidWithin,
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetAccountUpcomingRemindersUseCase {
+    type Response = Vec<Reminder>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetAccountUpcomingReminders";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        if self.within_ms <= 0 || self.within_ms > MAX_WITHIN_MS {
+            return Err(UseCaseErrors::InvalidWithin);
+        }
+
+        let before = ctx.sys.get_timestamp_millis() + self.within_ms;
+        let mut reminders = match ctx
+            .repos
+            .reminder_repo
+            .find_by_account_before(&self.account_id, before)
+            .await
+        {
+            Ok(reminders) => reminders,
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        };
+        // Same filtering as when the reminders are sent, so that reminders which
+        // have been replaced by a newer sync are not listed
+        remove_old_reminders(&mut reminders, ctx).await;
+        reminders.sort_by_key(|r| r.remind_at);
+
+        Ok(reminders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::create_event::CreateEventUseCase;
+    use nettu_scheduler_domain::{Account, Calendar, CalendarEventReminder};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[serial_test::serial]
+    #[test]
+    async fn lists_reminders_due_within_interval() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user_id = ID::default();
+        let calendar = Calendar::new(&user_id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let minute = 1000 * 60;
+        let start_ts = ctx.sys.get_timestamp_millis() + 20 * minute;
+        let usecase = CreateEventUseCase {
+            account_id: account.id.clone(),
+            calendar_id: calendar.id.clone(),
+            user_id,
+            start_ts,
+            duration: 30 * minute,
+            busy: false,
+            status: None,
+            recurrence: None,
+            reminder: Some(CalendarEventReminder { minutes_before: 10 }),
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+        let event = execute(usecase, &ctx).await.unwrap();
+
+        let mut usecase = GetAccountUpcomingRemindersUseCase {
+            account_id: account.id.clone(),
+            within_ms: 30 * minute,
+        };
+        let reminders = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].event_id, event.id);
+        assert_eq!(reminders[0].remind_at, start_ts - 10 * minute);
+
+        // The reminders are not consumed by listing them
+        let reminders = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(reminders.len(), 1);
+
+        let mut usecase = GetAccountUpcomingRemindersUseCase {
+            account_id: account.id.clone(),
+            within_ms: 5 * minute,
+        };
+        assert!(usecase.execute(&ctx).await.unwrap().is_empty());
+
+        let mut usecase = GetAccountUpcomingRemindersUseCase {
+            account_id: account.id,
+            within_ms: MAX_WITHIN_MS + 1,
+        };
+        assert!(matches!(
+            usecase.execute(&ctx).await,
+            Err(UseCaseErrors::InvalidWithin)
+        ));
+    }
+}
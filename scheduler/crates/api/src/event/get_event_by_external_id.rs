@@ -0,0 +1,161 @@
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::{protect_account_route, protect_route},
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_event_by_external_id::*;
+use nettu_scheduler_domain::{CalendarEvent, ID};
+use nettu_scheduler_infra::NettuContext;
+
+fn handle_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::NotFound(external_id) => NettuError::NotFound(format!(
+            "The calendar event with external id: {}, was not found.",
+            external_id
+        )),
+    }
+}
+
+pub async fn get_event_by_external_id_admin_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+
+    let usecase = GetEventByExternalIdUseCase {
+        account_id: account.id,
+        user_id: None,
+        external_id: path_params.0.external_id,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(handle_error)
+}
+
+pub async fn get_event_by_external_id_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, _policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = GetEventByExternalIdUseCase {
+        account_id: user.account_id,
+        user_id: Some(user.id),
+        external_id: path_params.0.external_id,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(handle_error)
+}
+
+/// Finds the `CalendarEvent` in the `Account` that was created with the given external id
+#[derive(Debug)]
+struct GetEventByExternalIdUseCase {
+    pub account_id: ID,
+    /// When set, only a `CalendarEvent` of this `User` is found
+    pub user_id: Option<ID>,
+    pub external_id: String,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    NotFound(String),
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetEventByExternalIdUseCase {
+    type Response = CalendarEvent;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetEventByExternalId";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let e = ctx
+            .repos
+            .event_repo
+            .find_by_external_id(&self.account_id, &self.external_id)
+            .await;
+        match e {
+            Some(event)
+                if self.user_id.is_none() || self.user_id.as_ref() == Some(&event.user_id) =>
+            {
+                Ok(event)
+            }
+            _ => Err(UseCaseErrors::NotFound(self.external_id.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_domain::{Account, Calendar, User};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn creates_event_with_external_id_and_fetches_it_back() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let calendar = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/user/{}/events", user.id))
+            .header("x-api-key", account.secret_api_key.clone())
+            .set_json(&serde_json::json!({
+                "calendarId": calendar.id,
+                "startTs": 500,
+                "duration": 800,
+                "externalId": "crm-1234",
+            }))
+            .to_request();
+        let created: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(created.event.external_id, Some("crm-1234".into()));
+
+        let req = test::TestRequest::get()
+            .uri("/user/events/external/crm-1234")
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(res.event.id, created.event.id);
+        assert_eq!(res.event.external_id, Some("crm-1234".into()));
+
+        // External ids are scoped to the account
+        let other_account = Account::default();
+        ctx.repos.account_repo.insert(&other_account).await.unwrap();
+        let req = test::TestRequest::get()
+            .uri("/user/events/external/crm-1234")
+            .header("x-api-key", other_account.secret_api_key.clone())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/user/events/external/crm-9999")
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
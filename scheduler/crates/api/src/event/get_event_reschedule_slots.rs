@@ -0,0 +1,332 @@
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::{protect_account_route, protect_route},
+        locale::{translate, translate_booking_query_errors, Locale, Message},
+        usecase::{execute, UseCase},
+    },
+    user::get_user_freebusy::GetFreeBusyUseCase,
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_event_reschedule_slots::*;
+use nettu_scheduler_domain::{
+    booking_slots::{
+        get_booking_slots, validate_bookingslots_query, BookingQueryError, BookingSlot,
+        BookingSlotsOptions, BookingSlotsQuery, BookingTimezone, SlotStart,
+    },
+    CompatibleInstances, Millis, ID,
+};
+use nettu_scheduler_infra::NettuContext;
+
+fn handle_error(e: UseCaseErrors, locale: Locale) -> NettuError {
+    let error = match e {
+        UseCaseErrors::NotFound(event_id) => NettuError::NotFound(translate(
+            Message::EventNotFound(&event_id.to_string()),
+            locale,
+        )),
+        UseCaseErrors::InvalidQuery(errors) => {
+            NettuError::BadClientData(translate_booking_query_errors(&errors, locale))
+        }
+        UseCaseErrors::InvalidTimespan => {
+            NettuError::BadClientData(translate(Message::InvalidTimespan, locale))
+        }
+    };
+    error.localize(locale)
+}
+
+pub async fn get_event_reschedule_slots_admin_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let locale = Locale::from_request(&http_req);
+
+    let query_params = query_params.0;
+    let usecase = GetEventRescheduleSlotsUseCase {
+        account_id: account.id,
+        user_id: None,
+        event_id: path_params.0.event_id,
+        date: query_params.date,
+        iana_tz: query_params.iana_tz,
+        utc_offset_minutes: query_params.utc_offset_minutes,
+        duration: query_params.duration,
+        interval: query_params.interval.unwrap_or(query_params.duration),
+        slot_start: query_params.slot_start.unwrap_or(SlotStart::Interval),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|res| HttpResponse::Ok().json(APIResponse::new(res.booking_slots, &res.timezone)))
+        .map_err(|e| handle_error(e, locale))
+}
+
+pub async fn get_event_reschedule_slots_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, _policy) = protect_route(&http_req, &ctx).await?;
+    let locale = Locale::from_request(&http_req);
+
+    let query_params = query_params.0;
+    let usecase = GetEventRescheduleSlotsUseCase {
+        account_id: user.account_id,
+        user_id: Some(user.id),
+        event_id: path_params.0.event_id,
+        date: query_params.date,
+        iana_tz: query_params.iana_tz,
+        utc_offset_minutes: query_params.utc_offset_minutes,
+        duration: query_params.duration,
+        interval: query_params.interval.unwrap_or(query_params.duration),
+        slot_start: query_params.slot_start.unwrap_or(SlotStart::Interval),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|res| HttpResponse::Ok().json(APIResponse::new(res.booking_slots, &res.timezone)))
+        .map_err(|e| handle_error(e, locale))
+}
+
+/// Finds the times on the given date that a `CalendarEvent` can be moved to.
+/// The availability of the `User` owning the event is computed without the event
+/// itself, so that it does not block its own move.
+#[derive(Debug)]
+struct GetEventRescheduleSlotsUseCase {
+    pub account_id: ID,
+    /// When set, only a `CalendarEvent` of this `User` is found
+    pub user_id: Option<ID>,
+    pub event_id: ID,
+    pub date: String,
+    pub iana_tz: Option<String>,
+    pub utc_offset_minutes: Option<i32>,
+    pub duration: i64,
+    pub interval: i64,
+    pub slot_start: SlotStart,
+}
+
+#[derive(Debug)]
+struct UseCaseRes {
+    booking_slots: Vec<BookingSlot>,
+    timezone: BookingTimezone,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    NotFound(ID),
+    /// Every invalid field of the query
+    InvalidQuery(Vec<BookingQueryError>),
+    InvalidTimespan,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetEventRescheduleSlotsUseCase {
+    type Response = UseCaseRes;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetEventRescheduleSlots";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let event = match ctx
+            .repos
+            .event_repo
+            .find_for_account(&self.event_id, &self.account_id)
+            .await
+        {
+            Some(event)
+                if self.user_id.is_none() || self.user_id.as_ref() == Some(&event.user_id) =>
+            {
+                event
+            }
+            _ => return Err(UseCaseErrors::NotFound(self.event_id.clone())),
+        };
+
+        let query = BookingSlotsQuery {
+            date: self.date.clone(),
+            iana_tz: self.iana_tz.clone(),
+            utc_offset_minutes: self.utc_offset_minutes,
+            interval: self.interval,
+            duration: self.duration,
+        };
+        let booking_timespan = match validate_bookingslots_query(&query) {
+            Ok(t) => t,
+            Err(errors) => return Err(UseCaseErrors::InvalidQuery(errors)),
+        };
+
+        let calendar_ids = ctx
+            .repos
+            .calendar_repo
+            .find_by_user(&event.user_id)
+            .await
+            .into_iter()
+            .map(|calendar| calendar.id)
+            .collect();
+        let mut freebusy = GetFreeBusyUseCase {
+            user_id: event.user_id.clone(),
+            calendar_ids: Some(calendar_ids),
+            start_ts: booking_timespan.start_ts,
+            end_ts: booking_timespan.end_ts,
+            include_free: true,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![event.id.clone()],
+            include_private_calendars: true,
+        };
+        let free = match freebusy.execute(ctx).await {
+            Ok(res) => res.free.unwrap_or_else(|| CompatibleInstances::new(vec![])),
+            Err(_) => return Err(UseCaseErrors::InvalidTimespan),
+        };
+
+        let booking_slots = get_booking_slots(
+            &free,
+            &BookingSlotsOptions {
+                start_ts: booking_timespan.start_ts,
+                end_ts: booking_timespan.end_ts,
+                duration: Millis(self.duration),
+                interval: Millis(self.interval),
+                slot_start: self.slot_start,
+                max_slots: ctx.config.max_booking_slots,
+            },
+        );
+
+        Ok(UseCaseRes {
+            booking_slots,
+            timezone: booking_timespan.tz,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_domain::Account;
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn fully_booked_day_is_bookable_at_the_rescheduled_event() {
+        // 2021-01-04 in UTC is fully booked
+        let day_start = 1609718400000;
+        let one_hour = 1000 * 60 * 60;
+        let seeded = NettuContext::seeded()
+            .with_calendar_events(&[
+                (day_start, day_start + 10 * one_hour),
+                (day_start + 10 * one_hour, day_start + 11 * one_hour),
+                (day_start + 11 * one_hour, day_start + 24 * one_hour),
+            ])
+            .seed(setup_context().await)
+            .await;
+        let ctx = seeded.ctx;
+        let account = seeded.account.unwrap();
+        let user = seeded.user.unwrap();
+        let calendar = seeded.calendar.unwrap();
+        let event = seeded.events[1].clone();
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+
+        let query = format!("date=2021-1-4&duration={}&interval={}", one_hour, one_hour);
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/events/{}/reschedule-slots?{}",
+                event.id, query
+            ))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        let starts = res
+            .booking_slots
+            .iter()
+            .map(|slot| slot.start)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![day_start + 10 * one_hour]);
+
+        // Without leaving out the event the day has no room at all
+        let mut freebusy = GetFreeBusyUseCase {
+            user_id: user.id.clone(),
+            calendar_ids: Some(vec![calendar.id.clone()]),
+            start_ts: day_start,
+            end_ts: day_start + 24 * one_hour,
+            include_free: true,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: true,
+        };
+        let free = freebusy.execute(&ctx).await.unwrap().free.unwrap();
+        assert!(free.is_empty());
+
+        // Events of other accounts are not found
+        let other_account = Account::default();
+        ctx.repos.account_repo.insert(&other_account).await.unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/events/{}/reschedule-slots?{}",
+                event.id, query
+            ))
+            .header("x-api-key", other_account.secret_api_key.clone())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn slot_start_query_aligns_slots_to_the_start_of_availability() {
+        // 2021-01-04 in UTC is free from 10:20 to 22:00
+        let day_start = 1609718400000;
+        let one_hour = 1000 * 60 * 60;
+        let free_start = day_start + 10 * one_hour + 1000 * 60 * 20;
+        let seeded = NettuContext::seeded()
+            .with_calendar_events(&[
+                (day_start, free_start),
+                (day_start + 12 * one_hour, day_start + 13 * one_hour),
+                (day_start + 22 * one_hour, day_start + 24 * one_hour),
+            ])
+            .seed(setup_context().await)
+            .await;
+        let ctx = seeded.ctx;
+        let account = seeded.account.unwrap();
+        let event = seeded.events[1].clone();
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+
+        let request = |slot_start: &'static str| {
+            let mut query = format!("date=2021-1-4&duration={}", one_hour);
+            if !slot_start.is_empty() {
+                query = format!("{}&slotStart={}", query, slot_start);
+            }
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/user/events/{}/reschedule-slots?{}",
+                    event.id, query
+                ))
+                .header("x-api-key", account.secret_api_key.clone())
+                .to_request()
+        };
+
+        // By default the slots are stepped from the start of the date
+        for slot_start in &["", "interval"] {
+            let res: APIResponse = test::read_response_json(&mut app, request(*slot_start)).await;
+            assert_eq!(res.booking_slots[0].start, day_start + 11 * one_hour);
+            assert_eq!(res.booking_slots.len(), 11);
+        }
+
+        let res: APIResponse =
+            test::read_response_json(&mut app, request("availabilityStart")).await;
+        assert_eq!(res.booking_slots[0].start, free_start);
+        assert_eq!(res.booking_slots.len(), 11);
+
+        let res = test::call_service(&mut app, request("somewhere")).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_account_route,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_sent_reminders::*;
+use nettu_scheduler_domain::{Reminder, ID};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn get_sent_reminders_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+
+    let usecase = GetSentRemindersUseCase {
+        account_id: account.id,
+        since_ts: query_params.since_ts,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|reminders| HttpResponse::Ok().json(APIResponse::new(reminders)))
+        .map_err(|e| match e {
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+/// Lists the `Reminder`s that were sent, or failed to be sent, to an `Account` after the
+/// given timestamp, so that a webhook receiver which missed some deliveries can backfill them
+#[derive(Debug)]
+struct GetSentRemindersUseCase {
+    account_id: ID,
+    since_ts: i64,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetSentRemindersUseCase {
+    type Response = Vec<Reminder>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetSentReminders";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let mut reminders = match ctx
+            .repos
+            .reminder_repo
+            .find_dispatched_by_account_after(&self.account_id, self.since_ts)
+            .await
+        {
+            Ok(reminders) => reminders,
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        };
+        reminders.sort_by_key(|r| (r.dispatched_at(), r.remind_at));
+
+        Ok(reminders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nettu_scheduler_infra::setup_context;
+
+    fn reminder(account_id: &ID, remind_at: i64, sent_at: Option<i64>) -> Reminder {
+        Reminder {
+            id: Default::default(),
+            event_id: Default::default(),
+            account_id: account_id.clone(),
+            remind_at,
+            priority: 0,
+            sent_at,
+            failed_at: None,
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn lists_reminders_sent_after_timestamp_for_backfill() {
+        let ctx = setup_context().await;
+        let account_id = ID::default();
+        let minute = 1000 * 60;
+
+        let sent_before_window = reminder(&account_id, 10 * minute, Some(10 * minute));
+        let sent_in_window_late = reminder(&account_id, 30 * minute, Some(30 * minute));
+        let sent_in_window_early = reminder(&account_id, 20 * minute, Some(20 * minute));
+        let failed_in_window = Reminder {
+            failed_at: Some(25 * minute),
+            ..reminder(&account_id, 25 * minute, None)
+        };
+        let pending = reminder(&account_id, 40 * minute, None);
+        let other_account = reminder(&ID::default(), 20 * minute, Some(20 * minute));
+        ctx.repos
+            .reminder_repo
+            .bulk_insert(&[
+                sent_before_window,
+                sent_in_window_late.clone(),
+                sent_in_window_early.clone(),
+                failed_in_window.clone(),
+                pending,
+                other_account,
+            ])
+            .await
+            .unwrap();
+
+        let mut usecase = GetSentRemindersUseCase {
+            account_id: account_id.clone(),
+            since_ts: 15 * minute,
+        };
+        let reminders = usecase.execute(&ctx).await.unwrap();
+        let ids = reminders.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                sent_in_window_early.id,
+                failed_in_window.id,
+                sent_in_window_late.id
+            ]
+        );
+
+        let mut usecase = GetSentRemindersUseCase {
+            account_id,
+            since_ts: 30 * minute,
+        };
+        assert!(usecase.execute(&ctx).await.unwrap().is_empty());
+    }
+}
@@ -1,16 +1,19 @@
 use crate::shared::usecase::UseCase;
 use actix_web::rt::time::Instant;
-use nettu_scheduler_domain::{Account, CalendarEvent, Reminder};
+use nettu_scheduler_domain::{Account, CalendarEvent, Millis, Reminder};
 use nettu_scheduler_infra::NettuContext;
 use std::time::Duration;
-use std::{cmp::Ordering, collections::HashMap};
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
 use tracing::error;
 
 /// Creates EventReminders for a calendar event
 #[derive(Debug)]
 pub struct GetUpcomingRemindersUseCase {
     /// Will fetch reminders for this interval
-    pub reminders_interval: i64,
+    pub reminders_interval: Millis,
 }
 
 #[derive(Debug)]
@@ -19,6 +22,8 @@ pub enum UseCaseErrors {}
 #[derive(Debug)]
 pub struct AccountEventReminders {
     pub events: Vec<CalendarEvent>,
+    /// The `Reminder`s that the `events` are sent for
+    pub reminders: Vec<Reminder>,
 }
 
 async fn get_accounts_from_reminders(
@@ -46,7 +51,8 @@ async fn create_reminders_for_accounts(
 ) -> Vec<(Account, AccountEventReminders)> {
     let account_lookup = get_accounts_from_reminders(&reminders, ctx).await;
 
-    let mut account_reminders: HashMap<String, (&Account, Vec<CalendarEvent>)> = HashMap::new();
+    let mut account_reminders: HashMap<String, (&Account, Vec<CalendarEvent>, Vec<Reminder>)> =
+        HashMap::new();
 
     for reminder in reminders {
         let account = match account_lookup.get(&reminder.account_id.as_string()) {
@@ -63,16 +69,22 @@ async fn create_reminders_for_accounts(
         match account_reminders.get_mut(&account.id.as_string()) {
             Some(acc_reminders) => {
                 acc_reminders.1.push(calendar_event);
+                acc_reminders.2.push(reminder);
             }
             None => {
-                account_reminders.insert(account.id.as_string(), (account, vec![calendar_event]));
+                account_reminders.insert(
+                    account.id.as_string(),
+                    (account, vec![calendar_event], vec![reminder]),
+                );
             }
         };
     }
 
     account_reminders
         .into_iter()
-        .map(|(_, (acc, events))| (acc.clone(), AccountEventReminders { events }))
+        .map(|(_, (acc, events, reminders))| {
+            (acc.clone(), AccountEventReminders { events, reminders })
+        })
         .collect()
 }
 
@@ -103,14 +115,14 @@ fn dedup_reminders(reminders: &mut Vec<Reminder>) {
 
 // Detects if there have been generated reminders with higher priority in the reminder repo and deletes
 // the old one if that is the case
-async fn remove_old_reminders(reminders: &mut Vec<Reminder>, ctx: &NettuContext) {
+pub(crate) async fn remove_old_reminders(reminders: &mut Vec<Reminder>, ctx: &NettuContext) {
     // Priority 0 reminders
     let reminders_p0 = reminders
         .iter()
         .filter(|r| r.priority == 0)
         .collect::<Vec<_>>();
 
-    let mut event_ids_to_remove = HashMap::new();
+    let mut event_ids_to_remove = HashSet::new();
 
     for i in (0..reminders_p0.len()).rev() {
         let reminder = reminders_p0[i];
@@ -121,11 +133,11 @@ async fn remove_old_reminders(reminders: &mut Vec<Reminder>, ctx: &NettuContext)
             .await
             .is_some()
         {
-            event_ids_to_remove.insert(reminder.event_id.as_string(), ());
+            event_ids_to_remove.insert(reminder.event_id.clone());
         }
     }
 
-    reminders.retain(|r| !event_ids_to_remove.contains_key(&r.event_id.as_string()));
+    reminders.retain(|r| !event_ids_to_remove.contains(&r.event_id));
 }
 
 #[async_trait::async_trait(?Send)]
@@ -136,16 +148,19 @@ impl UseCase for GetUpcomingRemindersUseCase {
 
     const NAME: &'static str = "GetUpcomingReminders";
 
-    /// This will run every minute
+    /// This will run once every reminders batch window
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
         // Find all occurences for the next interval and delete them
-        let ts = ctx.sys.get_timestamp_millis() + self.reminders_interval;
+        let ts = ctx.sys.get_timestamp_millis() + self.reminders_interval.0;
 
         // Get all reminders and filter out invalid / expired reminders
         let mut reminders = ctx.repos.reminder_repo.delete_all_before(ts).await;
         dedup_reminders(&mut reminders);
         remove_old_reminders(&mut reminders, ctx).await;
 
+        // All the reminders in the window are sent together when the first one is due
+        let send_at = reminders.iter().map(|r| r.remind_at).min().unwrap_or(ts);
+
         let event_lookup = ctx
             .repos
             .event_repo
@@ -163,7 +178,7 @@ impl UseCase for GetUpcomingRemindersUseCase {
 
         let grouped_reminders = create_reminders_for_accounts(reminders, event_lookup, ctx).await;
 
-        let millis_to_send = ts - ctx.sys.get_timestamp_millis();
+        let millis_to_send = send_at - ctx.sys.get_timestamp_millis();
         let instant = if millis_to_send > 0 {
             Instant::now() + Duration::from_millis(millis_to_send as u64)
         } else {
@@ -178,14 +193,16 @@ impl UseCase for GetUpcomingRemindersUseCase {
 mod tests {
     use crate::{
         event::{delete_event::DeleteEventUseCase, update_event::UpdateEventUseCase},
+        job_schedulers::{create_reminder_deliveries, deliver_webhooks, WebhookRetryPolicy},
         shared::usecase::execute,
     };
 
     use super::super::create_event::CreateEventUseCase;
     use super::*;
+    use actix_web::{client::Client, test, web, App, HttpResponse};
     use nettu_scheduler_domain::{Calendar, CalendarEventReminder, ID};
     use nettu_scheduler_infra::{setup_context as _setup_ctx, ISys};
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
 
     async fn setup_context() -> NettuContext {
         let ctx = _setup_ctx().await;
@@ -208,6 +225,8 @@ mod tests {
             event_id: event_id.clone(),
             priority,
             remind_at: 200,
+            sent_at: None,
+            failed_at: None,
         }
     }
 
@@ -318,10 +337,13 @@ mod tests {
             start_ts: ctx.sys.get_timestamp_millis(),
             duration: 1000 * 60 * 60 * 2,
             busy: false,
+            status: None,
             recurrence: Some(Default::default()),
             reminder: Some(CalendarEventReminder { minutes_before: 10 }),
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         execute(usecase, ctx).await.unwrap();
@@ -334,10 +356,13 @@ mod tests {
             start_ts: sys3.get_timestamp_millis() + 1000 * 60 * 5,
             duration: 1000 * 60 * 60 * 2,
             busy: false,
+            status: None,
             recurrence: None,
             reminder: Some(CalendarEventReminder { minutes_before: 10 }),
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         execute(usecase, ctx).await.unwrap();
@@ -353,7 +378,7 @@ mod tests {
         insert_events(&ctx).await;
 
         let usecase = GetUpcomingRemindersUseCase {
-            reminders_interval: 1000 * 60,
+            reminders_interval: Millis::from_minutes(1),
         };
         let res = execute(usecase, &ctx).await;
         assert!(res.is_ok());
@@ -363,7 +388,7 @@ mod tests {
 
         ctx.sys = Arc::new(StaticTimeSys2 {});
         let usecase = GetUpcomingRemindersUseCase {
-            reminders_interval: 1000 * 60,
+            reminders_interval: Millis::from_minutes(1),
         };
         let res = execute(usecase, &ctx).await;
         assert!(res.is_ok());
@@ -372,7 +397,7 @@ mod tests {
 
         ctx.sys = Arc::new(StaticTimeSys3 {});
         let usecase = GetUpcomingRemindersUseCase {
-            reminders_interval: 1000 * 60,
+            reminders_interval: Millis::from_minutes(1),
         };
         let res = execute(usecase, &ctx).await;
         assert!(res.is_ok());
@@ -381,7 +406,7 @@ mod tests {
         assert_eq!(res[0].1.events.len(), 2);
 
         let usecase = GetUpcomingRemindersUseCase {
-            reminders_interval: 1000 * 60,
+            reminders_interval: Millis::from_minutes(1),
         };
         let res = execute(usecase, &ctx).await;
         assert!(res.is_ok());
@@ -389,6 +414,86 @@ mod tests {
         assert_eq!(res.len(), 0);
     }
 
+    async fn webhook_receiver(
+        received: web::Data<Mutex<Vec<serde_json::Value>>>,
+        body: web::Json<serde_json::Value>,
+    ) -> HttpResponse {
+        received.lock().unwrap().push(body.into_inner());
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::main]
+    #[serial_test::serial]
+    #[test]
+    async fn reminders_due_within_batch_window_are_sent_in_single_webhook() {
+        let mut ctx = setup_context().await;
+        ctx.sys = Arc::new(StaticTimeSys1 {});
+        let now = ctx.sys.get_timestamp_millis();
+
+        let received = web::Data::new(Mutex::new(Vec::<serde_json::Value>::new()));
+        let received_server = received.clone();
+        let server = test::start(move || {
+            App::new()
+                .app_data(received_server.clone())
+                .route("/", web::post().to(webhook_receiver))
+        });
+
+        let (mut account, user_id, calendar) = insert_common_data(&ctx).await;
+        assert!(account.settings.set_webhook_url(Some(server.url("/"))));
+        ctx.repos.account_repo.save(&account).await.unwrap();
+
+        // Reminders are due in 1 and 4 minutes
+        for minutes_until_reminder in &[1, 4] {
+            let usecase = CreateEventUseCase {
+                account_id: account.id.clone(),
+                calendar_id: calendar.id.clone(),
+                user_id: user_id.clone(),
+                start_ts: now + 1000 * 60 * (10 + minutes_until_reminder),
+                duration: 1000 * 60 * 30,
+                busy: false,
+                status: None,
+                recurrence: None,
+                reminder: Some(CalendarEventReminder { minutes_before: 10 }),
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+            };
+            execute(usecase, &ctx).await.unwrap();
+        }
+
+        let usecase = GetUpcomingRemindersUseCase {
+            reminders_interval: Millis::from_minutes(5),
+        };
+        let (account_reminders, _) = execute(usecase, &ctx).await.unwrap();
+        assert_eq!(account_reminders.len(), 1);
+        assert_eq!(account_reminders[0].1.events.len(), 2);
+
+        let deliveries = create_reminder_deliveries(account_reminders, now);
+        let no_retries = WebhookRetryPolicy {
+            max_retries: 0,
+            backoff: Duration::from_millis(0),
+        };
+        let undelivered = deliver_webhooks(
+            &Client::new(),
+            deliveries,
+            Duration::from_secs(1),
+            &no_retries,
+        )
+        .await;
+        assert!(undelivered.is_empty());
+
+        // Both reminders are delivered in exactly one webhook call
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0]["data"]["events"]
+                .as_array()
+                .map(|events| events.len()),
+            Some(2)
+        );
+    }
+
     #[actix_web::main]
     #[serial_test::serial]
     #[test]
@@ -407,10 +512,13 @@ mod tests {
             start_ts: now,
             duration: 1000 * 60 * 60 * 2,
             busy: false,
+            status: None,
             recurrence: Some(Default::default()),
             reminder: Some(CalendarEventReminder { minutes_before }),
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let calendar_event = execute(usecase, &ctx).await.unwrap();
@@ -426,10 +534,16 @@ mod tests {
         let update_event_usecase = UpdateEventUseCase {
             event_id: calendar_event.id,
             busy: None,
+            status: None,
             duration: None,
+            end_ts: None,
             exdates: None,
             metadata: None,
-            reminder: Some(CalendarEventReminder { minutes_before }),
+            metadata_patch: None,
+            edit_mode: Default::default(),
+            instance_start_ts: None,
+            expected_version: None,
+            reminder: Some(Some(CalendarEventReminder { minutes_before })),
             recurrence: Some(Default::default()),
             is_service: None,
             start_ts: Some(new_start),
@@ -467,10 +581,13 @@ mod tests {
             start_ts: now,
             duration: 1000 * 60 * 60 * 2,
             busy: false,
+            status: None,
             recurrence: Some(Default::default()),
             reminder: Some(CalendarEventReminder { minutes_before: 10 }),
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let calendar_event = execute(usecase, &ctx).await.unwrap();
@@ -484,10 +601,16 @@ mod tests {
         let update_event_usecase = UpdateEventUseCase {
             event_id: calendar_event.id,
             busy: None,
+            status: None,
             duration: None,
+            end_ts: None,
             exdates: None,
             metadata: None,
-            reminder: None,
+            metadata_patch: None,
+            edit_mode: Default::default(),
+            instance_start_ts: None,
+            expected_version: None,
+            reminder: Some(None),
             recurrence: Some(Default::default()),
             is_service: None,
             start_ts: None,
@@ -515,10 +638,13 @@ mod tests {
             start_ts: now,
             duration: 1000 * 60 * 60 * 2,
             busy: false,
+            status: None,
             recurrence: Some(Default::default()),
             reminder: Some(CalendarEventReminder { minutes_before: 10 }),
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
         };
 
         let calendar_event = execute(usecase, &ctx).await.unwrap();
@@ -1,20 +1,44 @@
-mod create_event;
+mod add_event_exdate;
+pub mod create_event;
+mod create_events_batch;
 mod delete_event;
+mod get_account_upcoming_reminders;
 mod get_event;
+mod get_event_by_external_id;
 mod get_event_instances;
+mod get_event_reschedule_slots;
 mod get_events_by_meta;
+mod get_sent_reminders;
 pub mod get_upcoming_reminders;
+mod rebuild_reminders;
+mod remove_event_exdate;
+mod replace_event;
 mod subscribers;
 pub mod sync_event_reminders;
 mod update_event;
+mod validate_recurrence;
 
 use actix_web::web;
+use add_event_exdate::{add_event_exdate_admin_controller, add_event_exdate_controller};
 use create_event::{create_event_admin_controller, create_event_controller};
+use create_events_batch::{create_events_batch_admin_controller, create_events_batch_controller};
 use delete_event::{delete_event_admin_controller, delete_event_controller};
+use get_account_upcoming_reminders::get_account_upcoming_reminders_controller;
 use get_event::{get_event_admin_controller, get_event_controller};
+use get_event_by_external_id::{
+    get_event_by_external_id_admin_controller, get_event_by_external_id_controller,
+};
 use get_event_instances::{get_event_instances_admin_controller, get_event_instances_controller};
+use get_event_reschedule_slots::{
+    get_event_reschedule_slots_admin_controller, get_event_reschedule_slots_controller,
+};
 use get_events_by_meta::get_events_by_meta_controller;
+use get_sent_reminders::get_sent_reminders_controller;
+use rebuild_reminders::rebuild_reminders_controller;
+use remove_event_exdate::{remove_event_exdate_admin_controller, remove_event_exdate_controller};
+use replace_event::{replace_event_admin_controller, replace_event_controller};
 use update_event::{update_event_admin_controller, update_event_controller};
+use validate_recurrence::validate_recurrence_controller;
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/events", web::post().to(create_event_controller));
@@ -23,8 +47,44 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         web::post().to(create_event_admin_controller),
     );
 
+    cfg.route(
+        "/events/batch",
+        web::post().to(create_events_batch_controller),
+    );
+    cfg.route(
+        "/user/{user_id}/events/batch",
+        web::post().to(create_events_batch_admin_controller),
+    );
+
     cfg.route("/events/meta", web::get().to(get_events_by_meta_controller));
 
+    cfg.route(
+        "/recurrence/validate",
+        web::post().to(validate_recurrence_controller),
+    );
+
+    cfg.route(
+        "/reminders/upcoming",
+        web::get().to(get_account_upcoming_reminders_controller),
+    );
+    cfg.route(
+        "/reminders/sent",
+        web::get().to(get_sent_reminders_controller),
+    );
+    cfg.route(
+        "/admin/reminders/rebuild",
+        web::post().to(rebuild_reminders_controller),
+    );
+
+    cfg.route(
+        "/events/external/{external_id}",
+        web::get().to(get_event_by_external_id_controller),
+    );
+    cfg.route(
+        "/user/events/external/{external_id}",
+        web::get().to(get_event_by_external_id_admin_controller),
+    );
+
     cfg.route("/events/{event_id}", web::get().to(get_event_controller));
     cfg.route(
         "/user/events/{event_id}",
@@ -45,6 +105,23 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/user/events/{event_id}",
         web::put().to(update_event_admin_controller),
     );
+    cfg.route(
+        "/events/{event_id}",
+        web::patch().to(update_event_controller),
+    );
+    cfg.route(
+        "/user/events/{event_id}",
+        web::patch().to(update_event_admin_controller),
+    );
+
+    cfg.route(
+        "/events/{event_id}/replace",
+        web::put().to(replace_event_controller),
+    );
+    cfg.route(
+        "/user/events/{event_id}/replace",
+        web::put().to(replace_event_admin_controller),
+    );
 
     cfg.route(
         "/events/{event_id}/instances",
@@ -54,4 +131,31 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/user/events/{event_id}/instances",
         web::get().to(get_event_instances_admin_controller),
     );
+
+    cfg.route(
+        "/events/{event_id}/reschedule-slots",
+        web::get().to(get_event_reschedule_slots_controller),
+    );
+    cfg.route(
+        "/user/events/{event_id}/reschedule-slots",
+        web::get().to(get_event_reschedule_slots_admin_controller),
+    );
+
+    cfg.route(
+        "/events/{event_id}/exdates",
+        web::post().to(add_event_exdate_controller),
+    );
+    cfg.route(
+        "/user/events/{event_id}/exdates",
+        web::post().to(add_event_exdate_admin_controller),
+    );
+
+    cfg.route(
+        "/events/{event_id}/exdates/{ts}",
+        web::delete().to(remove_event_exdate_controller),
+    );
+    cfg.route(
+        "/user/events/{event_id}/exdates/{ts}",
+        web::delete().to(remove_event_exdate_admin_controller),
+    );
 }
@@ -0,0 +1,354 @@
+use super::sync_event_reminders::expand_event_reminders;
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_admin_route,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::rebuild_reminders::*;
+use nettu_scheduler_domain::ID;
+use nettu_scheduler_infra::NettuContext;
+use tracing::error;
+
+pub async fn rebuild_reminders_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    protect_admin_route(&http_req, &ctx)?;
+
+    let usecase = RebuildRemindersUseCase {
+        account_id: query_params.0.account_id,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|res| {
+            HttpResponse::Ok().json(APIResponse {
+                events: res.events,
+                reminders: res.reminders,
+            })
+        })
+        .map_err(|e| match e {
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+/// Throws away the pending `Reminder`s and expands them again from the `CalendarEvent`s.
+/// Unlike the periodic expansion job this is run on demand by an operator, to repair
+/// the stored reminders after they have become inconsistent. Only `Reminder`s that are
+/// still due and have not been sent already are created again.
+#[derive(Debug)]
+struct RebuildRemindersUseCase {
+    /// Only rebuilds the reminders of this `Account` when set, otherwise those of every `Account`
+    account_id: Option<ID>,
+}
+
+/// Number of `CalendarEvent`s loaded at a time, so that rebuilding the reminders
+/// of every `Account` does not load all the events into memory at once
+const EVENTS_PAGE_SIZE: usize = 500;
+
+#[derive(Debug)]
+struct UseCaseRes {
+    events: usize,
+    reminders: usize,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for RebuildRemindersUseCase {
+    type Response = UseCaseRes;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "RebuildReminders";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        // Sent reminders are kept, as they are the history of what has been delivered
+        ctx.repos
+            .reminder_repo
+            .delete_pending(self.account_id.as_ref())
+            .await
+            .map_err(|_| UseCaseErrors::StorageError)?;
+
+        let now = ctx.sys.get_timestamp_millis();
+        let mut events_count = 0;
+        let mut reminders_count = 0;
+        loop {
+            let events = ctx
+                .repos
+                .event_repo
+                .find_with_reminders(self.account_id.as_ref(), events_count, EVENTS_PAGE_SIZE)
+                .await
+                .map_err(|_| UseCaseErrors::StorageError)?;
+            events_count += events.len();
+
+            let event_ids = events.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+            let sent_reminders = ctx
+                .repos
+                .reminder_repo
+                .find_sent_by_events(&event_ids)
+                .await
+                .map_err(|_| UseCaseErrors::StorageError)?;
+
+            for event in &events {
+                ctx.repos
+                    .event_reminders_expansion_jobs_repo
+                    .delete_by_event(&event.id)
+                    .await
+                    .map_err(|_| UseCaseErrors::StorageError)?;
+
+                let calendar = match ctx.repos.calendar_repo.find(&event.calendar_id).await {
+                    Some(calendar) => calendar,
+                    None => continue,
+                };
+                let reminders = expand_event_reminders(event, &calendar, 1, ctx)
+                    .await
+                    .into_iter()
+                    .filter(|reminder| reminder.remind_at > now)
+                    .filter(|reminder| {
+                        !sent_reminders.iter().any(|sent| {
+                            sent.event_id == reminder.event_id
+                                && sent.remind_at == reminder.remind_at
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                match ctx.repos.reminder_repo.bulk_insert(&reminders).await {
+                    Ok(_) => reminders_count += reminders.len(),
+                    Err(err) => error!(
+                        "Unable to rebuild reminders for event {}, Error: {:?}",
+                        event.id, err
+                    ),
+                }
+            }
+
+            if events.len() < EVENTS_PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(UseCaseRes {
+            events: events_count,
+            reminders: reminders_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_domain::{
+        Calendar, CalendarEvent, CalendarEventReminder, EventStatus, RRuleOptions, Reminder,
+    };
+    use nettu_scheduler_infra::setup_context;
+
+    fn event(calendar: &Calendar, start_ts: i64, minutes_before: Option<i64>) -> CalendarEvent {
+        let mut event = CalendarEvent::new(calendar, start_ts, 1000 * 60 * 30, EventStatus::Busy);
+        event.reminder =
+            minutes_before.map(|minutes_before| CalendarEventReminder { minutes_before });
+        event
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rebuilds_pending_reminders_of_seeded_events() {
+        let seeded = NettuContext::seeded()
+            .with_calendar()
+            .seed(setup_context().await)
+            .await;
+        let ctx = seeded.ctx;
+        let account = seeded.account.unwrap();
+        let calendar = seeded.calendar.unwrap();
+        let other_seeded = NettuContext::seeded()
+            .with_calendar()
+            .seed(ctx.clone())
+            .await;
+        let other_account = other_seeded.account.unwrap();
+        let other_calendar = other_seeded.calendar.unwrap();
+
+        let day = 1000 * 60 * 60 * 24;
+        let tomorrow = ctx.sys.get_timestamp_millis() + day;
+        let single = event(&calendar, tomorrow, Some(10));
+        let mut daily = event(&calendar, tomorrow, Some(5));
+        assert!(daily.set_recurrence(
+            RRuleOptions {
+                count: Some(3),
+                ..Default::default()
+            },
+            &calendar.settings,
+            true
+        ));
+        let without_reminder = event(&calendar, tomorrow, None);
+        let other = event(&other_calendar, tomorrow, Some(10));
+        // Inserted straight into the repo, so that no reminders are created for them
+        for e in &[&single, &daily, &without_reminder, &other] {
+            ctx.repos.event_repo.insert(e).await.unwrap();
+        }
+
+        // A stale reminder of an event that no longer exists, and one that was already sent
+        let reminder = |remind_at: i64, sent_at: Option<i64>| Reminder {
+            id: Default::default(),
+            event_id: Default::default(),
+            account_id: account.id.clone(),
+            remind_at,
+            priority: 0,
+            sent_at,
+            failed_at: None,
+        };
+        let sent = reminder(tomorrow - day, Some(tomorrow - day));
+        ctx.repos
+            .reminder_repo
+            .bulk_insert(&[reminder(tomorrow, None), sent.clone()])
+            .await
+            .unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+        let pending = |account_id: ID| {
+            let ctx = ctx.clone();
+            async move {
+                ctx.repos
+                    .reminder_repo
+                    .find_by_account_before(&account_id, tomorrow + 3 * day)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Only operators can rebuild the reminders
+        let req = test::TestRequest::post()
+            .uri("/admin/reminders/rebuild")
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri(&format!(
+                "/admin/reminders/rebuild?accountId={}",
+                account.id
+            ))
+            .header(
+                "x-admin-secret",
+                ctx.config.create_account_secret_code.clone(),
+            )
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(res.events, 2);
+        assert_eq!(res.reminders, 4);
+
+        let reminders = pending(account.id.clone()).await;
+        assert_eq!(reminders.len(), 4);
+        assert_eq!(
+            reminders.iter().filter(|r| r.event_id == single.id).count(),
+            1
+        );
+        assert_eq!(
+            reminders.iter().filter(|r| r.event_id == daily.id).count(),
+            3
+        );
+        let sent_reminders = ctx
+            .repos
+            .reminder_repo
+            .find_dispatched_by_account_after(&account.id, 0)
+            .await
+            .unwrap();
+        assert_eq!(sent_reminders.len(), 1);
+        assert_eq!(sent_reminders[0].id, sent.id);
+        // Other accounts are left alone
+        assert!(pending(other_account.id.clone()).await.is_empty());
+
+        // Without an account every account is rebuilt
+        let req = test::TestRequest::post()
+            .uri("/admin/reminders/rebuild")
+            .header(
+                "x-admin-secret",
+                ctx.config.create_account_secret_code.clone(),
+            )
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(res.events, 3);
+        assert_eq!(res.reminders, 5);
+        assert_eq!(pending(account.id.clone()).await.len(), 4);
+        assert_eq!(pending(other_account.id.clone()).await.len(), 1);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn skips_past_and_already_sent_reminders() {
+        let seeded = NettuContext::seeded()
+            .with_calendar()
+            .seed(setup_context().await)
+            .await;
+        let ctx = seeded.ctx;
+        let account = seeded.account.unwrap();
+        let calendar = seeded.calendar.unwrap();
+
+        let now = ctx.sys.get_timestamp_millis();
+        let day = 1000 * 60 * 60 * 24;
+        let minute = 1000 * 60;
+        let past = event(&calendar, now - day, Some(10));
+        // Starts in the future, but should have been reminded of already
+        let starting_soon = event(&calendar, now + 5 * minute, Some(10));
+        let sent = event(&calendar, now + day, Some(10));
+        let mut daily = event(&calendar, now + day, Some(5));
+        assert!(daily.set_recurrence(
+            RRuleOptions {
+                count: Some(3),
+                ..Default::default()
+            },
+            &calendar.settings,
+            true
+        ));
+        for e in &[&past, &starting_soon, &sent, &daily] {
+            ctx.repos.event_repo.insert(e).await.unwrap();
+        }
+
+        let sent_reminder = |event: &CalendarEvent, remind_at: i64| Reminder {
+            id: Default::default(),
+            event_id: event.id.clone(),
+            account_id: account.id.clone(),
+            remind_at,
+            priority: 1,
+            sent_at: Some(now),
+            failed_at: None,
+        };
+        ctx.repos
+            .reminder_repo
+            .bulk_insert(&[
+                sent_reminder(&sent, sent.start_ts - 10 * minute),
+                sent_reminder(&daily, daily.start_ts - 5 * minute),
+            ])
+            .await
+            .unwrap();
+
+        let mut usecase = RebuildRemindersUseCase {
+            account_id: Some(account.id.clone()),
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(res.events, 4);
+        assert_eq!(res.reminders, 2);
+
+        let pending = ctx
+            .repos
+            .reminder_repo
+            .find_by_account_before(&account.id, now + 3 * day)
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 2);
+        assert!(pending
+            .iter()
+            .all(|r| r.event_id == daily.id && r.remind_at > daily.start_ts));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::shared::{
+    auth::{account_can_modify_event, protect_account_route, protect_route, Permission},
+    usecase::{execute_with_policy, PermissionBoundary, Subscriber, UseCaseErrorContainer},
+};
+use crate::{
+    error::NettuError,
+    shared::usecase::{execute, UseCase},
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::remove_event_exdate::*;
+use nettu_scheduler_domain::{CalendarEvent, ID};
+use nettu_scheduler_infra::NettuContext;
+
+use super::subscribers::{InvalidateBookingSlotsOnEventModified, SyncRemindersOnEventUpdated};
+
+fn handle_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::NotFound(entity, id) => {
+            NettuError::NotFound(format!("The {} with id: {}, was not found.", entity, id))
+        }
+        UseCaseErrors::ExdateNotFound(ts) => NettuError::NotFound(format!(
+            "The calendar event does not have an exdate at: {}",
+            ts
+        )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
+    }
+}
+
+pub async fn remove_event_exdate_admin_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let e = account_can_modify_event(&account, &path_params.event_id, &ctx).await?;
+
+    let usecase = RemoveEventExdateUseCase {
+        user_id: e.user_id,
+        event_id: e.id,
+        ts: path_params.ts,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(handle_error)
+}
+
+pub async fn remove_event_exdate_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, policy) = protect_route(&http_req, &ctx).await?;
+
+    let usecase = RemoveEventExdateUseCase {
+        user_id: user.id.clone(),
+        event_id: path_params.event_id.clone(),
+        ts: path_params.ts,
+    };
+
+    execute_with_policy(usecase, &policy, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(|e| match e {
+            UseCaseErrorContainer::Unauthorized(e) => NettuError::Unauthorized(e),
+            UseCaseErrorContainer::UseCase(e) => handle_error(e),
+        })
+}
+
+/// Brings back a single excluded instance of a `CalendarEvent`
+#[derive(Debug)]
+pub struct RemoveEventExdateUseCase {
+    pub user_id: ID,
+    pub event_id: ID,
+    pub ts: i64,
+}
+
+#[derive(Debug)]
+pub enum UseCaseErrors {
+    NotFound(String, ID),
+    ExdateNotFound(i64),
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for RemoveEventExdateUseCase {
+    type Response = CalendarEvent;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "RemoveEventExdate";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let event = match ctx.repos.event_repo.find(&self.event_id).await {
+            Some(event) if event.user_id == self.user_id => event,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar Event".into(),
+                    self.event_id.clone(),
+                ))
+            }
+        };
+
+        if !event.exdates.contains(&self.ts) {
+            return Err(UseCaseErrors::ExdateNotFound(self.ts));
+        }
+
+        let updated = ctx.sys.get_timestamp_millis();
+        match ctx
+            .repos
+            .event_repo
+            .remove_exdate(&event.id, self.ts, updated)
+            .await
+        {
+            Ok(Some(event)) => Ok(event),
+            Ok(None) => Err(UseCaseErrors::NotFound(
+                "Calendar Event".into(),
+                self.event_id.clone(),
+            )),
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(SyncRemindersOnEventUpdated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+impl PermissionBoundary for RemoveEventExdateUseCase {
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::UpdateCalendarEvent]
+    }
+}
@@ -0,0 +1,387 @@
+use crate::{
+    error::NettuError,
+    event,
+    shared::auth::protect_route,
+    shared::{
+        auth::{account_can_modify_event, protect_account_route, Permission},
+        usecase::{
+            execute, execute_with_policy, PermissionBoundary, Subscriber, UseCase,
+            UseCaseErrorContainer,
+        },
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use event::create_event::parse_duration;
+use event::subscribers::{InvalidateBookingSlotsOnEventModified, SyncRemindersOnEventUpdated};
+use event::update_event::parse_if_match;
+use nettu_scheduler_api_structs::replace_event::*;
+use nettu_scheduler_domain::{
+    CalendarEvent, CalendarEventReminder, EventStatus, Metadata, RRuleOptions, ID,
+};
+use nettu_scheduler_infra::NettuContext;
+
+fn handle_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::NotFound(entity, event_id) => NettuError::NotFound(format!(
+            "The {} with id: {}, was not found.",
+            entity, event_id
+        )),
+        UseCaseErrors::InvalidRecurrenceRule => {
+            NettuError::BadClientData("Invalid recurrence rule specified for the event".into())
+        }
+        UseCaseErrors::InvalidReminder => {
+            NettuError::BadClientData("Invalid reminder specified for the event".into())
+        }
+        UseCaseErrors::InvalidTimespan => NettuError::BadClientData(
+            "The provided start_ts and duration is invalid for the event".into(),
+        ),
+        UseCaseErrors::VersionMismatch(version) => NettuError::PreconditionFailed(format!(
+            "The calendar event has been modified since the version given in If-Match, the current version is: {}",
+            version
+        )),
+        UseCaseErrors::StorageError => NettuError::InternalError,
+    }
+}
+
+fn replace_usecase(
+    user_id: ID,
+    event_id: ID,
+    body: RequestBody,
+    expected_version: Option<i64>,
+) -> Result<ReplaceEventUseCase, NettuError> {
+    let duration = parse_duration(body.start_ts, body.duration, body.end_ts)?;
+    Ok(ReplaceEventUseCase {
+        user_id,
+        event_id,
+        start_ts: body.start_ts,
+        duration,
+        busy: body.busy,
+        status: body.status,
+        recurrence: body.recurrence,
+        is_service: body.is_service,
+        exdates: body.exdates,
+        reminder: body.reminder,
+        metadata: body.metadata,
+        expected_version,
+    })
+}
+
+pub async fn replace_event_admin_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let e = account_can_modify_event(&account, &path_params.event_id, &ctx).await?;
+    let expected_version = parse_if_match(&http_req)?;
+
+    let usecase = replace_usecase(e.user_id, e.id, body.0, expected_version)?;
+
+    execute(usecase, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(handle_error)
+}
+
+pub async fn replace_event_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let (user, policy) = protect_route(&http_req, &ctx).await?;
+    let expected_version = parse_if_match(&http_req)?;
+
+    let usecase = replace_usecase(
+        user.id,
+        path_params.event_id.clone(),
+        body.0,
+        expected_version,
+    )?;
+
+    execute_with_policy(usecase, &policy, &ctx)
+        .await
+        .map(|event| HttpResponse::Ok().json(APIResponse::new(event)))
+        .map_err(|e| match e {
+            UseCaseErrorContainer::Unauthorized(e) => NettuError::Unauthorized(e),
+            UseCaseErrorContainer::UseCase(e) => handle_error(e),
+        })
+}
+
+/// Replaces every client controlled field of a `CalendarEvent`, unlike
+/// `UpdateEventUseCase` which only changes the fields that are given.
+/// The `Calendar` of the event, its owner and creation time are kept.
+#[derive(Debug)]
+pub struct ReplaceEventUseCase {
+    pub user_id: ID,
+    pub event_id: ID,
+    pub start_ts: i64,
+    pub duration: i64,
+    pub busy: bool,
+    /// Overrides `busy` when set
+    pub status: Option<EventStatus>,
+    pub recurrence: Option<RRuleOptions>,
+    pub is_service: bool,
+    pub exdates: Vec<i64>,
+    pub reminder: Option<CalendarEventReminder>,
+    pub metadata: Metadata,
+    /// The `version` that the stored `CalendarEvent` must still have, so that
+    /// a client does not overwrite changes it has not seen
+    pub expected_version: Option<i64>,
+}
+
+#[derive(Debug)]
+pub enum UseCaseErrors {
+    NotFound(String, ID),
+    InvalidReminder,
+    InvalidTimespan,
+    StorageError,
+    InvalidRecurrenceRule,
+    /// The `CalendarEvent` has another version than expected, which is given
+    VersionMismatch(i64),
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for ReplaceEventUseCase {
+    type Response = CalendarEvent;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "ReplaceEvent";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let current = match ctx.repos.event_repo.find(&self.event_id).await {
+            Some(event) if event.user_id == self.user_id => event,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar Event".into(),
+                    self.event_id.clone(),
+                ))
+            }
+        };
+
+        if let Some(expected_version) = self.expected_version {
+            if current.version != expected_version {
+                return Err(UseCaseErrors::VersionMismatch(current.version));
+            }
+        }
+
+        if let Some(reminder) = &self.reminder {
+            if !reminder.is_valid() {
+                return Err(UseCaseErrors::InvalidReminder);
+            }
+        }
+
+        let calendar = match ctx.repos.calendar_repo.find(&current.calendar_id).await {
+            Some(cal) => cal,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar".into(),
+                    current.calendar_id.clone(),
+                ))
+            }
+        };
+
+        let end_ts = match self.start_ts.checked_add(self.duration) {
+            Some(end_ts) => end_ts,
+            None => return Err(UseCaseErrors::InvalidTimespan),
+        };
+        let mut e = CalendarEvent {
+            id: current.id,
+            busy: self.busy,
+            status: EventStatus::from_busy(self.busy),
+            start_ts: self.start_ts,
+            duration: self.duration,
+            created: current.created,
+            updated: ctx.sys.get_timestamp_millis(),
+            version: current.version + 1,
+            recurrence: None,
+            end_ts, // default, if recurrence changes, this will be updated
+            exdates: self.exdates.clone(),
+            calendar_id: current.calendar_id,
+            user_id: current.user_id,
+            account_id: current.account_id,
+            reminder: self.reminder.clone(),
+            is_service: self.is_service,
+            service_id: current.service_id,
+            metadata: self.metadata.clone(),
+            external_id: current.external_id,
+        };
+        if let Some(status) = self.status {
+            e.set_status(status);
+        }
+        if !e.has_valid_timespan() {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
+        if let Some(rrule_opts) = self.recurrence.clone() {
+            if !e.set_recurrence(rrule_opts, &calendar.settings, true) {
+                return Err(UseCaseErrors::InvalidRecurrenceRule);
+            };
+        }
+
+        let saved = match self.expected_version {
+            Some(version) => ctx.repos.event_repo.save_if_version(&e, version).await,
+            None => ctx.repos.event_repo.save(&e).await.map(|_| true),
+        };
+        match saved {
+            Ok(true) => Ok(e),
+            // Modified by someone else since it was read
+            Ok(false) => match ctx.repos.event_repo.find(&e.id).await {
+                Some(current) => Err(UseCaseErrors::VersionMismatch(current.version)),
+                None => Err(UseCaseErrors::NotFound(
+                    "Calendar Event".into(),
+                    e.id.clone(),
+                )),
+            },
+            Err(_) => Err(UseCaseErrors::StorageError),
+        }
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(SyncRemindersOnEventUpdated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+impl PermissionBoundary for ReplaceEventUseCase {
+    fn permissions(&self) -> Vec<Permission> {
+        vec![Permission::UpdateCalendarEvent]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::event::{create_event::CreateEventUseCase, update_event::UpdateEventUseCase};
+    use nettu_scheduler_domain::{Calendar, RRuleFrequenzy, User};
+    use nettu_scheduler_infra::setup_context;
+
+    async fn setup(ctx: &NettuContext) -> CalendarEvent {
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata.insert("group".into(), "a".into());
+        let usecase = CreateEventUseCase {
+            account_id: user.account_id.clone(),
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            start_ts: 500,
+            duration: 800,
+            busy: true,
+            status: None,
+            recurrence: Some(RRuleOptions {
+                freq: RRuleFrequenzy::Daily,
+                count: Some(5),
+                ..Default::default()
+            }),
+            reminder: Some(CalendarEventReminder { minutes_before: 10 }),
+            is_service: false,
+            service_id: None,
+            metadata,
+            external_id: None,
+        };
+        execute(usecase, ctx).await.unwrap()
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn update_retains_and_replace_resets_unspecified_fields() {
+        let ctx = setup_context().await;
+        let event = setup(&ctx).await;
+
+        // An update with only a new start_ts keeps everything else
+        let mut usecase = UpdateEventUseCase {
+            user_id: event.user_id.clone(),
+            event_id: event.id.clone(),
+            start_ts: Some(1000),
+            busy: None,
+            status: None,
+            duration: None,
+            end_ts: None,
+            reminder: None,
+            recurrence: None,
+            is_service: None,
+            exdates: None,
+            metadata: None,
+            metadata_patch: None,
+            edit_mode: Default::default(),
+            instance_start_ts: None,
+            expected_version: None,
+        };
+        let patched = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(patched.start_ts, 1000);
+        assert_eq!(patched.duration, event.duration);
+        assert!(patched.busy);
+        assert!(patched.recurrence.is_some());
+        assert_eq!(patched.reminder.unwrap().minutes_before, 10);
+        assert_eq!(patched.metadata, event.metadata);
+
+        // A replace with only a start_ts and duration resets everything else
+        let body: RequestBody =
+            serde_json::from_str(r#"{ "startTs": 2000, "duration": 400 }"#).unwrap();
+        let mut usecase =
+            replace_usecase(event.user_id.clone(), event.id.clone(), body, None).unwrap();
+        let replaced = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(replaced.start_ts, 2000);
+        assert_eq!(replaced.duration, 400);
+        assert_eq!(replaced.end_ts, 2400);
+        assert!(!replaced.busy);
+        assert!(replaced.recurrence.is_none());
+        assert!(replaced.reminder.is_none());
+        assert!(replaced.metadata.is_empty());
+        // The identity of the event is kept
+        assert_eq!(replaced.id, event.id);
+        assert_eq!(replaced.calendar_id, event.calendar_id);
+        assert_eq!(replaced.created, event.created);
+        assert_eq!(replaced.version, patched.version + 1);
+
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert!(stored.recurrence.is_none());
+        assert!(stored.reminder.is_none());
+        assert!(stored.metadata.is_empty());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn replace_with_stale_version_is_rejected() {
+        let ctx = setup_context().await;
+        let event = setup(&ctx).await;
+        let body = || serde_json::from_str(r#"{ "startTs": 2000, "duration": 400 }"#).unwrap();
+
+        let mut usecase = replace_usecase(
+            event.user_id.clone(),
+            event.id.clone(),
+            body(),
+            Some(event.version + 1),
+        )
+        .unwrap();
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::VersionMismatch(v)) if v == event.version));
+
+        let mut usecase = replace_usecase(
+            event.user_id.clone(),
+            event.id.clone(),
+            body(),
+            Some(event.version),
+        )
+        .unwrap();
+        let replaced = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(replaced.version, event.version + 1);
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(stored.start_ts, 2000);
+        assert_eq!(stored.version, event.version + 1);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn replace_requires_duration_or_end_ts() {
+        let body: RequestBody = serde_json::from_str(r#"{ "startTs": 2000 }"#).unwrap();
+        let res = replace_usecase(Default::default(), Default::default(), body, None);
+        assert!(matches!(res, Err(NettuError::BadClientData(_))));
+    }
+}
@@ -1,6 +1,10 @@
 use super::{
+    add_event_exdate::AddEventExdateUseCase,
     create_event::CreateEventUseCase,
+    create_events_batch::CreateEventsBatchUseCase,
     delete_event::DeleteEventUseCase,
+    remove_event_exdate::RemoveEventExdateUseCase,
+    replace_event::ReplaceEventUseCase,
     sync_event_reminders::{EventOperation, SyncEventRemindersTrigger, SyncEventRemindersUseCase},
     update_event::UpdateEventUseCase,
 };
@@ -21,6 +25,20 @@ impl Subscriber<CreateEventUseCase> for CreateRemindersOnEventCreated {
     }
 }
 
+#[async_trait::async_trait(?Send)]
+impl Subscriber<CreateEventsBatchUseCase> for CreateRemindersOnEventCreated {
+    async fn notify(&self, events: &Vec<CalendarEvent>, ctx: &nettu_scheduler_infra::NettuContext) {
+        for e in events {
+            let sync_event_reminders = SyncEventRemindersUseCase {
+                request: SyncEventRemindersTrigger::EventModified(&e, EventOperation::Created),
+            };
+
+            // Sideeffect, ignore result
+            let _ = execute(sync_event_reminders, ctx).await;
+        }
+    }
+}
+
 pub struct DeleteRemindersOnEventDeleted;
 
 #[async_trait::async_trait(?Send)]
@@ -48,3 +66,95 @@ impl Subscriber<UpdateEventUseCase> for SyncRemindersOnEventUpdated {
         let _ = execute(sync_event_reminders, ctx).await;
     }
 }
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<ReplaceEventUseCase> for SyncRemindersOnEventUpdated {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        let sync_event_reminders = SyncEventRemindersUseCase {
+            request: SyncEventRemindersTrigger::EventModified(&e, EventOperation::Updated),
+        };
+
+        // Sideeffect, ignore result
+        let _ = execute(sync_event_reminders, ctx).await;
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<AddEventExdateUseCase> for SyncRemindersOnEventUpdated {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        let sync_event_reminders = SyncEventRemindersUseCase {
+            request: SyncEventRemindersTrigger::EventModified(&e, EventOperation::Updated),
+        };
+
+        // Sideeffect, ignore result
+        let _ = execute(sync_event_reminders, ctx).await;
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<RemoveEventExdateUseCase> for SyncRemindersOnEventUpdated {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        let sync_event_reminders = SyncEventRemindersUseCase {
+            request: SyncEventRemindersTrigger::EventModified(&e, EventOperation::Updated),
+        };
+
+        // Sideeffect, ignore result
+        let _ = execute(sync_event_reminders, ctx).await;
+    }
+}
+
+/// Removes the cached `Service` booking slots that the modified `CalendarEvent`
+/// might have changed
+pub struct InvalidateBookingSlotsOnEventModified;
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<CreateEventUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<CreateEventsBatchUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, events: &Vec<CalendarEvent>, ctx: &nettu_scheduler_infra::NettuContext) {
+        // All the events of the batch belong to the same user
+        if let Some(e) = events.first() {
+            ctx.booking_slots_cache.invalidate_user(&e.user_id);
+        }
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<UpdateEventUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<ReplaceEventUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<DeleteEventUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<AddEventExdateUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<RemoveEventExdateUseCase> for InvalidateBookingSlotsOnEventModified {
+    async fn notify(&self, e: &CalendarEvent, ctx: &nettu_scheduler_infra::NettuContext) {
+        ctx.booking_slots_cache.invalidate_user(&e.user_id);
+    }
+}
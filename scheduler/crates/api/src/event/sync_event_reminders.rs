@@ -34,20 +34,21 @@ pub enum UseCaseErrors {
     CalendarNotFound,
 }
 
-async fn create_event_reminders(
+/// Expands the upcoming `Reminder`s of the `CalendarEvent` without storing them
+pub(crate) async fn expand_event_reminders(
     event: &CalendarEvent,
     calendar: &Calendar,
     priority: i64,
     ctx: &NettuContext,
-) -> Result<(), UseCaseErrors> {
+) -> Vec<Reminder> {
     let event_reminder_settings = match &event.reminder {
-        None => return Ok(()), // Nothing more to do
+        None => return vec![], // Nothing more to do
         Some(settings) => settings,
     };
     let millis_before = event_reminder_settings.minutes_before * 60 * 1000;
 
     let rrule_set = event.get_rrule_set(&calendar.settings);
-    let reminders = match rrule_set {
+    match rrule_set {
         Some(rrule_set) => {
             let rrule_set_iter = rrule_set.into_iter();
             let dates = rrule_set_iter
@@ -86,6 +87,8 @@ async fn create_event_reminders(
                     account_id: event.account_id.to_owned(),
                     remind_at: d.timestamp_millis() - millis_before,
                     priority,
+                    sent_at: None,
+                    failed_at: None,
                 })
                 .collect()
         }
@@ -95,8 +98,20 @@ async fn create_event_reminders(
             account_id: event.account_id.to_owned(),
             remind_at: event.start_ts - millis_before,
             priority,
+            sent_at: None,
+            failed_at: None,
         }],
-    };
+    }
+}
+
+/// Creates the upcoming `Reminder`s of the `CalendarEvent` and returns how many were created
+pub(crate) async fn create_event_reminders(
+    event: &CalendarEvent,
+    calendar: &Calendar,
+    priority: i64,
+    ctx: &NettuContext,
+) -> Result<usize, UseCaseErrors> {
+    let reminders = expand_event_reminders(event, calendar, priority, ctx).await;
 
     // create reminders for the next `self.expansion_interval`
     if ctx
@@ -109,7 +124,7 @@ async fn create_event_reminders(
         return Err(UseCaseErrors::StorageError);
     }
 
-    Ok(())
+    Ok(reminders.len())
 }
 
 #[async_trait::async_trait(?Send)]
@@ -170,7 +185,9 @@ impl<'a> UseCase for SyncEventRemindersUseCase<'a> {
                     }
                 };
 
-                create_event_reminders(calendar_event, &calendar, 1, ctx).await
+                create_event_reminders(calendar_event, &calendar, 1, ctx)
+                    .await
+                    .map(|_| ())
             }
             SyncEventRemindersTrigger::JobScheduler => {
                 let jobs = ctx
@@ -10,10 +10,17 @@ use crate::{
         },
     },
 };
-use actix_web::{web, HttpRequest, HttpResponse};
-use event::subscribers::SyncRemindersOnEventUpdated;
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use event::create_event::{resolve_duration, ResolveDurationError, INCONSISTENT_DURATION_MESSAGE};
+use event::subscribers::{InvalidateBookingSlotsOnEventModified, SyncRemindersOnEventUpdated};
+use event::sync_event_reminders::{
+    EventOperation, SyncEventRemindersTrigger, SyncEventRemindersUseCase,
+};
 use nettu_scheduler_api_structs::update_event::*;
-use nettu_scheduler_domain::{CalendarEvent, CalendarEventReminder, Metadata, RRuleOptions, ID};
+use nettu_scheduler_domain::{
+    patch_metadata, Calendar, CalendarEvent, CalendarEventReminder, EventEditMode, EventStatus,
+    Metadata, MetadataPatch, RRuleOptions, ID,
+};
 use nettu_scheduler_infra::NettuContext;
 
 fn handle_error(e: UseCaseErrors) -> NettuError {
@@ -28,10 +35,47 @@ fn handle_error(e: UseCaseErrors) -> NettuError {
         UseCaseErrors::InvalidReminder => {
             NettuError::BadClientData("Invalid reminder specified for the event".into())
         }
+        UseCaseErrors::InvalidTimespan => NettuError::BadClientData(
+            "The provided start_ts and duration is invalid for the event".into(),
+        ),
+        UseCaseErrors::InconsistentDuration => {
+            NettuError::BadClientData(INCONSISTENT_DURATION_MESSAGE.into())
+        }
+        UseCaseErrors::InvalidInstance => NettuError::BadClientData(
+            "The instance_start_ts has to be the start of an instance of the event".into(),
+        ),
+        UseCaseErrors::VersionMismatch(version) => NettuError::PreconditionFailed(format!(
+            "The calendar event has been modified since the version given in If-Match, the current version is: {}",
+            version
+        )),
         UseCaseErrors::StorageError => NettuError::InternalError,
     }
 }
 
+/// The `If-Match` header can give the `version` of a `CalendarEvent` as a plain
+/// number or as an entity tag like `"3"`, while `*` matches any version.
+pub(crate) fn parse_if_match(http_req: &HttpRequest) -> Result<Option<i64>, NettuError> {
+    let value = match http_req.headers().get(header::IF_MATCH) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<i64>()
+        .map(Some)
+        .map_err(|_| {
+            NettuError::BadClientData(format!(
+                "Malformed If-Match header: {}. It should be the version of the event.",
+                value
+            ))
+        })
+}
+
 pub async fn update_event_admin_controller(
     http_req: HttpRequest,
     body: web::Json<RequestBody>,
@@ -40,19 +84,26 @@ pub async fn update_event_admin_controller(
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
     let e = account_can_modify_event(&account, &path_params.event_id, &ctx).await?;
+    let expected_version = parse_if_match(&http_req)?;
 
     let body = body.0;
     let usecase = UpdateEventUseCase {
         user_id: e.user_id,
         event_id: e.id,
         duration: body.duration,
+        end_ts: body.end_ts,
         start_ts: body.start_ts,
         reminder: body.reminder,
         recurrence: body.recurrence,
         busy: body.busy,
+        status: body.status,
         is_service: body.is_service,
         exdates: body.exdates,
         metadata: body.metadata,
+        metadata_patch: body.metadata_patch,
+        edit_mode: body.edit_mode.unwrap_or_default(),
+        instance_start_ts: body.instance_start_ts,
+        expected_version,
     };
 
     execute(usecase, &ctx)
@@ -68,19 +119,26 @@ pub async fn update_event_controller(
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
     let (user, policy) = protect_route(&http_req, &ctx).await?;
+    let expected_version = parse_if_match(&http_req)?;
 
     let body = body.0;
     let usecase = UpdateEventUseCase {
         user_id: user.id.clone(),
         event_id: path_params.event_id.clone(),
         duration: body.duration,
+        end_ts: body.end_ts,
         start_ts: body.start_ts,
         reminder: body.reminder,
         recurrence: body.recurrence,
         busy: body.busy,
+        status: body.status,
         is_service: body.is_service,
         exdates: body.exdates,
         metadata: body.metadata,
+        metadata_patch: body.metadata_patch,
+        edit_mode: body.edit_mode.unwrap_or_default(),
+        instance_start_ts: body.instance_start_ts,
+        expected_version,
     };
 
     execute_with_policy(usecase, &policy, &ctx)
@@ -92,60 +150,71 @@ pub async fn update_event_controller(
         })
 }
 
+/// Partially updates a `CalendarEvent`, where only the given fields are changed.
+/// See `ReplaceEventUseCase` for replacing the full event.
 #[derive(Debug)]
 pub struct UpdateEventUseCase {
     pub user_id: ID,
     pub event_id: ID,
     pub start_ts: Option<i64>,
     pub busy: Option<bool>,
+    /// Overrides `busy` when set
+    pub status: Option<EventStatus>,
     pub duration: Option<i64>,
-    pub reminder: Option<CalendarEventReminder>,
+    /// Alternative to `duration` for clients that describe the end of the event
+    pub end_ts: Option<i64>,
+    /// The reminder is removed when it is set to `None`
+    pub reminder: Option<Option<CalendarEventReminder>>,
     pub recurrence: Option<RRuleOptions>,
     pub is_service: Option<bool>,
     pub exdates: Option<Vec<i64>>,
     pub metadata: Option<Metadata>,
+    pub metadata_patch: Option<MetadataPatch>,
+    /// Which instances of a recurring `CalendarEvent` are changed
+    pub edit_mode: EventEditMode,
+    /// Start of the instance that is edited when not every instance is changed
+    pub instance_start_ts: Option<i64>,
+    /// The `version` that the stored `CalendarEvent` must still have, so that
+    /// a client does not overwrite changes it has not seen
+    pub expected_version: Option<i64>,
 }
 
 #[derive(Debug)]
 pub enum UseCaseErrors {
     NotFound(String, ID),
     InvalidReminder,
+    InvalidTimespan,
+    InconsistentDuration,
     StorageError,
     InvalidRecurrenceRule,
+    /// The instance to edit is not an instance of the `CalendarEvent`
+    InvalidInstance,
+    /// The `CalendarEvent` has another version than expected, which is given
+    VersionMismatch(i64),
 }
 
-#[async_trait::async_trait(?Send)]
-impl UseCase for UpdateEventUseCase {
-    type Response = CalendarEvent;
-
-    type Errors = UseCaseErrors;
-
-    const NAME: &'static str = "UpdateEvent";
-
-    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+impl UpdateEventUseCase {
+    /// Applies the given changes to the `CalendarEvent` without storing it
+    fn apply_changes(
+        &self,
+        e: &mut CalendarEvent,
+        calendar: &Calendar,
+    ) -> Result<(), UseCaseErrors> {
         let UpdateEventUseCase {
-            user_id,
-            event_id,
             start_ts,
             busy,
+            status,
             duration,
+            end_ts,
             recurrence,
             exdates,
             reminder,
             is_service,
             metadata,
+            metadata_patch,
+            ..
         } = self;
 
-        let mut e = match ctx.repos.event_repo.find(&event_id).await {
-            Some(event) if event.user_id == *user_id => event,
-            _ => {
-                return Err(UseCaseErrors::NotFound(
-                    "Calendar Event".into(),
-                    event_id.clone(),
-                ))
-            }
-        };
-
         if let Some(is_service) = is_service {
             e.is_service = *is_service;
         }
@@ -156,21 +225,26 @@ impl UseCase for UpdateEventUseCase {
         if let Some(metadata) = metadata {
             e.metadata = metadata.clone();
         }
+        if let Some(metadata_patch) = metadata_patch {
+            patch_metadata(&mut e.metadata, metadata_patch);
+        }
 
-        if let Some(reminder) = &e.reminder {
-            if !reminder.is_valid() {
-                return Err(UseCaseErrors::InvalidReminder);
+        if let Some(reminder) = reminder {
+            if let Some(reminder) = reminder {
+                if !reminder.is_valid() {
+                    return Err(UseCaseErrors::InvalidReminder);
+                }
             }
+            e.reminder = reminder.clone();
         }
-        e.reminder = reminder.clone();
 
-        let calendar = match ctx.repos.calendar_repo.find(&e.calendar_id).await {
-            Some(cal) => cal,
-            _ => {
-                return Err(UseCaseErrors::NotFound(
-                    "Calendar".into(),
-                    e.calendar_id.clone(),
-                ))
+        let duration = match resolve_duration(start_ts.unwrap_or(e.start_ts), *duration, *end_ts) {
+            Ok(duration) => duration,
+            Err(ResolveDurationError::Inconsistent) => {
+                return Err(UseCaseErrors::InconsistentDuration)
+            }
+            Err(ResolveDurationError::InvalidTimespan) => {
+                return Err(UseCaseErrors::InvalidTimespan)
             }
         };
 
@@ -184,13 +258,18 @@ impl UseCase for UpdateEventUseCase {
             }
         }
         if let Some(duration) = duration {
-            if e.duration != *duration {
-                e.duration = *duration;
+            if e.duration != duration {
+                e.duration = duration;
                 start_or_duration_change = true;
             }
         }
-        if let Some(busy) = busy {
-            e.busy = *busy;
+        if let Some(status) = status {
+            e.set_status(*status);
+        } else if let Some(busy) = busy {
+            e.set_status(EventStatus::from_busy(*busy));
+        }
+        if !e.has_valid_timespan() {
+            return Err(UseCaseErrors::InvalidTimespan);
         }
 
         let valid_recurrence = if let Some(rrule_opts) = recurrence.clone() {
@@ -199,6 +278,9 @@ impl UseCase for UpdateEventUseCase {
         } else if start_or_duration_change && e.recurrence.is_some() {
             e.set_recurrence(e.recurrence.clone().unwrap(), &calendar.settings, true)
         } else {
+            if start_or_duration_change {
+                e.end_ts = e.start_ts + e.duration;
+            }
             true
         };
 
@@ -206,18 +288,133 @@ impl UseCase for UpdateEventUseCase {
             return Err(UseCaseErrors::InvalidRecurrenceRule);
         };
 
-        e.updated = ctx.sys.get_timestamp_millis();
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for UpdateEventUseCase {
+    type Response = CalendarEvent;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "UpdateEvent";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let mut e = match ctx.repos.event_repo.find(&self.event_id).await {
+            Some(event) if event.user_id == self.user_id => event,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar Event".into(),
+                    self.event_id.clone(),
+                ))
+            }
+        };
 
-        let repo_res = ctx.repos.event_repo.save(&e).await;
-        if repo_res.is_err() {
-            return Err(UseCaseErrors::StorageError);
+        if let Some(expected_version) = self.expected_version {
+            if e.version != expected_version {
+                return Err(UseCaseErrors::VersionMismatch(e.version));
+            }
+        }
+
+        let calendar = match ctx.repos.calendar_repo.find(&e.calendar_id).await {
+            Some(cal) => cal,
+            _ => {
+                return Err(UseCaseErrors::NotFound(
+                    "Calendar".into(),
+                    e.calendar_id.clone(),
+                ))
+            }
+        };
+
+        let split_ts = match (self.edit_mode, self.instance_start_ts) {
+            (EventEditMode::All, _) => None,
+            // A non-recurring `CalendarEvent` only has a single instance
+            _ if e.recurrence.is_none() => None,
+            // Editing the first instance and the following ones is the same as editing all of them
+            (EventEditMode::ThisAndFollowing, Some(ts)) if ts == e.start_ts => None,
+            (_, Some(ts)) if e.is_occurrence(ts, &calendar.settings) => Some(ts),
+            _ => return Err(UseCaseErrors::InvalidInstance),
+        };
+
+        // The changes are applied to the instances that are split out into a new
+        // `CalendarEvent`, while the original one keeps the rest of the instances
+        let original = split_ts.map(|ts| {
+            let mut original = e.clone();
+            e = match self.edit_mode {
+                EventEditMode::This => original.detach_instance(ts),
+                _ => original.split_at(ts, &calendar.settings),
+            };
+            original
+        });
+
+        self.apply_changes(&mut e, &calendar)?;
+
+        let now = ctx.sys.get_timestamp_millis();
+        e.updated = now;
+
+        match original {
+            None => {
+                e.version += 1;
+                save_event(&e, self.expected_version, ctx).await?;
+            }
+            Some(mut original) => {
+                original.updated = now;
+                original.version += 1;
+                // The split out instances are inserted first, and removed again if the original
+                // can not be shortened, so that no instances are lost when either write fails
+                if ctx.repos.event_repo.insert(&e).await.is_err() {
+                    return Err(UseCaseErrors::StorageError);
+                }
+                if let Err(err) = save_event(&original, self.expected_version, ctx).await {
+                    let _ = ctx.repos.event_repo.delete(&e.id).await;
+                    return Err(err);
+                }
+
+                // The `Reminder`s of the instances that were split out belong to the new `CalendarEvent`
+                let sync_event_reminders = SyncEventRemindersUseCase {
+                    request: SyncEventRemindersTrigger::EventModified(
+                        &original,
+                        EventOperation::Updated,
+                    ),
+                };
+                let _ = execute(sync_event_reminders, ctx).await;
+            }
         }
 
         Ok(e)
     }
 
     fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
-        vec![Box::new(SyncRemindersOnEventUpdated)]
+        vec![
+            Box::new(SyncRemindersOnEventUpdated),
+            Box::new(InvalidateBookingSlotsOnEventModified),
+        ]
+    }
+}
+
+/// Saves the changed `CalendarEvent`. When an expected version is given, it is only saved
+/// if the stored `CalendarEvent` still has that version, in the same operation.
+async fn save_event(
+    e: &CalendarEvent,
+    expected_version: Option<i64>,
+    ctx: &NettuContext,
+) -> Result<(), UseCaseErrors> {
+    let saved = match expected_version {
+        Some(version) => ctx.repos.event_repo.save_if_version(e, version).await,
+        None => ctx.repos.event_repo.save(e).await.map(|_| true),
+    };
+    match saved {
+        Ok(true) => Ok(()),
+        // Modified by someone else since it was read
+        Ok(false) => match ctx.repos.event_repo.find(&e.id).await {
+            Some(current) => Err(UseCaseErrors::VersionMismatch(current.version)),
+            None => Err(UseCaseErrors::NotFound(
+                "Calendar Event".into(),
+                e.id.clone(),
+            )),
+        },
+        Err(_) => Err(UseCaseErrors::StorageError),
     }
 }
 
@@ -229,10 +426,118 @@ impl PermissionBoundary for UpdateEventUseCase {
 
 #[cfg(test)]
 mod test {
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_domain::{Account, RRuleFrequenzy, User};
     use nettu_scheduler_infra::setup_context;
 
     use super::*;
 
+    struct TestContext {
+        ctx: NettuContext,
+        event: CalendarEvent,
+        user: User,
+    }
+
+    async fn setup() -> TestContext {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata.insert("group".into(), "a".into());
+        metadata.insert("owner".into(), "b".into());
+        let event = CalendarEvent {
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id.clone(),
+            busy: false,
+            status: EventStatus::Free,
+            duration: 800,
+            end_ts: 1300,
+            exdates: vec![],
+            id: Default::default(),
+            start_ts: 500,
+            recurrence: None,
+            reminder: Some(CalendarEventReminder { minutes_before: 10 }),
+            is_service: false,
+            service_id: None,
+            metadata,
+            external_id: None,
+            updated: Default::default(),
+            version: Default::default(),
+            created: Default::default(),
+        };
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+
+        TestContext { ctx, event, user }
+    }
+
+    const HOUR: i64 = 1000 * 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+
+    /// Turns the event into a daily event with five instances starting at midnight
+    async fn setup_recurring() -> TestContext {
+        let TestContext {
+            ctx,
+            mut event,
+            user,
+        } = setup().await;
+        let calendar = ctx
+            .repos
+            .calendar_repo
+            .find(&event.calendar_id)
+            .await
+            .unwrap();
+        event.start_ts = 0;
+        event.duration = HOUR;
+        let recurrence = RRuleOptions {
+            freq: RRuleFrequenzy::Daily,
+            interval: 1,
+            count: Some(5),
+            ..Default::default()
+        };
+        assert!(event.set_recurrence(recurrence, &calendar.settings, true));
+        ctx.repos.event_repo.save(&event).await.unwrap();
+
+        TestContext { ctx, event, user }
+    }
+
+    async fn instance_starts(ctx: &NettuContext, event: &CalendarEvent) -> Vec<i64> {
+        let calendar = ctx
+            .repos
+            .calendar_repo
+            .find(&event.calendar_id)
+            .await
+            .unwrap();
+        event
+            .expand(None, &calendar.settings)
+            .into_iter()
+            .map(|instance| instance.start_ts)
+            .collect()
+    }
+
+    fn update_usecase(event: &CalendarEvent) -> UpdateEventUseCase {
+        UpdateEventUseCase {
+            event_id: event.id.clone(),
+            start_ts: None,
+            duration: None,
+            end_ts: None,
+            reminder: Some(event.reminder.clone()),
+            recurrence: None,
+            busy: None,
+            status: None,
+            user_id: event.user_id.clone(),
+            is_service: None,
+            exdates: None,
+            metadata: None,
+            metadata_patch: None,
+            edit_mode: Default::default(),
+            instance_start_ts: None,
+            expected_version: None,
+        }
+    }
+
     #[actix_web::main]
     #[test]
     async fn update_notexisting_event() {
@@ -240,16 +545,346 @@ mod test {
             event_id: Default::default(),
             start_ts: Some(500),
             duration: Some(800),
+            end_ts: None,
             reminder: None,
             recurrence: None,
             busy: Some(false),
+            status: None,
             user_id: Default::default(),
             is_service: None,
             exdates: None,
             metadata: None,
+            metadata_patch: None,
+            edit_mode: Default::default(),
+            instance_start_ts: None,
+            expected_version: None,
         };
         let ctx = setup_context().await;
         let res = usecase.execute(&ctx).await;
         assert!(res.is_err());
     }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_invalid_reminder_and_keeps_event_unchanged() {
+        let TestContext { ctx, event, user } = setup().await;
+
+        let mut usecase = UpdateEventUseCase {
+            start_ts: Some(1000),
+            reminder: Some(Some(CalendarEventReminder {
+                minutes_before: -10,
+            })),
+            user_id: user.id.clone(),
+            ..update_usecase(&event)
+        };
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::InvalidReminder)));
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.start_ts, 500);
+        assert_eq!(event.reminder.unwrap().minutes_before, 10);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn replaces_metadata() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut metadata = Metadata::new();
+        metadata.insert("group".into(), "c".into());
+        let mut usecase = UpdateEventUseCase {
+            metadata: Some(metadata.clone()),
+            ..update_usecase(&event)
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.metadata, metadata);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn merges_metadata_patch() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut metadata_patch = MetadataPatch::new();
+        metadata_patch.insert("group".into(), Some("c".into()));
+        metadata_patch.insert("owner".into(), None);
+        metadata_patch.insert("room".into(), Some("d".into()));
+        let mut usecase = UpdateEventUseCase {
+            metadata_patch: Some(metadata_patch),
+            ..update_usecase(&event)
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.metadata.len(), 2);
+        assert_eq!(event.metadata.get("group"), Some(&"c".to_string()));
+        assert_eq!(event.metadata.get("room"), Some(&"d".to_string()));
+        assert!(event.metadata.get("owner").is_none());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_negative_duration() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut usecase = UpdateEventUseCase {
+            duration: Some(-1),
+            ..update_usecase(&event)
+        };
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::InvalidTimespan)));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn updates_end_ts_of_non_recurring_event() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut usecase = UpdateEventUseCase {
+            start_ts: Some(1000),
+            duration: Some(2000),
+            ..update_usecase(&event)
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.end_ts, 3000);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn derives_duration_from_end_ts() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut usecase = UpdateEventUseCase {
+            start_ts: Some(1000),
+            end_ts: Some(4000),
+            ..update_usecase(&event)
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.duration, 3000);
+        assert_eq!(event.end_ts, 4000);
+
+        // The current start_ts is used when only end_ts is given
+        let mut usecase = UpdateEventUseCase {
+            end_ts: Some(1500),
+            ..update_usecase(&event)
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.duration, 500);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_conflicting_duration_and_end_ts() {
+        let TestContext { ctx, event, .. } = setup().await;
+
+        let mut usecase = UpdateEventUseCase {
+            duration: Some(1000),
+            end_ts: Some(1000),
+            ..update_usecase(&event)
+        };
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::InconsistentDuration)));
+
+        let event = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(event.duration, 800);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn edits_single_instance_of_recurring_event() {
+        let TestContext { ctx, event, .. } = setup_recurring().await;
+
+        let mut usecase = UpdateEventUseCase {
+            start_ts: Some(2 * DAY + HOUR),
+            edit_mode: EventEditMode::This,
+            instance_start_ts: Some(2 * DAY),
+            ..update_usecase(&event)
+        };
+        let edited = usecase.execute(&ctx).await.unwrap();
+        assert_ne!(edited.id, event.id);
+        assert_eq!(edited.start_ts, 2 * DAY + HOUR);
+        assert_eq!(edited.end_ts, 2 * DAY + 2 * HOUR);
+        assert!(edited.recurrence.is_none());
+        assert!(edited.exdates.is_empty());
+
+        let original = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(original.exdates, vec![2 * DAY]);
+        assert_eq!(
+            instance_starts(&ctx, &original).await,
+            vec![0, DAY, 3 * DAY, 4 * DAY]
+        );
+        let stored = ctx.repos.event_repo.find(&edited.id).await.unwrap();
+        assert_eq!(stored.start_ts, 2 * DAY + HOUR);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn edits_instance_and_following_of_recurring_event() {
+        let TestContext { ctx, event, .. } = setup_recurring().await;
+
+        let mut usecase = UpdateEventUseCase {
+            duration: Some(2 * HOUR),
+            edit_mode: EventEditMode::ThisAndFollowing,
+            instance_start_ts: Some(2 * DAY),
+            ..update_usecase(&event)
+        };
+        let following = usecase.execute(&ctx).await.unwrap();
+        assert_ne!(following.id, event.id);
+        assert_eq!(following.start_ts, 2 * DAY);
+        assert_eq!(following.duration, 2 * HOUR);
+        assert_eq!(following.recurrence.as_ref().unwrap().count, Some(3));
+        assert_eq!(
+            instance_starts(&ctx, &following).await,
+            vec![2 * DAY, 3 * DAY, 4 * DAY]
+        );
+
+        let original = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(original.duration, HOUR);
+        let recurrence = original.recurrence.clone().unwrap();
+        assert_eq!(recurrence.until, Some((2 * DAY - 1) as isize));
+        assert!(recurrence.count.is_none());
+        assert_eq!(instance_starts(&ctx, &original).await, vec![0, DAY]);
+        assert_eq!(original.end_ts, DAY + HOUR);
+        assert!(ctx.repos.event_repo.find(&following.id).await.is_some());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn edits_all_instances_of_recurring_event() {
+        let TestContext { ctx, event, .. } = setup_recurring().await;
+
+        let mut usecase = UpdateEventUseCase {
+            duration: Some(2 * HOUR),
+            edit_mode: EventEditMode::All,
+            instance_start_ts: Some(2 * DAY),
+            ..update_usecase(&event)
+        };
+        let edited = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(edited.id, event.id);
+        assert_eq!(edited.duration, 2 * HOUR);
+        assert_eq!(
+            instance_starts(&ctx, &edited).await,
+            vec![0, DAY, 2 * DAY, 3 * DAY, 4 * DAY]
+        );
+
+        let events = ctx
+            .repos
+            .event_repo
+            .find_by_calendar(&event.calendar_id, None)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_editing_instance_that_does_not_exist() {
+        let TestContext { ctx, event, .. } = setup_recurring().await;
+
+        for edit_mode in &[EventEditMode::This, EventEditMode::ThisAndFollowing] {
+            let mut usecase = UpdateEventUseCase {
+                edit_mode: *edit_mode,
+                instance_start_ts: Some(2 * DAY + 1),
+                ..update_usecase(&event)
+            };
+            let res = usecase.execute(&ctx).await;
+            assert!(matches!(res, Err(UseCaseErrors::InvalidInstance)));
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn update_with_if_match_requires_current_version() {
+        let TestContext {
+            ctx, event, user, ..
+        } = setup().await;
+        let account = Account {
+            id: user.account_id.clone(),
+            ..Default::default()
+        };
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+        let update = |if_match: String| {
+            test::TestRequest::patch()
+                .uri(&format!("/user/events/{}", event.id))
+                .header("x-api-key", account.secret_api_key.clone())
+                .header("If-Match", if_match)
+                .set_json(&serde_json::json!({ "startTs": 600 }))
+                .to_request()
+        };
+
+        let res = test::call_service(&mut app, update(format!("{}", event.version + 1))).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(stored.start_ts, 500);
+
+        let res = test::call_service(&mut app, update(format!("\"{}\"", event.version))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(stored.start_ts, 600);
+        assert_eq!(stored.version, event.version + 1);
+
+        // The version the client had is now stale
+        let res = test::call_service(&mut app, update(format!("{}", event.version))).await;
+        assert_eq!(res.status(), StatusCode::PRECONDITION_FAILED);
+
+        let res = test::call_service(&mut app, update("v1".into())).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn put_and_patch_update_partially_and_null_removes_reminder() {
+        let TestContext {
+            ctx, event, user, ..
+        } = setup().await;
+        let account = Account {
+            id: user.account_id.clone(),
+            ..Default::default()
+        };
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::event::configure_routes),
+        )
+        .await;
+        let uri = format!("/user/events/{}", event.id);
+
+        let req = test::TestRequest::put()
+            .uri(&uri)
+            .header("x-api-key", account.secret_api_key.clone())
+            .set_json(&serde_json::json!({ "startTs": 600 }))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(stored.start_ts, 600);
+        assert_eq!(stored.duration, event.duration);
+        assert!(stored.reminder.is_some());
+
+        let req = test::TestRequest::patch()
+            .uri(&uri)
+            .header("x-api-key", account.secret_api_key.clone())
+            .set_json(&serde_json::json!({ "reminder": null }))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+        assert_eq!(stored.start_ts, 600);
+        assert!(stored.reminder.is_none());
+    }
 }
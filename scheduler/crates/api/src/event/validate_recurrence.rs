@@ -0,0 +1,189 @@
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_account_route,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::validate_recurrence::*;
+use nettu_scheduler_domain::{
+    CalendarEvent, CalendarSettings, EventInstance, EventStatus, RRuleOptions, TimeSpan,
+};
+use nettu_scheduler_infra::NettuContext;
+
+/// Number of `EventInstance`s returned as a preview of a valid recurrence rule
+const PREVIEW_INSTANCES: usize = 10;
+
+pub async fn validate_recurrence_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    protect_account_route(&http_req, &ctx).await?;
+
+    let body = body.0;
+    let usecase = ValidateRecurrenceUseCase {
+        recurrence: body.recurrence,
+        start_ts: body.start_ts,
+        duration: body.duration,
+        week_start: body.calendar_settings.week_start,
+        timezone: body.calendar_settings.timezone,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|instances| HttpResponse::Ok().json(APIResponse::new(instances)))
+        .map_err(|e| match e {
+            UseCaseErrors::InvalidRecurrenceRule => {
+                NettuError::BadClientData("Invalid recurrence rule specified for the event".into())
+            }
+            UseCaseErrors::InvalidTimespan => NettuError::BadClientData(
+                "The provided start_ts and duration is invalid for the event".into(),
+            ),
+            UseCaseErrors::InvalidSettings(err) => NettuError::BadClientData(format!(
+                "Bad calendar settings provided. Error message: {}",
+                err
+            )),
+        })
+}
+
+/// Validates a recurrence rule the same way as when a `CalendarEvent` is saved
+/// and previews its first instances, without persisting anything
+#[derive(Debug)]
+struct ValidateRecurrenceUseCase {
+    recurrence: RRuleOptions,
+    start_ts: i64,
+    duration: i64,
+    week_start: isize,
+    timezone: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum UseCaseErrors {
+    InvalidRecurrenceRule,
+    InvalidTimespan,
+    InvalidSettings(String),
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for ValidateRecurrenceUseCase {
+    type Response = Vec<EventInstance>;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "ValidateRecurrence";
+
+    async fn execute(&mut self, _ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let mut settings = CalendarSettings::default();
+        if !settings.set_week_start(self.week_start) {
+            return Err(UseCaseErrors::InvalidSettings(format!(
+                "Invalid week start: {}, must be between 0 and 6",
+                self.week_start
+            )));
+        }
+        if let Some(timezone) = &self.timezone {
+            if !settings.set_timezone(timezone) {
+                return Err(UseCaseErrors::InvalidSettings(format!(
+                    "Invalid timezone: {}, must be a valid IANA Timezone string",
+                    timezone
+                )));
+            }
+        }
+
+        let end_ts = match self.start_ts.checked_add(self.duration) {
+            Some(end_ts) => end_ts,
+            None => return Err(UseCaseErrors::InvalidTimespan),
+        };
+        let mut event = CalendarEvent {
+            id: Default::default(),
+            start_ts: self.start_ts,
+            duration: self.duration,
+            end_ts,
+            busy: false,
+            status: EventStatus::Free,
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+        };
+        if !event.has_valid_timespan() {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
+        if !event.set_recurrence(self.recurrence.clone(), &settings, true) {
+            return Err(UseCaseErrors::InvalidRecurrenceRule);
+        }
+
+        // Rules without count or until never end, so only expand the period that
+        // is allowed for until, which is more than enough to fill the preview
+        let two_years_in_millis = 1000 * 60 * 60 * 24 * 366 * 2;
+        let end_ts = std::cmp::min(event.end_ts, event.start_ts + two_years_in_millis);
+        let timespan = TimeSpan::new(event.start_ts, end_ts);
+        let mut instances = event.expand(Some(&timespan), &settings);
+        instances.truncate(PREVIEW_INSTANCES);
+
+        Ok(instances)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::RRuleFrequenzy;
+    use nettu_scheduler_infra::setup_context;
+
+    const DAY: i64 = 1000 * 60 * 60 * 24;
+
+    #[actix_web::main]
+    #[test]
+    async fn previews_instances_of_valid_rule() {
+        let ctx = setup_context().await;
+
+        let mut usecase = ValidateRecurrenceUseCase {
+            recurrence: RRuleOptions {
+                freq: RRuleFrequenzy::Daily,
+                interval: 2,
+                ..Default::default()
+            },
+            start_ts: 0,
+            duration: 1000 * 60 * 60,
+            week_start: 0,
+            timezone: None,
+        };
+        let instances = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(instances.len(), PREVIEW_INSTANCES);
+        assert_eq!(instances[0].start_ts, 0);
+        assert_eq!(instances[1].start_ts, 2 * DAY);
+        assert_eq!(instances[1].end_ts, 2 * DAY + 1000 * 60 * 60);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_invalid_rule() {
+        let ctx = setup_context().await;
+
+        let mut usecase = ValidateRecurrenceUseCase {
+            recurrence: RRuleOptions {
+                freq: RRuleFrequenzy::Daily,
+                interval: 1,
+                count: Some(1000),
+                ..Default::default()
+            },
+            start_ts: 0,
+            duration: 1000 * 60 * 60,
+            week_start: 0,
+            timezone: None,
+        };
+        let res = usecase.execute(&ctx).await;
+        assert_eq!(res.unwrap_err(), UseCaseErrors::InvalidRecurrenceRule);
+    }
+}
@@ -1,16 +1,34 @@
 use crate::{
     event::{
-        get_upcoming_reminders::GetUpcomingRemindersUseCase,
+        get_upcoming_reminders::{AccountEventReminders, GetUpcomingRemindersUseCase},
         sync_event_reminders::{SyncEventRemindersTrigger, SyncEventRemindersUseCase},
     },
     shared::usecase::execute,
 };
 use actix_web::client::Client;
-use actix_web::rt::time::{delay_until, interval, Instant};
-use nettu_scheduler_api_structs::send_account_event_reminders::AccountEventRemindersDTO;
-use nettu_scheduler_infra::NettuContext;
+use actix_web::rt::time::{delay_for, delay_until, interval, Instant, Interval};
+use futures::{
+    future::{self, Either},
+    pin_mut,
+    stream::{self, StreamExt},
+    Future,
+};
+use nettu_scheduler_api_structs::{
+    send_account_event_reminders::AccountEventRemindersDTO,
+    send_account_webhook::AccountWebhookEnvelopeDTO,
+};
+use nettu_scheduler_domain::{Account, AccountWebhookSettings, Reminder, ID};
+use nettu_scheduler_infra::{NettuContext, Shutdown};
+use serde::Serialize;
 use std::time::Duration;
-use tracing::error;
+use tracing::{error, warn};
+
+/// Maximum amount of time a webhook receiver has to respond to a delivery
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum amount of webhooks that are being delivered at the same time
+const MAX_CONCURRENT_WEBHOOK_DELIVERIES: usize = 16;
+/// How long sent and failed `Reminder`s are kept around for `Account`s to backfill them
+const SENT_REMINDERS_RETENTION_MS: i64 = 1000 * 60 * 60 * 24 * 7;
 
 pub fn get_start_delay(now_ts: usize, secs_before_min: usize) -> usize {
     let secs_to_next_minute = 60 - (now_ts / 1000) % 60;
@@ -21,17 +39,36 @@ pub fn get_start_delay(now_ts: usize, secs_before_min: usize) -> usize {
     }
 }
 
+/// Runs the job on every tick of the interval until shutdown is triggered.
+/// A job that is running when shutdown is triggered is allowed to complete.
+async fn run_until_shutdown<F, Fut>(shutdown: &Shutdown, mut interval: Interval, mut job: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    while !shutdown.is_triggered() {
+        let tick = interval.tick();
+        let triggered = shutdown.triggered();
+        pin_mut!(tick, triggered);
+        if let Either::Right(_) = future::select(tick, triggered).await {
+            break;
+        }
+
+        job().await;
+    }
+}
+
 pub fn start_reminders_expansion_job_scheduler(ctx: NettuContext) {
     actix_web::rt::spawn(async move {
-        let mut interval = interval(Duration::from_secs(30 * 60 * 1000));
-        loop {
-            interval.tick().await;
-
+        let interval = interval(Duration::from_secs(30 * 60 * 1000));
+        run_until_shutdown(&ctx.shutdown, interval, || async {
+            let _work = ctx.shutdown.start_work();
             let usecase = SyncEventRemindersUseCase {
                 request: SyncEventRemindersTrigger::JobScheduler,
             };
             let _ = execute(usecase, &ctx).await;
-        }
+        })
+        .await;
     });
 }
 
@@ -41,13 +78,26 @@ pub fn start_send_reminders_job(ctx: NettuContext) {
         let secs_to_next_run = get_start_delay(now as usize, 0);
         let start = Instant::now() + Duration::from_secs(secs_to_next_run as u64);
 
-        delay_until(start).await;
-        let mut minutely_interval = interval(Duration::from_secs(60));
-        loop {
-            minutely_interval.tick().await;
-            let context = ctx.clone();
-            actix_web::rt::spawn(send_reminders(context));
+        let start = delay_until(start);
+        let triggered = ctx.shutdown.triggered();
+        pin_mut!(start, triggered);
+        if let Either::Right(_) = future::select(start, triggered).await {
+            return;
         }
+        let batch_window = Duration::from_millis(ctx.config.reminders_batch_window.0 as u64);
+        let batch_interval = interval(batch_window);
+        run_until_shutdown(&ctx.shutdown, batch_interval, || {
+            // The batch is sent in the background so that the next tick is not delayed,
+            // but it still has to be sent before the shutdown completes
+            let work = ctx.shutdown.start_work();
+            let context = ctx.clone();
+            actix_web::rt::spawn(async move {
+                send_reminders(context).await;
+                drop(work);
+            });
+            future::ready(())
+        })
+        .await;
     });
 }
 
@@ -55,7 +105,7 @@ async fn send_reminders(context: NettuContext) {
     let client = Client::new();
 
     let usecase = GetUpcomingRemindersUseCase {
-        reminders_interval: 1000 * 60,
+        reminders_interval: context.config.reminders_batch_window,
     };
     let account_reminders = match execute(usecase, &context).await {
         Ok(res) => res,
@@ -70,26 +120,347 @@ async fn send_reminders(context: NettuContext) {
         account_reminders
     );
 
-    for (acc, reminders) in account_reminders.0 {
-        match acc.settings.webhook {
-            None => continue,
-            Some(webhook) => {
-                if let Err(e) = client
-                    .post(webhook.url)
-                    .header("nettu-scheduler-webhook-key", webhook.key)
-                    .send_json(&AccountEventRemindersDTO::new(reminders.events))
-                    .await
-                {
-                    error!("Error informing client of reminders: {:?}", e);
+    let sent_at = context.sys.get_timestamp_millis();
+    let reminders = account_reminders
+        .0
+        .iter()
+        .flat_map(|(_, reminders)| reminders.reminders.iter().cloned())
+        .collect::<Vec<_>>();
+    let deliveries = create_reminder_deliveries(account_reminders.0, sent_at);
+    let webhook_account_ids = deliveries
+        .iter()
+        .map(|delivery| delivery.account_id.clone())
+        .collect::<Vec<_>>();
+    let retry = WebhookRetryPolicy::from_config(&context);
+    let undelivered = deliver_webhooks(&client, deliveries, WEBHOOK_TIMEOUT, &retry).await;
+    dead_letter_webhooks(&undelivered);
+
+    let delivered_account_ids = webhook_account_ids
+        .into_iter()
+        .filter(|account_id| {
+            !undelivered
+                .iter()
+                .any(|delivery| delivery.account_id == *account_id)
+        })
+        .collect::<Vec<_>>();
+    let reminders = mark_dispatched_reminders(reminders, &delivered_account_ids, sent_at);
+    store_dispatched_reminders(&context, &reminders, sent_at).await;
+}
+
+/// Marks the `Reminder`s of the `Account`s whose webhook accepted the delivery as sent.
+/// The others are marked as failed, as the `Account` either has no webhook or it did
+/// not receive them.
+pub(crate) fn mark_dispatched_reminders(
+    reminders: Vec<Reminder>,
+    delivered_account_ids: &[ID],
+    dispatched_at: i64,
+) -> Vec<Reminder> {
+    reminders
+        .into_iter()
+        .map(|reminder| {
+            if delivered_account_ids.contains(&reminder.account_id) {
+                Reminder {
+                    sent_at: Some(dispatched_at),
+                    ..reminder
+                }
+            } else {
+                Reminder {
+                    failed_at: Some(dispatched_at),
+                    ..reminder
                 }
             }
+        })
+        .collect()
+}
+
+/// Keeps the sent and failed `Reminder`s so that they can be backfilled by the `Account`s,
+/// and removes the ones that are older than the retention period
+async fn store_dispatched_reminders(
+    ctx: &NettuContext,
+    reminders: &[Reminder],
+    dispatched_at: i64,
+) {
+    if !reminders.is_empty() {
+        if let Err(e) = ctx.repos.reminder_repo.bulk_insert(reminders).await {
+            error!("Unable to store the dispatched reminders. Error: {:?}", e);
+        }
+    }
+    if let Err(e) = ctx
+        .repos
+        .reminder_repo
+        .delete_dispatched_before(dispatched_at - SENT_REMINDERS_RETENTION_MS)
+        .await
+    {
+        error!("Unable to delete expired sent reminders. Error: {:?}", e);
+    }
+}
+
+pub(crate) type ReminderDelivery =
+    WebhookDelivery<AccountWebhookEnvelopeDTO<AccountEventRemindersDTO>>;
+
+/// Creates a webhook delivery for every `Account` that has a webhook registered
+pub(crate) fn create_reminder_deliveries(
+    account_reminders: Vec<(Account, AccountEventReminders)>,
+    sent_at: i64,
+) -> Vec<ReminderDelivery> {
+    account_reminders
+        .into_iter()
+        .filter_map(|(acc, reminders)| {
+            let payload = AccountWebhookEnvelopeDTO::new(
+                AccountEventRemindersDTO::TYPE,
+                acc.id.clone(),
+                sent_at,
+                AccountEventRemindersDTO::new(reminders.events),
+            );
+            acc.settings.webhook.map(|webhook| WebhookDelivery {
+                account_id: acc.id,
+                webhook,
+                payload,
+            })
+        })
+        .collect()
+}
+
+/// A payload that should be sent to the webhook of an `Account`
+pub struct WebhookDelivery<T: Serialize> {
+    pub account_id: ID,
+    pub webhook: AccountWebhookSettings,
+    pub payload: T,
+}
+
+/// How failed webhook deliveries are retried
+pub struct WebhookRetryPolicy {
+    /// How many times a failed delivery is retried before it is given up
+    pub max_retries: usize,
+    /// Delay before the first retry, which is doubled for every following retry
+    pub backoff: Duration,
+}
+
+impl WebhookRetryPolicy {
+    pub fn from_config(ctx: &NettuContext) -> Self {
+        Self {
+            max_retries: ctx.config.webhook_max_retries,
+            backoff: Duration::from_millis(ctx.config.webhook_retry_backoff_ms),
+        }
+    }
+}
+
+/// Delivers a single webhook in the background with the same timeout and retries as
+/// the reminders, so that the request triggering it does not wait for the receiver
+pub(crate) fn spawn_webhook_delivery<T: Serialize + 'static>(
+    ctx: &NettuContext,
+    delivery: WebhookDelivery<T>,
+) {
+    let work = ctx.shutdown.start_work();
+    let retry = WebhookRetryPolicy::from_config(ctx);
+    actix_web::rt::spawn(async move {
+        let undelivered =
+            deliver_webhooks(&Client::new(), vec![delivery], WEBHOOK_TIMEOUT, &retry).await;
+        dead_letter_webhooks(&undelivered);
+        drop(work);
+    });
+}
+
+/// Sends the given webhooks concurrently. Every delivery has to complete within
+/// the given timeout so that a slow receiver does not delay the deliveries to
+/// other `Account`s. Failed deliveries are retried according to the retry policy,
+/// and the ones that could not be delivered at all are returned.
+pub async fn deliver_webhooks<T: Serialize>(
+    client: &Client,
+    deliveries: Vec<WebhookDelivery<T>>,
+    timeout: Duration,
+    retry: &WebhookRetryPolicy,
+) -> Vec<WebhookDelivery<T>> {
+    stream::iter(deliveries)
+        .map(|delivery| async move {
+            if deliver_webhook(client, &delivery, timeout, retry).await {
+                None
+            } else {
+                Some(delivery)
+            }
+        })
+        .buffer_unordered(MAX_CONCURRENT_WEBHOOK_DELIVERIES)
+        .filter_map(future::ready)
+        .collect()
+        .await
+}
+
+/// Returns whether the receiver accepted the webhook within the allowed attempts
+async fn deliver_webhook<T: Serialize>(
+    client: &Client,
+    delivery: &WebhookDelivery<T>,
+    timeout: Duration,
+    retry: &WebhookRetryPolicy,
+) -> bool {
+    let mut backoff = retry.backoff;
+    for attempt in 0..=retry.max_retries {
+        if attempt > 0 {
+            delay_for(backoff).await;
+            backoff *= 2;
+        }
+        let res = client
+            .post(&delivery.webhook.url)
+            .timeout(timeout)
+            .header("nettu-scheduler-webhook-key", delivery.webhook.key.as_str())
+            .send_json(&delivery.payload)
+            .await;
+        match res {
+            Ok(res) if res.status().is_success() => return true,
+            Ok(res) => warn!(
+                "Webhook receiver of account: {} responded with status: {} on attempt: {}",
+                delivery.account_id,
+                res.status(),
+                attempt + 1
+            ),
+            Err(e) => warn!(
+                "Error delivering webhook to account: {} on attempt: {}. Error: {:?}",
+                delivery.account_id,
+                attempt + 1,
+                e
+            ),
         }
     }
+    false
+}
+
+/// Logs the full payload of the webhooks that could not be delivered, so that
+/// they can be investigated or replayed to the receiver
+fn dead_letter_webhooks<T: Serialize>(undelivered: &[WebhookDelivery<T>]) {
+    for delivery in undelivered {
+        let payload = serde_json::to_string(&delivery.payload)
+            .unwrap_or_else(|e| format!("<unable to serialize payload: {:?}>", e));
+        error!(
+            "Giving up delivering webhook to account: {} at url: {}. Dead-lettered payload: {}",
+            delivery.account_id, delivery.webhook.url, payload
+        );
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use actix_web::{rt::time::delay_for, test, web, App, HttpResponse};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    };
+
+    async fn slow_receiver() -> HttpResponse {
+        delay_for(Duration::from_secs(10)).await;
+        HttpResponse::Ok().finish()
+    }
+
+    async fn fast_receiver(received_at: web::Data<Mutex<Option<Instant>>>) -> HttpResponse {
+        *received_at.lock().unwrap() = Some(Instant::now());
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn slow_webhook_receiver_does_not_delay_other_deliveries() {
+        let slow_server = test::start(|| App::new().route("/", web::post().to(slow_receiver)));
+        let received_at = web::Data::new(Mutex::new(None::<Instant>));
+        let received_at_server = received_at.clone();
+        let fast_server = test::start(move || {
+            App::new()
+                .app_data(received_at_server.clone())
+                .route("/", web::post().to(fast_receiver))
+        });
+
+        let delivery = |url: String| WebhookDelivery {
+            account_id: ID::default(),
+            webhook: AccountWebhookSettings {
+                url,
+                key: "key".into(),
+            },
+            payload: AccountEventRemindersDTO::new(vec![]),
+        };
+        // The slow receiver is first to make sure it does not block the fast one
+        let deliveries = vec![
+            delivery(slow_server.url("/")),
+            delivery(fast_server.url("/")),
+        ];
+
+        let timeout = Duration::from_secs(1);
+        let no_retries = WebhookRetryPolicy {
+            max_retries: 0,
+            backoff: Duration::from_millis(0),
+        };
+        let started = Instant::now();
+        deliver_webhooks(&Client::new(), deliveries, timeout, &no_retries).await;
+
+        let fast_received_at = received_at
+            .lock()
+            .unwrap()
+            .expect("Fast receiver to receive the webhook");
+        assert!(fast_received_at.duration_since(started) < timeout);
+        // The slow delivery is aborted after the timeout instead of taking 10 seconds
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    /// Fails the given amount of requests before it starts accepting them
+    async fn flaky_receiver(state: web::Data<(AtomicUsize, usize)>) -> HttpResponse {
+        let attempt = state.0.fetch_add(1, Ordering::SeqCst);
+        if attempt < state.1 {
+            HttpResponse::InternalServerError().finish()
+        } else {
+            HttpResponse::Ok().finish()
+        }
+    }
+
+    async fn deliver_to_flaky_receiver(
+        failures: usize,
+    ) -> (usize, Vec<WebhookDelivery<serde_json::Value>>) {
+        let state = web::Data::new((AtomicUsize::new(0), failures));
+        let state_server = state.clone();
+        let server = test::start(move || {
+            App::new()
+                .app_data(state_server.clone())
+                .route("/", web::post().to(flaky_receiver))
+        });
+
+        let delivery = WebhookDelivery {
+            account_id: ID::default(),
+            webhook: AccountWebhookSettings {
+                url: server.url("/"),
+                key: "key".into(),
+            },
+            payload: serde_json::json!({ "id": "delivery-1" }),
+        };
+        let retry = WebhookRetryPolicy {
+            max_retries: 2,
+            backoff: Duration::from_millis(10),
+        };
+        let undelivered = deliver_webhooks(
+            &Client::new(),
+            vec![delivery],
+            Duration::from_secs(1),
+            &retry,
+        )
+        .await;
+
+        (state.0.load(Ordering::SeqCst), undelivered)
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn failed_webhook_delivery_is_retried() {
+        let (attempts, undelivered) = deliver_to_flaky_receiver(2).await;
+        assert_eq!(attempts, 3);
+        assert!(undelivered.is_empty());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn webhook_is_dead_lettered_after_last_retry() {
+        let (attempts, undelivered) = deliver_to_flaky_receiver(usize::MAX).await;
+        assert_eq!(attempts, 3);
+        assert_eq!(undelivered.len(), 1);
+        assert_eq!(
+            undelivered[0].payload,
+            serde_json::json!({ "id": "delivery-1" })
+        );
+    }
 
     #[test]
     fn start_delay_works() {
@@ -101,4 +472,88 @@ mod tests {
         assert_eq!(get_start_delay(59 * 1000, 0), 1);
         assert_eq!(get_start_delay(59 * 1000, 1), 60);
     }
+
+    #[test]
+    fn reminder_deliveries_are_wrapped_in_envelope() {
+        let mut account = Account::default();
+        account.settings.webhook = Some(AccountWebhookSettings {
+            url: "https://example.com".into(),
+            key: "key".into(),
+        });
+        let account_without_webhook = Account::default();
+        let reminders = || AccountEventReminders {
+            events: vec![],
+            reminders: vec![],
+        };
+        let sent_at = 1000;
+
+        let deliveries = create_reminder_deliveries(
+            vec![
+                (account.clone(), reminders()),
+                (account_without_webhook, reminders()),
+                (account.clone(), reminders()),
+            ],
+            sent_at,
+        );
+        assert_eq!(deliveries.len(), 2);
+
+        let payloads = deliveries
+            .iter()
+            .map(|delivery| serde_json::to_value(&delivery.payload).unwrap())
+            .collect::<Vec<_>>();
+        for payload in &payloads {
+            assert!(payload["id"].is_string());
+            assert_eq!(payload["type"], "event.reminders");
+            assert_eq!(payload["sentAt"], sent_at);
+            assert_eq!(payload["accountId"], account.id.to_string());
+            assert!(payload["data"]["events"].is_array());
+        }
+        assert_ne!(payloads[0]["id"], payloads[1]["id"]);
+    }
+
+    #[test]
+    fn only_delivered_reminders_are_marked_as_sent() {
+        let delivered_account_id = ID::default();
+        let reminder = |account_id: &ID| Reminder {
+            id: Default::default(),
+            event_id: Default::default(),
+            account_id: account_id.clone(),
+            remind_at: 0,
+            priority: 0,
+            sent_at: None,
+            failed_at: None,
+        };
+        let dispatched_at = 1000;
+
+        let reminders = mark_dispatched_reminders(
+            vec![reminder(&delivered_account_id), reminder(&ID::default())],
+            &[delivered_account_id],
+            dispatched_at,
+        );
+        assert_eq!(reminders[0].sent_at, Some(dispatched_at));
+        assert_eq!(reminders[0].failed_at, None);
+        assert_eq!(reminders[1].sent_at, None);
+        assert_eq!(reminders[1].failed_at, Some(dispatched_at));
+        assert!(reminders.iter().all(|reminder| !reminder.is_pending()));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn shutdown_stops_job_after_current_iteration() {
+        let shutdown = Shutdown::new();
+        let runs = AtomicUsize::new(0);
+        let completed_runs = AtomicUsize::new(0);
+
+        run_until_shutdown(&shutdown, interval(Duration::from_millis(1)), || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            shutdown.trigger();
+            // The iteration that was running when shutdown was triggered still completes
+            delay_for(Duration::from_millis(20)).await;
+            completed_runs.fetch_add(1, Ordering::SeqCst);
+        })
+        .await;
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert_eq!(completed_runs.load(Ordering::SeqCst), 1);
+    }
 }
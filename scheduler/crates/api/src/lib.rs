@@ -9,12 +9,21 @@ mod shared;
 mod status;
 mod user;
 
-use actix_web::{dev::Server, middleware, web, App, HttpServer};
+use actix_web::{
+    dev::{BodyEncoding, BodySize, MessageBody, Server, Service},
+    http::ContentEncoding,
+    middleware, web, App, HttpServer,
+};
+use error::path_params_error_handler;
 use job_schedulers::{start_reminders_expansion_job_scheduler, start_send_reminders_job};
 use nettu_scheduler_infra::NettuContext;
 use std::net::TcpListener;
 use tracing_actix_web::TracingLogger;
 
+/// Responses smaller than this amount of bytes are sent uncompressed, as
+/// compressing them saves next to nothing
+const COMPRESSION_THRESHOLD_BYTES: u64 = 1024;
+
 pub fn configure_server_api(cfg: &mut web::ServiceConfig) {
     account::configure_routes(cfg);
     calendar::configure_routes(cfg);
@@ -28,14 +37,19 @@ pub fn configure_server_api(cfg: &mut web::ServiceConfig) {
 pub struct Application {
     server: Server,
     port: u16,
+    context: NettuContext,
 }
 
 impl Application {
     pub async fn new(context: NettuContext) -> Result<Self, std::io::Error> {
         let (server, port) = Application::configure_server(context.clone()).await?;
-        Application::start_job_schedulers(context);
+        Application::start_job_schedulers(context.clone());
 
-        Ok(Self { server, port })
+        Ok(Self {
+            server,
+            port,
+            context,
+        })
     }
 
     pub fn port(&self) -> u16 {
@@ -57,8 +71,22 @@ impl Application {
             let ctx = context.clone();
 
             App::new()
+                // Registered before `Compress` so that it runs on the response first
+                .wrap_fn(|req, srv| {
+                    let res = srv.call(req);
+                    async move {
+                        let mut res = res.await?;
+                        if let BodySize::Sized(size) = res.response().body().size() {
+                            if size < COMPRESSION_THRESHOLD_BYTES {
+                                res.response_mut().encoding(ContentEncoding::Identity);
+                            }
+                        }
+                        Ok(res)
+                    }
+                })
                 .wrap(middleware::Compress::default())
                 .wrap(TracingLogger)
+                .app_data(web::PathConfig::default().error_handler(path_params_error_handler))
                 .data(ctx)
                 .service(web::scope("/api/v1").configure(|cfg| configure_server_api(cfg)))
             // .configure(|cfg| configure_server_api(cfg))
@@ -70,7 +98,12 @@ impl Application {
         Ok((server, port))
     }
 
+    /// Runs the server until it is stopped, and then lets the job schedulers
+    /// finish the work they have in progress
     pub async fn start(self) -> Result<(), std::io::Error> {
-        self.server.await
+        let res = self.server.await;
+        self.context.shutdown.trigger();
+        self.context.shutdown.drained().await;
+        res
     }
 }
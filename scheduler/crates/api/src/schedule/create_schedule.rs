@@ -110,7 +110,7 @@ impl UseCase for CreateScheduleUseCase {
         let user = ctx
             .repos
             .user_repo
-            .find_by_account_id(&self.user_id, &self.account_id)
+            .find_for_account(&self.user_id, &self.account_id)
             .await;
         if user.is_none() {
             return Err(UseCaseErrors::UserNotFound(self.user_id.clone()));
@@ -121,6 +121,8 @@ impl UseCase for CreateScheduleUseCase {
         if let Some(rules) = &self.rules {
             schedule.rules = rules.clone();
         }
+        schedule.created = ctx.sys.get_timestamp_millis();
+        schedule.updated = schedule.created;
 
         let res = ctx.repos.schedule_repo.insert(&schedule).await;
         match res {
@@ -82,9 +82,10 @@ impl UseCase for DeleteScheduleUseCase {
         let schedule = ctx.repos.schedule_repo.find(&self.schedule_id).await;
         match schedule {
             Some(schedule) if schedule.user_id == self.user_id => {
-                let res = ctx.repos.schedule_repo.delete(&schedule.id).await;
-                if res.is_none() {
-                    return Err(UseCaseErrors::StorageError);
+                match ctx.repos.schedule_repo.delete(&schedule.id).await {
+                    Ok(Some(_)) => (),
+                    Ok(None) => return Err(UseCaseErrors::NotFound(schedule.id)),
+                    Err(_) => return Err(UseCaseErrors::StorageError),
                 }
                 let res = ctx
                     .repos
@@ -94,6 +95,7 @@ impl UseCase for DeleteScheduleUseCase {
                 if res.is_err() {
                     return Err(UseCaseErrors::StorageError);
                 }
+                ctx.booking_slots_cache.invalidate_user(&schedule.user_id);
 
                 Ok(schedule)
             }
@@ -81,3 +81,67 @@ impl UseCase for GetScheduleUseCase {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono_tz::Europe::Oslo;
+    use nettu_scheduler_domain::ScheduleRule;
+    use nettu_scheduler_infra::setup_context;
+
+    async fn read_schedule(ctx: &NettuContext, schedule: &Schedule) -> APIResponse {
+        let mut usecase = GetScheduleUseCase {
+            user_id: schedule.user_id.clone(),
+            schedule_id: schedule.id.clone(),
+        };
+        let schedule = usecase.execute(ctx).await.expect("To find schedule");
+        APIResponse::new(schedule)
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn reads_back_created_and_updated_schedule() {
+        let ctx = setup_context().await;
+        let mut schedule = Schedule::new(ID::default(), ID::default(), &Oslo);
+        ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+
+        let res = read_schedule(&ctx, &schedule).await;
+        assert_eq!(res.schedule.timezone, "Europe/Oslo");
+        assert_eq!(
+            serde_json::to_value(&res.schedule.rules).unwrap(),
+            serde_json::to_value(&schedule.rules).unwrap()
+        );
+
+        let rules: Vec<ScheduleRule> = serde_json::from_value(serde_json::json!([{
+            "variant": { "type": "WDay", "value": "Sat" },
+            "intervals": [{
+                "start": { "hours": 10, "minutes": 0 },
+                "end": { "hours": 14, "minutes": 30 }
+            }]
+        }]))
+        .unwrap();
+        assert!(schedule.set_rules(&rules));
+        ctx.repos.schedule_repo.save(&schedule).await.unwrap();
+
+        let res = read_schedule(&ctx, &schedule).await;
+        assert_eq!(
+            serde_json::to_value(&res.schedule.rules).unwrap(),
+            serde_json::to_value(&rules).unwrap()
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn does_not_read_schedule_of_other_user() {
+        let ctx = setup_context().await;
+        let schedule = Schedule::new(ID::default(), ID::default(), &Oslo);
+        ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+
+        let mut usecase = GetScheduleUseCase {
+            user_id: ID::default(),
+            schedule_id: schedule.id.clone(),
+        };
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::NotFound(_))));
+    }
+}
@@ -122,12 +122,20 @@ impl UseCase for UpdateScheduleUseCase {
             }
         };
         if let Some(rules) = &self.rules {
-            schedule.set_rules(rules);
+            if !schedule.set_rules(rules) {
+                return Err(UseCaseErrors::InvalidSettings(
+                    "The schedule rules have overlapping intervals on the same day".into(),
+                ));
+            }
         }
+        schedule.updated = ctx.sys.get_timestamp_millis();
 
         let repo_res = ctx.repos.schedule_repo.save(&schedule).await;
         match repo_res {
-            Ok(_) => Ok(UseCaseRes { schedule }),
+            Ok(_) => {
+                ctx.booking_slots_cache.invalidate_user(&schedule.user_id);
+                Ok(UseCaseRes { schedule })
+            }
             Err(_) => Err(UseCaseErrors::StorageError),
         }
     }
@@ -138,3 +146,41 @@ impl PermissionBoundary for UpdateScheduleUseCase {
         vec![Permission::UpdateSchedule]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_infra::{setup_context, ISys};
+    use std::sync::Arc;
+
+    struct StaticTimeSys;
+    impl ISys for StaticTimeSys {
+        fn get_timestamp_millis(&self) -> i64 {
+            1000
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn it_bumps_updated_and_keeps_created() {
+        let mut ctx = setup_context().await;
+        ctx.sys = Arc::new(StaticTimeSys);
+        let user_id = ID::default();
+        let mut schedule = Schedule::new(user_id.clone(), ID::default(), &chrono_tz::UTC);
+        schedule.created = 10;
+        schedule.updated = 10;
+        ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+
+        let mut usecase = UpdateScheduleUseCase {
+            user_id,
+            schedule_id: schedule.id.clone(),
+            timezone: Some("Europe/Oslo".into()),
+            rules: None,
+        };
+        assert!(usecase.execute(&ctx).await.is_ok());
+
+        let schedule = ctx.repos.schedule_repo.find(&schedule.id).await.unwrap();
+        assert_eq!(schedule.created, 10);
+        assert_eq!(schedule.updated, 1000);
+    }
+}
@@ -25,19 +25,26 @@ pub async fn add_user_to_service_controller(
         buffer: body.buffer,
         closest_booking_time: body.closest_booking_time,
         furthest_booking_time: body.furthest_booking_time,
+        timezone: body.timezone.to_owned(),
     };
 
-    execute(usecase, &ctx).await
-        .map(|res| HttpResponse::Ok().json(APIResponse::new(res.service)))
+    execute(usecase, &ctx)
+        .await
+        .map(|res| HttpResponse::Ok().json(APIResponse::new(res.service, res.created)))
         .map_err(|e| match e {
             UseCaseErrors::StorageError => NettuError::InternalError,
-            UseCaseErrors::ServiceNotFound => NettuError::NotFound("The requested service was not found".into()),
-            UseCaseErrors::UserNotFound => NettuError::NotFound("The specified user was not found".into()),
-            UseCaseErrors::UserAlreadyInService => NettuError::Conflict("The specified user is already registered on the service, can not add the user more than once.".into()),
+            UseCaseErrors::ServiceNotFound => {
+                NettuError::NotFound("The requested service was not found".into())
+            }
+            UseCaseErrors::UserNotFound => {
+                NettuError::NotFound("The specified user was not found".into())
+            }
             UseCaseErrors::InvalidValue(e) => e.to_nettu_error(),
         })
 }
 
+/// Adds the `User` to the `Service`, or updates the `ServiceResource` of the
+/// `User` if it is already in the `Service`
 #[derive(Debug)]
 struct AddUserToServiceUseCase {
     pub account: Account,
@@ -48,11 +55,13 @@ struct AddUserToServiceUseCase {
     pub buffer: Option<i64>,
     pub closest_booking_time: Option<i64>,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug)]
 struct UseCaseRes {
     pub service: Service,
+    pub created: bool,
 }
 
 #[derive(Debug)]
@@ -60,7 +69,6 @@ enum UseCaseErrors {
     StorageError,
     ServiceNotFound,
     UserNotFound,
-    UserAlreadyInService,
     InvalidValue(UpdateServiceResourceError),
 }
 
@@ -76,43 +84,56 @@ impl UseCase for AddUserToServiceUseCase {
         if ctx
             .repos
             .user_repo
-            .find_by_account_id(&self.user_id, &self.account.id)
+            .find_for_account(&self.user_id, &self.account.id)
             .await
             .is_none()
         {
             return Err(UseCaseErrors::UserNotFound);
         }
 
-        let mut service = match ctx.repos.service_repo.find(&self.service_id).await {
-            Some(service) if service.account_id == self.account.id => service,
+        let mut service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await
+        {
+            Some(service) => service,
             _ => return Err(UseCaseErrors::ServiceNotFound),
         };
 
-        if let Some(_user_resource) = service.find_user(&self.user_id) {
-            return Err(UseCaseErrors::UserAlreadyInService);
-        }
-
-        let mut user_resource = ServiceResource::new(self.user_id.clone(), TimePlan::Empty, vec![]);
-
-        update_resource_values(
-            &mut user_resource,
-            &ServiceResourceUpdate {
-                availibility: self.availibility.clone(),
-                busy: self.busy.clone(),
-                buffer: self.buffer,
-                closest_booking_time: self.closest_booking_time,
-                furthest_booking_time: self.furthest_booking_time,
-            },
-            ctx,
-        )
-        .await
-        .map_err(UseCaseErrors::InvalidValue)?;
+        let update = ServiceResourceUpdate {
+            availibility: self.availibility.clone(),
+            busy: self.busy.clone(),
+            buffer: self.buffer,
+            closest_booking_time: self.closest_booking_time,
+            furthest_booking_time: self.furthest_booking_time,
+            timezone: self.timezone.clone(),
+        };
 
-        service.add_user(user_resource);
+        let created = match service.find_user_mut(&self.user_id) {
+            Some(user_resource) => {
+                update_resource_values(user_resource, &update, ctx)
+                    .await
+                    .map_err(UseCaseErrors::InvalidValue)?;
+                false
+            }
+            None => {
+                let mut user_resource =
+                    ServiceResource::new(self.user_id.clone(), TimePlan::Empty, vec![]);
+                update_resource_values(&mut user_resource, &update, ctx)
+                    .await
+                    .map_err(UseCaseErrors::InvalidValue)?;
+                service.add_user(user_resource);
+                true
+            }
+        };
 
         let res = ctx.repos.service_repo.save(&service).await;
         match res {
-            Ok(_) => Ok(UseCaseRes { service }),
+            Ok(_) => {
+                ctx.booking_slots_cache.invalidate_service(&service.id);
+                Ok(UseCaseRes { service, created })
+            }
             Err(_) => Err(UseCaseErrors::StorageError),
         }
     }
@@ -124,6 +145,7 @@ pub struct ServiceResourceUpdate {
     pub buffer: Option<i64>,
     pub closest_booking_time: Option<i64>,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug)]
@@ -132,6 +154,7 @@ pub enum UpdateServiceResourceError {
     CalendarNotOwnedByUser(String),
     ScheduleNotOwnedByUser(String),
     InvalidBookingTimespan(String),
+    InvalidTimezone(String),
 }
 
 impl UpdateServiceResourceError {
@@ -150,6 +173,12 @@ impl UpdateServiceResourceError {
             Self::InvalidBookingTimespan(e) => {
                 NettuError::BadClientData(e.to_string())
             }
+            Self::InvalidTimezone(timezone) => {
+                NettuError::BadClientData(format!(
+                    "Invalid timezone: {}. It should be a valid IANA TimeZone.",
+                    timezone
+                ))
+            }
         }
     }
 }
@@ -228,5 +257,54 @@ pub async fn update_resource_values(
     }
     user_resource.furthest_booking_time = update.furthest_booking_time;
 
+    if let Some(timezone) = &update.timezone {
+        if !user_resource.set_timezone(timezone) {
+            return Err(UpdateServiceResourceError::InvalidTimezone(
+                timezone.to_string(),
+            ));
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::User;
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn adding_the_same_user_twice_updates_the_resource() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let service = Service::new(account.id.clone());
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let usecase = |buffer: i64, closest_booking_time: i64| AddUserToServiceUseCase {
+            account: account.clone(),
+            service_id: service.id.clone(),
+            user_id: user.id.clone(),
+            availibility: None,
+            busy: None,
+            buffer: Some(buffer),
+            closest_booking_time: Some(closest_booking_time),
+            furthest_booking_time: None,
+            timezone: None,
+        };
+
+        let res = usecase(10, 0).execute(&ctx).await.unwrap();
+        assert!(res.created);
+
+        let res = usecase(20, 1000 * 60 * 60).execute(&ctx).await.unwrap();
+        assert!(!res.created);
+
+        let service = ctx.repos.service_repo.find(&service.id).await.unwrap();
+        assert_eq!(service.users.len(), 1);
+        assert_eq!(service.users[0].buffer, 20);
+        assert_eq!(service.users[0].closest_booking_time, 1000 * 60 * 60);
+    }
+}
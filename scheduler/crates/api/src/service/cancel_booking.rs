@@ -0,0 +1,380 @@
+use crate::{
+    error::NettuError,
+    event::sync_event_reminders::{
+        EventOperation, SyncEventRemindersTrigger, SyncEventRemindersUseCase,
+    },
+    job_schedulers::{spawn_webhook_delivery, WebhookDelivery},
+    shared::{
+        auth::protect_account_route,
+        usecase::{execute, Subscriber, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::{
+    cancel_booking::*, send_account_event_deleted::AccountEventDeletedDTO,
+    send_account_webhook::AccountWebhookEnvelopeDTO,
+};
+use nettu_scheduler_domain::{Account, CalendarEvent, EventInstance, TimeSpan, ID};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn cancel_booking_controller(
+    http_req: HttpRequest,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+
+    let usecase = CancelBookingUseCase {
+        account,
+        service_id: path_params.service_id.clone(),
+        event_id: path_params.event_id.clone(),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|usecase_res| {
+            HttpResponse::Ok().json(APIResponse::new(usecase_res.event, usecase_res.freed))
+        })
+        .map_err(|e| match e {
+            UseCaseErrors::ServiceNotFound => NettuError::NotFound(format!(
+                "The service with id: {} was not found.",
+                path_params.service_id
+            )),
+            UseCaseErrors::BookingNotFound => NettuError::NotFound(format!(
+                "The booking with id: {} was not found in the service.",
+                path_params.event_id
+            )),
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+/// Cancels a booking by removing the service `CalendarEvent` that holds
+/// the booked slot, which makes the slot available again.
+#[derive(Debug)]
+struct CancelBookingUseCase {
+    account: Account,
+    service_id: ID,
+    event_id: ID,
+}
+
+#[derive(Debug)]
+struct UseCaseRes {
+    pub event: CalendarEvent,
+    /// The instances of the booking that are no longer occupied
+    pub freed: Vec<EventInstance>,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    ServiceNotFound,
+    BookingNotFound,
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for CancelBookingUseCase {
+    type Response = UseCaseRes;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "CancelBooking";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await
+        {
+            Some(service) => service,
+            _ => return Err(UseCaseErrors::ServiceNotFound),
+        };
+
+        let event = match ctx
+            .repos
+            .event_repo
+            .find_for_account(&self.event_id, &self.account.id)
+            .await
+        {
+            Some(event) if event.is_service && service.find_user(&event.user_id).is_some() => event,
+            _ => return Err(UseCaseErrors::BookingNotFound),
+        };
+
+        // A recurring booking frees all of its instances, but only the ones within
+        // the limit for querying event instances are reported
+        let settings = ctx
+            .repos
+            .calendar_repo
+            .find(&event.calendar_id)
+            .await
+            .map(|calendar| calendar.settings)
+            .unwrap_or_default();
+        let until = event
+            .start_ts
+            .saturating_add(ctx.config.event_instances_query_duration_limit);
+        let timespan = TimeSpan::new(event.start_ts, std::cmp::min(event.end_ts, until));
+        let freed = event.expand(Some(&timespan), &settings);
+
+        match ctx.repos.event_repo.delete(&event.id).await {
+            Ok(Some(_)) => (),
+            Ok(None) => return Err(UseCaseErrors::BookingNotFound),
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        }
+        ctx.booking_slots_cache.invalidate_user(&event.user_id);
+
+        Ok(UseCaseRes { event, freed })
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![
+            Box::new(DeleteRemindersOnBookingCancelled),
+            Box::new(NotifyWebhookOnBookingCancelled),
+        ]
+    }
+}
+
+struct DeleteRemindersOnBookingCancelled;
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<CancelBookingUseCase> for DeleteRemindersOnBookingCancelled {
+    async fn notify(&self, res: &UseCaseRes, ctx: &NettuContext) {
+        let sync_event_reminders = SyncEventRemindersUseCase {
+            request: SyncEventRemindersTrigger::EventModified(&res.event, EventOperation::Deleted),
+        };
+
+        // Sideeffect, ignore result
+        let _ = execute(sync_event_reminders, ctx).await;
+    }
+}
+
+struct NotifyWebhookOnBookingCancelled;
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<CancelBookingUseCase> for NotifyWebhookOnBookingCancelled {
+    async fn notify(&self, res: &UseCaseRes, ctx: &NettuContext) {
+        let account = match ctx.repos.account_repo.find(&res.event.account_id).await {
+            Some(account) => account,
+            None => return,
+        };
+        let webhook = match account.settings.webhook {
+            Some(webhook) => webhook,
+            None => return,
+        };
+
+        let payload = AccountWebhookEnvelopeDTO::new(
+            AccountEventDeletedDTO::TYPE,
+            account.id.clone(),
+            ctx.sys.get_timestamp_millis(),
+            AccountEventDeletedDTO::new(res.event.clone()),
+        );
+        spawn_webhook_delivery(
+            ctx,
+            WebhookDelivery {
+                account_id: account.id,
+                webhook,
+                payload,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::user::get_user_freebusy::GetFreeBusyUseCase;
+    use nettu_scheduler_domain::{
+        Calendar, EventStatus, RRuleOptions, Service, ServiceResource, TimePlan,
+    };
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn cancelling_booking_frees_the_slot() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+
+        let user_id = ID::default();
+        let calendar = Calendar::new(&user_id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let mut service = Service::new(account.id.clone());
+        service.add_user(ServiceResource::new(
+            user_id.clone(),
+            TimePlan::Empty,
+            vec![calendar.id.clone()],
+        ));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        let booking = CalendarEvent {
+            id: Default::default(),
+            start_ts: one_hour,
+            duration: one_hour,
+            busy: true,
+            status: EventStatus::Busy,
+            end_ts: one_hour * 2,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: calendar.id.clone(),
+            user_id: user_id.clone(),
+            account_id: account.id.clone(),
+            reminder: None,
+            is_service: true,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+        ctx.repos.event_repo.insert(&booking).await.unwrap();
+
+        let mut freebusy = GetFreeBusyUseCase {
+            user_id: user_id.clone(),
+            calendar_ids: Some(vec![calendar.id.clone()]),
+            start_ts: 0,
+            end_ts: one_hour * 4,
+            include_free: false,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: true,
+        };
+        let busy = freebusy.execute(&ctx).await.unwrap().busy.inner();
+        assert_eq!(
+            busy,
+            vec![EventInstance {
+                busy: true,
+                start_ts: one_hour,
+                end_ts: one_hour * 2,
+                event_id: None,
+            }]
+        );
+
+        let mut usecase = CancelBookingUseCase {
+            account,
+            service_id: service.id.clone(),
+            event_id: booking.id.clone(),
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        let freed = res
+            .freed
+            .iter()
+            .map(|instance| (instance.start_ts, instance.end_ts))
+            .collect::<Vec<_>>();
+        assert_eq!(freed, vec![(one_hour, one_hour * 2)]);
+
+        let busy = freebusy.execute(&ctx).await.unwrap().busy.inner();
+        assert!(busy.is_empty());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn cancelling_recurring_booking_reports_every_freed_instance() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        let user_id = ID::default();
+        let calendar = Calendar::new(&user_id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let mut service = Service::new(account.id.clone());
+        service.add_user(ServiceResource::new(
+            user_id.clone(),
+            TimePlan::Empty,
+            vec![calendar.id.clone()],
+        ));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        let one_day = one_hour * 24;
+        let mut booking = CalendarEvent {
+            id: Default::default(),
+            start_ts: one_hour,
+            duration: one_hour,
+            busy: true,
+            status: EventStatus::Busy,
+            end_ts: one_hour * 2,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: calendar.id.clone(),
+            user_id: user_id.clone(),
+            account_id: account.id.clone(),
+            reminder: None,
+            is_service: true,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+        let daily = RRuleOptions {
+            count: Some(3),
+            ..Default::default()
+        };
+        assert!(booking.set_recurrence(daily, &calendar.settings, true));
+        ctx.repos.event_repo.insert(&booking).await.unwrap();
+
+        let mut usecase = CancelBookingUseCase {
+            account,
+            service_id: service.id.clone(),
+            event_id: booking.id.clone(),
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        let freed = res
+            .freed
+            .iter()
+            .map(|instance| instance.start_ts)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            freed,
+            vec![one_hour, one_hour + one_day, one_hour + 2 * one_day]
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_cancelling_event_that_is_not_a_booking() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        let user_id = ID::default();
+        let mut service = Service::new(account.id.clone());
+        service.add_user(ServiceResource::new(
+            user_id.clone(),
+            TimePlan::Empty,
+            vec![],
+        ));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let event = CalendarEvent {
+            id: Default::default(),
+            start_ts: 0,
+            duration: 1000,
+            busy: true,
+            status: EventStatus::Busy,
+            end_ts: 1000,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id,
+            account_id: account.id.clone(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+
+        let mut usecase = CancelBookingUseCase {
+            account,
+            service_id: service.id.clone(),
+            event_id: event.id.clone(),
+        };
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::BookingNotFound)));
+        assert!(ctx.repos.event_repo.find(&event.id).await.is_some());
+    }
+}
@@ -2,7 +2,7 @@ use crate::shared::usecase::{execute, UseCase};
 use crate::{error::NettuError, shared::auth::protect_account_route};
 use actix_web::{web, HttpRequest, HttpResponse};
 use nettu_scheduler_api_structs::create_service::*;
-use nettu_scheduler_domain::{Account, Metadata, Service};
+use nettu_scheduler_domain::{Account, BookingStrategy, Metadata, Millis, Service, ID};
 use nettu_scheduler_infra::NettuContext;
 
 pub async fn create_service_controller(
@@ -12,15 +12,35 @@ pub async fn create_service_controller(
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
 
+    let body = body.0;
     let usecase = CreateServiceUseCase {
         account,
-        metadata: body.0.metadata.unwrap_or_default(),
+        booking_strategy: body.booking_strategy.unwrap_or_default(),
+        default_schedule_id: body.default_schedule_id,
+        min_lead_time: body.min_lead_time_ms.unwrap_or_default(),
+        allowed_durations: body.allowed_durations.unwrap_or_default(),
+        allow_overbooking: body.allow_overbooking.unwrap_or_default(),
+        timezone: body.timezone,
+        metadata: body.metadata.unwrap_or_default(),
     };
 
     execute(usecase, &ctx)
         .await
         .map(|usecase_res| HttpResponse::Created().json(APIResponse::new(usecase_res.service)))
         .map_err(|e| match e {
+            UseCaseErrors::ScheduleNotFound(id) => {
+                NettuError::NotFound(format!("Schedule with id: {} was not found.", id))
+            }
+            UseCaseErrors::InvalidMinLeadTime => NettuError::BadClientData(
+                "The provided min_lead_time_ms must not be negative".into(),
+            ),
+            UseCaseErrors::InvalidAllowedDurations => NettuError::BadClientData(
+                "The provided allowed_durations must all be positive".into(),
+            ),
+            UseCaseErrors::InvalidTimezone(timezone) => NettuError::BadClientData(format!(
+                "Invalid timezone: {}. It should be a valid IANA TimeZone.",
+                timezone
+            )),
             UseCaseErrors::StorageError => NettuError::InternalError,
         })
 }
@@ -28,6 +48,12 @@ pub async fn create_service_controller(
 #[derive(Debug)]
 struct CreateServiceUseCase {
     account: Account,
+    booking_strategy: BookingStrategy,
+    default_schedule_id: Option<ID>,
+    min_lead_time: Millis,
+    allowed_durations: Vec<i64>,
+    allow_overbooking: bool,
+    timezone: Option<String>,
     metadata: Metadata,
 }
 #[derive(Debug)]
@@ -38,6 +64,10 @@ struct UseCaseRes {
 #[derive(Debug)]
 enum UseCaseErrors {
     StorageError,
+    ScheduleNotFound(ID),
+    InvalidMinLeadTime,
+    InvalidAllowedDurations,
+    InvalidTimezone(String),
 }
 
 #[async_trait::async_trait(?Send)]
@@ -50,7 +80,33 @@ impl UseCase for CreateServiceUseCase {
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
         let mut service = Service::new(self.account.id.clone());
+        service.booking_strategy = self.booking_strategy.clone();
+        service.allow_overbooking = self.allow_overbooking;
         service.metadata = self.metadata.clone();
+        if !service.set_min_lead_time(self.min_lead_time) {
+            return Err(UseCaseErrors::InvalidMinLeadTime);
+        }
+        if !service.set_allowed_durations(self.allowed_durations.clone()) {
+            return Err(UseCaseErrors::InvalidAllowedDurations);
+        }
+        if let Some(timezone) = &self.timezone {
+            if !service.set_timezone(timezone) {
+                return Err(UseCaseErrors::InvalidTimezone(timezone.clone()));
+            }
+        }
+        if let Some(schedule_id) = &self.default_schedule_id {
+            match ctx
+                .repos
+                .schedule_repo
+                .find_for_account(schedule_id, &self.account.id)
+                .await
+            {
+                Some(schedule) => {
+                    service.default_schedule_id = Some(schedule.id);
+                }
+                _ => return Err(UseCaseErrors::ScheduleNotFound(schedule_id.clone())),
+            }
+        }
 
         let res = ctx.repos.service_repo.insert(&service).await;
         match res {
@@ -1,18 +1,22 @@
 use crate::{
     error::NettuError,
+    event::sync_event_reminders::{
+        EventOperation, SyncEventRemindersTrigger, SyncEventRemindersUseCase,
+    },
     shared::{
         auth::protect_account_route,
-        usecase::{execute, UseCase},
+        usecase::{execute, Subscriber, UseCase},
     },
 };
 use actix_web::{web, HttpRequest, HttpResponse};
 use nettu_scheduler_api_structs::delete_service::*;
-use nettu_scheduler_domain::{Account, Service, ID};
+use nettu_scheduler_domain::{Account, CalendarEvent, Service, ID};
 use nettu_scheduler_infra::NettuContext;
 
 pub async fn delete_service_controller(
     http_req: HttpRequest,
     path_params: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
@@ -20,6 +24,7 @@ pub async fn delete_service_controller(
     let usecase = DeleteServiceUseCase {
         account,
         service_id: path_params.service_id.clone(),
+        force: query_params.force,
     };
 
     execute(usecase, &ctx)
@@ -30,24 +35,34 @@ pub async fn delete_service_controller(
                 "The service with id: {} was not found.",
                 path_params.service_id
             )),
+            UseCaseErrors::HasUpcomingBookings(count) => NettuError::Conflict(format!(
+                "The service has {} upcoming booking(s). Cancel them first or set force to also delete them.",
+                count
+            )),
             UseCaseErrors::StorageError => NettuError::InternalError,
         })
 }
 
+/// Deletes a `Service`. Upcoming bookings of the `Service` would be orphaned by this,
+/// so the deletion is refused while there are any, unless `force` is set, in which
+/// case the bookings are deleted as well.
 #[derive(Debug)]
 struct DeleteServiceUseCase {
     account: Account,
     service_id: ID,
+    force: bool,
 }
 
 #[derive(Debug)]
 struct UseCaseRes {
     pub service: Service,
+    pub deleted_bookings: Vec<CalendarEvent>,
 }
 
 #[derive(Debug)]
 enum UseCaseErrors {
     NotFound,
+    HasUpcomingBookings(usize),
     StorageError,
 }
 
@@ -60,21 +75,263 @@ impl UseCase for DeleteServiceUseCase {
     const NAME: &'static str = "DeleteService";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let res = ctx.repos.service_repo.find(&self.service_id).await;
-        match res {
-            Some(service) if service.account_id == self.account.id => {
-                if ctx
-                    .repos
-                    .service_repo
-                    .delete(&self.service_id)
-                    .await
-                    .is_none()
-                {
-                    return Err(UseCaseErrors::StorageError);
+        let service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await
+        {
+            Some(service) => service,
+            _ => return Err(UseCaseErrors::NotFound),
+        };
+
+        let bookings = match ctx
+            .repos
+            .event_repo
+            .find_by_service_after(&service.id, ctx.sys.get_timestamp_millis())
+            .await
+        {
+            Ok(bookings) => bookings,
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        };
+        if !bookings.is_empty() && !self.force {
+            return Err(UseCaseErrors::HasUpcomingBookings(bookings.len()));
+        }
+
+        match ctx.repos.service_repo.delete(&self.service_id).await {
+            Ok(Some(_)) => (),
+            Ok(None) => return Err(UseCaseErrors::NotFound),
+            Err(_) => return Err(UseCaseErrors::StorageError),
+        }
+        ctx.booking_slots_cache.invalidate_service(&service.id);
+
+        let mut deleted_bookings = Vec::with_capacity(bookings.len());
+        for booking in bookings {
+            match ctx.repos.event_repo.delete(&booking.id).await {
+                Ok(Some(_)) => {
+                    ctx.booking_slots_cache.invalidate_user(&booking.user_id);
+                    deleted_bookings.push(booking);
                 }
-                Ok(UseCaseRes { service })
+                // Already cancelled in the meantime
+                Ok(None) => (),
+                Err(_) => return Err(UseCaseErrors::StorageError),
+            }
+        }
+
+        Ok(UseCaseRes {
+            service,
+            deleted_bookings,
+        })
+    }
+
+    fn subscribers() -> Vec<Box<dyn Subscriber<Self>>> {
+        vec![Box::new(DeleteRemindersOnBookingsDeleted)]
+    }
+}
+
+struct DeleteRemindersOnBookingsDeleted;
+
+#[async_trait::async_trait(?Send)]
+impl Subscriber<DeleteServiceUseCase> for DeleteRemindersOnBookingsDeleted {
+    async fn notify(&self, res: &UseCaseRes, ctx: &NettuContext) {
+        for booking in &res.deleted_bookings {
+            let sync_event_reminders = SyncEventRemindersUseCase {
+                request: SyncEventRemindersTrigger::EventModified(booking, EventOperation::Deleted),
+            };
+
+            // Sideeffect, ignore result
+            let _ = execute(sync_event_reminders, ctx).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::{EventStatus, ServiceResource, TimePlan};
+    use nettu_scheduler_infra::{setup_context, DeleteResult, IServiceRepo, MetadataFindQuery};
+    use std::sync::Arc;
+
+    /// Finds services in the wrapped repo, but deleting them either fails
+    /// or behaves as if the service was already deleted
+    struct StubDeleteServiceRepo {
+        inner: Arc<dyn IServiceRepo>,
+        delete_fails: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl IServiceRepo for StubDeleteServiceRepo {
+        async fn insert(&self, service: &Service) -> anyhow::Result<()> {
+            self.inner.insert(service).await
+        }
+        async fn save(&self, service: &Service) -> anyhow::Result<()> {
+            self.inner.save(service).await
+        }
+        async fn find(&self, service_id: &ID) -> Option<Service> {
+            self.inner.find(service_id).await
+        }
+        async fn find_for_account(&self, service_id: &ID, account_id: &ID) -> Option<Service> {
+            self.inner.find_for_account(service_id, account_id).await
+        }
+        async fn count(&self) -> anyhow::Result<usize> {
+            self.inner.count().await
+        }
+        async fn delete(&self, _service_id: &ID) -> anyhow::Result<Option<Service>> {
+            if self.delete_fails {
+                Err(anyhow::anyhow!("Storage is unavailable"))
+            } else {
+                Ok(None)
             }
-            _ => Err(UseCaseErrors::NotFound),
         }
+        async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+            self.inner.delete_by_account(account_id).await
+        }
+        async fn remove_calendar_from_services(&self, calendar_id: &ID) -> anyhow::Result<()> {
+            self.inner.remove_calendar_from_services(calendar_id).await
+        }
+        async fn remove_schedule_from_services(&self, schedule_id: &ID) -> anyhow::Result<()> {
+            self.inner.remove_schedule_from_services(schedule_id).await
+        }
+        async fn remove_user_from_services(&self, user_id: &ID) -> anyhow::Result<()> {
+            self.inner.remove_user_from_services(user_id).await
+        }
+        async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Service> {
+            self.inner.find_by_metadata(query).await
+        }
+        async fn find_by_account(
+            &self,
+            account_id: &ID,
+            skip: usize,
+            limit: usize,
+        ) -> anyhow::Result<Vec<Service>> {
+            self.inner.find_by_account(account_id, skip, limit).await
+        }
+    }
+
+    async fn setup(delete_fails: bool) -> (NettuContext, DeleteServiceUseCase) {
+        let mut ctx = setup_context().await;
+        let account = Account::default();
+        let service = Service::new(account.id.clone());
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+        ctx.repos.service_repo = Arc::new(StubDeleteServiceRepo {
+            inner: ctx.repos.service_repo.clone(),
+            delete_fails,
+        });
+
+        let usecase = DeleteServiceUseCase {
+            account,
+            service_id: service.id,
+            force: false,
+        };
+        (ctx, usecase)
+    }
+
+    async fn insert_booking(service: &Service, start_ts: i64, ctx: &NettuContext) -> CalendarEvent {
+        let booking = CalendarEvent {
+            id: Default::default(),
+            start_ts,
+            duration: 1000 * 60 * 30,
+            end_ts: start_ts + 1000 * 60 * 30,
+            busy: true,
+            status: EventStatus::Busy,
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id: service.users[0].user_id.clone(),
+            account_id: service.account_id.clone(),
+            reminder: None,
+            is_service: true,
+            service_id: Some(service.id.clone()),
+            metadata: Default::default(),
+            external_id: None,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+        };
+        ctx.repos.event_repo.insert(&booking).await.unwrap();
+        booking
+    }
+
+    async fn setup_with_bookings(
+        force: bool,
+    ) -> (
+        NettuContext,
+        DeleteServiceUseCase,
+        CalendarEvent,
+        CalendarEvent,
+        CalendarEvent,
+    ) {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        let mut service = Service::new(account.id.clone());
+        service.add_user(ServiceResource::new(ID::default(), TimePlan::Empty, vec![]));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let hour = 1000 * 60 * 60;
+        let now = ctx.sys.get_timestamp_millis();
+        let past = insert_booking(&service, now - 2 * hour, &ctx).await;
+        let upcoming = insert_booking(&service, now + hour, &ctx).await;
+        // The same user is also booked through another service
+        let mut other_service = Service::new(account.id.clone());
+        other_service.add_user(service.users[0].clone());
+        let other_booking = insert_booking(&other_service, now + hour, &ctx).await;
+
+        let usecase = DeleteServiceUseCase {
+            account,
+            service_id: service.id,
+            force,
+        };
+        (ctx, usecase, past, upcoming, other_booking)
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn deleting_missing_service_is_not_found() {
+        let (ctx, mut usecase) = setup(false).await;
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::NotFound)));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn storage_failure_is_not_reported_as_not_found() {
+        let (ctx, mut usecase) = setup(true).await;
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::StorageError)));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn refuses_to_delete_service_with_upcoming_bookings() {
+        let (ctx, mut usecase, _, upcoming, _) = setup_with_bookings(false).await;
+        let res = usecase.execute(&ctx).await;
+        assert!(matches!(res, Err(UseCaseErrors::HasUpcomingBookings(1))));
+        assert!(ctx
+            .repos
+            .service_repo
+            .find(&usecase.service_id)
+            .await
+            .is_some());
+        assert!(ctx.repos.event_repo.find(&upcoming.id).await.is_some());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn forced_deletion_also_deletes_upcoming_bookings() {
+        let (ctx, mut usecase, past, upcoming, other_booking) = setup_with_bookings(true).await;
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(res.deleted_bookings.len(), 1);
+        assert_eq!(res.deleted_bookings[0].id, upcoming.id);
+        assert!(ctx
+            .repos
+            .service_repo
+            .find(&usecase.service_id)
+            .await
+            .is_none());
+        assert!(ctx.repos.event_repo.find(&upcoming.id).await.is_none());
+        // Bookings that have already ended are kept as history
+        assert!(ctx.repos.event_repo.find(&past.id).await.is_some());
+        // Bookings of other services are left alone
+        assert!(ctx.repos.event_repo.find(&other_booking.id).await.is_some());
     }
 }
@@ -57,9 +57,13 @@ impl UseCase for GetServiceUseCase {
     const NAME: &'static str = "GetService";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let res = ctx.repos.service_repo.find(&self.service_id).await;
+        let res = ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await;
         match res {
-            Some(service) if service.account_id == self.account.id => Ok(UseCaseRes { service }),
+            Some(service) => Ok(UseCaseRes { service }),
             _ => Err(UseCaseErrors::NotFound),
         }
     }
@@ -0,0 +1,251 @@
+use super::get_service_bookingslots::GetServiceBookingSlotsUseCase;
+use crate::error::NettuError;
+use crate::shared::{
+    locale::{translate, translate_booking_query_errors, Locale, Message},
+    usecase::{execute, UseCase},
+};
+use crate::user::get_user_freebusy::parse_vec_query_value;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures::future::join_all;
+use nettu_scheduler_api_structs::get_service_availability::*;
+use nettu_scheduler_domain::{
+    booking_slots::{get_service_free_events, validate_booking_day, BookingQueryError},
+    CompatibleInstances, EventInstance, TimeSpan, ID,
+};
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn get_service_availability_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let locale = Locale::from_request(&http_req);
+
+    let query_params = query_params.0;
+    let usecase = GetServiceAvailabilityUseCase {
+        service_id: path_params.service_id.clone(),
+        date: query_params.date,
+        iana_tz: query_params.iana_tz,
+        utc_offset_minutes: query_params.utc_offset_minutes,
+        exclude_user_ids: parse_vec_query_value(&query_params.exclude_user_ids),
+        user_ids: parse_vec_query_value(&query_params.user_ids),
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|free| HttpResponse::Ok().json(APIResponse { free: free.inner() }))
+        .map_err(|e| match e {
+            UseCaseErrors::InvalidQuery(errors) => {
+                NettuError::BadClientData(translate_booking_query_errors(&errors, locale))
+            }
+            UseCaseErrors::InvalidTimespan => {
+                NettuError::BadClientData(translate(Message::InvalidTimespan, locale))
+            }
+            UseCaseErrors::ServiceNotFound => NettuError::NotFound(translate(
+                Message::ServiceNotFound(&path_params.service_id.to_string()),
+                locale,
+            )),
+        })
+        .map_err(|e| e.localize(locale))
+}
+
+/// Finds the free intervals of a `Service` on the given date, which are the times
+/// the booking slots would be generated within, for clients showing a free/busy bar
+#[derive(Debug)]
+struct GetServiceAvailabilityUseCase {
+    pub service_id: ID,
+    pub date: String,
+    pub iana_tz: Option<String>,
+    pub utc_offset_minutes: Option<i32>,
+    /// `User`s in the `Service` that should not be considered
+    pub exclude_user_ids: Option<Vec<ID>>,
+    /// When set, only these `User`s in the `Service` are considered
+    pub user_ids: Option<Vec<ID>>,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    ServiceNotFound,
+    /// Every invalid field of the query
+    InvalidQuery(Vec<BookingQueryError>),
+    InvalidTimespan,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetServiceAvailabilityUseCase {
+    type Response = CompatibleInstances;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetServiceAvailability";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let service = match ctx.repos.service_repo.find(&self.service_id).await {
+            Some(s) => s,
+            None => return Err(UseCaseErrors::ServiceNotFound),
+        };
+
+        // Same timezone fallbacks as for the booking slots
+        if self.iana_tz.is_none() && self.utc_offset_minutes.is_none() {
+            self.iana_tz = service.timezone.map(|tz| tz.to_string());
+        }
+        if self.iana_tz.is_none() && self.utc_offset_minutes.is_none() {
+            if let Some(account) = ctx.repos.account_repo.find(&service.account_id).await {
+                self.iana_tz = account.settings.default_timezone.map(|tz| tz.to_string());
+            }
+        }
+
+        let booking_timespan =
+            match validate_booking_day(&self.date, &self.iana_tz, self.utc_offset_minutes) {
+                Ok(t) => t,
+                Err(errors) => return Err(UseCaseErrors::InvalidQuery(errors)),
+            };
+        let timespan = TimeSpan::new(booking_timespan.start_ts, booking_timespan.end_ts);
+        if timespan.greater_than(ctx.config.booking_slots_query_duration_limit) {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
+
+        let excluded = self.exclude_user_ids.as_deref().unwrap_or_default();
+        let users_free_events = join_all(
+            service
+                .users
+                .iter()
+                .filter(|user| !excluded.contains(&user.user_id))
+                .filter(|user| match &self.user_ids {
+                    Some(user_ids) => user_ids.contains(&user.user_id),
+                    None => true,
+                })
+                .map(|user| {
+                    GetServiceBookingSlotsUseCase::get_bookable_times(
+                        &service,
+                        user,
+                        timespan.clone(),
+                        ctx,
+                    )
+                }),
+        )
+        .await;
+
+        let mut free = get_service_free_events(users_free_events, &service.booking_strategy);
+
+        let first_bookable = ctx
+            .sys
+            .get_timestamp_millis()
+            .saturating_add(service.min_lead_time.0);
+        if first_bookable > booking_timespan.start_ts {
+            let within_lead_time = CompatibleInstances::new(vec![EventInstance {
+                start_ts: booking_timespan.start_ts,
+                end_ts: first_bookable,
+                busy: true,
+                event_id: None,
+            }]);
+            free.remove_intances(&within_lead_time, 0);
+        }
+
+        Ok(free)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{test, App};
+    use nettu_scheduler_domain::{
+        BookingStrategy, Calendar, CalendarEvent, EventStatus, Service, ServiceResource, TimePlan,
+    };
+    use nettu_scheduler_infra::{setup_context, ISys};
+    use std::sync::Arc;
+
+    struct DummySys {}
+
+    impl ISys for DummySys {
+        fn get_timestamp_millis(&self) -> i64 {
+            0
+        }
+    }
+
+    const HOUR: i64 = 1000 * 60 * 60;
+
+    /// Adds a `User` to the `Service` who is available within the given timespans
+    async fn add_available_user(
+        ctx: &NettuContext,
+        service: &mut Service,
+        available: &[(i64, i64)],
+    ) {
+        let mut resource = ServiceResource {
+            id: Default::default(),
+            user_id: Default::default(),
+            buffer: 0,
+            availibility: TimePlan::Empty,
+            busy: vec![],
+            closest_booking_time: 0,
+            furthest_booking_time: None,
+            timezone: None,
+        };
+        let calendar = Calendar::new(&resource.user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        resource.availibility = TimePlan::Calendar(calendar.id.clone());
+
+        for (start_ts, end_ts) in available {
+            let event =
+                CalendarEvent::new(&calendar, *start_ts, end_ts - start_ts, EventStatus::Free);
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+        }
+
+        service.add_user(resource);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn free_intervals_follow_booking_strategy() {
+        let mut ctx = setup_context().await;
+        ctx.sys = Arc::new(DummySys {});
+
+        let mut service = Service::new(Default::default());
+        add_available_user(
+            &ctx,
+            &mut service,
+            &[(HOUR, 3 * HOUR), (6 * HOUR, 7 * HOUR)],
+        )
+        .await;
+        add_available_user(&ctx, &mut service, &[(2 * HOUR, 4 * HOUR)]).await;
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+        let free_intervals = |service_id: &ID| {
+            test::TestRequest::get()
+                .uri(&format!(
+                    "/service/{}/availability?date=1970-1-1&ianaTz=UTC",
+                    service_id
+                ))
+                .to_request()
+        };
+        let free = |start_ts: i64, end_ts: i64| EventInstance {
+            start_ts,
+            end_ts,
+            busy: false,
+            event_id: None,
+        };
+
+        // Any of the users can take a round robin booking
+        let res: APIResponse =
+            test::read_response_json(&mut app, free_intervals(&service.id)).await;
+        assert_eq!(
+            res.free,
+            vec![free(HOUR, 4 * HOUR), free(6 * HOUR, 7 * HOUR)]
+        );
+
+        // Every user has to be available for a collective booking
+        service.booking_strategy = BookingStrategy::Collective;
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        let res: APIResponse =
+            test::read_response_json(&mut app, free_intervals(&service.id)).await;
+        assert_eq!(res.free, vec![free(2 * HOUR, 3 * HOUR)]);
+    }
+}
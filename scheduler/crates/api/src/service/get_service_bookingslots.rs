@@ -1,84 +1,128 @@
 use crate::error::NettuError;
-use crate::shared::usecase::{execute, UseCase};
-use actix_web::{web, HttpRequest, HttpResponse};
+use crate::shared::{
+    locale::{translate, translate_booking_query_errors, Locale, Message},
+    usecase::{execute, UseCase},
+};
+use crate::user::get_user_freebusy::parse_vec_query_value;
+use actix_web::{error::QueryPayloadError, web, HttpRequest, HttpResponse};
 use futures::future::join_all;
 use nettu_scheduler_api_structs::get_service_bookingslots::*;
 use nettu_scheduler_domain::{
     booking_slots::{
-        get_service_bookingslots, validate_bookingslots_query, validate_slots_interval,
-        BookingQueryError, BookingSlotsOptions, BookingSlotsQuery, ServiceBookingSlot,
+        get_service_bookingslots, validate_bookingslots_query, BookingQueryError,
+        BookingSlotsOptions, BookingSlotsQuery, BookingTimezone, ServiceBookingSlot, SlotStart,
         UserFreeEvents,
     },
-    get_free_busy, Calendar, CompatibleInstances, EventInstance, ServiceResource, TimePlan,
-    TimeSpan, ID,
+    get_free_busy, Calendar, CompatibleInstances, EventInstance, Millis, Service, ServiceResource,
+    TimePlan, TimeSpan, ID,
 };
-use nettu_scheduler_infra::NettuContext;
+use nettu_scheduler_infra::{NettuContext, ServiceBookingSlotsCacheKey};
+use std::collections::HashMap;
 use tracing::warn;
 
+/// Creates a descriptive error when the query params could not be deserialized,
+/// instead of the default actix error which does not say which field is invalid
+pub fn query_params_error_handler(err: QueryPayloadError, req: &HttpRequest) -> actix_web::Error {
+    let query = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .map(|query| query.into_inner())
+        .unwrap_or_default();
+
+    // Only the `duration` is required, the `interval` defaults to it
+    let invalid_millis_field =
+        [("duration", true), ("interval", false)]
+            .iter()
+            .find(|(field, required)| match query.get(*field) {
+                Some(val) => val.parse::<i64>().is_err(),
+                None => *required,
+            });
+
+    let locale = Locale::from_request(req);
+    let msg = match invalid_millis_field {
+        Some((field, _)) => translate(Message::InvalidMillis(field), locale),
+        None => err.to_string(),
+    };
+    NettuError::BadClientData(msg).localize(locale).into()
+}
+
 pub async fn get_service_bookingslots_controller(
-    _http_req: HttpRequest,
+    http_req: HttpRequest,
     query_params: web::Query<QueryParams>,
     path_params: web::Path<PathParams>,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
+    let locale = Locale::from_request(&http_req);
+
     let usecase = GetServiceBookingSlotsUseCase {
         service_id: path_params.service_id.clone(),
         iana_tz: query_params.iana_tz.clone(),
+        utc_offset_minutes: query_params.utc_offset_minutes,
         date: query_params.date.clone(),
         duration: query_params.duration,
-        interval: query_params.interval,
+        interval: query_params.interval.unwrap_or(query_params.duration),
+        slot_start: query_params.slot_start.unwrap_or(SlotStart::Interval),
+        exclude_user_ids: parse_vec_query_value(&query_params.exclude_user_ids),
+        user_ids: parse_vec_query_value(&query_params.user_ids),
     };
 
-    execute(usecase, &ctx).await
+    execute(usecase, &ctx)
+        .await
         .map(|usecase_res| {
-            HttpResponse::Ok().json(APIResponse::new(usecase_res.booking_slots))
-        })
-        .map_err(|e| match e {
-            UseCaseErrors::InvalidDate(msg) => {
-                NettuError::BadClientData(format!(
-                    "Invalid datetime: {}. Should be YYYY-MM-DD, e.g. January 1. 2020 => 2020-1-1",
-                    msg
-                ))
-            }
-            UseCaseErrors::InvalidTimezone(msg) => {
-                NettuError::BadClientData(format!(
-                    "Invalid timezone: {}. It should be a valid IANA TimeZone.",
-                    msg
-                ))
-            }
-            UseCaseErrors::InvalidInterval => {
-                NettuError::BadClientData(
-                    "Invalid interval specified. It should be between 10 - 60 minutes inclusively and be specified as milliseconds.".into()
-                )
-            }
-            UseCaseErrors::InvalidTimespan => {
-                NettuError::BadClientData("The provided start_ts and end_ts is invalid".into())
-            }
-            UseCaseErrors::ServiceNotFound => NettuError::NotFound(format!("Service with id: {}, was not found.", path_params.service_id)),
+            HttpResponse::Ok().json(APIResponse::new(
+                usecase_res.booking_slots,
+                &usecase_res.timezone,
+            ))
         })
+        .map_err(|e| handle_error(e, &path_params.service_id, locale))
+}
+
+pub(super) fn handle_error(e: UseCaseErrors, service_id: &ID, locale: Locale) -> NettuError {
+    let e = match e {
+        UseCaseErrors::InvalidQuery(errors) => {
+            NettuError::BadClientData(translate_booking_query_errors(&errors, locale))
+        }
+        UseCaseErrors::InvalidTimespan => {
+            NettuError::BadClientData(translate(Message::InvalidTimespan, locale))
+        }
+        UseCaseErrors::DurationNotAllowed(allowed) => {
+            NettuError::BadClientData(translate(Message::DurationNotAllowed(&allowed), locale))
+        }
+        UseCaseErrors::ServiceNotFound => NettuError::NotFound(translate(
+            Message::ServiceNotFound(&service_id.to_string()),
+            locale,
+        )),
+    };
+    e.localize(locale)
 }
 
 #[derive(Debug)]
-struct GetServiceBookingSlotsUseCase {
+pub(super) struct GetServiceBookingSlotsUseCase {
     pub service_id: ID,
     pub date: String,
     pub iana_tz: Option<String>,
+    pub utc_offset_minutes: Option<i32>,
     pub duration: i64,
     pub interval: i64,
+    pub slot_start: SlotStart,
+    /// `User`s in the `Service` that should not be considered when computing the booking slots
+    pub exclude_user_ids: Option<Vec<ID>>,
+    /// When set, only these `User`s in the `Service` are considered
+    pub user_ids: Option<Vec<ID>>,
 }
 
 #[derive(Debug)]
-struct UseCaseRes {
-    booking_slots: Vec<ServiceBookingSlot>,
+pub(super) struct UseCaseRes {
+    pub booking_slots: Vec<ServiceBookingSlot>,
+    pub timezone: BookingTimezone,
 }
 
 #[derive(Debug)]
-enum UseCaseErrors {
+pub(super) enum UseCaseErrors {
     ServiceNotFound,
-    InvalidInterval,
+    /// Every invalid field of the query
+    InvalidQuery(Vec<BookingQueryError>),
     InvalidTimespan,
-    InvalidDate(String),
-    InvalidTimezone(String),
+    /// The requested duration is not one of these allowed durations of the `Service`
+    DurationNotAllowed(Vec<i64>),
 }
 
 #[async_trait::async_trait(?Send)]
@@ -90,72 +134,150 @@ impl UseCase for GetServiceBookingSlotsUseCase {
     const NAME: &'static str = "GetServiceBookingSlots";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        if !validate_slots_interval(self.interval) {
-            return Err(UseCaseErrors::InvalidInterval);
+        // Read before anything is loaded, so that changes made while the free times
+        // are computed keep them from being cached
+        let generation = ctx.booking_slots_cache.generation();
+        let service = match ctx.repos.service_repo.find(&self.service_id).await {
+            Some(s) => s,
+            None => return Err(UseCaseErrors::ServiceNotFound),
+        };
+        if !service.allows_duration(self.duration) {
+            return Err(UseCaseErrors::DurationNotAllowed(
+                service.allowed_durations.clone(),
+            ));
+        }
+
+        // Queries without a timezone use the timezone of the `Service`, and otherwise
+        // the default timezone of the `Account`
+        if self.iana_tz.is_none() && self.utc_offset_minutes.is_none() {
+            self.iana_tz = service.timezone.map(|tz| tz.to_string());
+        }
+        if self.iana_tz.is_none() && self.utc_offset_minutes.is_none() {
+            if let Some(account) = ctx.repos.account_repo.find(&service.account_id).await {
+                self.iana_tz = account.settings.default_timezone.map(|tz| tz.to_string());
+            }
         }
 
         let query = BookingSlotsQuery {
             date: self.date.clone(),
             iana_tz: self.iana_tz.clone(),
+            utc_offset_minutes: self.utc_offset_minutes,
             interval: self.interval,
             duration: self.duration,
         };
         let booking_timespan = match validate_bookingslots_query(&query) {
             Ok(t) => t,
-            Err(e) => match e {
-                BookingQueryError::InvalidInterval => return Err(UseCaseErrors::InvalidInterval),
-                BookingQueryError::InvalidDate(d) => return Err(UseCaseErrors::InvalidDate(d)),
-                BookingQueryError::InvalidTimezone(d) => {
-                    return Err(UseCaseErrors::InvalidTimezone(d))
-                }
-            },
-        };
-
-        let service = match ctx.repos.service_repo.find(&self.service_id).await {
-            Some(s) => s,
-            None => return Err(UseCaseErrors::ServiceNotFound),
+            Err(errors) => return Err(UseCaseErrors::InvalidQuery(errors)),
         };
 
-        let mut usecase_futures: Vec<_> = Vec::with_capacity(service.users.len());
-
         let timespan = TimeSpan::new(booking_timespan.start_ts, booking_timespan.end_ts);
         if timespan.greater_than(ctx.config.booking_slots_query_duration_limit) {
             return Err(UseCaseErrors::InvalidTimespan);
         }
 
-        for user in &service.users {
-            let timespan = timespan.clone();
-            usecase_futures.push(self.get_bookable_times(user, timespan, ctx));
-        }
+        let excluded = self.exclude_user_ids.as_deref().unwrap_or_default();
+        let users = service
+            .users
+            .iter()
+            .filter(|user| !excluded.contains(&user.user_id))
+            .filter(|user| match &self.user_ids {
+                Some(user_ids) => user_ids.contains(&user.user_id),
+                None => true,
+            })
+            .collect::<Vec<_>>();
+
+        let cache_key = ServiceBookingSlotsCacheKey::new(
+            &service.id,
+            &self.date,
+            &booking_timespan.tz.to_string(),
+            excluded,
+            self.user_ids.as_deref(),
+        );
+        let now = ctx.sys.get_timestamp_millis();
+        let users_free_events = match ctx.booking_slots_cache.get(&cache_key, now) {
+            Some(users_free_events) => users_free_events,
+            None => {
+                let mut user_ids = service
+                    .users
+                    .iter()
+                    .map(|u| u.user_id.clone())
+                    .collect::<Vec<_>>();
+                // Changes to the default `Schedule` should also invalidate the cached free times
+                if let Some(schedule_id) = &service.default_schedule_id {
+                    if let Some(schedule) = ctx.repos.schedule_repo.find(schedule_id).await {
+                        user_ids.push(schedule.user_id);
+                    }
+                }
+                let mut usecase_futures: Vec<_> = Vec::with_capacity(users.len());
+                for user in &users {
+                    let timespan = timespan.clone();
+                    usecase_futures.push(Self::get_free_times(&service, user, timespan, ctx));
+                }
+                let users_free_events = join_all(usecase_futures).await;
 
-        let users_free_events = join_all(usecase_futures).await;
+                ctx.booking_slots_cache.insert(
+                    cache_key,
+                    user_ids,
+                    users_free_events.clone(),
+                    now,
+                    generation,
+                );
+                users_free_events
+            }
+        };
 
-        let booking_slots = get_service_bookingslots(
+        // When the `User`s can be booked depends on the current time, so it is not cached
+        let users_free_events = users
+            .into_iter()
+            .zip(users_free_events)
+            .map(|(user, user_free)| {
+                Self::restrict_to_booking_window(user, user_free, &timespan, ctx)
+            })
+            .collect::<Vec<_>>();
+
+        let mut booking_slots = get_service_bookingslots(
             users_free_events,
             &BookingSlotsOptions {
-                interval: self.interval,
-                duration: self.duration,
+                interval: Millis(self.interval),
+                slot_start: self.slot_start,
+                duration: Millis(self.duration),
                 end_ts: booking_timespan.end_ts,
                 start_ts: booking_timespan.start_ts,
+                max_slots: ctx.config.max_booking_slots,
             },
+            &service.booking_strategy,
         );
+        remove_slots_within_lead_time(&mut booking_slots, &service, now);
 
-        Ok(UseCaseRes { booking_slots })
+        Ok(UseCaseRes {
+            booking_slots,
+            timezone: booking_timespan.tz,
+        })
     }
 }
 
+/// Removes the booking slots that start sooner than the minimum lead time of the `Service`
+fn remove_slots_within_lead_time(
+    booking_slots: &mut Vec<ServiceBookingSlot>,
+    service: &Service,
+    now: i64,
+) {
+    let first_bookable = now.saturating_add(service.min_lead_time.0);
+    booking_slots.retain(|slot| slot.start >= first_bookable);
+}
+
 impl GetServiceBookingSlotsUseCase {
     async fn get_user_availibility(
-        &self,
+        service: &Service,
         user: &ServiceResource,
         user_calendars: &[Calendar],
         timespan: &TimeSpan,
         ctx: &NettuContext,
     ) -> CompatibleInstances {
         let empty = CompatibleInstances::new(vec![]);
-        match &user.availibility {
+        match service.get_availibility(user) {
             TimePlan::Calendar(id) => {
-                let calendar = match user_calendars.iter().find(|cal| cal.id == *id) {
+                let calendar = match user_calendars.iter().find(|cal| cal.id == id) {
                     Some(cal) => cal,
                     None => {
                         return empty;
@@ -178,20 +300,31 @@ impl GetServiceBookingSlotsUseCase {
             }
             TimePlan::Schedule(id) => match ctx.repos.schedule_repo.find(&id).await {
                 Some(schedule) if schedule.user_id == user.user_id => schedule.freebusy(&timespan),
+                // The default `Schedule` is shared by all the users in the `Service`
+                Some(schedule)
+                    if service.default_schedule_id.as_ref() == Some(&schedule.id)
+                        && schedule.account_id == service.account_id =>
+                {
+                    schedule.freebusy(&timespan)
+                }
                 _ => empty,
             },
             TimePlan::Empty => empty,
         }
     }
 
+    /// Finds the busy times of a `User`, where the bookings of the `Service` are
+    /// returned separately from the other busy events, including the bookings
+    /// of other `Service`s
     async fn get_user_busy(
-        &self,
+        service: &Service,
         user: &ServiceResource,
         busy_calendars: &[&Calendar],
         timespan: &TimeSpan,
         ctx: &NettuContext,
-    ) -> CompatibleInstances {
+    ) -> (CompatibleInstances, CompatibleInstances) {
         let mut busy_events: Vec<EventInstance> = vec![];
+        let mut booked_events: Vec<EventInstance> = vec![];
 
         for cal in busy_calendars {
             match ctx
@@ -201,26 +334,23 @@ impl GetServiceBookingSlotsUseCase {
                 .await
             {
                 Ok(calendar_events) => {
-                    let mut calendar_busy_events = calendar_events
-                        .into_iter()
-                        .filter(|e| e.busy)
-                        .map(|e| {
-                            let mut instances = e.expand(Some(&timespan), &cal.settings);
-
-                            // Add buffer to instances if event is a service event
-                            if user.buffer > 0 && e.is_service {
-                                let buffer_in_millis = user.buffer * 60 * 1000;
-                                for instance in instances.iter_mut() {
-                                    instance.end_ts += buffer_in_millis;
-                                }
-                            }
+                    for e in calendar_events.into_iter().filter(|e| e.busy) {
+                        let mut instances = e.expand(Some(&timespan), &cal.settings);
 
-                            instances
-                        })
-                        .flatten()
-                        .collect::<Vec<_>>();
+                        // Add buffer to instances if event is a service event
+                        if user.buffer > 0 && e.is_service {
+                            let buffer_in_millis = user.buffer * 60 * 1000;
+                            for instance in instances.iter_mut() {
+                                instance.end_ts += buffer_in_millis;
+                            }
+                        }
 
-                    busy_events.append(&mut calendar_busy_events);
+                        if e.service_id.as_ref() == Some(&service.id) {
+                            booked_events.append(&mut instances);
+                        } else {
+                            busy_events.append(&mut instances);
+                        }
+                    }
                 }
                 Err(e) => {
                     warn!("Unable to fetch user calendars: {}", e);
@@ -229,7 +359,10 @@ impl GetServiceBookingSlotsUseCase {
         }
 
         // This should be optimized later
-        CompatibleInstances::new(busy_events)
+        (
+            CompatibleInstances::new(busy_events),
+            CompatibleInstances::new(booked_events),
+        )
     }
 
     /// Ensure that calendar timespan fits within user settings for when
@@ -262,42 +395,86 @@ impl GetServiceBookingSlotsUseCase {
     }
 
     /// Finds the bookable times for a `User`.
-    async fn get_bookable_times(
-        &self,
+    pub(super) async fn get_bookable_times(
+        service: &Service,
         user: &ServiceResource,
-        mut timespan: TimeSpan,
+        timespan: TimeSpan,
         ctx: &NettuContext,
     ) -> UserFreeEvents {
-        let empty = UserFreeEvents {
-            free_events: CompatibleInstances::new(vec![]),
-            user_id: user.id.clone(),
-        };
-
         match Self::parse_calendar_timespan(user, timespan, ctx) {
-            Ok(parsed_timespan) => timespan = parsed_timespan,
-            Err(_) => return empty,
+            Ok(timespan) => Self::get_free_times(service, user, timespan, ctx).await,
+            Err(_) => UserFreeEvents {
+                free_events: CompatibleInstances::new(vec![]),
+                booked_events: CompatibleInstances::new(vec![]),
+                user_id: user.user_id.clone(),
+            },
         }
+    }
 
+    /// Finds the free times for a `User`, regardless of how soon or how far
+    /// into the future the `User` can be booked
+    async fn get_free_times(
+        service: &Service,
+        user: &ServiceResource,
+        timespan: TimeSpan,
+        ctx: &NettuContext,
+    ) -> UserFreeEvents {
         let user_calendars = ctx.repos.calendar_repo.find_by_user(&user.user_id).await;
         let busy_calendars = user_calendars
             .iter()
             .filter(|cal| user.busy.contains(&cal.id))
             .collect::<Vec<_>>();
 
-        let mut free_events = self
-            .get_user_availibility(user, &user_calendars, &timespan, ctx)
-            .await;
+        let mut free_events =
+            Self::get_user_availibility(service, user, &user_calendars, &timespan, ctx).await;
 
-        let busy_events = self
-            .get_user_busy(user, &busy_calendars, &timespan, ctx)
-            .await;
+        let (busy_events, mut booked_events) =
+            Self::get_user_busy(service, user, &busy_calendars, &timespan, ctx).await;
 
         free_events.remove_intances(&busy_events, 0);
+        // Without overbooking the existing bookings are as busy as any other event
+        if !service.allow_overbooking {
+            free_events.remove_intances(&booked_events, 0);
+            booked_events = CompatibleInstances::new(vec![]);
+        }
 
         UserFreeEvents {
             free_events,
-            user_id: user.id.clone(),
+            booked_events,
+            user_id: user.user_id.clone(),
+        }
+    }
+
+    /// Removes the free times of a `User` that are sooner than its `closest_booking_time`
+    /// or further into the future than its `furthest_booking_time`
+    fn restrict_to_booking_window(
+        user: &ServiceResource,
+        mut user_free: UserFreeEvents,
+        timespan: &TimeSpan,
+        ctx: &NettuContext,
+    ) -> UserFreeEvents {
+        match Self::parse_calendar_timespan(user, timespan.clone(), ctx) {
+            Ok(window) => {
+                let outside_window = [
+                    (timespan.start(), window.start()),
+                    (window.end(), timespan.end()),
+                ]
+                .iter()
+                .filter(|(start_ts, end_ts)| start_ts < end_ts)
+                .map(|(start_ts, end_ts)| EventInstance {
+                    start_ts: *start_ts,
+                    end_ts: *end_ts,
+                    busy: true,
+                    event_id: None,
+                })
+                .collect();
+                user_free
+                    .free_events
+                    .remove_intances(&CompatibleInstances::new(outside_window), 0);
+            }
+            Err(_) => user_free.free_events = CompatibleInstances::new(vec![]),
         }
+        user_free
     }
 }
 
@@ -306,9 +483,15 @@ mod test {
     use std::sync::Arc;
 
     use super::*;
+    use crate::event::create_event::CreateEventUseCase;
+    use actix_web::{http::StatusCode, test, App};
     use chrono::prelude::*;
     use chrono::Utc;
-    use nettu_scheduler_domain::{Calendar, CalendarEvent, RRuleOptions, Service, ServiceResource};
+    use chrono_tz::America::{Los_Angeles, New_York};
+    use nettu_scheduler_domain::{
+        Account, BookingStrategy, Calendar, CalendarEvent, EventStatus, RRuleOptions, Schedule,
+        Service, ServiceResource,
+    };
     use nettu_scheduler_infra::{setup_context, ISys};
 
     struct TestContext {
@@ -343,6 +526,7 @@ mod test {
             busy: vec![],
             closest_booking_time: 0,
             furthest_booking_time: None,
+            timezone: None,
         };
         let mut resource2 = ServiceResource {
             id: Default::default(),
@@ -352,6 +536,7 @@ mod test {
             busy: vec![],
             closest_booking_time: 0,
             furthest_booking_time: None,
+            timezone: None,
         };
 
         let account_id = ID::default();
@@ -378,51 +563,63 @@ mod test {
             id: Default::default(),
             account_id: account_id.clone(),
             busy: false,
+            status: EventStatus::Free,
             calendar_id: calendar_user_1.id,
             duration: 1000 * 60 * 60,
-            end_ts: 0,
+            end_ts: 1000 * 60 * 60 * 2,
             exdates: vec![],
             recurrence: None,
             start_ts: 1000 * 60 * 60,
             user_id: resource1.user_id.to_owned(),
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let availibility_event2 = CalendarEvent {
             id: ID::default(),
             account_id: account_id.clone(),
             busy: false,
+            status: EventStatus::Free,
             calendar_id: calendar_user_2.id.clone(),
             duration: 1000 * 60 * 60,
-            end_ts: 0,
+            end_ts: 1000 * 60 * 60 * 2,
             exdates: vec![],
             recurrence: None,
             start_ts: 1000 * 60 * 60,
             user_id: resource2.user_id.to_owned(),
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let mut availibility_event3 = CalendarEvent {
             id: ID::default(),
             account_id: account_id.clone(),
             busy: false,
+            status: EventStatus::Free,
             calendar_id: calendar_user_2.id,
             duration: 1000 * 60 * 105,
-            end_ts: 0,
+            end_ts: 1000 * 60 * 60 * 4 + 1000 * 60 * 105,
             exdates: vec![],
             recurrence: None,
             start_ts: 1000 * 60 * 60 * 4,
             user_id: resource1.user_id.to_owned(),
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let recurrence = RRuleOptions {
@@ -460,8 +657,12 @@ mod test {
             date: "2010-1-1".into(),
             duration: 1000 * 60 * 60,
             iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
             interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
             service_id: service.id,
+            exclude_user_ids: None,
+            user_ids: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -479,8 +680,12 @@ mod test {
             date: "2010-1-1".into(),
             duration: 1000 * 60 * 60,
             iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
             interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
             service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -502,8 +707,12 @@ mod test {
             date: "1970-1-1".into(),
             duration: 1000 * 60 * 60,
             iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
             interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
             service_id: service.id,
+            exclude_user_ids: None,
+            user_ids: None,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -524,4 +733,820 @@ mod test {
             }
         }
     }
+
+    #[actix_web::main]
+    #[test]
+    async fn get_collective_bookingslots_with_multiple_users_in_service() {
+        let TestContext { ctx, mut service } = setup().await;
+        service.booking_strategy = BookingStrategy::Collective;
+        setup_service_users(&ctx, &mut service).await;
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-1".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+
+        let res = usecase.execute(&ctx).await;
+        assert!(res.is_ok());
+        assert!(res.unwrap().booking_slots.is_empty());
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "1970-1-1".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
+            service_id: service.id,
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+
+        let res = usecase.execute(&ctx).await;
+        assert!(res.is_ok());
+        let booking_slots = res.unwrap().booking_slots;
+        assert_eq!(booking_slots.len(), 1);
+        assert_eq!(booking_slots[0].user_ids.len(), 2);
+        assert_eq!(
+            booking_slots[0].start,
+            Utc.ymd(1970, 1, 1).and_hms(1, 0, 0).timestamp_millis()
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn get_bookingslots_excluding_a_user_in_service() {
+        let TestContext { ctx, mut service } = setup().await;
+        setup_service_users(&ctx, &mut service).await;
+        let excluded_user = service.users[0].user_id.clone();
+        let included_user = service.users[1].user_id.clone();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "1970-1-1".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: Some(vec![excluded_user]),
+            user_ids: None,
+        };
+
+        let res = usecase.execute(&ctx).await;
+        assert!(res.is_ok());
+        let booking_slots = res.unwrap().booking_slots;
+        // The excluded user is only available at 1:00, where the other user is available as well
+        assert_eq!(booking_slots.len(), 5);
+        assert_eq!(
+            booking_slots[0].start,
+            Utc.ymd(1970, 1, 1).and_hms(1, 0, 0).timestamp_millis()
+        );
+        for slot in booking_slots {
+            assert_eq!(slot.user_ids, vec![included_user.clone()]);
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn get_bookingslots_for_requested_users_only() {
+        let TestContext { ctx, mut service } = setup().await;
+
+        // Every user is available from 9:00 to 17:30 on weekdays
+        for _ in 0..3 {
+            let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+            let schedule = Schedule::new(resource.user_id.clone(), ID::default(), &Tz::UTC);
+            ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+            resource.set_availibility(TimePlan::Schedule(schedule.id.clone()));
+            service.add_user(resource);
+        }
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        let requested = service.users[1].clone();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 60,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: Some(vec![requested.user_id.clone()]),
+        };
+
+        let booking_slots = usecase.execute(&ctx).await.unwrap().booking_slots;
+        assert!(!booking_slots.is_empty());
+        for slot in booking_slots {
+            assert_eq!(slot.user_ids, vec![requested.user_id.clone()]);
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn get_bookingslots_with_users_in_different_timezones() {
+        let TestContext { ctx, mut service } = setup().await;
+
+        // Both schedules are available from 9:00 to 17:30 on weekdays in their own timezone
+        for timezone in &[New_York, Los_Angeles] {
+            let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+            let schedule = Schedule::new(resource.user_id.clone(), ID::default(), timezone);
+            ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+            resource.set_availibility(TimePlan::Schedule(schedule.id.clone()));
+            assert!(resource.set_timezone(timezone.name()));
+            service.add_user(resource);
+        }
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        let new_york_user = service.users[0].user_id.clone();
+        let los_angeles_user = service.users[1].user_id.clone();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 60,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+
+        let res = usecase.execute(&ctx).await;
+        assert!(res.is_ok());
+        let booking_slots = res.unwrap().booking_slots;
+        let slot_at = |hour: u32| {
+            let start = Utc.ymd(2010, 1, 4).and_hms(hour, 0, 0).timestamp_millis();
+            booking_slots.iter().find(|slot| slot.start == start)
+        };
+
+        // New York is UTC-5 and Los Angeles is UTC-8 in January
+        assert!(slot_at(13).is_none());
+        assert_eq!(slot_at(14).unwrap().user_ids, vec![new_york_user.clone()]);
+        assert_eq!(slot_at(16).unwrap().user_ids, vec![new_york_user.clone()]);
+        for hour in 17..22 {
+            let slot = slot_at(hour).unwrap();
+            assert!(slot.user_ids.contains(&new_york_user));
+            assert!(slot.user_ids.contains(&los_angeles_user));
+        }
+        assert_eq!(slot_at(22).unwrap().user_ids, vec![los_angeles_user]);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn users_without_availibility_inherit_the_default_schedule() {
+        let TestContext { ctx, mut service } = setup().await;
+
+        // The default schedule is owned by someone outside of the service
+        let default_schedule = Schedule::new(ID::default(), service.account_id.clone(), &New_York);
+        ctx.repos
+            .schedule_repo
+            .insert(&default_schedule)
+            .await
+            .unwrap();
+        service.default_schedule_id = Some(default_schedule.id.clone());
+
+        let inheriting = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let mut own = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let own_schedule = Schedule::new(own.user_id.clone(), ID::default(), &Los_Angeles);
+        ctx.repos.schedule_repo.insert(&own_schedule).await.unwrap();
+        own.set_availibility(TimePlan::Schedule(own_schedule.id.clone()));
+        let inheriting_id = inheriting.user_id.clone();
+        let own_id = own.user_id.clone();
+        service.add_user(inheriting);
+        service.add_user(own);
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 60,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let booking_slots = usecase.execute(&ctx).await.unwrap().booking_slots;
+        let slot_at = |hour: u32| {
+            let start = Utc.ymd(2010, 1, 4).and_hms(hour, 0, 0).timestamp_millis();
+            booking_slots.iter().find(|slot| slot.start == start)
+        };
+
+        assert_eq!(slot_at(14).unwrap().user_ids, vec![inheriting_id]);
+        assert_eq!(slot_at(17).unwrap().user_ids.len(), 2);
+        assert_eq!(slot_at(22).unwrap().user_ids, vec![own_id]);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn query_without_timezone_uses_the_default_timezone_of_the_account() {
+        let TestContext { ctx, service } = setup().await;
+        let mut account = Account::default();
+        account.id = service.account_id.clone();
+        assert!(account
+            .settings
+            .set_default_timezone(Some("America/New_York")));
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: None,
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 60,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(res.timezone, BookingTimezone::Iana(New_York));
+
+        // The timezone of the query takes precedence
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: 1000 * 60 * 60,
+            iana_tz: Some(Los_Angeles.to_string()),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 60,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(res.timezone, BookingTimezone::Iana(Los_Angeles));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn query_without_timezone_groups_slots_by_the_days_of_the_service_timezone() {
+        let TestContext { ctx, mut service } = setup().await;
+        let mut account = Account::default();
+        account.id = service.account_id.clone();
+        assert!(account
+            .settings
+            .set_default_timezone(Some("America/Los_Angeles")));
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+
+        let hour = 1000 * 60 * 60;
+        let day_start = Utc.ymd(2010, 1, 4).and_hms(0, 0, 0).timestamp_millis();
+        let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let calendar = Calendar::new(&resource.user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let event = CalendarEvent::new(
+            &calendar,
+            day_start - 12 * hour,
+            48 * hour,
+            EventStatus::Free,
+        );
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+        resource.set_availibility(TimePlan::Calendar(calendar.id.clone()));
+        service.add_user(resource);
+        // The timezone of the service takes precedence over the one of the account
+        assert!(service.set_timezone("America/New_York"));
+        assert!(!service.set_timezone("Mars/Olympus"));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: hour,
+            iana_tz: None,
+            utc_offset_minutes: None,
+            interval: hour,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(res.timezone, BookingTimezone::Iana(New_York));
+        // 2010-1-4 in New York is from 05:00 UTC to 05:00 UTC the next day
+        let starts = res
+            .booking_slots
+            .iter()
+            .map(|s| s.start)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            (5..29).map(|h| day_start + h * hour).collect::<Vec<_>>()
+        );
+    }
+
+    struct StaticTimeSys(i64);
+
+    impl ISys for StaticTimeSys {
+        fn get_timestamp_millis(&self) -> i64 {
+            self.0
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn removes_slots_within_the_min_lead_time_of_the_service() {
+        let TestContext {
+            mut ctx,
+            mut service,
+        } = setup().await;
+        let hour = 1000 * 60 * 60;
+        let day_start = Utc.ymd(2010, 1, 4).and_hms(0, 0, 0).timestamp_millis();
+        ctx.sys = Arc::new(StaticTimeSys(day_start + 6 * hour));
+
+        let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let calendar = Calendar::new(&resource.user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let event = CalendarEvent::new(&calendar, day_start, 12 * hour, EventStatus::Free);
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+        resource.set_availibility(TimePlan::Calendar(calendar.id.clone()));
+        service.add_user(resource);
+        assert!(service.set_min_lead_time(Millis::from_hours(2)));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: hour,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: hour,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let booking_slots = usecase.execute(&ctx).await.unwrap().booking_slots;
+        let starts = booking_slots.iter().map(|s| s.start).collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            (8..12).map(|h| day_start + h * hour).collect::<Vec<_>>()
+        );
+
+        // A lead time that is further away than any timestamp leaves no slots
+        let mut booking_slots = booking_slots;
+        assert!(service.set_min_lead_time(Millis(i64::MAX)));
+        remove_slots_within_lead_time(&mut booking_slots, &service, day_start);
+        assert!(booking_slots.is_empty());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn only_allows_the_durations_offered_by_the_service() {
+        let TestContext { ctx, mut service } = setup().await;
+        let minute = 1000 * 60;
+        assert!(service.set_allowed_durations(vec![60 * minute, 30 * minute]));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let service_id = service.id.clone();
+        let usecase = |duration| GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 15 * minute,
+            slot_start: SlotStart::Interval,
+            service_id: service_id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let res = usecase(45 * minute).execute(&ctx).await;
+        match res {
+            Err(UseCaseErrors::DurationNotAllowed(allowed)) => {
+                assert_eq!(allowed, vec![30 * minute, 60 * minute])
+            }
+            _ => panic!("Expected the duration to not be allowed"),
+        }
+        assert!(usecase(30 * minute).execute(&ctx).await.is_ok());
+        assert!(usecase(60 * minute).execute(&ctx).await.is_ok());
+
+        // Any duration is allowed when the service has no allowed durations
+        assert!(service.set_allowed_durations(vec![]));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        assert!(usecase(45 * minute).execute(&ctx).await.is_ok());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_window_shorter_than_duration() {
+        let TestContext { ctx, service } = setup().await;
+        let day = 1000 * 60 * 60 * 24;
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: day + 1,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        assert!(matches!(
+            usecase.execute(&ctx).await,
+            Err(UseCaseErrors::InvalidQuery(errors))
+                if errors == vec![BookingQueryError::WindowShorterThanDuration]
+        ));
+
+        usecase.duration = day;
+        assert!(usecase.execute(&ctx).await.is_ok());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn caches_bookingslots_until_a_blocking_event_is_created() {
+        let TestContext { ctx, mut service } = setup().await;
+
+        let user_id = ID::default();
+        let account_id = ID::default();
+        let availibility_calendar = Calendar::new(&user_id, &account_id);
+        let busy_calendar = Calendar::new(&user_id, &account_id);
+        ctx.repos
+            .calendar_repo
+            .insert(&availibility_calendar)
+            .await
+            .unwrap();
+        ctx.repos
+            .calendar_repo
+            .insert(&busy_calendar)
+            .await
+            .unwrap();
+        service.add_user(ServiceResource::new(
+            user_id.clone(),
+            TimePlan::Calendar(availibility_calendar.id.clone()),
+            vec![busy_calendar.id.clone()],
+        ));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let hour = 1000 * 60 * 60;
+        ctx.repos
+            .event_repo
+            .insert(&CalendarEvent::new(
+                &availibility_calendar,
+                hour,
+                2 * hour,
+                EventStatus::Free,
+            ))
+            .await
+            .unwrap();
+
+        let usecase = || GetServiceBookingSlotsUseCase {
+            date: "1970-1-1".into(),
+            duration: hour,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: 1000 * 60 * 15,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+
+        let booking_slots = usecase().execute(&ctx).await.unwrap().booking_slots;
+        assert_eq!(booking_slots.len(), 5);
+        assert_eq!(booking_slots[0].start, hour);
+
+        // Written directly to the repo, so the cache is not invalidated and
+        // the second query is served the previously computed slots
+        ctx.repos
+            .event_repo
+            .insert(&CalendarEvent::new(
+                &availibility_calendar,
+                3 * hour,
+                hour,
+                EventStatus::Free,
+            ))
+            .await
+            .unwrap();
+        let cached_booking_slots = usecase().execute(&ctx).await.unwrap().booking_slots;
+        assert_eq!(cached_booking_slots, booking_slots);
+
+        let create_busy_event = CreateEventUseCase {
+            account_id,
+            calendar_id: busy_calendar.id.clone(),
+            user_id,
+            start_ts: hour,
+            duration: 2 * hour,
+            busy: true,
+            status: None,
+            recurrence: None,
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        };
+        execute(create_busy_event, &ctx).await.unwrap();
+
+        let booking_slots = usecase().execute(&ctx).await.unwrap().booking_slots;
+        assert_eq!(booking_slots.len(), 1);
+        assert_eq!(booking_slots[0].start, 3 * hour);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn applies_the_booking_window_of_the_users_to_cached_slots() {
+        let TestContext {
+            mut ctx,
+            mut service,
+        } = setup().await;
+        let hour = 1000 * 60 * 60;
+        let day_start = Utc.ymd(2010, 1, 4).and_hms(0, 0, 0).timestamp_millis();
+        ctx.sys = Arc::new(StaticTimeSys(day_start + 6 * hour));
+
+        let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let calendar = Calendar::new(&resource.user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let event = CalendarEvent::new(&calendar, day_start, 24 * hour, EventStatus::Free);
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+        resource.set_availibility(TimePlan::Calendar(calendar.id.clone()));
+        resource.closest_booking_time = 60;
+        resource.furthest_booking_time = Some(4 * 60);
+        service.add_user(resource);
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: hour,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: hour,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let starts = |booking_slots: Vec<ServiceBookingSlot>| {
+            booking_slots.iter().map(|s| s.start).collect::<Vec<_>>()
+        };
+        let booking_slots = usecase.execute(&ctx).await.unwrap().booking_slots;
+        assert_eq!(
+            starts(booking_slots),
+            (7..10).map(|h| day_start + h * hour).collect::<Vec<_>>()
+        );
+
+        // The free times are cached, but the booking window moves with the time
+        ctx.sys = Arc::new(StaticTimeSys(day_start + 8 * hour));
+        let booking_slots = usecase.execute(&ctx).await.unwrap().booking_slots;
+        assert_eq!(
+            starts(booking_slots),
+            (9..12).map(|h| day_start + h * hour).collect::<Vec<_>>()
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn fully_booked_slots_are_only_offered_when_overbooking_is_allowed() {
+        let TestContext { ctx, mut service } = setup().await;
+        let hour = 1000 * 60 * 60;
+
+        let user_id = ID::default();
+        let availibility_calendar = Calendar::new(&user_id, &service.account_id);
+        let busy_calendar = Calendar::new(&user_id, &service.account_id);
+        for calendar in &[&availibility_calendar, &busy_calendar] {
+            ctx.repos.calendar_repo.insert(calendar).await.unwrap();
+        }
+        service.add_user(ServiceResource::new(
+            user_id,
+            TimePlan::Calendar(availibility_calendar.id.clone()),
+            vec![busy_calendar.id.clone()],
+        ));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        ctx.repos
+            .event_repo
+            .insert(&CalendarEvent::new(
+                &availibility_calendar,
+                hour,
+                3 * hour,
+                EventStatus::Free,
+            ))
+            .await
+            .unwrap();
+        // An existing booking from 2:00 to 3:00
+        let mut booking = CalendarEvent::new(&busy_calendar, 2 * hour, hour, EventStatus::Busy);
+        booking.is_service = true;
+        booking.service_id = Some(service.id.clone());
+        ctx.repos.event_repo.insert(&booking).await.unwrap();
+        // A booking of another service from 3:00 to 4:00, which the user is just busy with
+        let mut other_booking =
+            CalendarEvent::new(&busy_calendar, 3 * hour, hour, EventStatus::Busy);
+        other_booking.is_service = true;
+        other_booking.service_id = Some(ID::default());
+        ctx.repos.event_repo.insert(&other_booking).await.unwrap();
+
+        let usecase = || GetServiceBookingSlotsUseCase {
+            date: "1970-1-1".into(),
+            duration: hour,
+            iana_tz: Utc.to_string().into(),
+            utc_offset_minutes: None,
+            interval: hour,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+
+        let booking_slots = usecase().execute(&ctx).await.unwrap().booking_slots;
+        let slots = booking_slots
+            .iter()
+            .map(|s| (s.start, s.overbook))
+            .collect::<Vec<_>>();
+        assert_eq!(slots, vec![(hour, false)]);
+
+        service.allow_overbooking = true;
+        ctx.repos.service_repo.save(&service).await.unwrap();
+        ctx.booking_slots_cache.invalidate_service(&service.id);
+        let booking_slots = usecase().execute(&ctx).await.unwrap().booking_slots;
+        let slots = booking_slots
+            .iter()
+            .map(|s| (s.start, s.overbook))
+            .collect::<Vec<_>>();
+        assert_eq!(slots, vec![(hour, false), (2 * hour, true)]);
+        assert_eq!(
+            booking_slots[1].user_ids,
+            vec![service.users[0].user_id.clone()]
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_non_numeric_duration_with_descriptive_error() {
+        let TestContext { ctx, service } = setup().await;
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration=abc&interval=900000&date=2010-1-1",
+                service.id
+            ))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(res).await;
+        assert!(String::from_utf8_lossy(&body)
+            .contains("duration must be an integer number of milliseconds"));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_non_numeric_duration_in_the_requested_language() {
+        let TestContext { ctx, service } = setup().await;
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration=abc&interval=900000&date=2010-1-1",
+                service.id
+            ))
+            .header("Accept-Language", "nb-NO")
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(res).await;
+        assert_eq!(
+            String::from_utf8_lossy(&body),
+            "Ugyldige data. Feilmelding: `duration må være et heltall i millisekunder`"
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn omitted_interval_defaults_to_back_to_back_slots() {
+        let TestContext { ctx, mut service } = setup().await;
+        setup_service_users(&ctx, &mut service).await;
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+
+        let duration = 1000 * 60 * 30;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration={}&date=2010-1-1&ianaTz=UTC",
+                service.id, duration
+            ))
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        let starts = res
+            .booking_slots
+            .iter()
+            .map(|slot| slot.start)
+            .collect::<Vec<_>>();
+        // The availibility from 04:00 to 05:45 fits three slots without overlap
+        assert_eq!(
+            starts,
+            vec![
+                Utc.ymd(2010, 1, 1).and_hms(4, 0, 0).timestamp_millis(),
+                Utc.ymd(2010, 1, 1).and_hms(4, 30, 0).timestamp_millis(),
+                Utc.ymd(2010, 1, 1).and_hms(5, 0, 0).timestamp_millis(),
+            ]
+        );
+        for pair in starts.windows(2) {
+            assert_eq!(pair[1] - pair[0], duration);
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn reports_all_invalid_query_params_at_once() {
+        let TestContext { ctx, service } = setup().await;
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration=1800000&interval=1000&date=2010-1-1&ianaTz=Mars/Olympus",
+                service.id
+            ))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(res).await;
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("interval: Invalid interval specified"));
+        assert!(body.contains("ianaTz: Invalid timezone: Mars/Olympus"));
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration=1800000&date=2010-1-1&ianaTz=UTC&utcOffsetMinutes=120",
+                service.id
+            ))
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = test::read_body(res).await;
+        assert!(String::from_utf8_lossy(&body).starts_with("utcOffsetMinutes: "));
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn utc_offset_of_query_takes_precedence_over_the_service_timezone() {
+        let TestContext { ctx, mut service } = setup().await;
+        let hour = 1000 * 60 * 60;
+        let day_start = Utc.ymd(2010, 1, 4).and_hms(0, 0, 0).timestamp_millis();
+        let mut resource = ServiceResource::new(Default::default(), TimePlan::Empty, vec![]);
+        let calendar = Calendar::new(&resource.user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let event = CalendarEvent::new(
+            &calendar,
+            day_start - 12 * hour,
+            48 * hour,
+            EventStatus::Free,
+        );
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+        resource.set_availibility(TimePlan::Calendar(calendar.id.clone()));
+        service.add_user(resource);
+        assert!(service.set_timezone("America/New_York"));
+        ctx.repos.service_repo.save(&service).await.unwrap();
+
+        let mut usecase = GetServiceBookingSlotsUseCase {
+            date: "2010-1-4".into(),
+            duration: hour,
+            iana_tz: None,
+            utc_offset_minutes: Some(120),
+            interval: hour,
+            slot_start: SlotStart::Interval,
+            service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(
+            res.timezone,
+            BookingTimezone::UtcOffset(FixedOffset::east(2 * 60 * 60))
+        );
+        // 2010-1-4 at +02:00 is from 22:00 UTC the day before to 22:00 UTC
+        let starts = res
+            .booking_slots
+            .iter()
+            .map(|s| s.start)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            starts,
+            (-2..22).map(|h| day_start + h * hour).collect::<Vec<_>>()
+        );
+    }
 }
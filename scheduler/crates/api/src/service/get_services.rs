@@ -0,0 +1,76 @@
+use crate::{
+    error::NettuError,
+    shared::{auth::protect_account_route, pagination::page_limit},
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::get_services::*;
+use nettu_scheduler_infra::NettuContext;
+
+pub async fn get_services_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<QueryParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+
+    let services = ctx
+        .repos
+        .service_repo
+        .find_by_account(
+            &account.id,
+            query_params.skip.unwrap_or(0),
+            page_limit(query_params.limit),
+        )
+        .await
+        .map_err(|_| NettuError::InternalError)?;
+    Ok(HttpResponse::Ok().json(APIResponse::new(services)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_domain::{Account, Service};
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn lists_services_of_account() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let service1 = Service::new(account.id.clone());
+        let service2 = Service::new(account.id.clone());
+        // Inserted in reverse order to check that the ordering does not depend on it
+        ctx.repos.service_repo.insert(&service2).await.unwrap();
+        ctx.repos.service_repo.insert(&service1).await.unwrap();
+        // A service of another account is not listed
+        let other_service = Service::new(Default::default());
+        ctx.repos.service_repo.insert(&other_service).await.unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/service")
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res: APIResponse = test::read_body_json(res).await;
+        let ids = res.services.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![service1.id.clone(), service2.id.clone()]);
+
+        let req = test::TestRequest::get()
+            .uri("/service?skip=1&limit=1")
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: APIResponse = test::read_response_json(&mut app, req).await;
+        assert_eq!(res.services.len(), 1);
+        assert_eq!(res.services[0].id, service2.id);
+    }
+}
@@ -1,26 +1,35 @@
 mod add_user_to_service;
+mod cancel_booking;
 mod create_service;
 mod delete_service;
 mod get_service;
+mod get_service_availability;
 mod get_service_bookingslots;
+mod get_services;
 mod get_services_by_meta;
 mod remove_user_from_service;
 mod update_service;
 mod update_service_user;
+mod warmup_service_bookingslots;
 
 use actix_web::web;
 use add_user_to_service::add_user_to_service_controller;
+use cancel_booking::cancel_booking_controller;
 use create_service::create_service_controller;
 use delete_service::delete_service_controller;
 use get_service::get_service_controller;
-use get_service_bookingslots::get_service_bookingslots_controller;
+use get_service_availability::get_service_availability_controller;
+use get_service_bookingslots::{get_service_bookingslots_controller, query_params_error_handler};
+use get_services::get_services_controller;
 use get_services_by_meta::get_services_by_meta_controller;
 use remove_user_from_service::remove_user_from_service_controller;
 use update_service::update_service_controller;
 use update_service_user::update_service_user_controller;
+use warmup_service_bookingslots::warmup_service_bookingslots_controller;
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/service", web::post().to(create_service_controller));
+    cfg.route("/service", web::get().to(get_services_controller));
     cfg.route(
         "/service/meta",
         web::get().to(get_services_by_meta_controller),
@@ -49,8 +58,21 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/service/{service_id}/users/{user_id}",
         web::put().to(update_service_user_controller),
     );
+    cfg.service(
+        web::resource("/service/{service_id}/booking")
+            .app_data(web::QueryConfig::default().error_handler(query_params_error_handler))
+            .route(web::get().to(get_service_bookingslots_controller)),
+    );
+    cfg.route(
+        "/service/{service_id}/warmup",
+        web::post().to(warmup_service_bookingslots_controller),
+    );
+    cfg.route(
+        "/service/{service_id}/availability",
+        web::get().to(get_service_availability_controller),
+    );
     cfg.route(
-        "/service/{service_id}/booking",
-        web::get().to(get_service_bookingslots_controller),
+        "/service/{service_id}/booking/{event_id}",
+        web::delete().to(cancel_booking_controller),
     );
 }
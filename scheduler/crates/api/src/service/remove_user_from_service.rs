@@ -66,14 +66,22 @@ impl UseCase for RemoveUserFromServiceUseCase {
     const NAME: &'static str = "RemoveUserFromService";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let mut service = match ctx.repos.service_repo.find(&self.service_id).await {
-            Some(service) if service.account_id == self.account.id => service,
+        let mut service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await
+        {
+            Some(service) => service,
             _ => return Err(UseCaseErrors::ServiceNotFound),
         };
 
         match service.remove_user(&self.user_id) {
             Some(_) => match ctx.repos.service_repo.save(&service).await {
-                Ok(_) => Ok(UseCaseRes { service }),
+                Ok(_) => {
+                    ctx.booking_slots_cache.invalidate_service(&service.id);
+                    Ok(UseCaseRes { service })
+                }
                 Err(_) => Err(UseCaseErrors::StorageError),
             },
             None => Err(UseCaseErrors::UserNotFound),
@@ -2,7 +2,7 @@ use crate::shared::usecase::{execute, UseCase};
 use crate::{error::NettuError, shared::auth::protect_account_route};
 use actix_web::{web, HttpRequest, HttpResponse};
 use nettu_scheduler_api_structs::update_service::*;
-use nettu_scheduler_domain::{Metadata, Service, ID};
+use nettu_scheduler_domain::{BookingStrategy, Metadata, Millis, Service, ID};
 use nettu_scheduler_infra::NettuContext;
 
 pub async fn update_service_controller(
@@ -13,10 +13,17 @@ pub async fn update_service_controller(
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
 
+    let body = body.0;
     let usecase = UpdateServiceUseCase {
         account_id: account.id,
         service_id: path.0.service_id,
-        metadata: body.0.metadata,
+        booking_strategy: body.booking_strategy,
+        default_schedule_id: body.default_schedule_id,
+        min_lead_time: body.min_lead_time_ms,
+        allowed_durations: body.allowed_durations,
+        allow_overbooking: body.allow_overbooking,
+        timezone: body.timezone,
+        metadata: body.metadata,
     };
 
     execute(usecase, &ctx)
@@ -26,6 +33,19 @@ pub async fn update_service_controller(
             UseCaseErrors::ServiceNotFound(id) => {
                 NettuError::NotFound(format!("Service with id: {} was not found.", id))
             }
+            UseCaseErrors::ScheduleNotFound(id) => {
+                NettuError::NotFound(format!("Schedule with id: {} was not found.", id))
+            }
+            UseCaseErrors::InvalidMinLeadTime => NettuError::BadClientData(
+                "The provided min_lead_time_ms must not be negative".into(),
+            ),
+            UseCaseErrors::InvalidAllowedDurations => NettuError::BadClientData(
+                "The provided allowed_durations must all be positive".into(),
+            ),
+            UseCaseErrors::InvalidTimezone(timezone) => NettuError::BadClientData(format!(
+                "Invalid timezone: {}. It should be a valid IANA TimeZone.",
+                timezone
+            )),
             UseCaseErrors::StorageError => NettuError::InternalError,
         })
 }
@@ -34,6 +54,12 @@ pub async fn update_service_controller(
 struct UpdateServiceUseCase {
     account_id: ID,
     service_id: ID,
+    booking_strategy: Option<BookingStrategy>,
+    default_schedule_id: Option<ID>,
+    min_lead_time: Option<Millis>,
+    allowed_durations: Option<Vec<i64>>,
+    allow_overbooking: Option<bool>,
+    timezone: Option<String>,
     metadata: Option<Metadata>,
 }
 #[derive(Debug)]
@@ -45,6 +71,10 @@ struct UseCaseRes {
 enum UseCaseErrors {
     StorageError,
     ServiceNotFound(ID),
+    ScheduleNotFound(ID),
+    InvalidMinLeadTime,
+    InvalidAllowedDurations,
+    InvalidTimezone(String),
 }
 
 #[async_trait::async_trait(?Send)]
@@ -56,11 +86,50 @@ impl UseCase for UpdateServiceUseCase {
     const NAME: &'static str = "UpdateService";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let mut service = match ctx.repos.service_repo.find(&self.service_id).await {
-            Some(service) if service.account_id == self.account_id => service,
+        let mut service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account_id)
+            .await
+        {
+            Some(service) => service,
             _ => return Err(UseCaseErrors::ServiceNotFound(self.service_id.clone())),
         };
 
+        if let Some(booking_strategy) = &self.booking_strategy {
+            service.booking_strategy = booking_strategy.clone();
+        }
+        if let Some(schedule_id) = &self.default_schedule_id {
+            match ctx
+                .repos
+                .schedule_repo
+                .find_for_account(schedule_id, &self.account_id)
+                .await
+            {
+                Some(schedule) => {
+                    service.default_schedule_id = Some(schedule.id);
+                }
+                _ => return Err(UseCaseErrors::ScheduleNotFound(schedule_id.clone())),
+            }
+        }
+        if let Some(min_lead_time) = self.min_lead_time {
+            if !service.set_min_lead_time(min_lead_time) {
+                return Err(UseCaseErrors::InvalidMinLeadTime);
+            }
+        }
+        if let Some(allowed_durations) = &self.allowed_durations {
+            if !service.set_allowed_durations(allowed_durations.clone()) {
+                return Err(UseCaseErrors::InvalidAllowedDurations);
+            }
+        }
+        if let Some(allow_overbooking) = self.allow_overbooking {
+            service.allow_overbooking = allow_overbooking;
+        }
+        if let Some(timezone) = &self.timezone {
+            if !service.set_timezone(timezone) {
+                return Err(UseCaseErrors::InvalidTimezone(timezone.clone()));
+            }
+        }
         if let Some(metadata) = &self.metadata {
             service.metadata = metadata.clone();
         }
@@ -69,7 +138,10 @@ impl UseCase for UpdateServiceUseCase {
             .service_repo
             .save(&service)
             .await
-            .map(|_| UseCaseRes { service })
+            .map(|_| {
+                ctx.booking_slots_cache.invalidate_service(&service.id);
+                UseCaseRes { service }
+            })
             .map_err(|_| UseCaseErrors::StorageError)
     }
 }
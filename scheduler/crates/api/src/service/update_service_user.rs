@@ -30,6 +30,7 @@ pub async fn update_service_user_controller(
         buffer: body.buffer,
         closest_booking_time: body.closest_booking_time,
         furthest_booking_time: body.furthest_booking_time,
+        timezone: body.timezone.to_owned(),
     };
 
     execute(usecase, &ctx)
@@ -57,6 +58,7 @@ struct UpdateServiceUserUseCase {
     pub buffer: Option<i64>,
     pub closest_booking_time: Option<i64>,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug)]
@@ -81,8 +83,13 @@ impl UseCase for UpdateServiceUserUseCase {
     const NAME: &'static str = "UpdateServiceUser";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let mut service = match ctx.repos.service_repo.find(&self.service_id).await {
-            Some(service) if service.account_id == self.account.id => service,
+        let mut service = match ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account.id)
+            .await
+        {
+            Some(service) => service,
             _ => return Err(UseCaseErrors::ServiceNotFound),
         };
 
@@ -99,6 +106,7 @@ impl UseCase for UpdateServiceUserUseCase {
                 buffer: self.buffer,
                 closest_booking_time: self.closest_booking_time,
                 furthest_booking_time: self.furthest_booking_time,
+                timezone: self.timezone.clone(),
             },
             ctx,
         )
@@ -107,7 +115,10 @@ impl UseCase for UpdateServiceUserUseCase {
 
         let res = ctx.repos.service_repo.save(&service).await;
         match res {
-            Ok(_) => Ok(UseCaseRes { service }),
+            Ok(_) => {
+                ctx.booking_slots_cache.invalidate_service(&service.id);
+                Ok(UseCaseRes { service })
+            }
             Err(_) => Err(UseCaseErrors::StorageError),
         }
     }
@@ -0,0 +1,286 @@
+use super::get_service_bookingslots::{self, GetServiceBookingSlotsUseCase};
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_account_route,
+        locale::Locale,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use nettu_scheduler_api_structs::warmup_service_bookingslots::*;
+use nettu_scheduler_domain::{booking_slots::SlotStart, ID};
+use nettu_scheduler_infra::NettuContext;
+use std::time::Instant;
+
+/// Bounds how long a single warm-up request can keep computing booking slots
+const MAX_WARMUP_DATES: usize = 31;
+
+pub async fn warmup_service_bookingslots_controller(
+    http_req: HttpRequest,
+    body: web::Json<RequestBody>,
+    path_params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let locale = Locale::from_request(&http_req);
+
+    let body = body.0;
+    let usecase = WarmupServiceBookingSlotsUseCase {
+        account_id: account.id,
+        service_id: path_params.service_id.clone(),
+        dates: body.dates,
+        duration: body.duration,
+        interval: body.interval.unwrap_or(body.duration),
+        iana_tz: body.iana_tz,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|res| {
+            HttpResponse::Ok().json(APIResponse {
+                dates: res.dates,
+                total_duration_ms: res.total_duration_ms,
+            })
+        })
+        .map_err(|e| match e {
+            UseCaseErrors::ServiceNotFound => NettuError::NotFound(format!(
+                "Service with id: {} was not found.",
+                path_params.service_id
+            )),
+            UseCaseErrors::TooManyDates => NettuError::BadClientData(format!(
+                "At most {} dates can be warmed up at once",
+                MAX_WARMUP_DATES
+            )),
+            UseCaseErrors::BookingSlots(e) => {
+                get_service_bookingslots::handle_error(e, &path_params.service_id, locale)
+            }
+        })
+}
+
+/// Computes the booking slots of a `Service` for each of the given dates, so that they
+/// are cached before the first client asks for them. The slots are cached under the
+/// same keys as a bookingslots query without any user filters.
+#[derive(Debug)]
+struct WarmupServiceBookingSlotsUseCase {
+    account_id: ID,
+    service_id: ID,
+    dates: Vec<String>,
+    duration: i64,
+    interval: i64,
+    iana_tz: Option<String>,
+}
+
+#[derive(Debug)]
+struct UseCaseRes {
+    dates: Vec<WarmedDate>,
+    total_duration_ms: u64,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    ServiceNotFound,
+    TooManyDates,
+    /// Computing the booking slots of one of the dates failed
+    BookingSlots(get_service_bookingslots::UseCaseErrors),
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for WarmupServiceBookingSlotsUseCase {
+    type Response = UseCaseRes;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "WarmupServiceBookingSlots";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        if self.dates.len() > MAX_WARMUP_DATES {
+            return Err(UseCaseErrors::TooManyDates);
+        }
+        if ctx
+            .repos
+            .service_repo
+            .find_for_account(&self.service_id, &self.account_id)
+            .await
+            .is_none()
+        {
+            return Err(UseCaseErrors::ServiceNotFound);
+        }
+
+        let started = Instant::now();
+        let mut dates = Vec::with_capacity(self.dates.len());
+
+        for date in &self.dates {
+            let date_started = Instant::now();
+            let mut bookingslots = GetServiceBookingSlotsUseCase {
+                service_id: self.service_id.clone(),
+                date: date.clone(),
+                iana_tz: self.iana_tz.clone(),
+                utc_offset_minutes: None,
+                duration: self.duration,
+                interval: self.interval,
+                slot_start: SlotStart::Interval,
+                exclude_user_ids: None,
+                user_ids: None,
+            };
+            let res = bookingslots
+                .execute(ctx)
+                .await
+                .map_err(UseCaseErrors::BookingSlots)?;
+
+            dates.push(WarmedDate {
+                date: date.clone(),
+                booking_slots: res.booking_slots.len(),
+                duration_ms: date_started.elapsed().as_millis() as u64,
+            });
+        }
+
+        Ok(UseCaseRes {
+            dates,
+            total_duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use nettu_scheduler_api_structs::get_service_bookingslots::APIResponse as BookingSlotsResponse;
+    use nettu_scheduler_domain::{
+        Account, Calendar, CalendarEvent, EventStatus, Service, ServiceResource, TimePlan,
+    };
+    use nettu_scheduler_infra::{setup_context, ISys};
+    use std::sync::Arc;
+
+    struct DummySys {}
+
+    impl ISys for DummySys {
+        fn get_timestamp_millis(&self) -> i64 {
+            0
+        }
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn warmed_dates_are_served_from_the_cache() {
+        let mut ctx = setup_context().await;
+        ctx.sys = Arc::new(DummySys {});
+        let hour = 1000 * 60 * 60;
+
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let other_account = Account::default();
+        ctx.repos.account_repo.insert(&other_account).await.unwrap();
+        let mut service = Service::new(account.id.clone());
+        let user_id = ID::default();
+        let calendar = Calendar::new(&user_id, &service.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        service.add_user(ServiceResource::new(
+            user_id,
+            TimePlan::Calendar(calendar.id.clone()),
+            vec![],
+        ));
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+        ctx.repos
+            .event_repo
+            .insert(&CalendarEvent::new(
+                &calendar,
+                hour,
+                2 * hour,
+                EventStatus::Free,
+            ))
+            .await
+            .unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx.clone())
+                .configure(crate::service::configure_routes),
+        )
+        .await;
+        let body = RequestBody {
+            dates: vec!["1970-1-1".into(), "1970-1-2".into()],
+            duration: hour,
+            interval: None,
+            iana_tz: Some("UTC".into()),
+        };
+
+        let warmup = |account: &Account, body: &RequestBody| {
+            test::TestRequest::post()
+                .uri(&format!("/service/{}/warmup", service.id))
+                .header("x-api-key", account.secret_api_key.clone())
+                .set_json(body)
+                .to_request()
+        };
+
+        // Only the account of the service can warm up its cache
+        let req = test::TestRequest::post()
+            .uri(&format!("/service/{}/warmup", service.id))
+            .set_json(&body)
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        let res = test::call_service(&mut app, warmup(&other_account, &body)).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        // The cause of an invalid date is told
+        let invalid = RequestBody {
+            dates: vec!["1970-1-1".into(), "1970-13-1".into()],
+            duration: hour,
+            interval: None,
+            iana_tz: Some("UTC".into()),
+        };
+        let res = test::call_service(&mut app, warmup(&account, &invalid)).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let message = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(message.contains("1970-13-1"));
+
+        let too_many = RequestBody {
+            dates: vec!["1970-1-1".into(); MAX_WARMUP_DATES + 1],
+            duration: hour,
+            interval: None,
+            iana_tz: Some("UTC".into()),
+        };
+        let res = test::call_service(&mut app, warmup(&account, &too_many)).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let res: APIResponse = test::read_response_json(&mut app, warmup(&account, &body)).await;
+        let warmed = res
+            .dates
+            .iter()
+            .map(|d| (d.date.as_str(), d.booking_slots))
+            .collect::<Vec<_>>();
+        assert_eq!(warmed, vec![("1970-1-1", 2), ("1970-1-2", 0)]);
+        assert!(res
+            .dates
+            .iter()
+            .all(|d| d.duration_ms <= res.total_duration_ms));
+
+        // Written directly to the repo, so the cache is not invalidated and the
+        // slots computed by the warm-up are served
+        ctx.repos
+            .event_repo
+            .insert(&CalendarEvent::new(
+                &calendar,
+                5 * hour,
+                hour,
+                EventStatus::Free,
+            ))
+            .await
+            .unwrap();
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/service/{}/booking?duration={}&date=1970-1-1&ianaTz=UTC",
+                service.id, hour
+            ))
+            .to_request();
+        let res: BookingSlotsResponse = test::read_response_json(&mut app, req).await;
+        let starts = res
+            .booking_slots
+            .iter()
+            .map(|slot| slot.start)
+            .collect::<Vec<_>>();
+        assert_eq!(starts, vec![hour, 2 * hour]);
+    }
+}
@@ -4,5 +4,6 @@ mod route_guards;
 pub use policy::{Permission, Policy};
 pub use route_guards::{
     account_can_modify_calendar, account_can_modify_event, account_can_modify_schedule,
-    account_can_modify_user, protect_account_route, protect_public_account_route, protect_route,
+    account_can_modify_user, protect_account_route, protect_account_update_route,
+    protect_admin_route, protect_public_account_route, protect_route,
 };
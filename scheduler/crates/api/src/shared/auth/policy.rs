@@ -57,6 +57,14 @@ impl Policy {
             reject: None,
         }
     }
+
+    /// `Policy` that authorizes every `Permission`
+    pub fn allow_all() -> Self {
+        Self {
+            allow: Some(vec![Permission::All]),
+            reject: None,
+        }
+    }
 }
 
 impl Default for Policy {
@@ -1,7 +1,7 @@
 use actix_web::HttpRequest;
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use nettu_scheduler_domain::{Account, Calendar, CalendarEvent, Schedule, User, ID};
-use nettu_scheduler_infra::NettuContext;
+use nettu_scheduler_infra::{AccountCacheKey, NettuContext};
 use serde::{Deserialize, Serialize};
 
 use crate::{error::NettuError, shared::Guard};
@@ -50,7 +50,7 @@ pub async fn auth_user_req(
                 Ok(claims) => ctx
                     .repos
                     .user_repo
-                    .find_by_account_id(&claims.nettu_scheduler_user_id, &account.id)
+                    .find_for_account(&claims.nettu_scheduler_user_id, &account.id)
                     .await
                     .map(|user| (user, claims.scheduler_policy.unwrap_or_default())),
                 Err(_e) => None,
@@ -60,10 +60,29 @@ pub async fn auth_user_req(
     }
 }
 
+/// Looks up the `Account` in the `AccountCache` before querying the repo, so that
+/// rapid requests from the same client only query the `Account` once.
+/// An `Account` that is not found is not cached.
+async fn find_account_cached(key: AccountCacheKey, ctx: &NettuContext) -> Option<Account> {
+    let now = ctx.sys.get_timestamp_millis();
+    if let Some(account) = ctx.account_cache.get(&key, now) {
+        return Some(account);
+    }
+
+    let account = match &key {
+        AccountCacheKey::ApiKey(api_key) => ctx.repos.account_repo.find_by_apikey(api_key).await,
+        AccountCacheKey::AccountId(account_id) => ctx.repos.account_repo.find(account_id).await,
+    }?;
+    ctx.account_cache.insert(key, account.clone(), now);
+    Some(account)
+}
+
 /// Finds out which `Account` the client is associated with.
 pub async fn get_client_account(req: &HttpRequest, ctx: &NettuContext) -> Option<Account> {
     match get_nettu_account_header(req) {
-        Some(Ok(account_id)) => ctx.repos.account_repo.find(&account_id).await,
+        Some(Ok(account_id)) => {
+            find_account_cached(AccountCacheKey::AccountId(account_id), ctx).await
+        }
         _ => None,
     }
 }
@@ -101,11 +120,47 @@ fn decode_token(account: &Account, token: &str) -> anyhow::Result<Claims> {
     Ok(claims)
 }
 
+/// Lets an `Account` admin act as one of its `User`s by providing the id of the
+/// `User` in the `x-act-as-user` header together with the api key of the `Account`.
+/// The admin can do anything on behalf of the `User`, so the `Policy` allows everything.
+async fn impersonate_user(
+    req: &HttpRequest,
+    user_id: &str,
+    ctx: &NettuContext,
+) -> Result<(User, Policy), NettuError> {
+    let account = protect_account_route(req, ctx).await?;
+    let user_id = Guard::against_malformed_id(user_id.to_string())?;
+
+    match ctx
+        .repos
+        .user_repo
+        .find_for_account(&user_id, &account.id)
+        .await
+    {
+        Some(user) => Ok((user, Policy::allow_all())),
+        None => Err(NettuError::Unauthorized(format!(
+            "Unable to act as user with id: {}, as it was not found in the account",
+            user_id
+        ))),
+    }
+}
+
 /// Protects routes that can be accessed by authenticated `User`s.
+/// `Account` admins can also access them by impersonating a `User`,
+/// see `impersonate_user`.
 pub async fn protect_route(
     req: &HttpRequest,
     ctx: &NettuContext,
 ) -> Result<(User, Policy), NettuError> {
+    if let Some(user_id) = req.headers().get("x-act-as-user") {
+        return match user_id.to_str() {
+            Ok(user_id) => impersonate_user(req, user_id, ctx).await,
+            Err(_) => Err(NettuError::Unauthorized(
+                "Malformed user id provided in x-act-as-user header".into(),
+            )),
+        };
+    }
+
     let account = match get_client_account(req, ctx).await {
         Some(account) => account,
         None => {
@@ -145,7 +200,7 @@ pub async fn protect_account_route(
         }
     };
 
-    let account = ctx.repos.account_repo.find_by_apikey(api_key).await;
+    let account = find_account_cached(AccountCacheKey::ApiKey(api_key.to_string()), ctx).await;
 
     match account {
         Some(acc) => Ok(acc),
@@ -155,6 +210,50 @@ pub async fn protect_account_route(
     }
 }
 
+/// Protects an `Account` admin route that changes and saves the `Account`. Unlike
+/// `protect_account_route` the `Account` is always loaded from the repo, so that
+/// a stale cached copy of it is never saved over newer changes.
+pub async fn protect_account_update_route(
+    req: &HttpRequest,
+    ctx: &NettuContext,
+) -> Result<Account, NettuError> {
+    let account = protect_account_route(req, ctx).await?;
+
+    match ctx.repos.account_repo.find(&account.id).await {
+        Some(acc) => Ok(acc),
+        None => Err(NettuError::Unauthorized(
+            "Invalid api-key provided in x-api-key header".to_string(),
+        )),
+    }
+}
+
+/// Protects an operator route, like reading stats across all `Account`s.
+/// The operator authenticates with the same secret code that is used to create `Account`s
+pub fn protect_admin_route(req: &HttpRequest, ctx: &NettuContext) -> Result<(), NettuError> {
+    let secret = match req.headers().get("x-admin-secret") {
+        Some(secret) => match secret.to_str() {
+            Ok(secret) => secret,
+            Err(_) => {
+                return Err(NettuError::Unauthorized(
+                    "Malformed admin secret provided".to_string(),
+                ))
+            }
+        },
+        None => {
+            return Err(NettuError::Unauthorized(
+                "Unable to find admin secret in x-admin-secret header".to_string(),
+            ))
+        }
+    };
+
+    if secret != ctx.config.create_account_secret_code {
+        return Err(NettuError::Unauthorized(
+            "Invalid admin secret provided in x-admin-secret header".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// Only checks which account the request is connected to.
 /// If it cannot decide from the request which account the
 /// client belongs to it will return `NettuError`
@@ -166,7 +265,7 @@ pub async fn protect_public_account_route(
         Some(res) => {
             let account_id = res?;
 
-            match ctx.repos.account_repo.find(&account_id).await {
+            match find_account_cached(AccountCacheKey::AccountId(account_id), ctx).await {
                 Some(acc) => Ok(acc),
                 None => Err(NettuError::UnidentifiableClient(
                     "Could not find out which account the client belongs to".into(),
@@ -185,9 +284,14 @@ pub async fn account_can_modify_user(
     user_id: &ID,
     ctx: &NettuContext,
 ) -> Result<User, NettuError> {
-    match ctx.repos.user_repo.find(user_id).await {
-        Some(user) if user.account_id == account.id => Ok(user),
-        _ => Err(NettuError::NotFound(format!(
+    match ctx
+        .repos
+        .user_repo
+        .find_for_account(user_id, &account.id)
+        .await
+    {
+        Some(user) => Ok(user),
+        None => Err(NettuError::NotFound(format!(
             "User with id: {} was not found",
             user_id
         ))),
@@ -201,8 +305,13 @@ pub async fn account_can_modify_calendar(
     calendar_id: &ID,
     ctx: &NettuContext,
 ) -> Result<Calendar, NettuError> {
-    match ctx.repos.calendar_repo.find(calendar_id).await {
-        Some(cal) if cal.account_id == account.id => Ok(cal),
+    match ctx
+        .repos
+        .calendar_repo
+        .find_for_account(calendar_id, &account.id)
+        .await
+    {
+        Some(cal) => Ok(cal),
         _ => Err(NettuError::NotFound(format!(
             "Calendar with id: {} was not found",
             calendar_id
@@ -217,8 +326,13 @@ pub async fn account_can_modify_event(
     event_id: &ID,
     ctx: &NettuContext,
 ) -> Result<CalendarEvent, NettuError> {
-    match ctx.repos.event_repo.find(event_id).await {
-        Some(event) if event.account_id == account.id => Ok(event),
+    match ctx
+        .repos
+        .event_repo
+        .find_for_account(event_id, &account.id)
+        .await
+    {
+        Some(event) => Ok(event),
         _ => Err(NettuError::NotFound(format!(
             "Calendar event with id: {} was not found",
             event_id
@@ -233,8 +347,13 @@ pub async fn account_can_modify_schedule(
     schedule_id: &ID,
     ctx: &NettuContext,
 ) -> Result<Schedule, NettuError> {
-    match ctx.repos.schedule_repo.find(schedule_id).await {
-        Some(schedule) if schedule.account_id == account.id => Ok(schedule),
+    match ctx
+        .repos
+        .schedule_repo
+        .find_for_account(schedule_id, &account.id)
+        .await
+    {
+        Some(schedule) => Ok(schedule),
         _ => Err(NettuError::NotFound(format!(
             "Schedule with id: {} was not found",
             schedule_id
@@ -245,6 +364,7 @@ pub async fn account_can_modify_schedule(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::shared::auth::Permission;
     use actix_web::test::TestRequest;
     use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
     use nettu_scheduler_domain::PEMKey;
@@ -377,6 +497,44 @@ mod test {
         assert!(res.is_err());
     }
 
+    #[actix_web::main]
+    #[test]
+    async fn admin_can_act_as_user_in_account() {
+        let ctx = setup_context().await;
+        let account = setup_account(&ctx).await;
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+
+        let req = TestRequest::with_header("x-api-key", account.secret_api_key.clone())
+            .header("x-act-as-user", user.id.to_string())
+            .to_http_request();
+        let (res_user, policy) = protect_route(&req, &ctx).await.unwrap();
+        assert_eq!(res_user.id, user.id);
+        assert!(policy.authorize(&[Permission::CreateCalendarEvent]));
+
+        // The api key is required to act as the user
+        let req = TestRequest::with_header("nettu-account", account.id.to_string())
+            .header("x-act-as-user", user.id.to_string())
+            .to_http_request();
+        assert!(protect_route(&req, &ctx).await.is_err());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn rejects_acting_as_user_in_different_account() {
+        let ctx = setup_context().await;
+        let account = setup_account(&ctx).await;
+        let account2 = setup_account(&ctx).await;
+        let user = User::new(account2.id.clone()); // user belongs to account2
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+
+        let req = TestRequest::with_header("x-api-key", account.secret_api_key.clone())
+            .header("x-act-as-user", user.id.to_string())
+            .to_http_request();
+        let res = protect_route(&req, &ctx).await;
+        assert!(matches!(res, Err(NettuError::Unauthorized(_))));
+    }
+
     #[actix_web::main]
     #[test]
     async fn rejects_req_without_headers() {
@@ -387,4 +545,74 @@ mod test {
         let res = protect_route(&req, &ctx).await;
         assert!(res.is_err());
     }
+
+    #[actix_web::main]
+    #[test]
+    async fn caches_account_between_rapid_requests() {
+        let ctx = setup_context().await;
+        let account = setup_account(&ctx).await;
+        let req =
+            TestRequest::with_header("x-api-key", account.secret_api_key.clone()).to_http_request();
+        assert!(protect_account_route(&req, &ctx).await.is_ok());
+
+        // The second request does not look up the account in the repo again,
+        // otherwise it would not be found after it got deleted
+        ctx.repos.account_repo.delete(&account.id).await.unwrap();
+        let res = protect_account_route(&req, &ctx).await;
+        assert_eq!(res.unwrap().id, account.id);
+
+        // Until the account is updated
+        ctx.account_cache.invalidate_account(&account.id);
+        assert!(protect_account_route(&req, &ctx).await.is_err());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn update_route_loads_account_from_repo() {
+        let ctx = setup_context().await;
+        let mut account = setup_account(&ctx).await;
+        let req =
+            TestRequest::with_header("x-api-key", account.secret_api_key.clone()).to_http_request();
+        assert!(protect_account_route(&req, &ctx).await.is_ok());
+
+        // Changed by another instance of the api, which does not invalidate this cache
+        account.public_jwt_key = None;
+        ctx.repos.account_repo.save(&account).await.unwrap();
+        let cached = protect_account_route(&req, &ctx).await.unwrap();
+        assert!(cached.public_jwt_key.is_some());
+        let fresh = protect_account_update_route(&req, &ctx).await.unwrap();
+        assert!(fresh.public_jwt_key.is_none());
+
+        ctx.repos.account_repo.delete(&account.id).await.unwrap();
+        assert!(protect_account_update_route(&req, &ctx).await.is_err());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn caches_account_from_account_header() {
+        let ctx = setup_context().await;
+        let account = setup_account(&ctx).await;
+        let req =
+            TestRequest::with_header("nettu-account", account.id.to_string()).to_http_request();
+        assert!(protect_public_account_route(&req, &ctx).await.is_ok());
+
+        ctx.repos.account_repo.delete(&account.id).await.unwrap();
+        assert!(protect_public_account_route(&req, &ctx).await.is_ok());
+
+        ctx.account_cache.invalidate_account(&account.id);
+        assert!(protect_public_account_route(&req, &ctx).await.is_err());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn does_not_cache_unknown_account() {
+        let ctx = setup_context().await;
+        let account = get_account();
+        let req =
+            TestRequest::with_header("x-api-key", account.secret_api_key.clone()).to_http_request();
+        assert!(protect_account_route(&req, &ctx).await.is_err());
+
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        assert!(protect_account_route(&req, &ctx).await.is_ok());
+    }
 }
@@ -0,0 +1,209 @@
+use actix_web::{http::header, HttpRequest};
+use nettu_scheduler_domain::booking_slots::BookingQueryError;
+
+/// The languages that client facing error messages can be given in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+    English,
+    Norwegian,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::English
+    }
+}
+
+impl Locale {
+    fn from_language_tag(tag: &str) -> Option<Self> {
+        let primary = tag.split('-').next().unwrap_or_default();
+        match primary.trim().to_lowercase().as_str() {
+            "en" => Some(Self::English),
+            "no" | "nb" | "nn" => Some(Self::Norwegian),
+            _ => None,
+        }
+    }
+
+    /// Picks the first supported language listed in an `Accept-Language` header value,
+    /// e.g. "nb-NO,nb;q=0.9,en;q=0.8". Defaults to `English`.
+    pub fn from_accept_language(value: &str) -> Self {
+        let mut languages = value
+            .split(',')
+            .filter_map(|lang| {
+                let mut parts = lang.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Some((tag, quality))
+            })
+            .collect::<Vec<_>>();
+        languages.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        languages
+            .into_iter()
+            .find_map(|(tag, _)| Self::from_language_tag(tag))
+            .unwrap_or_default()
+    }
+
+    pub fn from_request(req: &HttpRequest) -> Self {
+        req.headers()
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|val| val.to_str().ok())
+            .map(Self::from_accept_language)
+            .unwrap_or_default()
+    }
+}
+
+/// Client facing error messages that are available in multiple languages
+#[derive(Debug)]
+pub enum Message<'a> {
+    InvalidDate(&'a str),
+    InvalidTimezone(&'a str),
+    InvalidUtcOffset(i32),
+    ConflictingTimezones,
+    InvalidInterval,
+    InvalidTimespan,
+    WindowShorterThanDuration,
+    DurationNotAllowed(&'a [i64]),
+    ServiceNotFound(&'a str),
+    EventNotFound(&'a str),
+    InvalidMillis(&'a str),
+}
+
+pub fn translate(message: Message, locale: Locale) -> String {
+    match locale {
+        Locale::English => match message {
+            Message::InvalidDate(date) => format!(
+                "Invalid datetime: {}. Should be YYYY-MM-DD, e.g. January 1. 2020 => 2020-1-1",
+                date
+            ),
+            Message::InvalidTimezone(tz) => format!(
+                "Invalid timezone: {}. It should be a valid IANA TimeZone.",
+                tz
+            ),
+            Message::InvalidUtcOffset(minutes) => format!(
+                "Invalid UTC offset: {}. It should be between -840 and 840 minutes.",
+                minutes
+            ),
+            Message::ConflictingTimezones => "Only one of ianaTz and utcOffsetMinutes can be specified".into(),
+            Message::InvalidInterval => "Invalid interval specified. It should be between 10 - 60 minutes inclusively and be specified as milliseconds.".into(),
+            Message::InvalidTimespan => "The provided start_ts and end_ts is invalid".into(),
+            Message::WindowShorterThanDuration => "The requested window is shorter than the slot duration".into(),
+            Message::DurationNotAllowed(allowed) => format!(
+                "The duration is not offered by the service. It should be one of: {} milliseconds.",
+                join_durations(allowed)
+            ),
+            Message::ServiceNotFound(id) => format!("Service with id: {}, was not found.", id),
+            Message::EventNotFound(id) => format!("The calendar event with id: {}, was not found.", id),
+            Message::InvalidMillis(field) => format!("{} must be an integer number of milliseconds", field),
+        },
+        Locale::Norwegian => match message {
+            Message::InvalidDate(date) => format!(
+                "Ugyldig dato: {}. Den skal være på formatet YYYY-MM-DD, f.eks. 1. januar 2020 => 2020-1-1",
+                date
+            ),
+            Message::InvalidTimezone(tz) => format!(
+                "Ugyldig tidssone: {}. Den må være en gyldig IANA-tidssone.",
+                tz
+            ),
+            Message::InvalidUtcOffset(minutes) => format!(
+                "Ugyldig UTC-forskyvning: {}. Den må være mellom -840 og 840 minutter.",
+                minutes
+            ),
+            Message::ConflictingTimezones => "Bare én av ianaTz og utcOffsetMinutes kan oppgis".into(),
+            Message::InvalidInterval => "Ugyldig intervall. Det må være mellom 10 og 60 minutter og oppgis i millisekunder.".into(),
+            Message::InvalidTimespan => "Oppgitt start_ts og end_ts er ugyldig".into(),
+            Message::WindowShorterThanDuration => "Det forespurte tidsvinduet er kortere enn varigheten til tidene".into(),
+            Message::DurationNotAllowed(allowed) => format!(
+                "Varigheten tilbys ikke av tjenesten. Den må være en av: {} millisekunder.",
+                join_durations(allowed)
+            ),
+            Message::ServiceNotFound(id) => format!("Tjenesten med id: {}, ble ikke funnet.", id),
+            Message::EventNotFound(id) => format!("Kalenderhendelsen med id: {}, ble ikke funnet.", id),
+            Message::InvalidMillis(field) => format!("{} må være et heltall i millisekunder", field),
+        },
+    }
+}
+
+/// Lists every invalid field of a booking slots query together with why it is invalid
+pub fn translate_booking_query_errors(errors: &[BookingQueryError], locale: Locale) -> String {
+    errors
+        .iter()
+        .map(|e| {
+            let message = match e {
+                BookingQueryError::InvalidInterval => Message::InvalidInterval,
+                BookingQueryError::InvalidDate(date) => Message::InvalidDate(date),
+                BookingQueryError::InvalidTimezone(tz) => Message::InvalidTimezone(tz),
+                BookingQueryError::InvalidUtcOffset(minutes) => Message::InvalidUtcOffset(*minutes),
+                BookingQueryError::ConflictingTimezones => Message::ConflictingTimezones,
+                BookingQueryError::WindowShorterThanDuration => Message::WindowShorterThanDuration,
+            };
+            format!("{}: {}", e.field(), translate(message, locale))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn join_durations(durations: &[i64]) -> String {
+    durations
+        .iter()
+        .map(|duration| duration.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn parses_accept_language() {
+        assert_eq!(Locale::from_accept_language("nb-NO"), Locale::Norwegian);
+        assert_eq!(
+            Locale::from_accept_language("en-US,en;q=0.5"),
+            Locale::English
+        );
+        assert_eq!(
+            Locale::from_accept_language("en;q=0.5, nb;q=0.8"),
+            Locale::Norwegian
+        );
+        assert_eq!(
+            Locale::from_accept_language("de-DE, no;q=0.4"),
+            Locale::Norwegian
+        );
+    }
+
+    #[test]
+    fn unknown_locale_falls_back_to_english() {
+        assert_eq!(Locale::from_accept_language("de-DE"), Locale::English);
+        assert_eq!(Locale::from_accept_language(""), Locale::English);
+
+        let req = TestRequest::default()
+            .header("Accept-Language", "fr-FR")
+            .to_http_request();
+        assert_eq!(
+            translate(Message::InvalidTimespan, Locale::from_request(&req)),
+            "The provided start_ts and end_ts is invalid"
+        );
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(Locale::from_request(&req), Locale::English);
+    }
+
+    #[test]
+    fn supported_locale_is_translated() {
+        let req = TestRequest::default()
+            .header("Accept-Language", "nb-NO,nb;q=0.9,en;q=0.8")
+            .to_http_request();
+        assert_eq!(
+            translate(Message::InvalidTimespan, Locale::from_request(&req)),
+            "Oppgitt start_ts og end_ts er ugyldig"
+        );
+        assert_eq!(
+            translate(Message::InvalidTimezone("Mars/Olympus"), Locale::Norwegian),
+            "Ugyldig tidssone: Mars/Olympus. Den må være en gyldig IANA-tidssone."
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod auth;
 mod guard;
+pub mod locale;
+pub mod pagination;
 pub mod usecase;
 pub use guard::Guard;
 // mod controller;
@@ -0,0 +1,25 @@
+use std::cmp::min;
+
+/// Number of entities in a page when the client does not give a `limit`
+const DEFAULT_LIMIT: usize = 20;
+/// Most entities that a client can get in a single page
+pub const MAX_LIMIT: usize = 100;
+
+/// The `limit` to query the repos with for the `limit` given by the client.
+/// A `limit` of 0 gives an empty page.
+pub fn page_limit(limit: Option<usize>) -> usize {
+    min(limit.unwrap_or(DEFAULT_LIMIT), MAX_LIMIT)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn caps_the_limit() {
+        assert_eq!(page_limit(None), DEFAULT_LIMIT);
+        assert_eq!(page_limit(Some(0)), 0);
+        assert_eq!(page_limit(Some(50)), 50);
+        assert_eq!(page_limit(Some(MAX_LIMIT + 1)), MAX_LIMIT);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    error::NettuError,
+    shared::{
+        auth::protect_admin_route,
+        usecase::{execute, UseCase},
+    },
+};
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures::join;
+use nettu_scheduler_api_structs::get_admin_stats::*;
+use nettu_scheduler_infra::{NettuContext, StoreBackend};
+
+pub async fn get_admin_stats_controller(
+    http_req: HttpRequest,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    protect_admin_route(&http_req, &ctx)?;
+
+    execute(GetAdminStatsUseCase {}, &ctx)
+        .await
+        .map(|stats| {
+            HttpResponse::Ok().json(APIResponse {
+                store: stats.backend.to_string(),
+                accounts: stats.accounts,
+                users: stats.users,
+                calendars: stats.calendars,
+                events: stats.events,
+                services: stats.services,
+            })
+        })
+        .map_err(|e| match e {
+            UseCaseErrors::StorageError => NettuError::InternalError,
+        })
+}
+
+/// Reports which store backend is active and how many entities it holds.
+/// The counts are cheap to compute as they never load the entities.
+#[derive(Debug)]
+struct GetAdminStatsUseCase {}
+
+#[derive(Debug, PartialEq)]
+struct AdminStats {
+    backend: StoreBackend,
+    accounts: usize,
+    users: usize,
+    calendars: usize,
+    events: usize,
+    services: usize,
+}
+
+#[derive(Debug)]
+enum UseCaseErrors {
+    StorageError,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetAdminStatsUseCase {
+    type Response = AdminStats;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetAdminStats";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let (accounts, users, calendars, events, services) = join!(
+            ctx.repos.account_repo.count(),
+            ctx.repos.user_repo.count(),
+            ctx.repos.calendar_repo.count(),
+            ctx.repos.event_repo.count(),
+            ctx.repos.service_repo.count()
+        );
+        match (accounts, users, calendars, events, services) {
+            (Ok(accounts), Ok(users), Ok(calendars), Ok(events), Ok(services)) => Ok(AdminStats {
+                backend: ctx.repos.backend,
+                accounts,
+                users,
+                calendars,
+                events,
+                services,
+            }),
+            _ => Err(UseCaseErrors::StorageError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::{Account, Calendar, Service, User};
+
+    #[actix_web::main]
+    #[test]
+    async fn reports_inmemory_backend_and_counts() {
+        let ctx = NettuContext::create_inmemory();
+        let mut usecase = GetAdminStatsUseCase {};
+        assert_eq!(
+            usecase.execute(&ctx).await.unwrap(),
+            AdminStats {
+                backend: StoreBackend::InMemory,
+                accounts: 0,
+                users: 0,
+                calendars: 0,
+                events: 0,
+                services: 0,
+            }
+        );
+
+        for _ in 0..2 {
+            let account = Account::default();
+            ctx.repos.account_repo.insert(&account).await.unwrap();
+            let user = User::new(account.id.clone());
+            ctx.repos.user_repo.insert(&user).await.unwrap();
+            let calendar = Calendar::new(&user.id, &account.id);
+            ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        }
+        let service = Service::new(Default::default());
+        ctx.repos.service_repo.insert(&service).await.unwrap();
+
+        assert_eq!(
+            usecase.execute(&ctx).await.unwrap(),
+            AdminStats {
+                backend: StoreBackend::InMemory,
+                accounts: 2,
+                users: 2,
+                calendars: 2,
+                events: 0,
+                services: 1,
+            }
+        );
+    }
+}
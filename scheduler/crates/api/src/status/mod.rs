@@ -1,6 +1,8 @@
-use actix_web::{web, HttpResponse};
+mod get_admin_stats;
 
-use nettu_scheduler_api_structs::get_service_health::*;
+use actix_web::{web, HttpResponse};
+use get_admin_stats::get_admin_stats_controller;
+use nettu_scheduler_api_structs::{get_service_health::*, openapi::openapi_document};
 
 async fn status() -> HttpResponse {
     HttpResponse::Ok().json(APIResponse {
@@ -8,6 +10,12 @@ async fn status() -> HttpResponse {
     })
 }
 
+async fn openapi() -> HttpResponse {
+    HttpResponse::Ok().json(openapi_document())
+}
+
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg.route("/", web::get().to(status));
+    cfg.route("/openapi.json", web::get().to(openapi));
+    cfg.route("/admin/stats", web::get().to(get_admin_stats_controller));
 }
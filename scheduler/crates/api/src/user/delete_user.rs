@@ -55,14 +55,18 @@ impl UseCase for DeleteUserUseCase {
     const NAME: &'static str = "DeleteUser";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let user = match ctx.repos.user_repo.find(&self.user_id).await {
-            Some(u) if u.account_id == self.account.id => {
-                match ctx.repos.user_repo.delete(&self.user_id).await {
-                    Some(u) => u,
-                    None => return Err(UseCaseErrors::StorageError),
-                }
-            }
-            _ => return Err(UseCaseErrors::UserNotFound),
+        let user = match ctx
+            .repos
+            .user_repo
+            .find_for_account(&self.user_id, &self.account.id)
+            .await
+        {
+            Some(_) => match ctx.repos.user_repo.delete(&self.user_id).await {
+                Ok(Some(u)) => u,
+                Ok(None) => return Err(UseCaseErrors::UserNotFound),
+                Err(_) => return Err(UseCaseErrors::StorageError),
+            },
+            None => return Err(UseCaseErrors::UserNotFound),
         };
 
         let _ = join_all(vec![
@@ -75,6 +79,7 @@ impl UseCase for DeleteUserUseCase {
             .service_repo
             .remove_user_from_services(&user.id)
             .await;
+        ctx.booking_slots_cache.invalidate_user(&user.id);
 
         Ok(UseCaseRes { user })
     }
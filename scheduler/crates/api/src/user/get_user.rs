@@ -6,6 +6,7 @@ use crate::{
     },
 };
 use actix_web::{web, HttpRequest, HttpResponse};
+use futures::join;
 use nettu_scheduler_api_structs::get_user::*;
 use nettu_scheduler_domain::{Account, User, ID};
 use nettu_scheduler_infra::NettuContext;
@@ -13,6 +14,7 @@ use nettu_scheduler_infra::NettuContext;
 pub async fn get_user_controller(
     http_req: HttpRequest,
     path_params: web::Path<PathParams>,
+    query_params: web::Query<QueryParams>,
     ctx: web::Data<NettuContext>,
 ) -> Result<HttpResponse, NettuError> {
     let account = protect_account_route(&http_req, &ctx).await?;
@@ -20,15 +22,25 @@ pub async fn get_user_controller(
     let usecase = GetUserUseCase {
         account,
         user_id: path_params.user_id.clone(),
+        include_counts: query_params.include_counts,
     };
     execute(usecase, &ctx)
         .await
-        .map(|usecase_res| HttpResponse::Ok().json(APIResponse::new(usecase_res.user)))
+        .map(|usecase_res| {
+            let res = match usecase_res.counts {
+                Some(counts) => {
+                    APIResponse::with_counts(usecase_res.user, counts.calendars, counts.schedules)
+                }
+                None => APIResponse::new(usecase_res.user),
+            };
+            HttpResponse::Ok().json(res)
+        })
         .map_err(|e| match e {
             UseCaseErrors::UserNotFound => NettuError::NotFound(format!(
                 "A user with id: {}, was not found.",
                 path_params.user_id
             )),
+            UseCaseErrors::StorageError => NettuError::InternalError,
         })
 }
 
@@ -36,16 +48,25 @@ pub async fn get_user_controller(
 struct GetUserUseCase {
     account: Account,
     user_id: ID,
+    include_counts: bool,
+}
+
+#[derive(Debug)]
+struct UserCounts {
+    calendars: usize,
+    schedules: usize,
 }
 
 #[derive(Debug)]
 struct UseCaseRes {
     pub user: User,
+    pub counts: Option<UserCounts>,
 }
 
 #[derive(Debug)]
 enum UseCaseErrors {
     UserNotFound,
+    StorageError,
 }
 
 #[async_trait::async_trait(?Send)]
@@ -57,11 +78,71 @@ impl UseCase for GetUserUseCase {
     const NAME: &'static str = "GetUser";
 
     async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
-        let user = match ctx.repos.user_repo.find(&self.user_id).await {
-            Some(u) if u.account_id == self.account.id => u,
-            _ => return Err(UseCaseErrors::UserNotFound),
+        let user = match ctx
+            .repos
+            .user_repo
+            .find_for_account(&self.user_id, &self.account.id)
+            .await
+        {
+            Some(u) => u,
+            None => return Err(UseCaseErrors::UserNotFound),
         };
 
-        Ok(UseCaseRes { user })
+        if !self.include_counts {
+            return Ok(UseCaseRes { user, counts: None });
+        }
+
+        let (calendars, schedules) = join!(
+            ctx.repos.calendar_repo.count_by_user(&user.id),
+            ctx.repos.schedule_repo.count_by_user(&user.id)
+        );
+        match (calendars, schedules) {
+            (Ok(calendars), Ok(schedules)) => Ok(UseCaseRes {
+                user,
+                counts: Some(UserCounts {
+                    calendars,
+                    schedules,
+                }),
+            }),
+            _ => Err(UseCaseErrors::StorageError),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nettu_scheduler_domain::Calendar;
+    use nettu_scheduler_infra::setup_context;
+
+    #[actix_web::main]
+    #[test]
+    async fn includes_calendar_and_schedule_counts_when_requested() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        for _ in 0..2 {
+            let calendar = Calendar::new(&user.id, &account.id);
+            ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        }
+
+        let mut usecase = GetUserUseCase {
+            account: account.clone(),
+            user_id: user.id.clone(),
+            include_counts: true,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        let counts = res.counts.expect("Counts to be included");
+        assert_eq!(counts.calendars, 2);
+        assert_eq!(counts.schedules, 0);
+
+        let mut usecase = GetUserUseCase {
+            account,
+            user_id: user.id.clone(),
+            include_counts: false,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert!(res.counts.is_none());
     }
 }
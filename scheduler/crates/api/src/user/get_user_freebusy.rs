@@ -1,14 +1,18 @@
-use crate::shared::usecase::{execute, UseCase};
+use crate::shared::{
+    auth::{account_can_modify_user, protect_account_route},
+    usecase::{execute, UseCase},
+};
 use crate::{error::NettuError, shared::auth::protect_public_account_route};
 use actix_web::{web, HttpRequest, HttpResponse};
 use futures::future::join_all;
 use nettu_scheduler_api_structs::get_user_freebusy::{APIResponse, PathParams, QueryParams};
-use nettu_scheduler_domain::{CompatibleInstances, EventInstance, TimeSpan, ID};
+use nettu_scheduler_api_structs::get_user_freebusy_details;
+use nettu_scheduler_domain::{CalendarEvent, CompatibleInstances, EventInstance, TimeSpan, ID};
 use nettu_scheduler_infra::NettuContext;
 use std::collections::HashMap;
 
 /// "1,2,3" -> Vec<1,2,3>
-fn parse_vec_query_value(val: &Option<String>) -> Option<Vec<ID>> {
+pub(crate) fn parse_vec_query_value(val: &Option<String>) -> Option<Vec<ID>> {
     val.as_ref().map(|ids| {
         ids.split(',')
             .map(String::from)
@@ -34,6 +38,10 @@ pub async fn get_freebusy_controller(
         calendar_ids,
         start_ts: query_params.start_ts,
         end_ts: query_params.end_ts,
+        include_free: query_params.include_free,
+        tentative_as_busy: query_params.tentative_as_busy,
+        exclude_event_ids: vec![],
+        include_private_calendars: false,
     };
 
     execute(usecase, &ctx)
@@ -41,27 +49,82 @@ pub async fn get_freebusy_controller(
         .map(|usecase_res| {
             HttpResponse::Ok().json(APIResponse {
                 busy: usecase_res.busy.inner(),
+                free: usecase_res.free.map(|free| free.inner()),
                 user_id: usecase_res.user_id.to_string(),
             })
         })
-        .map_err(|e| match e {
-            UseCaseErrors::InvalidTimespan => {
-                NettuError::BadClientData("The provided start_ts and end_ts is invalid".into())
-            }
+        .map_err(handle_error)
+}
+
+fn handle_error(e: UseCaseErrors) -> NettuError {
+    match e {
+        UseCaseErrors::InvalidTimespan => {
+            NettuError::BadClientData("The provided start_ts and end_ts is invalid".into())
+        }
+    }
+}
+
+pub async fn get_freebusy_details_admin_controller(
+    http_req: HttpRequest,
+    query_params: web::Query<get_user_freebusy_details::QueryParams>,
+    params: web::Path<PathParams>,
+    ctx: web::Data<NettuContext>,
+) -> Result<HttpResponse, NettuError> {
+    let account = protect_account_route(&http_req, &ctx).await?;
+    let user = account_can_modify_user(&account, &params.user_id, &ctx).await?;
+
+    let usecase = GetFreeBusyDetailsUseCase {
+        freebusy: GetFreeBusyUseCase {
+            user_id: user.id,
+            calendar_ids: parse_vec_query_value(&query_params.calendar_ids),
+            start_ts: query_params.start_ts,
+            end_ts: query_params.end_ts,
+            include_free: false,
+            tentative_as_busy: query_params.tentative_as_busy,
+            exclude_event_ids: vec![],
+            include_private_calendars: true,
+        },
+        include_breakdown: query_params.include_breakdown,
+    };
+
+    execute(usecase, &ctx)
+        .await
+        .map(|res| {
+            HttpResponse::Ok().json(get_user_freebusy_details::APIResponse::new(
+                res.busy,
+                res.busy_by_calendar,
+                params.0.user_id,
+            ))
         })
+        .map_err(handle_error)
 }
 
 #[derive(Debug)]
 pub struct GetFreeBusyUseCase {
     pub user_id: ID,
+    /// Ids of `Calendar`s that do not belong to the `User` are silently ignored,
+    /// instead of being rejected, so that a caller can not find out whether a
+    /// `Calendar` of another `User` exists
     pub calendar_ids: Option<Vec<ID>>,
     pub start_ts: i64,
     pub end_ts: i64,
+    pub include_free: bool,
+    /// Tentative events are treated as free unless this is set
+    pub tentative_as_busy: bool,
+    /// `CalendarEvent`s that are left out, like an event that is being rescheduled
+    /// and should not block its own new time
+    pub exclude_event_ids: Vec<ID>,
+    /// Also consider `Calendar`s marked as private in their `Metadata`, which should
+    /// only be done for the account admin
+    pub include_private_calendars: bool,
 }
 
 #[derive(Debug)]
 pub struct GetFreeBusyResponse {
     pub busy: CompatibleInstances,
+    /// The parts of the requested timespan that are not busy,
+    /// only set when `include_free` is requested
+    pub free: Option<CompatibleInstances>,
     pub user_id: ID,
 }
 
@@ -88,38 +151,122 @@ impl UseCase for GetFreeBusyUseCase {
             .get_event_instances_from_calendars(&timespan, ctx)
             .await
             .into_iter()
+            .flat_map(|(_, instances)| instances)
             .filter(|e| e.busy)
+            // Which events the user is busy with is only told to the account admin,
+            // see `GetFreeBusyDetailsUseCase`
+            .map(|e| EventInstance {
+                event_id: None,
+                ..e
+            })
             .collect::<Vec<_>>();
-
         let busy = CompatibleInstances::new(busy_event_instances);
 
+        let free = if self.include_free {
+            Some(busy.complement(self.start_ts, self.end_ts))
+        } else {
+            None
+        };
+
         Ok(GetFreeBusyResponse {
             busy,
+            free,
             user_id: self.user_id.to_owned(),
         })
     }
 }
 
+/// Admin variant of `GetFreeBusyUseCase` that tells which `CalendarEvent` each
+/// busy instance comes from, which is kept out of the regular freebusy response
+/// to not leak the events of a `User` to anyone knowing the account id
+#[derive(Debug)]
+pub struct GetFreeBusyDetailsUseCase {
+    pub freebusy: GetFreeBusyUseCase,
+    /// Also return the busy instances of each `Calendar` on their own
+    pub include_breakdown: bool,
+}
+
+#[derive(Debug)]
+pub struct GetFreeBusyDetailsResponse {
+    /// Sorted by `start_ts`
+    pub busy: Vec<(EventInstance, CalendarEvent)>,
+    /// The busy instances of every requested `Calendar` that has any, sorted
+    /// by `start_ts`. Only set when `include_breakdown` is requested
+    pub busy_by_calendar: Option<HashMap<ID, Vec<EventInstance>>>,
+}
+
+#[async_trait::async_trait(?Send)]
+impl UseCase for GetFreeBusyDetailsUseCase {
+    type Response = GetFreeBusyDetailsResponse;
+
+    type Errors = UseCaseErrors;
+
+    const NAME: &'static str = "GetUserFreebusyDetails";
+
+    async fn execute(&mut self, ctx: &NettuContext) -> Result<Self::Response, Self::Errors> {
+        let timespan = TimeSpan::new(self.freebusy.start_ts, self.freebusy.end_ts);
+        if timespan.greater_than(ctx.config.event_instances_query_duration_limit) {
+            return Err(UseCaseErrors::InvalidTimespan);
+        }
+
+        let mut busy = self
+            .freebusy
+            .get_event_instances_from_calendars(&timespan, ctx)
+            .await
+            .into_iter()
+            .flat_map(|(event, instances)| {
+                instances
+                    .into_iter()
+                    .filter(|instance| instance.busy)
+                    .map(move |instance| (instance, event.clone()))
+            })
+            .collect::<Vec<_>>();
+        busy.sort_by(|(i1, _), (i2, _)| i1.cmp(i2));
+
+        let busy_by_calendar = if self.include_breakdown {
+            let mut busy_by_calendar: HashMap<ID, Vec<EventInstance>> = HashMap::new();
+            for (instance, event) in &busy {
+                busy_by_calendar
+                    .entry(event.calendar_id.clone())
+                    .or_default()
+                    .push(instance.clone());
+            }
+            Some(busy_by_calendar)
+        } else {
+            None
+        };
+
+        Ok(GetFreeBusyDetailsResponse {
+            busy,
+            busy_by_calendar,
+        })
+    }
+}
+
 impl GetFreeBusyUseCase {
+    /// The instances within the timespan of every `CalendarEvent` in the requested
+    /// `Calendar`s, grouped by the event they are an instance of
     async fn get_event_instances_from_calendars(
         &self,
         timespan: &TimeSpan,
         ctx: &NettuContext,
-    ) -> Vec<EventInstance> {
+    ) -> Vec<(CalendarEvent, Vec<EventInstance>)> {
         let calendar_ids = match &self.calendar_ids {
             Some(ids) if !ids.is_empty() => ids,
             _ => return vec![],
         };
 
-        // can probably make query to event repo instead
-        let mut calendars = ctx.repos.calendar_repo.find_by_user(&self.user_id).await;
-
-        if !calendar_ids.is_empty() {
-            calendars = calendars
-                .into_iter()
-                .filter(|cal| calendar_ids.contains(&cal.id))
-                .collect();
-        }
+        // Only the calendars of the user are looked up, so that the availibility of
+        // other users never leaks through foreign calendar ids
+        let calendars = ctx
+            .repos
+            .calendar_repo
+            .find_by_user(&self.user_id)
+            .await
+            .into_iter()
+            .filter(|cal| calendar_ids.contains(&cal.id))
+            .filter(|cal| self.include_private_calendars || !cal.is_private())
+            .collect::<Vec<_>>();
 
         let calendars_lookup: HashMap<_, _> = calendars
             .iter()
@@ -139,17 +286,23 @@ impl GetFreeBusyUseCase {
             .map(|events| {
                 events
                     .into_iter()
+                    .filter(|event| !self.exclude_event_ids.contains(&event.id))
                     .map(|event| {
                         let calendar = calendars_lookup
                             .get(&event.calendar_id.to_string())
                             .unwrap();
-                        event.expand(Some(&timespan), &calendar.settings)
+                        let busy = event.status.is_busy(self.tentative_as_busy);
+                        let instances = event
+                            .expand(Some(&timespan), &calendar.settings)
+                            .into_iter()
+                            .map(|instance| EventInstance { busy, ..instance })
+                            .collect::<Vec<_>>();
+                        (event, instances)
                     })
                     // It is possible that there are no instances in the expanded event, should remove them
-                    .filter(|instances| !instances.is_empty())
+                    .filter(|(_, instances)| !instances.is_empty())
             })
             .flatten()
-            .flatten()
             .collect::<Vec<_>>()
     }
 }
@@ -157,8 +310,12 @@ impl GetFreeBusyUseCase {
 #[cfg(test)]
 mod test {
     use super::*;
-    use nettu_scheduler_domain::{Calendar, CalendarEvent, Entity, RRuleOptions, User};
-    use nettu_scheduler_infra::setup_context;
+    use actix_web::{test, App};
+    use nettu_scheduler_domain::{
+        Account, Calendar, CalendarEvent, Entity, EventStatus, Metadata, RRuleOptions, User,
+        CALENDAR_VISIBILITY_KEY,
+    };
+    use nettu_scheduler_infra::{setup_context, SeededContext};
 
     #[test]
     fn it_parses_vec_query_params_correctly() {
@@ -187,6 +344,7 @@ mod test {
             user_id: user.id.clone(),
             account_id: user.account_id.clone(),
             busy: true,
+            status: EventStatus::Busy,
             duration: one_hour,
             end_ts: CalendarEvent::get_max_timestamp(),
             exdates: vec![],
@@ -195,8 +353,11 @@ mod test {
             recurrence: None,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let e1rr = RRuleOptions {
@@ -210,6 +371,7 @@ mod test {
             user_id: user.id.clone(),
             account_id: user.account_id.clone(),
             busy: true,
+            status: EventStatus::Busy,
             duration: one_hour,
             end_ts: CalendarEvent::get_max_timestamp(),
             exdates: vec![],
@@ -218,8 +380,11 @@ mod test {
             recurrence: None,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let e2rr = RRuleOptions {
@@ -233,6 +398,7 @@ mod test {
             user_id: user.id.clone(),
             account_id: user.account_id.clone(),
             busy: true,
+            status: EventStatus::Busy,
             duration: one_hour,
             end_ts: one_hour,
             exdates: vec![],
@@ -241,8 +407,11 @@ mod test {
             recurrence: None,
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             updated: Default::default(),
+            version: Default::default(),
             created: Default::default(),
         };
         let e3rr = RRuleOptions {
@@ -261,6 +430,10 @@ mod test {
             calendar_ids: Some(vec![calendar.id.clone()]),
             start_ts: 86400000,
             end_ts: 172800000,
+            include_free: false,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: false,
         };
 
         let res = usecase.execute(&ctx).await;
@@ -273,6 +446,7 @@ mod test {
                 busy: true,
                 start_ts: 86400000,
                 end_ts: 90000000,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -281,7 +455,510 @@ mod test {
                 busy: true,
                 start_ts: 100800000,
                 end_ts: 104400000,
+                event_id: None,
+            }
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn freebusy_includes_free_intervals() {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+
+        let calendar = Calendar::new(&user.id(), &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let one_hour = 1000 * 60 * 60;
+        let mut e1 = CalendarEvent {
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id.clone(),
+            busy: true,
+            status: EventStatus::Busy,
+            duration: one_hour,
+            end_ts: CalendarEvent::get_max_timestamp(),
+            exdates: vec![],
+            id: Default::default(),
+            start_ts: one_hour * 2,
+            recurrence: None,
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            updated: Default::default(),
+            version: Default::default(),
+            created: Default::default(),
+        };
+        let e1rr = RRuleOptions {
+            count: Some(100),
+            ..Default::default()
+        };
+        e1.set_recurrence(e1rr, &calendar.settings, true);
+        ctx.repos.event_repo.insert(&e1).await.unwrap();
+
+        let start_ts = 86400000;
+        let end_ts = 172800000;
+        let mut usecase = GetFreeBusyUseCase {
+            user_id: user.id().clone(),
+            calendar_ids: Some(vec![calendar.id.clone()]),
+            start_ts,
+            end_ts,
+            include_free: true,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: false,
+        };
+
+        let res = usecase.execute(&ctx).await.unwrap();
+        let busy = res.busy.inner();
+        let free = res.free.expect("Free instances to be included").inner();
+        assert_eq!(busy.len(), 1);
+        assert_eq!(free.len(), 2);
+        assert_eq!(free[0].start_ts, start_ts);
+        assert_eq!(free[0].end_ts, busy[0].start_ts);
+        assert_eq!(free[1].start_ts, busy[0].end_ts);
+        assert_eq!(free[1].end_ts, end_ts);
+
+        // Free and busy partition the window without overlap
+        let mut all = free.iter().chain(busy.iter()).collect::<Vec<_>>();
+        all.sort();
+        for pair in all.windows(2) {
+            assert!(pair[0].end_ts <= pair[1].start_ts);
+        }
+        let total = all.iter().map(|i| i.end_ts - i.start_ts).sum::<i64>();
+        assert_eq!(total, end_ts - start_ts);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn tentative_events_are_free_unless_treated_as_busy() {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+
+        let calendar = Calendar::new(&user.id(), &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let one_hour = 1000 * 60 * 60;
+        let mut event = CalendarEvent {
+            calendar_id: calendar.id.clone(),
+            user_id: user.id.clone(),
+            account_id: user.account_id.clone(),
+            busy: false,
+            status: EventStatus::Free,
+            duration: one_hour,
+            end_ts: one_hour * 3,
+            exdates: vec![],
+            id: Default::default(),
+            start_ts: one_hour * 2,
+            recurrence: None,
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            updated: Default::default(),
+            version: Default::default(),
+            created: Default::default(),
+        };
+        event.set_status(EventStatus::Tentative);
+        assert!(!event.busy);
+        ctx.repos.event_repo.insert(&event).await.unwrap();
+
+        let mut usecase = GetFreeBusyUseCase {
+            user_id: user.id().clone(),
+            calendar_ids: Some(vec![calendar.id.clone()]),
+            start_ts: 0,
+            end_ts: one_hour * 24,
+            include_free: false,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: false,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert!(res.busy.is_empty());
+
+        usecase.tentative_as_busy = true;
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(
+            res.busy.inner(),
+            vec![EventInstance {
+                busy: true,
+                start_ts: one_hour * 2,
+                end_ts: one_hour * 3,
+                event_id: None,
+            }]
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn freebusy_ignores_calendars_of_other_users() {
+        let ctx = setup_context().await;
+        let user = User::new(Default::default());
+        let other_user = User::new(user.account_id.clone());
+
+        let calendar = Calendar::new(&user.id, &user.account_id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+        let foreign_calendar = Calendar::new(&other_user.id, &other_user.account_id);
+        ctx.repos
+            .calendar_repo
+            .insert(&foreign_calendar)
+            .await
+            .unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        for (calendar, start_ts) in &[(&calendar, one_hour), (&foreign_calendar, one_hour * 4)] {
+            let event = CalendarEvent {
+                calendar_id: calendar.id.clone(),
+                user_id: calendar.user_id.clone(),
+                account_id: calendar.account_id.clone(),
+                busy: true,
+                status: EventStatus::Busy,
+                duration: one_hour,
+                end_ts: start_ts + one_hour,
+                exdates: vec![],
+                id: Default::default(),
+                start_ts: *start_ts,
+                recurrence: None,
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+                updated: Default::default(),
+                version: Default::default(),
+                created: Default::default(),
+            };
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+        }
+
+        let mut usecase = GetFreeBusyUseCase {
+            user_id: user.id.clone(),
+            calendar_ids: Some(vec![calendar.id.clone(), foreign_calendar.id.clone()]),
+            start_ts: 0,
+            end_ts: one_hour * 24,
+            include_free: false,
+            tentative_as_busy: false,
+            exclude_event_ids: vec![],
+            include_private_calendars: false,
+        };
+        let res = usecase.execute(&ctx).await.unwrap();
+        assert_eq!(
+            res.busy.inner().into_iter().collect::<Vec<_>>(),
+            vec![EventInstance {
+                busy: true,
+                start_ts: one_hour,
+                end_ts: one_hour * 2,
+                event_id: None,
+            }]
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn freebusy_response_is_sorted_and_merged() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let calendar = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        // Inserted out of order, and the first two overlap each other
+        for (start_ts, end_ts) in &[(3, 5), (4, 6), (1, 2)] {
+            let event = CalendarEvent {
+                calendar_id: calendar.id.clone(),
+                user_id: user.id.clone(),
+                account_id: account.id.clone(),
+                busy: true,
+                status: EventStatus::Busy,
+                duration: (end_ts - start_ts) * one_hour,
+                end_ts: end_ts * one_hour,
+                exdates: vec![],
+                id: Default::default(),
+                start_ts: start_ts * one_hour,
+                recurrence: None,
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+                updated: Default::default(),
+                version: Default::default(),
+                created: Default::default(),
+            };
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+        }
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::user::configure_routes),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/{}/freebusy?startTs=0&endTs={}&calendarIds={}&includeFree=true",
+                user.id,
+                one_hour * 24,
+                calendar.id
+            ))
+            .header("nettu-account", account.id.to_string())
+            .to_request();
+        let res: serde_json::Value = test::read_response_json(&mut app, req).await;
+
+        let busy: Vec<EventInstance> = serde_json::from_value(res["busy"].clone()).unwrap();
+        let instance = |start_ts: i64, end_ts: i64, busy: bool| EventInstance {
+            start_ts: start_ts * one_hour,
+            end_ts: end_ts * one_hour,
+            busy,
+            event_id: None,
+        };
+        assert_eq!(busy, vec![instance(1, 2, true), instance(3, 6, true)]);
+        let free: Vec<EventInstance> = serde_json::from_value(res["free"].clone()).unwrap();
+        assert_eq!(
+            free,
+            vec![
+                instance(0, 1, false),
+                instance(2, 3, false),
+                instance(6, 24, false)
+            ]
+        );
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn freebusy_breakdown_keys_busy_instances_by_calendar() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let work = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&work).await.unwrap();
+        let private = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&private).await.unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        // The last block of `work` overlaps the block of `private`
+        for (calendar, start_ts, end_ts) in &[(&work, 1, 2), (&work, 3, 5), (&private, 4, 6)] {
+            let event = CalendarEvent {
+                calendar_id: calendar.id.clone(),
+                user_id: user.id.clone(),
+                account_id: account.id.clone(),
+                busy: true,
+                status: EventStatus::Busy,
+                duration: (end_ts - start_ts) * one_hour,
+                end_ts: end_ts * one_hour,
+                exdates: vec![],
+                id: Default::default(),
+                start_ts: start_ts * one_hour,
+                recurrence: None,
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata: Default::default(),
+                external_id: None,
+                updated: Default::default(),
+                version: Default::default(),
+                created: Default::default(),
+            };
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+        }
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::user::configure_routes),
+        )
+        .await;
+        let query = format!(
+            "startTs=0&endTs={}&calendarIds={},{}",
+            one_hour * 24,
+            work.id,
+            private.id
+        );
+        let spans = |instances: &[EventInstance]| {
+            instances
+                .iter()
+                .map(|i| (i.start_ts / one_hour, i.end_ts / one_hour))
+                .collect::<Vec<_>>()
+        };
+
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/{}/freebusy/details?{}&includeBreakdown=true",
+                user.id, query
+            ))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: get_user_freebusy_details::APIResponse =
+            test::read_response_json(&mut app, req).await;
+        assert_eq!(res.busy.len(), 3);
+        let busy_by_calendar = res.busy_by_calendar.unwrap();
+        assert_eq!(busy_by_calendar.len(), 2);
+        assert_eq!(spans(&busy_by_calendar[&work.id]), vec![(1, 2), (3, 5)]);
+        assert_eq!(spans(&busy_by_calendar[&private.id]), vec![(4, 6)]);
+
+        // The breakdown is left out unless asked for
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy/details?{}", user.id, query))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: serde_json::Value = test::read_response_json(&mut app, req).await;
+        assert!(res.get("busyByCalendar").is_none());
+
+        // And it is never part of the public freebusy
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/user/{}/freebusy?{}&includeBreakdown=true",
+                user.id, query
+            ))
+            .header("nettu-account", account.id.to_string())
+            .to_request();
+        let res: serde_json::Value = test::read_response_json(&mut app, req).await;
+        assert!(res.get("busyByCalendar").is_none());
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn only_admin_freebusy_details_include_events() {
+        let ctx = setup_context().await;
+        let account = Account::default();
+        ctx.repos.account_repo.insert(&account).await.unwrap();
+        let user = User::new(account.id.clone());
+        ctx.repos.user_repo.insert(&user).await.unwrap();
+        let calendar = Calendar::new(&user.id, &account.id);
+        ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+        let one_hour = 1000 * 60 * 60;
+        let mut events = vec![];
+        // Overlapping events, which are merged in the regular freebusy response
+        for (start_ts, end_ts, reason) in &[(3, 5, None), (1, 4, Some("Dentist"))] {
+            let mut metadata = Metadata::new();
+            if let Some(reason) = reason {
+                metadata.insert("reason".into(), reason.to_string());
             }
+            let event = CalendarEvent {
+                calendar_id: calendar.id.clone(),
+                user_id: user.id.clone(),
+                account_id: account.id.clone(),
+                busy: true,
+                status: EventStatus::Busy,
+                duration: (end_ts - start_ts) * one_hour,
+                end_ts: end_ts * one_hour,
+                exdates: vec![],
+                id: Default::default(),
+                start_ts: start_ts * one_hour,
+                recurrence: None,
+                reminder: None,
+                is_service: false,
+                service_id: None,
+                metadata,
+                external_id: None,
+                updated: Default::default(),
+                version: Default::default(),
+                created: Default::default(),
+            };
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+            events.push(event);
+        }
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::user::configure_routes),
+        )
+        .await;
+        let query = format!(
+            "startTs=0&endTs={}&calendarIds={}",
+            one_hour * 24,
+            calendar.id
         );
+
+        // The regular freebusy response stays anonymous
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy?{}", user.id, query))
+            .header("nettu-account", account.id.to_string())
+            .to_request();
+        let res: serde_json::Value = test::read_response_json(&mut app, req).await;
+        let busy = res["busy"].as_array().unwrap();
+        assert_eq!(busy.len(), 1);
+        assert!(busy[0].get("eventId").is_none());
+        assert!(busy[0].get("reason").is_none());
+
+        // The details are not public
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy/details?{}", user.id, query))
+            .header("nettu-account", account.id.to_string())
+            .to_request();
+        let res = test::call_service(&mut app, req).await;
+        assert!(!res.status().is_success());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy/details?{}", user.id, query))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: get_user_freebusy_details::APIResponse =
+            test::read_response_json(&mut app, req).await;
+        assert_eq!(res.user_id, user.id);
+        assert_eq!(res.busy.len(), 2);
+        assert_eq!(res.busy[0].event_id, events[1].id);
+        assert_eq!(res.busy[0].start_ts, one_hour);
+        assert_eq!(res.busy[0].reason, Some("Dentist".into()));
+        assert_eq!(res.busy[1].event_id, events[0].id);
+        assert_eq!(res.busy[1].start_ts, 3 * one_hour);
+        assert_eq!(res.busy[1].reason, None);
+    }
+
+    #[actix_web::main]
+    #[test]
+    async fn private_calendars_are_only_in_admin_freebusy() {
+        let one_hour = 1000 * 60 * 60;
+        let SeededContext {
+            ctx,
+            account,
+            user,
+            calendar,
+            ..
+        } = NettuContext::seeded()
+            .with_calendar_events(&[(one_hour, 2 * one_hour)])
+            .build()
+            .await;
+        let (account, user, mut calendar) = (account.unwrap(), user.unwrap(), calendar.unwrap());
+        calendar
+            .metadata
+            .insert(CALENDAR_VISIBILITY_KEY.into(), "private".into());
+        ctx.repos.calendar_repo.save(&calendar).await.unwrap();
+
+        let mut app = test::init_service(
+            App::new()
+                .data(ctx)
+                .configure(crate::user::configure_routes),
+        )
+        .await;
+        let query = format!(
+            "startTs=0&endTs={}&calendarIds={}",
+            one_hour * 24,
+            calendar.id
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy?{}", user.id, query))
+            .header("nettu-account", account.id.to_string())
+            .to_request();
+        let res: serde_json::Value = test::read_response_json(&mut app, req).await;
+        assert!(res["busy"].as_array().unwrap().is_empty());
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/user/{}/freebusy/details?{}", user.id, query))
+            .header("x-api-key", account.secret_api_key.clone())
+            .to_request();
+        let res: get_user_freebusy_details::APIResponse =
+            test::read_response_json(&mut app, req).await;
+        assert_eq!(res.busy.len(), 1);
+        assert_eq!(res.busy[0].start_ts, one_hour);
     }
 }
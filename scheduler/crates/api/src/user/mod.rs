@@ -2,7 +2,7 @@ pub mod create_user;
 mod delete_user;
 mod get_me;
 mod get_user;
-mod get_user_freebusy;
+pub mod get_user_freebusy;
 mod get_users_by_meta;
 mod update_user;
 
@@ -11,7 +11,7 @@ use create_user::create_user_controller;
 use delete_user::delete_user_controller;
 use get_me::get_me_controller;
 use get_user::get_user_controller;
-use get_user_freebusy::get_freebusy_controller;
+use get_user_freebusy::{get_freebusy_controller, get_freebusy_details_admin_controller};
 use get_users_by_meta::get_users_by_meta_controller;
 use update_user::update_user_controller;
 
@@ -26,4 +26,8 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         "/user/{user_id}/freebusy",
         web::get().to(get_freebusy_controller),
     );
+    cfg.route(
+        "/user/{user_id}/freebusy/details",
+        web::get().to(get_freebusy_details_admin_controller),
+    );
 }
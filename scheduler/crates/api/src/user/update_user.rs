@@ -59,7 +59,7 @@ impl UseCase for UpdateUserUseCase {
         let mut user = match ctx
             .repos
             .user_repo
-            .find_by_account_id(&self.user_id, &self.account_id)
+            .find_for_account(&self.user_id, &self.account_id)
             .await
         {
             Some(user) => user,
@@ -1,5 +1,6 @@
-use nettu_scheduler_domain::Account;
+use nettu_scheduler_domain::{Account, ID};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::dtos::AccountDTO;
 
@@ -55,6 +56,7 @@ pub mod set_account_pub_key {
     #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default, alias = "public_jwt_key")]
         pub public_jwt_key: Option<String>,
     }
 
@@ -67,14 +69,72 @@ pub mod set_account_webhook {
     #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(alias = "webhook_url")]
         pub webhook_url: String,
     }
 
     pub type APIResponse = AccountResponse;
 }
 
+pub mod set_account_default_timezone {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        /// IANA timezone used when queries do not specify one, or `None` to use UTC
+        #[serde(alias = "default_timezone")]
+        pub default_timezone: Option<String>,
+    }
+
+    pub type APIResponse = AccountResponse;
+}
+
+pub mod set_account_calendar_limit {
+    use super::*;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        /// Maximum number of `Calendar`s that a `User` in the `Account` can have
+        #[serde(alias = "max_calendars_per_user")]
+        pub max_calendars_per_user: usize,
+    }
+
+    pub type APIResponse = AccountResponse;
+}
+
 pub mod delete_account_webhook {
     use super::*;
 
     pub type APIResponse = AccountResponse;
 }
+
+pub mod send_account_webhook {
+    use super::*;
+
+    /// Envelope that every webhook sent to an `Account` is wrapped in
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountWebhookEnvelopeDTO<T> {
+        /// Unique id of this delivery which receivers can use to ignore duplicates
+        pub id: Uuid,
+        pub r#type: String,
+        /// Timestamp in millis of when this webhook was sent
+        pub sent_at: i64,
+        pub account_id: ID,
+        pub data: T,
+    }
+
+    impl<T> AccountWebhookEnvelopeDTO<T> {
+        pub fn new(r#type: &str, account_id: ID, sent_at: i64, data: T) -> Self {
+            Self {
+                id: Uuid::new_v4(),
+                r#type: r#type.into(),
+                sent_at,
+                account_id,
+                data,
+            }
+        }
+    }
+}
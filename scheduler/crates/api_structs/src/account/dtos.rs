@@ -11,6 +11,12 @@ pub struct AccountDTO {
 
 impl AccountDTO {
     pub fn new(account: &Account) -> Self {
+        Self::from(account)
+    }
+}
+
+impl From<&Account> for AccountDTO {
+    fn from(account: &Account) -> Self {
         Self {
             id: account.id.clone(),
             public_jwt_key: account.public_jwt_key.clone(),
@@ -23,10 +29,18 @@ impl AccountDTO {
 #[serde(rename_all = "camelCase")]
 pub struct AccountSettingsDTO {
     pub webhook: Option<AccountWebhookSettingsDTO>,
+    pub max_calendars_per_user: usize,
+    pub default_timezone: Option<String>,
 }
 
 impl AccountSettingsDTO {
     pub fn new(settings: &AccountSettings) -> Self {
+        Self::from(settings)
+    }
+}
+
+impl From<&AccountSettings> for AccountSettingsDTO {
+    fn from(settings: &AccountSettings) -> Self {
         let webhook_settings = settings
             .webhook
             .as_ref()
@@ -34,6 +48,8 @@ impl AccountSettingsDTO {
 
         Self {
             webhook: webhook_settings,
+            max_calendars_per_user: settings.max_calendars_per_user,
+            default_timezone: settings.default_timezone.map(|tz| tz.to_string()),
         }
     }
 }
@@ -47,6 +63,12 @@ pub struct AccountWebhookSettingsDTO {
 
 impl AccountWebhookSettingsDTO {
     pub fn new(settings: &AccountWebhookSettings) -> Self {
+        Self::from(settings)
+    }
+}
+
+impl From<&AccountWebhookSettings> for AccountWebhookSettingsDTO {
+    fn from(settings: &AccountWebhookSettings) -> Self {
         Self {
             url: settings.url.clone(),
             key: settings.key.clone(),
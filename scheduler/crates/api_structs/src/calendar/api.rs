@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::dtos::{CalendarDTO, EventWithInstancesDTO};
@@ -15,6 +16,16 @@ impl CalendarResponse {
             calendar: CalendarDTO::new(calendar),
         }
     }
+
+    pub fn with_stats(
+        calendar: Calendar,
+        event_count: usize,
+        events_last_updated: Option<i64>,
+    ) -> Self {
+        Self {
+            calendar: CalendarDTO::with_stats(calendar, event_count, events_last_updated),
+        }
+    }
 }
 
 pub mod create_calendar {
@@ -30,7 +41,7 @@ pub mod create_calendar {
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
         pub timezone: String,
-        #[serde(default)]
+        #[serde(default, alias = "week_start")]
         pub week_start: isize,
         #[serde(default)]
         pub metadata: Option<Metadata>,
@@ -39,6 +50,38 @@ pub mod create_calendar {
     pub type APIResponse = CalendarResponse;
 }
 
+pub mod block_calendar_time {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub calendar_id: ID,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        pub duration: i64,
+        /// Stored in the metadata of the created `CalendarEvent`
+        #[serde(default)]
+        pub reason: Option<String>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub event_id: ID,
+    }
+
+    impl APIResponse {
+        pub fn new(event_id: ID) -> Self {
+            Self { event_id }
+        }
+    }
+}
+
 pub mod delete_calendar {
     use super::*;
 
@@ -65,7 +108,9 @@ pub mod get_calendar_events {
     #[derive(Debug, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub struct QueryParams {
+        #[serde(alias = "start_ts")]
         pub start_ts: i64,
+        #[serde(alias = "end_ts")]
         pub end_ts: i64,
     }
 
@@ -92,6 +137,72 @@ pub mod get_calendar_events {
     }
 }
 
+pub mod get_calendar_conflicts {
+    use super::*;
+    use nettu_scheduler_domain::EventConflict;
+
+    #[derive(Debug, Deserialize)]
+    pub struct PathParams {
+        pub calendar_id: ID,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        #[serde(alias = "end_ts")]
+        pub end_ts: i64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub conflicts: Vec<EventConflict>,
+    }
+
+    impl APIResponse {
+        pub fn new(conflicts: Vec<EventConflict>) -> Self {
+            Self { conflicts }
+        }
+    }
+}
+
+pub mod check_calendar_conflict {
+    use super::*;
+    use nettu_scheduler_domain::RRuleOptions;
+
+    #[derive(Debug, Deserialize)]
+    pub struct PathParams {
+        pub calendar_id: ID,
+    }
+
+    /// A proposed busy `CalendarEvent`, which is only checked and never stored
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        pub duration: i64,
+        #[serde(default)]
+        pub recurrence: Option<RRuleOptions>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        /// The busy instances in the `Calendar` overlapping with the proposed event,
+        /// which is empty when it would not conflict
+        pub conflicts: Vec<EventInstance>,
+    }
+
+    impl APIResponse {
+        pub fn new(conflicts: Vec<EventInstance>) -> Self {
+            Self { conflicts }
+        }
+    }
+}
+
 pub mod get_calendar {
     use super::*;
 
@@ -100,6 +211,15 @@ pub mod get_calendar {
         pub calendar_id: ID,
     }
 
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Also return how many `CalendarEvent`s the `Calendar` has and when
+        /// they were last updated
+        #[serde(default, alias = "include_stats")]
+        pub include_stats: bool,
+    }
+
     pub type APIResponse = CalendarResponse;
 }
 
@@ -117,6 +237,38 @@ pub mod get_calendars_by_meta {
     }
 
     #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub calendars: Vec<CalendarDTO>,
+    }
+
+    impl APIResponse {
+        pub fn new(calendars: Vec<Calendar>) -> Self {
+            Self {
+                calendars: calendars.into_iter().map(CalendarDTO::new).collect(),
+            }
+        }
+    }
+}
+
+pub mod get_user_calendars {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub user_id: ID,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        #[serde(default)]
+        pub skip: Option<usize>,
+        pub limit: Option<usize>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct APIResponse {
         pub calendars: Vec<CalendarDTO>,
     }
@@ -134,28 +286,126 @@ pub mod get_user_freebusy {
     use super::*;
     use std::collections::VecDeque;
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, JsonSchema)]
     pub struct PathParams {
         pub user_id: ID,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetUserFreeBusyQuery")]
     pub struct QueryParams {
+        #[serde(alias = "start_ts")]
         pub start_ts: i64,
+        #[serde(alias = "end_ts")]
         pub end_ts: i64,
-        #[serde(default)]
+        #[serde(default, alias = "calendar_ids")]
         pub calendar_ids: Option<String>,
+        /// Also return the free intervals within the requested timespan
+        #[serde(default, alias = "include_free")]
+        pub include_free: bool,
+        /// Treat tentative events as busy instead of free
+        #[serde(default, alias = "tentative_as_busy")]
+        pub tentative_as_busy: bool,
     }
 
-    #[derive(Debug, Serialize)]
+    #[derive(Debug, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetUserFreeBusyResponse")]
     pub struct APIResponse {
+        /// Sorted ascending by `start_ts`, where overlapping busy instances
+        /// are merged so that no two instances overlap
         pub busy: VecDeque<EventInstance>,
+        /// Sorted and non-overlapping the same way as `busy`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub free: Option<VecDeque<EventInstance>>,
         pub user_id: String,
     }
 }
 
+pub mod get_user_freebusy_details {
+    use super::*;
+    use nettu_scheduler_domain::CalendarEvent;
+    use std::collections::HashMap;
+
+    pub use super::get_user_freebusy::PathParams;
+
+    #[derive(Debug, Deserialize, JsonSchema)]
+    #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetUserFreeBusyDetailsQuery")]
+    pub struct QueryParams {
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        #[serde(alias = "end_ts")]
+        pub end_ts: i64,
+        #[serde(default, alias = "calendar_ids")]
+        pub calendar_ids: Option<String>,
+        /// Treat tentative events as busy instead of free
+        #[serde(default, alias = "tentative_as_busy")]
+        pub tentative_as_busy: bool,
+        /// Also return which busy instances come from which calendar
+        #[serde(default, alias = "include_breakdown")]
+        pub include_breakdown: bool,
+    }
+
+    /// A busy instance together with the `CalendarEvent` that makes it busy
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BusyEventInstanceDTO {
+        pub start_ts: i64,
+        pub end_ts: i64,
+        pub event_id: ID,
+        /// The `title` metadata value of the event
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub title: Option<String>,
+        /// The `reason` metadata value of the event, e.g. set when blocking time
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub reason: Option<String>,
+    }
+
+    impl BusyEventInstanceDTO {
+        pub fn new(instance: EventInstance, event: &CalendarEvent) -> Self {
+            Self {
+                start_ts: instance.start_ts,
+                end_ts: instance.end_ts,
+                event_id: event.id.clone(),
+                title: event.metadata.get("title").cloned(),
+                reason: event.metadata.get("reason").cloned(),
+            }
+        }
+    }
+
+    #[derive(Debug, Deserialize, Serialize, JsonSchema)]
+    #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetUserFreeBusyDetailsResponse")]
+    pub struct APIResponse {
+        /// Sorted ascending by `start_ts`. Unlike the regular freebusy response the
+        /// instances are not merged, so overlapping events are all listed
+        pub busy: Vec<BusyEventInstanceDTO>,
+        /// Busy instances keyed by calendar id, only set when the breakdown is asked for
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub busy_by_calendar: Option<HashMap<ID, Vec<EventInstance>>>,
+        pub user_id: ID,
+    }
+
+    impl APIResponse {
+        pub fn new(
+            busy: Vec<(EventInstance, CalendarEvent)>,
+            busy_by_calendar: Option<HashMap<ID, Vec<EventInstance>>>,
+            user_id: ID,
+        ) -> Self {
+            Self {
+                busy: busy
+                    .into_iter()
+                    .map(|(instance, event)| BusyEventInstanceDTO::new(instance, &event))
+                    .collect(),
+                busy_by_calendar,
+                user_id,
+            }
+        }
+    }
+}
+
 pub mod update_calendar {
     use super::*;
     use nettu_scheduler_domain::Metadata;
@@ -168,11 +418,13 @@ pub mod update_calendar {
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct CalendarSettings {
-        #[serde(default)]
+        #[serde(default, alias = "week_start")]
         pub week_start: Option<isize>,
+        #[serde(default)]
         pub timezone: Option<String>,
     }
 
+    /// Fields that are left out or `null`, also within the `settings`, keep their current value
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
@@ -8,6 +8,15 @@ pub struct CalendarDTO {
     pub user_id: ID,
     pub settings: CalendarSettingsDTO,
     pub metadata: Metadata,
+    pub created: i64,
+    pub updated: i64,
+    /// Only included when the stats are explicitly requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_count: Option<usize>,
+    /// Most recent `updated` timestamp of the events in the `Calendar`. Only
+    /// included when the stats are explicitly requested and there are any events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub events_last_updated: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -19,17 +28,45 @@ pub struct CalendarSettingsDTO {
 
 impl CalendarDTO {
     pub fn new(calendar: Calendar) -> Self {
+        Self::from(calendar)
+    }
+
+    pub fn with_stats(
+        calendar: Calendar,
+        event_count: usize,
+        events_last_updated: Option<i64>,
+    ) -> Self {
+        Self {
+            event_count: Some(event_count),
+            events_last_updated,
+            ..Self::new(calendar)
+        }
+    }
+}
+
+impl From<Calendar> for CalendarDTO {
+    fn from(calendar: Calendar) -> Self {
         Self {
             id: calendar.id.clone(),
             user_id: calendar.user_id.clone(),
             settings: CalendarSettingsDTO::new(&calendar.settings),
             metadata: calendar.metadata,
+            created: calendar.created,
+            updated: calendar.updated,
+            event_count: None,
+            events_last_updated: None,
         }
     }
 }
 
 impl CalendarSettingsDTO {
     pub fn new(settings: &CalendarSettings) -> Self {
+        Self::from(settings)
+    }
+}
+
+impl From<&CalendarSettings> for CalendarSettingsDTO {
+    fn from(settings: &CalendarSettings) -> Self {
         Self {
             week_start: settings.week_start,
             timezone: settings.timezone.to_string(),
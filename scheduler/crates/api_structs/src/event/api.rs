@@ -1,7 +1,39 @@
 use crate::dtos::CalendarEventDTO;
 use nettu_scheduler_domain::{CalendarEvent, EventInstance};
-use nettu_scheduler_domain::{CalendarEventReminder, RRuleOptions, ID};
-use serde::{Deserialize, Serialize};
+use nettu_scheduler_domain::{CalendarEventReminder, EventStatus, RRuleOptions, ID};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Accepts a recurrence either as structured `RRuleOptions` or as an iCalendar
+/// rule like `FREQ=WEEKLY;BYDAY=MO,WE;INTERVAL=2`
+fn deserialize_recurrence<'de, D>(deserializer: D) -> Result<Option<RRuleOptions>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Recurrence {
+        Rule(String),
+        Options(RRuleOptions),
+    }
+
+    match Option::<Recurrence>::deserialize(deserializer)? {
+        Some(Recurrence::Rule(rule)) => RRuleOptions::from_rrule_string(&rule)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        Some(Recurrence::Options(options)) => Ok(Some(options)),
+        None => Ok(None),
+    }
+}
+
+/// Tells a field that is left out, which is `None`, apart from a field that is `null`,
+/// which is `Some(None)`
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
 
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -27,22 +59,203 @@ pub mod create_event {
         pub user_id: ID,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Debug, Serialize, Deserialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(alias = "calendar_id")]
         pub calendar_id: ID,
+        #[serde(alias = "start_ts")]
         pub start_ts: i64,
-        pub duration: i64,
+        /// Either `duration` or `end_ts` has to be provided, and when both
+        /// are provided they have to agree with each other
+        #[serde(default)]
+        pub duration: Option<i64>,
+        #[serde(default, alias = "end_ts")]
+        pub end_ts: Option<i64>,
+        #[serde(default)]
         pub busy: Option<bool>,
+        /// Takes precedence over `busy`, which can only describe free and busy events
+        #[serde(default)]
+        pub status: Option<EventStatus>,
+        #[serde(default, deserialize_with = "deserialize_recurrence")]
         pub recurrence: Option<RRuleOptions>,
-        pub reminder: Option<CalendarEventReminder>,
         #[serde(default)]
+        pub reminder: Option<CalendarEventReminder>,
+        #[serde(default, alias = "is_service")]
         pub is_service: Option<bool>,
+        /// The `Service` that the event is a booking of, which also makes it a service event
+        #[serde(default, alias = "service_id")]
+        pub service_id: Option<ID>,
         #[serde(default)]
         pub metadata: Option<Metadata>,
+        /// Identifier of the event in an external system, which the event can be
+        /// looked up by later on
+        #[serde(default, alias = "external_id")]
+        pub external_id: Option<String>,
     }
 
     pub type APIResponse = CalendarEventResponse;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn request_body_accepts_camel_case_and_deprecated_snake_case_fields() {
+            let calendar_id = ID::default();
+            let camel_case = format!(
+                r#"{{ "calendarId": "{}", "startTs": 10, "endTs": 20, "isService": true }}"#,
+                calendar_id
+            );
+            let snake_case = format!(
+                r#"{{ "calendar_id": "{}", "start_ts": 10, "end_ts": 20, "is_service": true }}"#,
+                calendar_id
+            );
+
+            for json in &[camel_case, snake_case] {
+                let body: RequestBody = serde_json::from_str(json).unwrap();
+                assert_eq!(body.calendar_id, calendar_id);
+                assert_eq!(body.start_ts, 10);
+                assert_eq!(body.end_ts, Some(20));
+                assert_eq!(body.is_service, Some(true));
+            }
+        }
+
+        #[test]
+        fn request_body_accepts_recurrence_as_rrule_string() {
+            let calendar_id = ID::default();
+            let rule = format!(
+                r#"{{ "calendarId": "{}", "startTs": 10, "duration": 10, "recurrence": "FREQ=WEEKLY;BYDAY=MO,WE;INTERVAL=2" }}"#,
+                calendar_id
+            );
+            let body: RequestBody = serde_json::from_str(&rule).unwrap();
+            let recurrence = body.recurrence.unwrap();
+            assert_eq!(recurrence.interval, 2);
+            assert_eq!(recurrence.byweekday.unwrap().len(), 2);
+
+            let options = format!(
+                r#"{{ "calendarId": "{}", "startTs": 10, "duration": 10, "recurrence": {{ "freq": "daily", "interval": 1 }} }}"#,
+                calendar_id
+            );
+            let body: RequestBody = serde_json::from_str(&options).unwrap();
+            assert_eq!(
+                body.recurrence.unwrap().to_rrule_string().unwrap(),
+                "FREQ=DAILY"
+            );
+
+            let without = format!(
+                r#"{{ "calendarId": "{}", "startTs": 10, "duration": 10 }}"#,
+                calendar_id
+            );
+            let body: RequestBody = serde_json::from_str(&without).unwrap();
+            assert!(body.recurrence.is_none());
+
+            let invalid = format!(
+                r#"{{ "calendarId": "{}", "startTs": 10, "recurrence": "FREQ=SOMETIMES" }}"#,
+                calendar_id
+            );
+            assert!(serde_json::from_str::<RequestBody>(&invalid).is_err());
+        }
+    }
+}
+
+pub mod create_events_batch {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    pub struct PathParams {
+        pub user_id: ID,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        /// Either all of the events are created, or none of them
+        pub events: Vec<create_event::RequestBody>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub events: Vec<CalendarEventDTO>,
+    }
+
+    impl APIResponse {
+        pub fn new(events: Vec<CalendarEvent>) -> Self {
+            Self {
+                events: events.into_iter().map(CalendarEventDTO::new).collect(),
+            }
+        }
+    }
+}
+
+pub mod add_event_exdate {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub event_id: ID,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        /// Start of the instance of the `CalendarEvent` to exclude
+        pub ts: i64,
+    }
+
+    pub type APIResponse = CalendarEventResponse;
+}
+
+pub mod remove_event_exdate {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub event_id: ID,
+        pub ts: i64,
+    }
+
+    pub type APIResponse = CalendarEventResponse;
+}
+
+pub mod validate_recurrence {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Default)]
+    #[serde(rename_all = "camelCase")]
+    pub struct CalendarSettingsBody {
+        #[serde(default, alias = "week_start")]
+        pub week_start: isize,
+        #[serde(default)]
+        pub timezone: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        pub recurrence: RRuleOptions,
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        #[serde(default)]
+        pub duration: i64,
+        /// Settings of the `Calendar` the event would be created in, defaults to UTC
+        /// with the week starting on monday
+        #[serde(default, alias = "calendar_settings")]
+        pub calendar_settings: CalendarSettingsBody,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub instances: Vec<EventInstance>,
+    }
+
+    impl APIResponse {
+        pub fn new(instances: Vec<EventInstance>) -> Self {
+            Self { instances }
+        }
+    }
 }
 
 pub mod delete_event {
@@ -67,7 +280,9 @@ pub mod get_event_instances {
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(rename_all = "camelCase")]
     pub struct QueryParams {
+        #[serde(alias = "start_ts")]
         pub start_ts: i64,
+        #[serde(alias = "end_ts")]
         pub end_ts: i64,
     }
 
@@ -88,6 +303,74 @@ pub mod get_event_instances {
     }
 }
 
+pub mod get_event_reschedule_slots {
+    use super::*;
+    use nettu_scheduler_domain::booking_slots::{BookingSlot, BookingTimezone, SlotStart};
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub event_id: ID,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        #[serde(alias = "iana_tz")]
+        pub iana_tz: Option<String>,
+        /// Offset from UTC in minutes, for clients that do not know their IANA timezone.
+        /// Can not be combined with `iana_tz`.
+        #[serde(default, alias = "utc_offset_minutes")]
+        pub utc_offset_minutes: Option<i32>,
+        pub duration: i64,
+        /// Time between the starts of two slots, defaults to the `duration`
+        #[serde(default)]
+        pub interval: Option<i64>,
+        /// Where the slots are stepped by the `interval` from, defaults to the
+        /// start of the date
+        #[serde(default)]
+        pub slot_start: Option<SlotStart>,
+        pub date: String,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RescheduleSlotDTO {
+        pub start: i64,
+        /// `start` as an RFC 3339 date in the timezone of the query
+        pub start_rfc3339: String,
+        pub duration: i64,
+        pub available_until: i64,
+    }
+
+    impl RescheduleSlotDTO {
+        pub fn new(slot: BookingSlot, tz: &BookingTimezone) -> Self {
+            Self {
+                start: slot.start,
+                start_rfc3339: tz.to_rfc3339(slot.start),
+                duration: slot.duration,
+                available_until: slot.available_until,
+            }
+        }
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub booking_slots: Vec<RescheduleSlotDTO>,
+    }
+
+    impl APIResponse {
+        pub fn new(booking_slots: Vec<BookingSlot>, tz: &BookingTimezone) -> Self {
+            Self {
+                booking_slots: booking_slots
+                    .into_iter()
+                    .map(|slot| RescheduleSlotDTO::new(slot, tz))
+                    .collect(),
+            }
+        }
+    }
+}
+
 pub mod get_event {
     use super::*;
 
@@ -99,6 +382,17 @@ pub mod get_event {
     pub type APIResponse = CalendarEventResponse;
 }
 
+pub mod get_event_by_external_id {
+    use super::*;
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub external_id: String,
+    }
+
+    pub type APIResponse = CalendarEventResponse;
+}
+
 pub mod get_events_by_meta {
     use super::*;
 
@@ -113,6 +407,7 @@ pub mod get_events_by_meta {
     }
 
     #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct APIResponse {
         pub events: Vec<CalendarEventDTO>,
     }
@@ -126,23 +421,173 @@ pub mod get_events_by_meta {
     }
 }
 
+pub mod get_account_upcoming_reminders {
+    use super::*;
+    use crate::dtos::ReminderDTO;
+    use nettu_scheduler_domain::Reminder;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Only `Reminder`s that are due within this many millis from now are returned
+        #[serde(alias = "within_ms")]
+        pub within_ms: i64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub reminders: Vec<ReminderDTO>,
+    }
+
+    impl APIResponse {
+        pub fn new(reminders: Vec<Reminder>) -> Self {
+            Self {
+                reminders: reminders.into_iter().map(ReminderDTO::new).collect(),
+            }
+        }
+    }
+}
+
+pub mod get_sent_reminders {
+    use super::*;
+    use crate::dtos::ReminderDTO;
+    use nettu_scheduler_domain::Reminder;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Only `Reminder`s that were sent, or failed to be sent, after this timestamp are returned
+        #[serde(alias = "since_ts")]
+        pub since_ts: i64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub reminders: Vec<ReminderDTO>,
+    }
+
+    impl APIResponse {
+        pub fn new(reminders: Vec<Reminder>) -> Self {
+            Self {
+                reminders: reminders.into_iter().map(ReminderDTO::new).collect(),
+            }
+        }
+    }
+}
+
+pub mod rebuild_reminders {
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Only the `Reminder`s of this `Account` are rebuilt, instead of those of every `Account`
+        #[serde(default, alias = "account_id")]
+        pub account_id: Option<ID>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        /// How many `CalendarEvent`s with reminder settings were found
+        pub events: usize,
+        /// How many pending `Reminder`s were created for them
+        pub reminders: usize,
+    }
+}
+
 pub mod update_event {
-    use nettu_scheduler_domain::Metadata;
+    use nettu_scheduler_domain::{EventEditMode, Metadata, MetadataPatch};
 
     use super::*;
 
+    /// Only the given fields are changed, a field that is `null` is treated as left out.
+    /// The exception is `reminder`, which is removed when it is `null`.
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default, alias = "start_ts")]
         pub start_ts: Option<i64>,
+        #[serde(default)]
         pub duration: Option<i64>,
+        /// Alternative to `duration`, which is derived as `end_ts - start_ts`
+        #[serde(default, alias = "end_ts")]
+        pub end_ts: Option<i64>,
+        #[serde(default)]
         pub busy: Option<bool>,
+        /// Takes precedence over `busy`, which can only describe free and busy events
+        #[serde(default)]
+        pub status: Option<EventStatus>,
+        #[serde(default, deserialize_with = "deserialize_recurrence")]
         pub recurrence: Option<RRuleOptions>,
+        #[serde(default, alias = "is_service")]
         pub is_service: Option<bool>,
+        #[serde(default)]
         pub exdates: Option<Vec<i64>>,
-        pub reminder: Option<CalendarEventReminder>,
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        pub reminder: Option<Option<CalendarEventReminder>>,
         #[serde(default)]
         pub metadata: Option<Metadata>,
+        /// Upserts the given keys into the existing metadata instead of replacing it.
+        /// A key with a `null` value is removed.
+        #[serde(default, alias = "metadata_patch")]
+        pub metadata_patch: Option<MetadataPatch>,
+        /// Which instances of a recurring event are changed: "this", "this-and-following"
+        /// or "all", which is the default
+        #[serde(default, alias = "edit_mode")]
+        pub edit_mode: Option<EventEditMode>,
+        /// Start of the instance that is edited when not every instance is changed
+        #[serde(default, alias = "instance_start_ts")]
+        pub instance_start_ts: Option<i64>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub event_id: ID,
+    }
+
+    pub type APIResponse = CalendarEventResponse;
+}
+
+pub mod replace_event {
+    use nettu_scheduler_domain::Metadata;
+
+    use super::*;
+
+    /// The full representation of the event, where omitted fields are reset
+    /// to their defaults instead of keeping their current values
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        #[serde(alias = "start_ts")]
+        pub start_ts: i64,
+        /// Either `duration` or `end_ts` has to be provided, and when both
+        /// are provided they have to agree with each other
+        #[serde(default)]
+        pub duration: Option<i64>,
+        #[serde(default, alias = "end_ts")]
+        pub end_ts: Option<i64>,
+        #[serde(default)]
+        pub busy: bool,
+        /// Takes precedence over `busy`, which can only describe free and busy events
+        #[serde(default)]
+        pub status: Option<EventStatus>,
+        #[serde(default, deserialize_with = "deserialize_recurrence")]
+        pub recurrence: Option<RRuleOptions>,
+        #[serde(default, alias = "is_service")]
+        pub is_service: bool,
+        #[serde(default)]
+        pub exdates: Vec<i64>,
+        #[serde(default)]
+        pub reminder: Option<CalendarEventReminder>,
+        #[serde(default)]
+        pub metadata: Metadata,
     }
 
     #[derive(Deserialize)]
@@ -163,6 +608,8 @@ pub mod send_account_event_reminders {
     }
 
     impl AccountEventRemindersDTO {
+        pub const TYPE: &'static str = "event.reminders";
+
         pub fn new(events: Vec<CalendarEvent>) -> Self {
             Self {
                 events: events.into_iter().map(CalendarEventDTO::new).collect(),
@@ -170,3 +617,23 @@ pub mod send_account_event_reminders {
         }
     }
 }
+
+pub mod send_account_event_deleted {
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct AccountEventDeletedDTO {
+        event: CalendarEventDTO,
+    }
+
+    impl AccountEventDeletedDTO {
+        pub const TYPE: &'static str = "event.deleted";
+
+        pub fn new(event: CalendarEvent) -> Self {
+            Self {
+                event: CalendarEventDTO::new(event),
+            }
+        }
+    }
+}
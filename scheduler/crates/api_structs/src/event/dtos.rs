@@ -1,47 +1,255 @@
 use nettu_scheduler_domain::{
-    CalendarEvent, CalendarEventReminder, EventInstance, Metadata, RRuleOptions, ID,
+    Calendar, CalendarEvent, CalendarEventReminder, EventInstance, EventStatus, Metadata,
+    RRuleOptions, Reminder, ID,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CalendarEventDTO {
     pub id: ID,
     pub start_ts: i64,
     pub duration: i64,
     pub busy: bool,
+    pub status: EventStatus,
     pub updated: i64,
+    /// Can be given in the `If-Match` header when changing the event, so that
+    /// changes made by others since it was read are not overwritten
+    #[serde(default)]
+    pub version: i64,
     pub created: i64,
     pub recurrence: Option<RRuleOptions>,
     pub exdates: Vec<i64>,
     pub calendar_id: ID,
     pub user_id: ID,
     pub reminder: Option<CalendarEventReminder>,
+    /// The `Service` that the event is a booking of, as given when creating it
+    #[serde(default)]
+    pub service_id: Option<ID>,
     pub metadata: Metadata,
+    #[serde(default)]
+    pub external_id: Option<String>,
 }
 
 impl CalendarEventDTO {
     pub fn new(event: CalendarEvent) -> Self {
+        Self::from(event)
+    }
+}
+
+impl From<CalendarEvent> for CalendarEventDTO {
+    fn from(event: CalendarEvent) -> Self {
         Self {
-            id: event.id.clone(),
+            id: event.id,
             start_ts: event.start_ts,
             duration: event.duration,
             busy: event.busy,
+            status: event.status,
             updated: event.updated,
+            version: event.version,
             created: event.created,
             recurrence: event.recurrence,
             exdates: event.exdates,
-            calendar_id: event.calendar_id.clone(),
-            user_id: event.user_id.clone(),
+            calendar_id: event.calendar_id,
+            user_id: event.user_id,
             reminder: event.reminder,
+            service_id: event.service_id,
             metadata: event.metadata,
+            external_id: event.external_id,
         }
     }
 }
 
+/// Reasons for why a `CalendarEventDTO` does not describe a valid `CalendarEvent`
+#[derive(Debug, PartialEq)]
+pub enum InvalidCalendarEventDTO {
+    /// The `Calendar` given is not the one the event belongs to
+    InvalidCalendar,
+    InvalidTimespan,
+    InvalidRecurrence,
+    InvalidReminder,
+}
+
+/// The fields of the `CalendarEvent` that are not exposed in the `CalendarEventDTO`
+/// are restored from the `Calendar` that the event belongs to
+impl TryFrom<(CalendarEventDTO, &Calendar)> for CalendarEvent {
+    type Error = InvalidCalendarEventDTO;
+
+    fn try_from((dto, calendar): (CalendarEventDTO, &Calendar)) -> Result<Self, Self::Error> {
+        if dto.calendar_id != calendar.id || dto.user_id != calendar.user_id {
+            return Err(InvalidCalendarEventDTO::InvalidCalendar);
+        }
+        let mut event = Self {
+            id: dto.id,
+            start_ts: dto.start_ts,
+            duration: dto.duration,
+            end_ts: dto.start_ts.saturating_add(dto.duration),
+            busy: dto.busy,
+            status: dto.status,
+            updated: dto.updated,
+            version: dto.version,
+            created: dto.created,
+            recurrence: None,
+            exdates: dto.exdates,
+            calendar_id: dto.calendar_id,
+            user_id: dto.user_id,
+            account_id: calendar.account_id.clone(),
+            reminder: dto.reminder,
+            is_service: dto.service_id.is_some(),
+            service_id: dto.service_id,
+            metadata: dto.metadata,
+            external_id: dto.external_id,
+        };
+
+        if !event.has_valid_timespan() {
+            return Err(InvalidCalendarEventDTO::InvalidTimespan);
+        }
+        if let Some(recurrence) = dto.recurrence {
+            if !event.set_recurrence(recurrence, &calendar.settings, true) {
+                return Err(InvalidCalendarEventDTO::InvalidRecurrence);
+            }
+        }
+        if let Some(reminder) = &event.reminder {
+            if !reminder.is_valid() {
+                return Err(InvalidCalendarEventDTO::InvalidReminder);
+            }
+        }
+
+        Ok(event)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventWithInstancesDTO {
     pub event: CalendarEventDTO,
     pub instances: Vec<EventInstance>,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReminderDTO {
+    pub event_id: ID,
+    pub remind_at: i64,
+    /// When the `Reminder` was sent, which is only set for sent `Reminder`s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<i64>,
+    /// When sending the `Reminder` was given up, which is only set for failed `Reminder`s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_at: Option<i64>,
+}
+
+impl ReminderDTO {
+    pub fn new(reminder: Reminder) -> Self {
+        Self::from(reminder)
+    }
+}
+
+impl From<Reminder> for ReminderDTO {
+    fn from(reminder: Reminder) -> Self {
+        Self {
+            event_id: reminder.event_id,
+            remind_at: reminder.remind_at,
+            sent_at: reminder.sent_at,
+            failed_at: reminder.failed_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nettu_scheduler_domain::RRuleFrequenzy;
+
+    fn generate_event(calendar: &Calendar) -> CalendarEvent {
+        let mut metadata = Metadata::new();
+        metadata.insert("group".into(), "marketing".into());
+        CalendarEvent {
+            id: Default::default(),
+            start_ts: 1000 * 60 * 60,
+            duration: 1000 * 60 * 30,
+            end_ts: 1000 * 60 * 60 * 49 + 1000 * 60 * 30,
+            busy: true,
+            status: EventStatus::Busy,
+            recurrence: Some(RRuleOptions {
+                freq: RRuleFrequenzy::Daily,
+                interval: 1,
+                count: Some(3),
+                ..Default::default()
+            }),
+            exdates: vec![1000 * 60 * 60 * 25],
+            calendar_id: calendar.id.clone(),
+            user_id: calendar.user_id.clone(),
+            account_id: calendar.account_id.clone(),
+            reminder: Some(CalendarEventReminder { minutes_before: 10 }),
+            is_service: true,
+            service_id: Some(Default::default()),
+            metadata,
+            external_id: None,
+            created: 1,
+            updated: 2,
+            version: 3,
+        }
+    }
+
+    #[test]
+    fn calendar_event_round_trips_through_dto() {
+        let calendar = Calendar::new(&ID::default(), &ID::default());
+        let event = generate_event(&calendar);
+
+        let dto: CalendarEventDTO = event.clone().into();
+        let converted = CalendarEvent::try_from((dto, &calendar)).expect("To be a valid event");
+
+        assert_eq!(converted.id, event.id);
+        assert_eq!(converted.start_ts, event.start_ts);
+        assert_eq!(converted.duration, event.duration);
+        assert_eq!(converted.end_ts, event.end_ts);
+        assert_eq!(converted.busy, event.busy);
+        assert_eq!(converted.status, event.status);
+        assert_eq!(converted.created, event.created);
+        assert_eq!(converted.updated, event.updated);
+        assert_eq!(converted.version, event.version);
+        assert_eq!(
+            converted.recurrence.map(|r| (r.freq, r.count)),
+            Some((RRuleFrequenzy::Daily, Some(3)))
+        );
+        assert_eq!(converted.exdates, event.exdates);
+        assert_eq!(converted.calendar_id, event.calendar_id);
+        assert_eq!(converted.user_id, event.user_id);
+        assert_eq!(converted.account_id, event.account_id);
+        assert_eq!(converted.reminder.map(|r| r.minutes_before), Some(10));
+        assert_eq!(converted.is_service, event.is_service);
+        assert_eq!(converted.service_id, event.service_id);
+        assert_eq!(converted.metadata, event.metadata);
+    }
+
+    #[test]
+    fn invalid_dto_is_not_converted_to_calendar_event() {
+        let calendar = Calendar::new(&ID::default(), &ID::default());
+
+        let mut dto = CalendarEventDTO::from(generate_event(&calendar));
+        dto.duration = -1;
+        assert_eq!(
+            CalendarEvent::try_from((dto, &calendar)).unwrap_err(),
+            InvalidCalendarEventDTO::InvalidTimespan
+        );
+
+        let mut dto = CalendarEventDTO::from(generate_event(&calendar));
+        dto.reminder = Some(CalendarEventReminder {
+            minutes_before: 60 * 24 + 1,
+        });
+        assert_eq!(
+            CalendarEvent::try_from((dto, &calendar)).unwrap_err(),
+            InvalidCalendarEventDTO::InvalidReminder
+        );
+
+        let dto = CalendarEventDTO::from(generate_event(&calendar));
+        let other_calendar = Calendar::new(&calendar.user_id, &calendar.account_id);
+        assert_eq!(
+            CalendarEvent::try_from((dto, &other_calendar)).unwrap_err(),
+            InvalidCalendarEventDTO::InvalidCalendar
+        );
+    }
+}
@@ -1,3 +1,10 @@
+//! Request and response types of the Nettu scheduler api.
+//!
+//! Every request body, query string and response body uses `camelCase` field
+//! names. Path parameters keep the `snake_case` names of the route segments.
+//! Requests are still accepted with `snake_case` field names for now, but that
+//! is deprecated and will be removed in a future release.
+
 mod account;
 mod calendar;
 mod event;
@@ -6,6 +13,8 @@ mod service;
 mod status;
 mod user;
 
+pub mod openapi;
+
 pub mod dtos {
     pub(crate) use crate::account::dtos::*;
     pub(crate) use crate::calendar::dtos::*;
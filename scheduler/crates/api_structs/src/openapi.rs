@@ -0,0 +1,185 @@
+use crate::{
+    dtos::CalendarEventDTO,
+    get_service_bookingslots::{self, ServiceBookingSlotDTO},
+    get_user_freebusy, get_user_freebusy_details,
+};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::ObjectValidation,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+/// Builds an OpenAPI document for the booking slots and freebusy routes, where
+/// `components.schemas` also describe the other key response types. The schemas
+/// are generated from the type definitions, so they follow the serde attributes
+/// like the `camelCase` renames.
+pub fn openapi_document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    paths.insert(
+        "/service/{service_id}/booking".into(),
+        get_operation::<
+            get_service_bookingslots::PathParams,
+            get_service_bookingslots::QueryParams,
+            get_service_bookingslots::APIResponse,
+        >(&mut gen, "Booking slots of the `Service` on a date"),
+    );
+    paths.insert(
+        "/user/{user_id}/freebusy".into(),
+        get_operation::<
+            get_user_freebusy::PathParams,
+            get_user_freebusy::QueryParams,
+            get_user_freebusy::APIResponse,
+        >(&mut gen, "Busy times of the `User` within a timespan"),
+    );
+    paths.insert(
+        "/user/{user_id}/freebusy/details".into(),
+        get_operation::<
+            get_user_freebusy_details::PathParams,
+            get_user_freebusy_details::QueryParams,
+            get_user_freebusy_details::APIResponse,
+        >(
+            &mut gen,
+            "Busy events of the `User` within a timespan, for admins of the `Account`",
+        ),
+    );
+    gen.subschema_for::<CalendarEventDTO>();
+    gen.subschema_for::<ServiceBookingSlotDTO>();
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Nettu scheduler",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/api/v1" }],
+        "paths": paths,
+        "components": {
+            "schemas": gen.definitions(),
+        },
+    })
+}
+
+/// Describes a `GET` route by its path params `P`, query params `Q` and response `R`
+fn get_operation<P: JsonSchema, Q: JsonSchema, R: JsonSchema>(
+    gen: &mut SchemaGenerator,
+    summary: &str,
+) -> Value {
+    let mut parameters = parameters::<P>(gen, "path");
+    parameters.extend(parameters::<Q>(gen, "query"));
+    json!({
+        "get": {
+            "summary": summary,
+            "parameters": parameters,
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "content": {
+                        "application/json": {
+                            "schema": gen.subschema_for::<R>(),
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
+
+/// Describes every field of `T` as a parameter in the given location
+fn parameters<T: JsonSchema>(gen: &mut SchemaGenerator, location: &str) -> Vec<Value> {
+    let ObjectValidation {
+        properties,
+        required,
+        ..
+    } = match gen.root_schema_for::<T>().schema.object {
+        Some(object) => *object,
+        None => return vec![],
+    };
+    properties
+        .into_iter()
+        .map(|(name, schema)| {
+            json!({
+                "required": location == "path" || required.contains(&name),
+                "name": name,
+                "in": location,
+                "schema": schema,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn describes_booking_slots_response() {
+        let document = openapi_document();
+        let schemas = &document["components"]["schemas"];
+
+        let booking_slots =
+            &schemas["GetServiceBookingSlotsResponse"]["properties"]["bookingSlots"];
+        assert_eq!(booking_slots["type"], "array");
+        assert_eq!(
+            booking_slots["items"]["$ref"],
+            "#/components/schemas/ServiceBookingSlotDTO"
+        );
+
+        let slot = &schemas["ServiceBookingSlotDTO"]["properties"];
+        assert_eq!(slot["start"]["type"], "integer");
+        assert_eq!(slot["startRfc3339"]["type"], "string");
+        assert_eq!(slot["userIds"]["items"]["type"], "string");
+
+        assert_eq!(
+            schemas["CalendarEventDTO"]["properties"]["calendarId"]["type"],
+            "string"
+        );
+        assert!(schemas["GetUserFreeBusyResponse"]["properties"]["busy"].is_object());
+    }
+
+    #[test]
+    fn describes_event_instance_as_serialized() {
+        let document = openapi_document();
+        let instance = &document["components"]["schemas"]["EventInstance"];
+
+        for field in &["startTs", "endTs", "duration", "busy", "eventId"] {
+            assert!(instance["properties"][field].is_object(), "{}", field);
+        }
+        let required = instance["required"].as_array().unwrap();
+        assert!(required.contains(&"duration".into()));
+        assert!(!required.contains(&"eventId".into()));
+    }
+
+    #[test]
+    fn describes_booking_slots_route() {
+        let document = openapi_document();
+        let operation = &document["paths"]["/service/{service_id}/booking"]["get"];
+
+        assert_eq!(
+            operation["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/GetServiceBookingSlotsResponse"
+        );
+        let parameters = operation["parameters"].as_array().unwrap();
+        let parameter = |name: &str| {
+            parameters
+                .iter()
+                .find(|parameter| parameter["name"] == name)
+                .unwrap_or_else(|| panic!("Expected the {} parameter", name))
+        };
+        assert_eq!(parameter("service_id")["in"], "path");
+        assert_eq!(parameter("service_id")["required"], true);
+        assert_eq!(parameter("duration")["in"], "query");
+        assert_eq!(parameter("duration")["required"], true);
+        assert_eq!(parameter("duration")["schema"]["type"], "integer");
+        assert_eq!(parameter("ianaTz")["required"], false);
+        assert_eq!(parameter("date")["schema"]["type"], "string");
+
+        for path in &[
+            "/user/{user_id}/freebusy",
+            "/user/{user_id}/freebusy/details",
+        ] {
+            assert!(document["paths"][path]["get"]["parameters"].is_array());
+        }
+    }
+}
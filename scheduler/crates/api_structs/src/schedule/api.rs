@@ -31,6 +31,7 @@ pub mod create_schedule {
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
         pub timezone: String,
+        #[serde(default)]
         pub rules: Option<Vec<ScheduleRule>>,
     }
 
@@ -69,12 +70,33 @@ pub mod update_schedule {
         pub schedule_id: ID,
     }
 
+    /// Fields that are left out and fields that are `null` both leave the value unchanged
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default)]
         pub timezone: Option<String>,
+        #[serde(default)]
         pub rules: Option<Vec<ScheduleRule>>,
     }
 
     pub type APIResponse = ScheduleResponse;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn omitted_and_null_fields_are_none() {
+            for json in &[r#"{}"#, r#"{ "timezone": null, "rules": null }"#] {
+                let body: RequestBody = serde_json::from_str(json).unwrap();
+                assert!(body.timezone.is_none());
+                assert!(body.rules.is_none());
+            }
+
+            let body: RequestBody = serde_json::from_str(r#"{ "timezone": "UTC" }"#).unwrap();
+            assert_eq!(body.timezone, Some("UTC".into()));
+            assert!(body.rules.is_none());
+        }
+    }
 }
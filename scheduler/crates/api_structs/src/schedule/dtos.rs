@@ -8,15 +8,25 @@ pub struct ScheduleDTO {
     pub user_id: ID,
     pub rules: Vec<ScheduleRule>,
     pub timezone: String,
+    pub created: i64,
+    pub updated: i64,
 }
 
 impl ScheduleDTO {
     pub fn new(schedule: Schedule) -> Self {
+        Self::from(schedule)
+    }
+}
+
+impl From<Schedule> for ScheduleDTO {
+    fn from(schedule: Schedule) -> Self {
         Self {
             id: schedule.id.clone(),
             user_id: schedule.user_id.clone(),
             rules: schedule.rules,
             timezone: schedule.timezone.to_string(),
+            created: schedule.created,
+            updated: schedule.updated,
         }
     }
 }
@@ -1,4 +1,5 @@
-use nettu_scheduler_domain::{Service, TimePlan, ID};
+use nettu_scheduler_domain::{BookingStrategy, Millis, Service, TimePlan, ID};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::dtos::ServiceDTO;
@@ -28,16 +29,38 @@ pub mod add_user_to_service {
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(alias = "user_id")]
         pub user_id: ID,
+        #[serde(default)]
         pub availibility: Option<TimePlan>,
+        #[serde(default)]
         pub busy: Option<Vec<ID>>,
         #[serde(default)]
         pub buffer: Option<i64>,
+        #[serde(default, alias = "closest_booking_time")]
         pub closest_booking_time: Option<i64>,
+        #[serde(default, alias = "furthest_booking_time")]
         pub furthest_booking_time: Option<i64>,
+        #[serde(default)]
+        pub timezone: Option<String>,
     }
 
-    pub type APIResponse = ServiceResponse;
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub service: ServiceDTO,
+        /// False if the `User` was already in the `Service` and was updated instead
+        pub created: bool,
+    }
+
+    impl APIResponse {
+        pub fn new(service: Service, created: bool) -> Self {
+            Self {
+                service: ServiceDTO::new(service),
+                created,
+            }
+        }
+    }
 }
 
 pub mod create_service {
@@ -48,6 +71,23 @@ pub mod create_service {
     #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default, alias = "booking_strategy")]
+        pub booking_strategy: Option<BookingStrategy>,
+        /// `Schedule` used by the users of the `Service` that have no availibility of their own
+        #[serde(default, alias = "default_schedule_id")]
+        pub default_schedule_id: Option<ID>,
+        /// Booking slots starting sooner than this many milliseconds from now are not offered
+        #[serde(default, alias = "min_lead_time_ms")]
+        pub min_lead_time_ms: Option<Millis>,
+        /// Durations in milliseconds that bookingslots can be requested for, or empty to allow any
+        #[serde(default, alias = "allowed_durations")]
+        pub allowed_durations: Option<Vec<i64>>,
+        /// Offers booking slots where every user is already booked, marked as `overbook`
+        #[serde(default, alias = "allow_overbooking")]
+        pub allow_overbooking: Option<bool>,
+        /// IANA timezone used for bookingslots queries that do not specify one
+        #[serde(default)]
+        pub timezone: Option<String>,
         #[serde(default)]
         pub metadata: Option<Metadata>,
     }
@@ -60,9 +100,27 @@ pub mod update_service {
 
     use super::*;
 
+    /// A field that is left out or `null` keeps the current value of the `Service`
     #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default, alias = "booking_strategy")]
+        pub booking_strategy: Option<BookingStrategy>,
+        /// `Schedule` used by the users of the `Service` that have no availibility of their own
+        #[serde(default, alias = "default_schedule_id")]
+        pub default_schedule_id: Option<ID>,
+        /// Booking slots starting sooner than this many milliseconds from now are not offered
+        #[serde(default, alias = "min_lead_time_ms")]
+        pub min_lead_time_ms: Option<Millis>,
+        /// Durations in milliseconds that bookingslots can be requested for, or empty to allow any
+        #[serde(default, alias = "allowed_durations")]
+        pub allowed_durations: Option<Vec<i64>>,
+        /// Offers booking slots where every user is already booked, marked as `overbook`
+        #[serde(default, alias = "allow_overbooking")]
+        pub allow_overbooking: Option<bool>,
+        /// IANA timezone used for bookingslots queries that do not specify one
+        #[serde(default)]
+        pub timezone: Option<String>,
         #[serde(default)]
         pub metadata: Option<Metadata>,
     }
@@ -77,56 +135,194 @@ pub mod update_service {
 
 pub mod get_service_bookingslots {
     use super::*;
-    use nettu_scheduler_domain::booking_slots::ServiceBookingSlot;
+    use nettu_scheduler_domain::booking_slots::{BookingTimezone, ServiceBookingSlot, SlotStart};
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, JsonSchema)]
     pub struct PathParams {
         pub service_id: ID,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetServiceBookingSlotsQuery")]
     pub struct QueryParams {
+        #[serde(alias = "iana_tz")]
         pub iana_tz: Option<String>,
+        /// Offset from UTC in minutes, for clients that do not know their IANA timezone.
+        /// Can not be combined with `iana_tz`.
+        #[serde(default, alias = "utc_offset_minutes")]
+        pub utc_offset_minutes: Option<i32>,
         pub duration: i64,
-        pub interval: i64,
+        /// Time between the starts of two slots, defaults to the `duration`
+        /// so that the slots are back to back without overlapping
+        #[serde(default)]
+        pub interval: Option<i64>,
+        /// Where the slots are stepped by the `interval` from, defaults to the
+        /// start of the date
+        #[serde(default)]
+        pub slot_start: Option<SlotStart>,
         pub date: String,
+        /// Comma separated ids of the `User`s that should not be considered
+        #[serde(default, alias = "exclude_user_ids")]
+        pub exclude_user_ids: Option<String>,
+        /// Comma separated ids of the only `User`s that should be considered
+        #[serde(default, alias = "user_ids")]
+        pub user_ids: Option<String>,
     }
 
-    #[derive(Deserialize, Serialize)]
+    #[derive(Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
     pub struct ServiceBookingSlotDTO {
         pub start: i64,
+        /// `start` as an RFC 3339 date in the timezone of the query
+        pub start_rfc3339: String,
         pub duration: i64,
         pub user_ids: Vec<ID>,
+        pub user_count: usize,
+        /// Every user that could take this slot already has a booking at this time
+        pub overbook: bool,
     }
 
     impl ServiceBookingSlotDTO {
-        pub fn new(slot: ServiceBookingSlot) -> Self {
+        pub fn new(slot: ServiceBookingSlot, tz: &BookingTimezone) -> Self {
             Self {
                 duration: slot.duration,
                 start: slot.start,
+                start_rfc3339: tz.to_rfc3339(slot.start),
+                user_count: slot.user_ids.len(),
                 user_ids: slot.user_ids,
+                overbook: slot.overbook,
             }
         }
     }
 
-    #[derive(Deserialize, Serialize)]
+    #[derive(Deserialize, Serialize, JsonSchema)]
     #[serde(rename_all = "camelCase")]
+    #[schemars(rename = "GetServiceBookingSlotsResponse")]
     pub struct APIResponse {
         pub booking_slots: Vec<ServiceBookingSlotDTO>,
     }
 
     impl APIResponse {
-        pub fn new(booking_slots: Vec<ServiceBookingSlot>) -> Self {
+        pub fn new(booking_slots: Vec<ServiceBookingSlot>, tz: &BookingTimezone) -> Self {
             Self {
                 booking_slots: booking_slots
                     .into_iter()
-                    .map(ServiceBookingSlotDTO::new)
+                    .map(|slot| ServiceBookingSlotDTO::new(slot, tz))
                     .collect(),
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use chrono::TimeZone;
+        use chrono_tz::America::New_York;
+
+        #[test]
+        fn booking_slot_dto_has_derived_fields() {
+            let start = chrono::Utc
+                .ymd(2021, 1, 4)
+                .and_hms(14, 0, 0)
+                .timestamp_millis();
+            let slot = ServiceBookingSlot {
+                start,
+                duration: 1000 * 60 * 30,
+                user_ids: vec![ID::default(), ID::default()],
+                overbook: false,
+            };
+
+            let dto = ServiceBookingSlotDTO::new(slot, &BookingTimezone::Iana(New_York));
+            assert_eq!(dto.start, start);
+            assert_eq!(dto.start_rfc3339, "2021-01-04T09:00:00-05:00");
+            assert_eq!(dto.user_count, 2);
+            assert_eq!(dto.user_ids.len(), 2);
+        }
+
+        #[test]
+        fn query_params_accept_camel_case_and_deprecated_snake_case_fields() {
+            let camel_case = r#"{ "ianaTz": "UTC", "duration": 1, "interval": 2, "date": "2021-1-4", "excludeUserIds": "a", "userIds": "b" }"#;
+            let snake_case = r#"{ "iana_tz": "UTC", "duration": 1, "interval": 2, "date": "2021-1-4", "exclude_user_ids": "a", "user_ids": "b" }"#;
+
+            for json in &[camel_case, snake_case] {
+                let query: QueryParams = serde_json::from_str(json).unwrap();
+                assert_eq!(query.iana_tz, Some("UTC".into()));
+                assert_eq!(query.utc_offset_minutes, None);
+                assert_eq!(query.exclude_user_ids, Some("a".into()));
+                assert_eq!(query.user_ids, Some("b".into()));
+            }
+        }
+    }
+}
+
+pub mod get_service_availability {
+    use super::*;
+    use nettu_scheduler_domain::EventInstance;
+    use std::collections::VecDeque;
+
+    pub use super::get_service_bookingslots::PathParams;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        #[serde(alias = "iana_tz")]
+        pub iana_tz: Option<String>,
+        /// Offset from UTC in minutes, for clients that do not know their IANA timezone.
+        /// Can not be combined with `iana_tz`.
+        #[serde(default, alias = "utc_offset_minutes")]
+        pub utc_offset_minutes: Option<i32>,
+        pub date: String,
+        /// Comma separated ids of the `User`s that should not be considered
+        #[serde(default, alias = "exclude_user_ids")]
+        pub exclude_user_ids: Option<String>,
+        /// Comma separated ids of the only `User`s that should be considered
+        #[serde(default, alias = "user_ids")]
+        pub user_ids: Option<String>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        /// Sorted ascending by `start_ts` and merged so that no two intervals overlap
+        pub free: VecDeque<EventInstance>,
+    }
+}
+
+pub mod warmup_service_bookingslots {
+    use super::*;
+
+    pub use super::get_service_bookingslots::PathParams;
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct RequestBody {
+        /// The dates to compute the booking slots for, like the `date` of a bookingslots query
+        pub dates: Vec<String>,
+        pub duration: i64,
+        /// Defaults to the `duration`, like for a bookingslots query
+        #[serde(default)]
+        pub interval: Option<i64>,
+        /// Defaults to the timezone of the `Service`, like for a bookingslots query
+        #[serde(default, alias = "iana_tz")]
+        pub iana_tz: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct WarmedDate {
+        pub date: String,
+        pub booking_slots: usize,
+        /// Time spent computing the booking slots of this date
+        pub duration_ms: u64,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub dates: Vec<WarmedDate>,
+        pub total_duration_ms: u64,
+    }
 }
 
 pub mod get_service {
@@ -154,6 +350,7 @@ pub mod get_services_by_meta {
     }
 
     #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct APIResponse {
         pub services: Vec<ServiceDTO>,
     }
@@ -167,6 +364,20 @@ pub mod get_services_by_meta {
     }
 }
 
+pub mod get_services {
+    use super::*;
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        #[serde(default)]
+        pub skip: Option<usize>,
+        pub limit: Option<usize>,
+    }
+
+    pub type APIResponse = get_services_by_meta::APIResponse;
+}
+
 pub mod delete_service {
     use super::*;
 
@@ -175,6 +386,15 @@ pub mod delete_service {
         pub service_id: ID,
     }
 
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Also deletes the upcoming bookings of the `Service` instead of
+        /// refusing to delete it while there are any
+        #[serde(default)]
+        pub force: bool,
+    }
+
     pub type APIResponse = ServiceResponse;
 }
 
@@ -199,15 +419,69 @@ pub mod update_service_user {
         pub user_id: ID,
     }
 
+    /// A field that is left out or `null` keeps the current value of the `ServiceResource`
     #[derive(Deserialize, Serialize)]
     #[serde(rename_all = "camelCase")]
     pub struct RequestBody {
+        #[serde(default)]
         pub availibility: Option<TimePlan>,
+        #[serde(default)]
         pub busy: Option<Vec<ID>>,
+        #[serde(default)]
         pub buffer: Option<i64>,
+        #[serde(default, alias = "closest_booking_time")]
         pub closest_booking_time: Option<i64>,
+        #[serde(default, alias = "furthest_booking_time")]
         pub furthest_booking_time: Option<i64>,
+        #[serde(default)]
+        pub timezone: Option<String>,
     }
 
     pub type APIResponse = ServiceResponse;
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn omitted_fields_are_none() {
+            let body: RequestBody = serde_json::from_str(r#"{ "buffer": 10 }"#).unwrap();
+            assert_eq!(body.buffer, Some(10));
+            assert!(body.availibility.is_none());
+            assert!(body.busy.is_none());
+            assert!(body.closest_booking_time.is_none());
+            assert!(body.furthest_booking_time.is_none());
+            assert!(body.timezone.is_none());
+        }
+    }
+}
+
+pub mod cancel_booking {
+    use super::*;
+    use crate::dtos::CalendarEventDTO;
+    use nettu_scheduler_domain::{CalendarEvent, EventInstance};
+
+    #[derive(Deserialize)]
+    pub struct PathParams {
+        pub service_id: ID,
+        pub event_id: ID,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        pub event: CalendarEventDTO,
+        /// Every instance of the booking that was freed, which for a recurring booking
+        /// only goes as far ahead as event instances can be queried
+        pub freed: Vec<EventInstance>,
+    }
+
+    impl APIResponse {
+        pub fn new(event: CalendarEvent, freed: Vec<EventInstance>) -> Self {
+            Self {
+                event: CalendarEventDTO::new(event),
+                freed,
+            }
+        }
+    }
 }
@@ -1,4 +1,6 @@
-use nettu_scheduler_domain::{Metadata, Service, ServiceResource, TimePlan, ID};
+use nettu_scheduler_domain::{
+    BookingStrategy, Metadata, Millis, Service, ServiceResource, TimePlan, ID,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize)]
@@ -11,10 +13,17 @@ pub struct ServiceResourceDTO {
     pub buffer: i64,
     pub closest_booking_time: i64,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 impl ServiceResourceDTO {
     pub fn new(resource: ServiceResource) -> Self {
+        Self::from(resource)
+    }
+}
+
+impl From<ServiceResource> for ServiceResourceDTO {
+    fn from(resource: ServiceResource) -> Self {
         Self {
             id: resource.id,
             user_id: resource.user_id,
@@ -23,6 +32,7 @@ impl ServiceResourceDTO {
             buffer: resource.buffer,
             closest_booking_time: resource.closest_booking_time,
             furthest_booking_time: resource.furthest_booking_time,
+            timezone: resource.timezone.map(|tz| tz.to_string()),
         }
     }
 }
@@ -32,11 +42,23 @@ impl ServiceResourceDTO {
 pub struct ServiceDTO {
     pub id: ID,
     pub users: Vec<ServiceResourceDTO>,
+    pub booking_strategy: BookingStrategy,
+    pub default_schedule_id: Option<ID>,
+    pub min_lead_time_ms: Millis,
+    pub allowed_durations: Vec<i64>,
+    pub allow_overbooking: bool,
+    pub timezone: Option<String>,
     pub metadata: Metadata,
 }
 
 impl ServiceDTO {
     pub fn new(service: Service) -> Self {
+        Self::from(service)
+    }
+}
+
+impl From<Service> for ServiceDTO {
+    fn from(service: Service) -> Self {
         Self {
             id: service.id.clone(),
             users: service
@@ -44,6 +66,12 @@ impl ServiceDTO {
                 .into_iter()
                 .map(ServiceResourceDTO::new)
                 .collect(),
+            booking_strategy: service.booking_strategy,
+            default_schedule_id: service.default_schedule_id,
+            min_lead_time_ms: service.min_lead_time,
+            allowed_durations: service.allowed_durations,
+            allow_overbooking: service.allow_overbooking,
+            timezone: service.timezone.map(|tz| tz.to_string()),
             metadata: service.metadata,
         }
     }
@@ -9,3 +9,19 @@ pub mod get_service_health {
         pub message: String,
     }
 }
+
+pub mod get_admin_stats {
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct APIResponse {
+        /// The store backend in use, either `inmemory` or `mongodb`
+        pub store: String,
+        pub accounts: usize,
+        pub users: usize,
+        pub calendars: usize,
+        pub events: usize,
+        pub services: usize,
+    }
+}
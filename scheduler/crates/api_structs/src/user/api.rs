@@ -15,6 +15,12 @@ impl UserResponse {
             user: UserDTO::new(user),
         }
     }
+
+    pub fn with_counts(user: User, calendar_count: usize, schedule_count: usize) -> Self {
+        Self {
+            user: UserDTO::with_counts(user, calendar_count, schedule_count),
+        }
+    }
 }
 
 pub mod get_me {
@@ -76,6 +82,14 @@ pub mod get_user {
         pub user_id: ID,
     }
 
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct QueryParams {
+        /// Also return how many `Calendar`s and `Schedule`s the `User` has
+        #[serde(default, alias = "include_counts")]
+        pub include_counts: bool,
+    }
+
     pub type APIResponse = UserResponse;
 }
 
@@ -93,6 +107,7 @@ pub mod get_users_by_meta {
     }
 
     #[derive(Deserialize, Serialize)]
+    #[serde(rename_all = "camelCase")]
     pub struct APIResponse {
         pub users: Vec<UserDTO>,
     }
@@ -6,13 +6,35 @@ use serde::{Deserialize, Serialize};
 pub struct UserDTO {
     pub id: ID,
     pub metadata: Metadata,
+    /// Only included when the counts are explicitly requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calendar_count: Option<usize>,
+    /// Only included when the counts are explicitly requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_count: Option<usize>,
 }
 
 impl UserDTO {
     pub fn new(user: User) -> Self {
+        Self::from(user)
+    }
+
+    pub fn with_counts(user: User, calendar_count: usize, schedule_count: usize) -> Self {
+        Self {
+            calendar_count: Some(calendar_count),
+            schedule_count: Some(schedule_count),
+            ..Self::new(user)
+        }
+    }
+}
+
+impl From<User> for UserDTO {
+    fn from(user: User) -> Self {
         Self {
             id: user.id,
             metadata: user.metadata,
+            calendar_count: None,
+            schedule_count: None,
         }
     }
 }
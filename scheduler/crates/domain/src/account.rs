@@ -1,8 +1,13 @@
 use crate::shared::entity::{Entity, ID};
+use chrono_tz::Tz;
 use nettu_scheduler_utils::create_random_secret;
 use serde::{Deserialize, Serialize};
 
 const API_KEY_LEN: usize = 30;
+/// Default upper bound on the number of `Calendar`s a single `User` can have
+pub const DEFAULT_MAX_CALENDARS_PER_USER: usize = 100;
+/// Highest number of `Calendar`s per `User` that an `Account` can allow
+pub const MAX_CALENDARS_PER_USER_LIMIT: usize = 10_000;
 
 /// An `Account` acts as a namespace for all other resources and lets multiple different
 /// applications use the same instance of this server without interfering
@@ -32,6 +37,10 @@ impl PEMKey {
 #[derive(Debug, Clone)]
 pub struct AccountSettings {
     pub webhook: Option<AccountWebhookSettings>,
+    /// Maximum number of `Calendar`s that a `User` in this `Account` can have
+    pub max_calendars_per_user: usize,
+    /// Timezone used by queries that do not specify one, instead of UTC
+    pub default_timezone: Option<Tz>,
 }
 
 #[derive(Debug, Clone)]
@@ -41,6 +50,30 @@ pub struct AccountWebhookSettings {
 }
 
 impl AccountSettings {
+    pub fn set_default_timezone(&mut self, timezone: Option<&str>) -> bool {
+        match timezone {
+            Some(timezone) => match timezone.parse::<Tz>() {
+                Ok(tzid) => {
+                    self.default_timezone = Some(tzid);
+                    true
+                }
+                Err(_) => false,
+            },
+            None => {
+                self.default_timezone = None;
+                true
+            }
+        }
+    }
+
+    pub fn set_max_calendars_per_user(&mut self, max_calendars_per_user: usize) -> bool {
+        if max_calendars_per_user == 0 || max_calendars_per_user > MAX_CALENDARS_PER_USER_LIMIT {
+            return false;
+        }
+        self.max_calendars_per_user = max_calendars_per_user;
+        true
+    }
+
     pub fn set_webhook_url(&mut self, webhook_url: Option<String>) -> bool {
         match webhook_url {
             Some(url) => {
@@ -73,7 +106,11 @@ impl AccountSettings {
 
 impl Default for AccountSettings {
     fn default() -> Self {
-        Self { webhook: None }
+        Self {
+            webhook: None,
+            max_calendars_per_user: DEFAULT_MAX_CALENDARS_PER_USER,
+            default_timezone: None,
+        }
     }
 }
 
@@ -120,6 +157,33 @@ mod test {
         assert!(acc.secret_api_key.len() > API_KEY_LEN);
     }
 
+    #[test]
+    fn it_sets_default_timezone() {
+        let mut settings = AccountSettings::default();
+        assert!(settings.set_default_timezone(Some("Europe/Oslo")));
+        assert_eq!(settings.default_timezone, Some(chrono_tz::Europe::Oslo));
+        assert!(!settings.set_default_timezone(Some("Europe/Nowhere")));
+        assert_eq!(settings.default_timezone, Some(chrono_tz::Europe::Oslo));
+        assert!(settings.set_default_timezone(None));
+        assert!(settings.default_timezone.is_none());
+    }
+
+    #[test]
+    fn it_sets_max_calendars_per_user_within_the_limit() {
+        let mut settings = AccountSettings::default();
+        assert!(!settings.set_max_calendars_per_user(0));
+        assert!(!settings.set_max_calendars_per_user(MAX_CALENDARS_PER_USER_LIMIT + 1));
+        assert_eq!(
+            settings.max_calendars_per_user,
+            DEFAULT_MAX_CALENDARS_PER_USER
+        );
+        assert!(settings.set_max_calendars_per_user(MAX_CALENDARS_PER_USER_LIMIT));
+        assert_eq!(
+            settings.max_calendars_per_user,
+            MAX_CALENDARS_PER_USER_LIMIT
+        );
+    }
+
     #[test]
     fn it_rejects_invalid_public_key() {
         assert!(PEMKey::new("badpem".into()).is_err());
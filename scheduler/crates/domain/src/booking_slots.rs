@@ -1,9 +1,12 @@
-use crate::{date, event_instance::EventInstance, CompatibleInstances, ID};
+use crate::{
+    date, event_instance::EventInstance, BookingStrategy, CompatibleInstances, Millis, ID,
+};
 use chrono::prelude::*;
 
 use chrono_tz::Tz;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tracing::warn;
 
 #[derive(Serialize, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -11,6 +14,9 @@ pub struct BookingSlot {
     pub start: i64,
     pub duration: i64,
     pub available_until: i64,
+    /// How many slots of `duration` can be booked back to back from `start`
+    /// before the availability ends, including this one
+    pub consecutive_slots: i64,
 }
 
 fn is_cursor_in_events(
@@ -18,72 +24,197 @@ fn is_cursor_in_events(
     duration: i64,
     events: &CompatibleInstances,
 ) -> Option<&EventInstance> {
-    for event in events.as_ref() {
-        if event.start_ts <= cursor && event.end_ts >= cursor + duration {
-            return Some(event);
+    // The first event ending at or after `cursor + duration` is the only candidate,
+    // as every later event starts even later
+    let index = events.find_first_ending_after(cursor + duration - 1);
+    events
+        .get(index)
+        .filter(|event| event.start_ts <= cursor && event.end_ts >= cursor + duration)
+}
+
+/// Default upper bound for how many `BookingSlot`s are computed per query
+pub const DEFAULT_MAX_BOOKING_SLOTS: usize = 1000;
+
+/// Where the `BookingSlot`s are stepped by the interval from
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum SlotStart {
+    /// Slots start at every interval from the start of the window
+    Interval,
+    /// Slots start at every interval from the start of each free event, so that
+    /// the first slot aligns with when the availability begins
+    AvailabilityStart,
+}
+
+impl std::fmt::Display for SlotStart {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Interval => write!(f, "interval"),
+            Self::AvailabilityStart => write!(f, "availabilityStart"),
         }
     }
-    None
 }
 
 pub struct BookingSlotsOptions {
     pub start_ts: i64,
     pub end_ts: i64,
-    pub duration: i64,
-    pub interval: i64,
+    pub duration: Millis,
+    pub interval: Millis,
+    pub slot_start: SlotStart,
+    /// Upper bound for the number of `BookingSlot`s to return. A free event
+    /// spanning a huge timespan combined with a tiny interval would otherwise
+    /// generate an unbounded amount of slots.
+    pub max_slots: usize,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct UserFreeEvents {
     pub free_events: CompatibleInstances,
+    /// Existing bookings of the `User` in the `Service` that are still part of the `free_events`,
+    /// which is only the case when the `Service` allows overbooking
+    pub booked_events: CompatibleInstances,
     pub user_id: ID,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct ServiceBookingSlot {
     pub start: i64,
     pub duration: i64,
     pub user_ids: Vec<ID>,
+    /// Every `User` that could take this slot already has a booking overlapping with it
+    pub overbook: bool,
+}
+
+/// Whether any of the booked events overlaps with the slot starting at `start`
+fn is_slot_booked(start: i64, duration: i64, booked_events: &CompatibleInstances) -> bool {
+    // The first event ending after `start` is the only candidate,
+    // as every later event starts even later
+    let index = booked_events.find_first_ending_after(start);
+    booked_events
+        .get(index)
+        .map(|event| event.start_ts < start + duration)
+        .unwrap_or(false)
 }
 
 pub fn get_service_bookingslots(
     users_free: Vec<UserFreeEvents>,
     options: &BookingSlotsOptions,
+    strategy: &BookingStrategy,
 ) -> Vec<ServiceBookingSlot> {
-    let mut slots_lookup: HashMap<i64, ServiceBookingSlot> = HashMap::new();
+    // The users that are available at each slot start, and whether they are already booked then
+    let mut slots_lookup: HashMap<i64, Vec<(ID, bool)>> = HashMap::new();
 
     for user in &users_free {
         let slots = get_booking_slots(&user.free_events, options);
         for slot in slots {
-            if let Some(val) = slots_lookup.get(&slot.start) {
-                let mut user_ids = val.user_ids.clone();
-                user_ids.push(user.user_id.clone());
-                slots_lookup.insert(
-                    slot.start,
-                    ServiceBookingSlot {
-                        duration: slot.duration,
-                        start: slot.start,
-                        user_ids,
-                    },
-                );
-            } else {
-                slots_lookup.insert(
-                    slot.start,
-                    ServiceBookingSlot {
-                        duration: slot.duration,
-                        start: slot.start,
-                        user_ids: vec![user.user_id.clone()],
-                    },
-                );
-            }
+            let booked = is_slot_booked(slot.start, slot.duration, &user.booked_events);
+            slots_lookup
+                .entry(slot.start)
+                .or_default()
+                .push((user.user_id.clone(), booked));
         }
     }
 
-    let mut slots = slots_lookup.drain().map(|s| s.1).collect::<Vec<_>>();
+    let mut slots = slots_lookup
+        .drain()
+        .filter_map(|(start, users)| {
+            let booked_count = users.iter().filter(|(_, booked)| *booked).count();
+            let (user_ids, overbook) = match strategy {
+                BookingStrategy::Collective => {
+                    if users.len() != users_free.len() {
+                        return None;
+                    }
+                    let user_ids = users.into_iter().map(|(user_id, _)| user_id).collect();
+                    (user_ids, booked_count > 0)
+                }
+                // Users that are not booked yet take precedence over overbooking the others
+                BookingStrategy::RoundRobin => {
+                    let overbook = booked_count == users.len();
+                    let user_ids = users
+                        .into_iter()
+                        .filter(|(_, booked)| overbook || !booked)
+                        .map(|(user_id, _)| user_id)
+                        .collect();
+                    (user_ids, overbook)
+                }
+            };
+            Some(ServiceBookingSlot {
+                start,
+                duration: options.duration.0,
+                user_ids,
+                overbook,
+            })
+        })
+        .collect::<Vec<_>>();
     slots.sort_by_key(|s| s.start);
     slots
 }
 
+/// Combines the free events of the `User`s in a `Service` into the times the `Service`
+/// is available, before they are split into booking slots. A collective `Service` is
+/// only available when all of the `User`s are free, and otherwise when any of them is.
+pub fn get_service_free_events(
+    users_free: Vec<UserFreeEvents>,
+    strategy: &BookingStrategy,
+) -> CompatibleInstances {
+    let mut users_free = users_free
+        .into_iter()
+        .map(|user| user.free_events.inner().into_iter().collect::<Vec<_>>());
+
+    let free_events = match strategy {
+        BookingStrategy::Collective => match users_free.next() {
+            Some(first) => users_free.fold(first, |free, user_free| {
+                intersect_free_events(&free, &user_free)
+            }),
+            None => vec![],
+        },
+        BookingStrategy::RoundRobin => users_free.flatten().collect(),
+    };
+
+    CompatibleInstances::new(
+        free_events
+            .into_iter()
+            .map(|event| EventInstance {
+                event_id: None,
+                ..event
+            })
+            .collect(),
+    )
+}
+
+/// Finds the times covered by both lists of sorted and non-overlapping free events
+fn intersect_free_events(
+    events1: &[EventInstance],
+    events2: &[EventInstance],
+) -> Vec<EventInstance> {
+    let mut intersection = vec![];
+    let (mut i, mut j) = (0, 0);
+    while let (Some(event1), Some(event2)) = (events1.get(i), events2.get(j)) {
+        let start_ts = std::cmp::max(event1.start_ts, event2.start_ts);
+        let end_ts = std::cmp::min(event1.end_ts, event2.end_ts);
+        if start_ts < end_ts {
+            intersection.push(EventInstance {
+                start_ts,
+                end_ts,
+                busy: false,
+                event_id: None,
+            });
+        }
+        // The event ending first can not overlap with any later event of the other list
+        if event1.end_ts < event2.end_ts {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    intersection
+}
+
+/// Finds the `BookingSlot`s within the free events.
+///
+/// Warning: the result is truncated to the first `max_slots` slots if there
+/// are more available, so callers will not see slots at the end of the timespan.
 pub fn get_booking_slots(
     free_events: &CompatibleInstances,
     options: &BookingSlotsOptions,
@@ -92,26 +223,47 @@ pub fn get_booking_slots(
     let &BookingSlotsOptions {
         start_ts,
         end_ts,
-        duration,
-        interval,
+        duration: Millis(duration),
+        interval: Millis(interval),
+        slot_start,
+        max_slots,
     } = options;
 
-    if duration < 1 {
+    // A non-positive interval would never move the cursor forward
+    if duration < 1 || interval < 1 {
         return booking_slots;
     }
 
-    let mut cursor = start_ts;
-    while cursor + duration <= end_ts {
+    // Slots start at every `interval` from `start_ts`, or from the start of each free event.
+    // When the interval does not divide the window, the last slot is the last of those
+    // starts where the slot still ends within the window, and the remainder of the window
+    // after it is not bookable.
+    let last_start = end_ts - duration;
+    let cursors: Box<dyn Iterator<Item = i64>> = match slot_start {
+        SlotStart::Interval => Box::new((start_ts..=last_start).step_by(interval as usize)),
+        SlotStart::AvailabilityStart => Box::new(free_events.as_ref().iter().flat_map(|event| {
+            let first = std::cmp::max(event.start_ts, start_ts);
+            let last = std::cmp::min(event.end_ts - duration, last_start);
+            (first..=last).step_by(interval as usize)
+        })),
+    };
+    for cursor in cursors {
         let available_event = is_cursor_in_events(cursor, duration, &free_events);
         if let Some(event) = available_event {
+            if booking_slots.len() == max_slots {
+                warn!(
+                    "Booking slots were truncated to the first {} slots, the remaining slots from {} to {} were not computed",
+                    max_slots, cursor, end_ts
+                );
+                break;
+            }
             booking_slots.push(BookingSlot {
                 start: cursor,
                 duration,
                 available_until: event.end_ts,
+                consecutive_slots: (event.end_ts - cursor) / duration,
             });
         }
-
-        cursor += interval;
     }
 
     booking_slots
@@ -123,47 +275,184 @@ pub fn validate_slots_interval(interval: i64) -> bool {
     interval >= min_interval && interval <= max_interval
 }
 
+/// The bookable window has to fit at least one slot of the requested duration,
+/// otherwise there could never be any booking slots within it
+pub fn validate_slots_window(start_ts: i64, end_ts: i64, duration: i64) -> bool {
+    end_ts - start_ts >= duration
+}
+
 pub struct BookingSlotsQuery {
     pub date: String,
     pub iana_tz: Option<String>,
+    /// Alternative to `iana_tz` for clients that only know their offset from UTC
+    pub utc_offset_minutes: Option<i32>,
     pub duration: i64,
     pub interval: i64,
 }
 
+#[derive(Debug, PartialEq)]
 pub enum BookingQueryError {
     InvalidInterval,
     InvalidDate(String),
     InvalidTimezone(String),
+    InvalidUtcOffset(i32),
+    /// Both `iana_tz` and `utc_offset_minutes` were given
+    ConflictingTimezones,
+    WindowShorterThanDuration,
+}
+
+impl BookingQueryError {
+    /// Name of the query param that is invalid
+    pub fn field(&self) -> &'static str {
+        match self {
+            Self::InvalidInterval => "interval",
+            Self::InvalidDate(_) => "date",
+            Self::InvalidTimezone(_) => "ianaTz",
+            Self::InvalidUtcOffset(_) | Self::ConflictingTimezones => "utcOffsetMinutes",
+            Self::WindowShorterThanDuration => "duration",
+        }
+    }
+}
+
+/// Largest offset from UTC in minutes that is in use, in either direction
+const MAX_UTC_OFFSET_MINUTES: i32 = 60 * 14;
+
+/// The timezone a booking date is given in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookingTimezone {
+    Iana(Tz),
+    /// A fixed offset from UTC, which does not follow daylight saving time
+    UtcOffset(FixedOffset),
+}
+
+impl BookingTimezone {
+    /// Timestamp of the start of the given date in this timezone
+    fn day_start(&self, year: i32, month: u32, day: u32) -> i64 {
+        match self {
+            Self::Iana(tz) => tz.ymd(year, month, day).and_hms(0, 0, 0).timestamp_millis(),
+            Self::UtcOffset(offset) => offset
+                .ymd(year, month, day)
+                .and_hms(0, 0, 0)
+                .timestamp_millis(),
+        }
+    }
+
+    /// Formats the timestamp as an RFC 3339 date in this timezone
+    pub fn to_rfc3339(&self, timestamp: i64) -> String {
+        match self {
+            Self::Iana(tz) => tz.timestamp_millis(timestamp).to_rfc3339(),
+            Self::UtcOffset(offset) => offset.timestamp_millis(timestamp).to_rfc3339(),
+        }
+    }
+}
+
+impl std::fmt::Display for BookingTimezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Iana(tz) => write!(f, "{}", tz),
+            Self::UtcOffset(offset) => write!(f, "{}", offset),
+        }
+    }
 }
 
 pub struct BookingTimespan {
     pub start_ts: i64,
     pub end_ts: i64,
+    /// The timezone the booking date was given in
+    pub tz: BookingTimezone,
 }
 
+/// Validates every field of the query, so that all the invalid fields are
+/// reported at once instead of only the first one
 pub fn validate_bookingslots_query(
     query: &BookingSlotsQuery,
-) -> Result<BookingTimespan, BookingQueryError> {
+) -> Result<BookingTimespan, Vec<BookingQueryError>> {
+    let mut errors = vec![];
     if !validate_slots_interval(query.interval) {
-        return Err(BookingQueryError::InvalidInterval);
+        errors.push(BookingQueryError::InvalidInterval);
     }
 
-    let iana_tz = query.iana_tz.clone().unwrap_or_else(|| "UTC".into());
-    let tz: Tz = match iana_tz.parse() {
-        Ok(tz) => tz,
-        Err(_) => return Err(BookingQueryError::InvalidTimezone(iana_tz)),
+    let timespan = match parse_booking_day(
+        &query.date,
+        &query.iana_tz,
+        query.utc_offset_minutes,
+        &mut errors,
+    ) {
+        Some(timespan) => timespan,
+        // The window can not be checked without knowing which day it is
+        None => return Err(errors),
     };
+    if !validate_slots_window(timespan.start_ts, timespan.end_ts, query.duration) {
+        errors.push(BookingQueryError::WindowShorterThanDuration);
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    Ok(timespan)
+}
+
+/// Validates the date and timezone of a query for a single day that, unlike
+/// `validate_bookingslots_query`, has no slots with a `duration` and `interval`
+pub fn validate_booking_day(
+    date: &str,
+    iana_tz: &Option<String>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<BookingTimespan, Vec<BookingQueryError>> {
+    let mut errors = vec![];
+    match parse_booking_day(date, iana_tz, utc_offset_minutes, &mut errors) {
+        Some(timespan) if errors.is_empty() => Ok(timespan),
+        _ => Err(errors),
+    }
+}
 
-    let parsed_date = match date::is_valid_date(&query.date) {
-        Ok(val) => val,
-        Err(_) => return Err(BookingQueryError::InvalidDate(query.date.clone())),
+/// Finds the start and end of the day in the timezone, and adds the invalid
+/// fields to the `errors`
+fn parse_booking_day(
+    date: &str,
+    iana_tz: &Option<String>,
+    utc_offset_minutes: Option<i32>,
+    errors: &mut Vec<BookingQueryError>,
+) -> Option<BookingTimespan> {
+    let tz = match (iana_tz, utc_offset_minutes) {
+        (Some(_), Some(_)) => {
+            errors.push(BookingQueryError::ConflictingTimezones);
+            None
+        }
+        (None, Some(minutes)) if minutes.abs() <= MAX_UTC_OFFSET_MINUTES => {
+            Some(BookingTimezone::UtcOffset(FixedOffset::east(minutes * 60)))
+        }
+        (None, Some(minutes)) => {
+            errors.push(BookingQueryError::InvalidUtcOffset(minutes));
+            None
+        }
+        (iana_tz, None) => {
+            let iana_tz = iana_tz.clone().unwrap_or_else(|| "UTC".into());
+            match iana_tz.parse::<Tz>() {
+                Ok(tz) => Some(BookingTimezone::Iana(tz)),
+                Err(_) => {
+                    errors.push(BookingQueryError::InvalidTimezone(iana_tz));
+                    None
+                }
+            }
+        }
     };
 
-    let date = tz.ymd(parsed_date.0, parsed_date.1, parsed_date.2);
-    let start_ts = date.and_hms(0, 0, 0).timestamp_millis();
-    let end_ts = start_ts + 1000 * 60 * 60 * 24;
+    let parsed_date = match date::is_valid_date(date) {
+        Ok(val) => Some(val),
+        Err(_) => {
+            errors.push(BookingQueryError::InvalidDate(date.to_string()));
+            None
+        }
+    };
 
-    Ok(BookingTimespan { start_ts, end_ts })
+    let (tz, parsed_date) = (tz?, parsed_date?);
+    let start_ts = tz.day_start(parsed_date.0, parsed_date.1, parsed_date.2);
+    Some(BookingTimespan {
+        start_ts,
+        end_ts: start_ts + 1000 * 60 * 60 * 24,
+        tz,
+    })
 }
 
 #[cfg(test)]
@@ -178,19 +467,50 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
         assert!(slots.is_empty());
     }
 
+    #[test]
+    fn get_booking_slots_is_truncated_to_max_slots() {
+        let day = 1000 * 60 * 60 * 24;
+        let e1 = EventInstance {
+            busy: false,
+            start_ts: 0,
+            end_ts: day,
+            event_id: None,
+        };
+
+        let slots = get_booking_slots(
+            &CompatibleInstances::new(vec![e1]),
+            &BookingSlotsOptions {
+                start_ts: 0,
+                end_ts: day,
+                duration: Millis(1),
+                interval: Millis(1),
+                slot_start: SlotStart::Interval,
+                max_slots: 100,
+            },
+        );
+
+        assert_eq!(slots.len(), 100);
+        assert_eq!(slots[0].start, 0);
+        assert_eq!(slots[99].start, 99);
+        assert!(slots.iter().all(|s| s.available_until == day));
+    }
+
     #[test]
     fn get_booking_slots_from_one_event_1() {
         let e1 = EventInstance {
             busy: false,
             start_ts: 2,
             end_ts: 12,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -198,20 +518,73 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
         assert!(slots.is_empty());
     }
 
+    #[test]
+    fn get_booking_slots_aligned_to_availability_start() {
+        let free_events = CompatibleInstances::new(vec![
+            EventInstance {
+                busy: false,
+                start_ts: 2,
+                end_ts: 22,
+                event_id: None,
+            },
+            EventInstance {
+                busy: false,
+                start_ts: 35,
+                end_ts: 50,
+                event_id: None,
+            },
+        ]);
+        let slot_starts = |start_ts: i64, end_ts: i64, slot_start: SlotStart| {
+            get_booking_slots(
+                &free_events,
+                &BookingSlotsOptions {
+                    start_ts,
+                    end_ts,
+                    duration: Millis(10),
+                    interval: Millis(10),
+                    slot_start,
+                    max_slots: DEFAULT_MAX_BOOKING_SLOTS,
+                },
+            )
+            .into_iter()
+            .map(|slot| (slot.start, slot.consecutive_slots))
+            .collect::<Vec<_>>()
+        };
+
+        // Stepping from the window start misses the beginning of both free events
+        assert_eq!(
+            slot_starts(0, 100, SlotStart::Interval),
+            vec![(10, 1), (40, 1)]
+        );
+        assert_eq!(
+            slot_starts(0, 100, SlotStart::AvailabilityStart),
+            vec![(2, 2), (12, 1), (35, 1)]
+        );
+
+        // Free events are still clipped to the window
+        assert_eq!(
+            slot_starts(5, 40, SlotStart::AvailabilityStart),
+            vec![(5, 1)]
+        );
+    }
+
     #[test]
     fn get_booking_slots_from_one_event_2() {
         let e1 = EventInstance {
             busy: false,
             start_ts: 2,
             end_ts: 22,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -219,8 +592,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -230,7 +605,8 @@ mod test {
             BookingSlot {
                 available_until: 22,
                 duration: 10,
-                start: 10
+                start: 10,
+                consecutive_slots: 1
             }
         );
     }
@@ -241,6 +617,7 @@ mod test {
             busy: false,
             start_ts: 2,
             end_ts: 42,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -248,8 +625,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -259,7 +638,8 @@ mod test {
             BookingSlot {
                 available_until: 42,
                 duration: 10,
-                start: 10
+                start: 10,
+                consecutive_slots: 3
             }
         );
         assert_eq!(
@@ -267,7 +647,8 @@ mod test {
             BookingSlot {
                 available_until: 42,
                 duration: 10,
-                start: 20
+                start: 20,
+                consecutive_slots: 2
             }
         );
         assert_eq!(
@@ -275,7 +656,8 @@ mod test {
             BookingSlot {
                 available_until: 42,
                 duration: 10,
-                start: 30
+                start: 30,
+                consecutive_slots: 1
             }
         );
     }
@@ -286,12 +668,14 @@ mod test {
             busy: false,
             start_ts: 0,
             end_ts: 22,
+            event_id: None,
         };
 
         let e2 = EventInstance {
             busy: false,
             start_ts: 30,
             end_ts: 50,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -299,8 +683,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -310,7 +696,8 @@ mod test {
             BookingSlot {
                 available_until: 22,
                 duration: 10,
-                start: 0
+                start: 0,
+                consecutive_slots: 2
             }
         );
         assert_eq!(
@@ -318,7 +705,8 @@ mod test {
             BookingSlot {
                 available_until: 22,
                 duration: 10,
-                start: 10
+                start: 10,
+                consecutive_slots: 1
             }
         );
         assert_eq!(
@@ -326,7 +714,8 @@ mod test {
             BookingSlot {
                 available_until: 50,
                 duration: 10,
-                start: 30
+                start: 30,
+                consecutive_slots: 2
             }
         );
         assert_eq!(
@@ -334,7 +723,8 @@ mod test {
             BookingSlot {
                 available_until: 50,
                 duration: 10,
-                start: 40
+                start: 40,
+                consecutive_slots: 1
             }
         );
     }
@@ -345,36 +735,42 @@ mod test {
             busy: false,
             start_ts: 0,
             end_ts: 2,
+            event_id: None,
         };
 
         let e2 = EventInstance {
             busy: false,
             start_ts: 33,
             end_ts: 50,
+            event_id: None,
         };
 
         let e3 = EventInstance {
             busy: false,
             start_ts: 80,
             end_ts: 90,
+            event_id: None,
         };
 
         let e4 = EventInstance {
             busy: false,
             start_ts: 90,
             end_ts: 100,
+            event_id: None,
         };
 
         let e5 = EventInstance {
             busy: false,
             start_ts: 99,
             end_ts: 120,
+            event_id: None,
         };
 
         let e6 = EventInstance {
             busy: false,
             start_ts: 140,
             end_ts: 160,
+            event_id: None,
         };
         let availibility = CompatibleInstances::new(vec![e1, e3, e4, e2, e6, e5]);
 
@@ -383,8 +779,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 99,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -394,7 +792,8 @@ mod test {
             BookingSlot {
                 available_until: 50,
                 duration: 10,
-                start: 40
+                start: 40,
+                consecutive_slots: 1
             }
         );
         assert_eq!(
@@ -402,7 +801,8 @@ mod test {
             BookingSlot {
                 available_until: 120,
                 duration: 10,
-                start: 80
+                start: 80,
+                consecutive_slots: 4
             }
         );
     }
@@ -413,6 +813,7 @@ mod test {
             busy: false,
             start_ts: 81,
             end_ts: 100,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -420,8 +821,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -431,7 +834,8 @@ mod test {
             BookingSlot {
                 available_until: 100,
                 duration: 10,
-                start: 90
+                start: 90,
+                consecutive_slots: 1
             }
         );
     }
@@ -442,6 +846,7 @@ mod test {
             busy: false,
             start_ts: 81,
             end_ts: 120,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -449,8 +854,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 0,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -460,17 +867,62 @@ mod test {
             BookingSlot {
                 available_until: 120, // consider wether this should be available_event.end_ts or bookingoptions.end_ts
                 duration: 10,
-                start: 90
+                start: 90,
+                consecutive_slots: 3
             }
         );
     }
 
+    fn slot_starts(end_ts: i64, duration: i64, interval: i64) -> Vec<i64> {
+        let e1 = EventInstance {
+            busy: false,
+            start_ts: 0,
+            end_ts: 200,
+            event_id: None,
+        };
+
+        get_booking_slots(
+            &CompatibleInstances::new(vec![e1]),
+            &BookingSlotsOptions {
+                start_ts: 0,
+                end_ts,
+                duration: Millis(duration),
+                interval: Millis(interval),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
+            },
+        )
+        .into_iter()
+        .map(|slot| slot.start)
+        .collect()
+    }
+
+    #[test]
+    fn interval_that_does_not_divide_the_window() {
+        // The slot at 90 ends exactly at the end of the window
+        assert_eq!(slot_starts(100, 10, 30), vec![0, 30, 60, 90]);
+        // The slot at 90 would cross the end of the window
+        assert_eq!(slot_starts(100, 20, 30), vec![0, 30, 60]);
+        assert_eq!(slot_starts(100, 40, 30), vec![0, 30, 60]);
+        assert_eq!(slot_starts(100, 41, 30), vec![0, 30]);
+        // The only slot fills the whole window
+        assert_eq!(slot_starts(100, 100, 30), vec![0]);
+        assert!(slot_starts(100, 101, 30).is_empty());
+    }
+
+    #[test]
+    fn non_positive_interval_gives_no_slots() {
+        assert!(slot_starts(100, 10, 0).is_empty());
+        assert!(slot_starts(100, 10, -30).is_empty());
+    }
+
     #[test]
     fn slot_that_crosses_start() {
         let e1 = EventInstance {
             busy: false,
             start_ts: 2,
             end_ts: 30,
+            event_id: None,
         };
 
         let slots = get_booking_slots(
@@ -478,8 +930,10 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 10,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
         );
 
@@ -489,7 +943,8 @@ mod test {
             BookingSlot {
                 available_until: 30,
                 duration: 10,
-                start: 10
+                start: 10,
+                consecutive_slots: 2
             }
         );
         assert_eq!(
@@ -497,7 +952,8 @@ mod test {
             BookingSlot {
                 available_until: 30,
                 duration: 10,
-                start: 20
+                start: 20,
+                consecutive_slots: 1
             }
         );
     }
@@ -508,6 +964,7 @@ mod test {
             busy: false,
             start_ts: 2,
             end_ts: 30,
+            event_id: None,
         };
 
         let user_id = ID::default();
@@ -515,6 +972,7 @@ mod test {
         let mut users_free = vec![];
         users_free.push(UserFreeEvents {
             free_events: CompatibleInstances::new(vec![e1]),
+            booked_events: CompatibleInstances::new(vec![]),
             user_id: user_id.clone(),
         });
 
@@ -523,9 +981,12 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 10,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
+            &BookingStrategy::RoundRobin,
         );
 
         assert_eq!(slots.len(), 2);
@@ -534,7 +995,8 @@ mod test {
             ServiceBookingSlot {
                 duration: 10,
                 start: 10,
-                user_ids: vec![user_id.clone()]
+                user_ids: vec![user_id.clone()],
+                overbook: false,
             }
         );
         assert_eq!(
@@ -542,7 +1004,8 @@ mod test {
             ServiceBookingSlot {
                 duration: 10,
                 start: 20,
-                user_ids: vec![user_id.clone()]
+                user_ids: vec![user_id.clone()],
+                overbook: false,
             }
         );
     }
@@ -553,12 +1016,14 @@ mod test {
             busy: false,
             start_ts: 2,
             end_ts: 30,
+            event_id: None,
         };
 
         let e2 = EventInstance {
             busy: false,
             start_ts: 33,
             end_ts: 52,
+            event_id: None,
         };
 
         let user_id_1 = ID::default();
@@ -566,10 +1031,12 @@ mod test {
         let mut users_free = vec![];
         users_free.push(UserFreeEvents {
             free_events: CompatibleInstances::new(vec![e1.clone()]),
+            booked_events: CompatibleInstances::new(vec![]),
             user_id: user_id_1.clone(),
         });
         users_free.push(UserFreeEvents {
             free_events: CompatibleInstances::new(vec![e1, e2]),
+            booked_events: CompatibleInstances::new(vec![]),
             user_id: user_id_2.clone(),
         });
 
@@ -578,9 +1045,12 @@ mod test {
             &BookingSlotsOptions {
                 start_ts: 10,
                 end_ts: 100,
-                duration: 10,
-                interval: 10,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
             },
+            &BookingStrategy::RoundRobin,
         );
         assert_eq!(slots.len(), 3);
         assert_eq!(
@@ -588,7 +1058,8 @@ mod test {
             ServiceBookingSlot {
                 duration: 10,
                 start: 10,
-                user_ids: vec![user_id_1.clone(), user_id_2.clone()]
+                user_ids: vec![user_id_1.clone(), user_id_2.clone()],
+                overbook: false,
             }
         );
         assert_eq!(
@@ -596,7 +1067,8 @@ mod test {
             ServiceBookingSlot {
                 duration: 10,
                 start: 20,
-                user_ids: vec![user_id_1.clone(), user_id_2.clone()]
+                user_ids: vec![user_id_1.clone(), user_id_2.clone()],
+                overbook: false,
             }
         );
         assert_eq!(
@@ -604,8 +1076,287 @@ mod test {
             ServiceBookingSlot {
                 duration: 10,
                 start: 40,
-                user_ids: vec![user_id_2.clone()]
+                user_ids: vec![user_id_2.clone()],
+                overbook: false,
+            }
+        );
+    }
+
+    #[test]
+    fn generate_collective_service_bookingslots_with_two_users_in_service() {
+        let e1 = EventInstance {
+            busy: false,
+            start_ts: 2,
+            end_ts: 30,
+            event_id: None,
+        };
+
+        let e2 = EventInstance {
+            busy: false,
+            start_ts: 33,
+            end_ts: 52,
+            event_id: None,
+        };
+
+        let user_id_1 = ID::default();
+        let user_id_2 = ID::default();
+        let mut users_free = vec![];
+        users_free.push(UserFreeEvents {
+            free_events: CompatibleInstances::new(vec![e1.clone()]),
+            booked_events: CompatibleInstances::new(vec![]),
+            user_id: user_id_1.clone(),
+        });
+        users_free.push(UserFreeEvents {
+            free_events: CompatibleInstances::new(vec![e1, e2]),
+            booked_events: CompatibleInstances::new(vec![]),
+            user_id: user_id_2.clone(),
+        });
+
+        let slots = get_service_bookingslots(
+            users_free,
+            &BookingSlotsOptions {
+                start_ts: 10,
+                end_ts: 100,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: DEFAULT_MAX_BOOKING_SLOTS,
+            },
+            &BookingStrategy::Collective,
+        );
+        assert_eq!(slots.len(), 2);
+        assert_eq!(
+            slots[0],
+            ServiceBookingSlot {
+                duration: 10,
+                start: 10,
+                user_ids: vec![user_id_1.clone(), user_id_2.clone()],
+                overbook: false,
             }
         );
+        assert_eq!(
+            slots[1],
+            ServiceBookingSlot {
+                duration: 10,
+                start: 20,
+                user_ids: vec![user_id_1.clone(), user_id_2.clone()],
+                overbook: false,
+            }
+        );
+    }
+
+    #[test]
+    fn is_cursor_in_events_matches_linear_scan() {
+        let events = CompatibleInstances::new(
+            (0..2000)
+                .map(|i| EventInstance {
+                    start_ts: i * 30,
+                    end_ts: i * 30 + (i % 7) * 5 + 1,
+                    busy: false,
+                    event_id: None,
+                })
+                .collect(),
+        );
+        for duration in &[1, 10, 20] {
+            for cursor in (-20..2000 * 30).step_by(7) {
+                let expected = events
+                    .as_ref()
+                    .iter()
+                    .find(|event| event.start_ts <= cursor && event.end_ts >= cursor + duration);
+                assert_eq!(is_cursor_in_events(cursor, *duration, &events), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_consecutive_slots_until_the_availability_ends() {
+        let events = vec![
+            EventInstance {
+                busy: false,
+                start_ts: 0,
+                end_ts: 30,
+                event_id: None,
+            },
+            EventInstance {
+                busy: false,
+                start_ts: 40,
+                end_ts: 55,
+                event_id: None,
+            },
+            EventInstance {
+                busy: false,
+                start_ts: 60,
+                end_ts: 70,
+                event_id: None,
+            },
+        ];
+
+        let slots = get_booking_slots(
+            &CompatibleInstances::new(events),
+            &BookingSlotsOptions {
+                start_ts: 0,
+                end_ts: 100,
+                duration: Millis(10),
+                interval: Millis(10),
+                slot_start: SlotStart::Interval,
+                max_slots: 100,
+            },
+        );
+
+        let consecutive = slots
+            .iter()
+            .map(|slot| (slot.start, slot.consecutive_slots))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            consecutive,
+            vec![(0, 3), (10, 2), (20, 1), (40, 1), (60, 1)]
+        );
+    }
+
+    #[test]
+    fn window_has_to_fit_the_duration() {
+        assert!(validate_slots_window(0, 100, 100));
+        assert!(validate_slots_window(0, 100, 10));
+        assert!(!validate_slots_window(0, 100, 101));
+
+        let day = 1000 * 60 * 60 * 24;
+        let query = |duration| BookingSlotsQuery {
+            date: "2021-1-1".into(),
+            iana_tz: None,
+            utc_offset_minutes: None,
+            duration,
+            interval: 1000 * 60 * 15,
+        };
+        assert!(validate_bookingslots_query(&query(day)).is_ok());
+        assert_eq!(
+            validate_bookingslots_query(&query(day + 1)).err(),
+            Some(vec![BookingQueryError::WindowShorterThanDuration])
+        );
+    }
+
+    #[test]
+    fn reports_all_invalid_fields_of_query() {
+        let query = BookingSlotsQuery {
+            date: "2021-13-1".into(),
+            iana_tz: Some("Mars/Olympus".into()),
+            utc_offset_minutes: None,
+            duration: 1000 * 60 * 30,
+            interval: 1000,
+        };
+        let errors = validate_bookingslots_query(&query).err().unwrap();
+        assert_eq!(
+            errors,
+            vec![
+                BookingQueryError::InvalidInterval,
+                BookingQueryError::InvalidTimezone("Mars/Olympus".into()),
+                BookingQueryError::InvalidDate("2021-13-1".into()),
+            ]
+        );
+        assert_eq!(
+            errors.iter().map(|e| e.field()).collect::<Vec<_>>(),
+            vec!["interval", "ianaTz", "date"]
+        );
+
+        // The window is also checked when the date and timezone are valid
+        let query = BookingSlotsQuery {
+            date: "2021-1-1".into(),
+            iana_tz: None,
+            utc_offset_minutes: None,
+            duration: 1000 * 60 * 60 * 25,
+            interval: 1000,
+        };
+        assert_eq!(
+            validate_bookingslots_query(&query).err(),
+            Some(vec![
+                BookingQueryError::InvalidInterval,
+                BookingQueryError::WindowShorterThanDuration
+            ])
+        );
+    }
+
+    #[test]
+    fn utc_offset_gives_same_day_window_as_matching_iana_timezone() {
+        let query = |iana_tz: Option<&str>, utc_offset_minutes| BookingSlotsQuery {
+            date: "2021-1-4".into(),
+            iana_tz: iana_tz.map(String::from),
+            utc_offset_minutes,
+            duration: 1000 * 60 * 30,
+            interval: 1000 * 60 * 15,
+        };
+        // Cairo is at +02:00 in January, and did not observe daylight saving time in 2021
+        let iana = validate_bookingslots_query(&query(Some("Africa/Cairo"), None)).unwrap();
+        let offset = validate_bookingslots_query(&query(None, Some(120))).unwrap();
+        assert_eq!(offset.start_ts, iana.start_ts);
+        assert_eq!(offset.end_ts, iana.end_ts);
+        assert_eq!(
+            offset.start_ts,
+            Utc.ymd(2021, 1, 3).and_hms(22, 0, 0).timestamp_millis()
+        );
+        assert_eq!(
+            offset.tz.to_rfc3339(offset.start_ts),
+            "2021-01-04T00:00:00+02:00"
+        );
+        assert_eq!(
+            offset.tz.to_rfc3339(offset.start_ts),
+            iana.tz.to_rfc3339(iana.start_ts)
+        );
+
+        let offset = validate_bookingslots_query(&query(None, Some(-330))).unwrap();
+        assert_eq!(
+            offset.start_ts,
+            Utc.ymd(2021, 1, 4).and_hms(5, 30, 0).timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_or_conflicting_utc_offset() {
+        let query = |iana_tz: Option<&str>, utc_offset_minutes| BookingSlotsQuery {
+            date: "2021-1-4".into(),
+            iana_tz: iana_tz.map(String::from),
+            utc_offset_minutes,
+            duration: 1000 * 60 * 30,
+            interval: 1000 * 60 * 15,
+        };
+        assert_eq!(
+            validate_bookingslots_query(&query(Some("Africa/Cairo"), Some(120))).err(),
+            Some(vec![BookingQueryError::ConflictingTimezones])
+        );
+        let errors = validate_bookingslots_query(&query(None, Some(60 * 15)))
+            .err()
+            .unwrap();
+        assert_eq!(errors, vec![BookingQueryError::InvalidUtcOffset(60 * 15)]);
+        assert_eq!(errors[0].field(), "utcOffsetMinutes");
+    }
+
+    #[test]
+    fn service_free_events_per_booking_strategy() {
+        let free = |start_ts: i64, end_ts: i64| EventInstance {
+            busy: false,
+            start_ts,
+            end_ts,
+            event_id: None,
+        };
+        let users_free = || {
+            vec![
+                UserFreeEvents {
+                    free_events: CompatibleInstances::new(vec![free(0, 20), free(40, 60)]),
+                    booked_events: CompatibleInstances::new(vec![]),
+                    user_id: ID::default(),
+                },
+                UserFreeEvents {
+                    free_events: CompatibleInstances::new(vec![free(10, 50), free(70, 80)]),
+                    booked_events: CompatibleInstances::new(vec![]),
+                    user_id: ID::default(),
+                },
+            ]
+        };
+
+        let round_robin = get_service_free_events(users_free(), &BookingStrategy::RoundRobin);
+        assert_eq!(round_robin.inner(), vec![free(0, 60), free(70, 80)]);
+
+        let collective = get_service_free_events(users_free(), &BookingStrategy::Collective);
+        assert_eq!(collective.inner(), vec![free(10, 20), free(40, 50)]);
+
+        assert!(get_service_free_events(vec![], &BookingStrategy::Collective).is_empty());
     }
 }
@@ -7,6 +7,11 @@ use crate::{
 };
 use chrono_tz::{Tz, UTC};
 
+/// Reserved `Metadata` key for the visibility of a `Calendar`. A `Calendar` with
+/// the value `private` is left out of freebusy queries that are not done by
+/// the account admin.
+pub const CALENDAR_VISIBILITY_KEY: &str = "visibility";
+
 #[derive(Debug, Clone)]
 pub struct Calendar {
     pub id: ID,
@@ -14,6 +19,8 @@ pub struct Calendar {
     pub account_id: ID,
     pub settings: CalendarSettings,
     pub metadata: Metadata,
+    pub created: i64,
+    pub updated: i64,
 }
 
 impl Meta for Calendar {
@@ -27,6 +34,7 @@ impl Meta for Calendar {
 
 #[derive(Debug, Clone)]
 pub struct CalendarSettings {
+    /// First day of the week, where 0 is monday and 6 is sunday
     pub week_start: isize,
     pub timezone: Tz,
 }
@@ -69,8 +77,17 @@ impl Calendar {
             account_id: account_id.clone(),
             settings: Default::default(),
             metadata: Default::default(),
+            created: Default::default(),
+            updated: Default::default(),
         }
     }
+
+    pub fn is_private(&self) -> bool {
+        self.metadata
+            .get(CALENDAR_VISIBILITY_KEY)
+            .map(|visibility| visibility == "private")
+            .unwrap_or(false)
+    }
 }
 
 impl Entity for Calendar {
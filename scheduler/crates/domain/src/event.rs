@@ -1,5 +1,5 @@
 use crate::{
-    calendar::CalendarSettings,
+    calendar::{Calendar, CalendarSettings},
     shared::entity::Entity,
     shared::{metadata::Metadata, recurrence::RRuleOptions},
     timespan::TimeSpan,
@@ -15,10 +15,19 @@ pub struct CalendarEvent {
     pub id: ID,
     pub start_ts: i64,
     pub duration: i64,
+    /// Whether this event blocks the `User`. Always in sync with `status`, which
+    /// should be changed through `set_status`.
     pub busy: bool,
+    pub status: EventStatus,
+    /// End of the last instance of this event. This is `start_ts + duration` for
+    /// non-recurring events, but for recurring events it depends on the recurrence
+    /// rule and is therefore stored so that events can be queried by timespan.
     pub end_ts: i64,
     pub created: i64,
     pub updated: i64,
+    /// Incremented on every change of the `CalendarEvent`, so that clients can tell
+    /// whether it was modified since they read it
+    pub version: i64,
     pub recurrence: Option<RRuleOptions>,
     pub exdates: Vec<i64>,
     pub calendar_id: ID,
@@ -26,7 +35,13 @@ pub struct CalendarEvent {
     pub account_id: ID,
     pub reminder: Option<CalendarEventReminder>,
     pub is_service: bool,
+    /// The `Service` that this `CalendarEvent` is a booking of. Service events created
+    /// before bookings referenced their `Service` do not have it.
+    pub service_id: Option<ID>,
     pub metadata: Metadata,
+    /// Identifier of the event in an external system, so that integrations can
+    /// look up the `CalendarEvent` without keeping their own id mapping
+    pub external_id: Option<String>,
 }
 
 impl Entity for CalendarEvent {
@@ -44,7 +59,57 @@ impl Meta for CalendarEvent {
     }
 }
 
+/// Availability of the `User` during a `CalendarEvent`, similar to the
+/// iCalendar `TRANSP` and `STATUS` properties
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "lowercase")]
+pub enum EventStatus {
+    Free,
+    /// Not confirmed yet. Treated as free unless the caller asks
+    /// for tentative events to be treated as busy.
+    Tentative,
+    Busy,
+}
+
+impl EventStatus {
+    pub fn from_busy(busy: bool) -> Self {
+        if busy {
+            Self::Busy
+        } else {
+            Self::Free
+        }
+    }
+
+    pub fn is_busy(&self, tentative_as_busy: bool) -> bool {
+        match self {
+            Self::Free => false,
+            Self::Tentative => tentative_as_busy,
+            Self::Busy => true,
+        }
+    }
+}
+
+/// Which instances of a recurring `CalendarEvent` an edit applies to
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventEditMode {
+    /// Only the given instance, which is split out into its own `CalendarEvent`
+    This,
+    /// The given instance and the ones after it, which are split out into a new series
+    ThisAndFollowing,
+    /// Every instance of the `CalendarEvent`
+    All,
+}
+
+impl Default for EventEditMode {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct CalendarEventReminder {
     pub minutes_before: i64,
@@ -59,6 +124,31 @@ impl CalendarEventReminder {
 }
 
 impl CalendarEvent {
+    /// Creates a non-recurring `CalendarEvent` in the given `Calendar`
+    pub fn new(calendar: &Calendar, start_ts: i64, duration: i64, status: EventStatus) -> Self {
+        Self {
+            id: Default::default(),
+            start_ts,
+            duration,
+            busy: status.is_busy(false),
+            status,
+            end_ts: start_ts + duration,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+            recurrence: None,
+            exdates: vec![],
+            calendar_id: calendar.id.clone(),
+            user_id: calendar.user_id.clone(),
+            account_id: calendar.account_id.clone(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+        }
+    }
+
     fn update_endtime(&mut self, calendar_settings: &CalendarSettings) -> bool {
         match self.recurrence.clone() {
             Some(recurrence) => {
@@ -67,7 +157,11 @@ impl CalendarEvent {
                     || rrule_options.until.is_some()
                 {
                     let expand = self.expand(None, calendar_settings);
-                    self.end_ts = expand.last().unwrap().end_ts;
+                    // Every instance might be excluded after the series has been split
+                    self.end_ts = expand
+                        .last()
+                        .map(|instance| instance.end_ts)
+                        .unwrap_or(self.start_ts + self.duration);
                 } else {
                     self.end_ts = Self::get_max_timestamp();
                 }
@@ -99,6 +193,18 @@ impl CalendarEvent {
         5609882500905 // Mon Oct 09 2147 06:41:40 GMT+0200 (Central European Summer Time)
     }
 
+    /// Checks that the event does not have a negative duration and that it
+    /// is within the range of timestamps that can be expanded
+    pub fn has_valid_timespan(&self) -> bool {
+        self.duration >= 0
+            && self.start_ts >= 0
+            && self
+                .start_ts
+                .checked_add(self.duration)
+                .map(|end_ts| end_ts <= Self::get_max_timestamp())
+                .unwrap_or(false)
+    }
+
     pub fn get_rrule_set(&self, calendar_settings: &CalendarSettings) -> Option<RRuleSet> {
         self.recurrence.clone().map(|recurrence| {
             let rrule_options = recurrence.get_parsed_options(self.start_ts, calendar_settings);
@@ -114,6 +220,103 @@ impl CalendarEvent {
         })
     }
 
+    pub fn set_status(&mut self, status: EventStatus) {
+        self.status = status;
+        self.busy = status.is_busy(false);
+    }
+
+    /// Excludes the instance starting at the given timestamp from this `CalendarEvent`
+    /// and returns it as a new non-recurring `CalendarEvent`
+    pub fn detach_instance(&mut self, ts: i64) -> CalendarEvent {
+        self.exdates.push(ts);
+
+        CalendarEvent {
+            id: Default::default(),
+            start_ts: ts,
+            end_ts: ts + self.duration,
+            recurrence: None,
+            exdates: vec![],
+            external_id: None,
+            version: 0,
+            ..self.clone()
+        }
+    }
+
+    /// Ends the recurrence of this `CalendarEvent` right before the instance starting at
+    /// the given timestamp, and returns the rest of the series as a new `CalendarEvent`
+    /// that starts with that instance
+    pub fn split_at(&mut self, ts: i64, calendar_settings: &CalendarSettings) -> CalendarEvent {
+        let mut recurrence = self.recurrence.clone().unwrap_or_default();
+
+        // The count of a recurrence also includes the excluded instances
+        let following_count = recurrence.count.map(|count| {
+            let preceding = CalendarEvent {
+                exdates: vec![],
+                ..self.clone()
+            }
+            .expand(None, calendar_settings)
+            .into_iter()
+            .filter(|instance| instance.start_ts < ts)
+            .count();
+            count - preceding as i32
+        });
+        let mut following = CalendarEvent {
+            id: Default::default(),
+            start_ts: ts,
+            recurrence: Some(RRuleOptions {
+                count: following_count,
+                ..recurrence.clone()
+            }),
+            exdates: self.exdates.iter().filter(|d| **d >= ts).cloned().collect(),
+            external_id: None,
+            version: 0,
+            ..self.clone()
+        };
+        following.update_endtime(calendar_settings);
+
+        recurrence.count = None;
+        recurrence.until = Some((ts - 1) as isize);
+        self.recurrence = Some(recurrence);
+        self.exdates.retain(|d| *d < ts);
+        self.update_endtime(calendar_settings);
+
+        following
+    }
+
+    /// Checks if an instance of this `CalendarEvent` starts at the given timestamp
+    pub fn is_occurrence(&self, ts: i64, calendar_settings: &CalendarSettings) -> bool {
+        let timespan = TimeSpan::new(ts, ts + self.duration);
+        self.expand(Some(&timespan), calendar_settings)
+            .iter()
+            .any(|instance| instance.start_ts == ts)
+    }
+
+    /// Previews the first `count` instances that start at or after `from`, like the
+    /// upcoming windows of a recurring availability `CalendarEvent`
+    pub fn preview_instances(
+        &self,
+        from: i64,
+        count: usize,
+        calendar_settings: &CalendarSettings,
+    ) -> Vec<EventInstance> {
+        // Expanding every instance of a recurrence without an end would be slow,
+        // so the expanded timespan is widened until it has enough instances
+        let last_end_ts = std::cmp::min(self.end_ts, Self::get_max_timestamp());
+        let mut window = 1000 * 60 * 60 * 24 * 7;
+        loop {
+            let end_ts = std::cmp::min(from + window, Self::get_max_timestamp());
+            let mut instances = self.expand(Some(&TimeSpan::new(from, end_ts)), calendar_settings);
+            if instances.len() >= count || end_ts >= last_end_ts {
+                instances.truncate(count);
+                return instances;
+            }
+            window *= 4;
+        }
+    }
+
+    /// Expands the `CalendarEvent` into its `EventInstance`s. The `CalendarSettings` are
+    /// given explicitly, as the timezone and the week start of the `Calendar` decide
+    /// where the weeks of weekly recurrences with an interval begin.
     pub fn expand(
         &self,
         timespan: Option<&TimeSpan>,
@@ -151,6 +354,7 @@ impl CalendarEvent {
                             start_ts,
                             end_ts: start_ts + self.duration,
                             busy: self.busy,
+                            event_id: Some(self.id.clone()),
                         }
                     })
                     .collect()
@@ -163,6 +367,7 @@ impl CalendarEvent {
                         start_ts: self.start_ts,
                         end_ts: self.start_ts + self.duration,
                         busy: self.busy,
+                        event_id: Some(self.id.clone()),
                     }]
                 }
             }
@@ -176,6 +381,47 @@ mod test {
     use crate::{shared::recurrence::WeekDay, RRuleFrequenzy};
     use chrono_tz::UTC;
 
+    #[test]
+    fn validates_event_timespan() {
+        let mut event = CalendarEvent {
+            id: Default::default(),
+            start_ts: 1521317491239,
+            busy: false,
+            status: EventStatus::Free,
+            duration: 1000 * 60 * 60,
+            recurrence: None,
+            end_ts: 1521317491239 + 1000 * 60 * 60,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            updated: Default::default(),
+            version: Default::default(),
+            created: Default::default(),
+        };
+        assert!(event.has_valid_timespan());
+        event.duration = 0;
+        assert!(event.has_valid_timespan());
+        event.duration = -1;
+        assert!(!event.has_valid_timespan());
+        event.duration = 1000;
+        event.start_ts = -1000;
+        assert!(!event.has_valid_timespan());
+        event.start_ts = CalendarEvent::get_max_timestamp();
+        assert!(!event.has_valid_timespan());
+        // Overflowing the end of the event is rejected instead of panicking
+        event.start_ts = i64::MAX;
+        assert!(!event.has_valid_timespan());
+        event.start_ts = 1000;
+        event.duration = i64::MAX;
+        assert!(!event.has_valid_timespan());
+    }
+
     #[test]
     fn daily_calendar_event() {
         let settings = CalendarSettings {
@@ -186,6 +432,7 @@ mod test {
             id: Default::default(),
             start_ts: 1521317491239,
             busy: false,
+            status: EventStatus::Free,
             duration: 1000 * 60 * 60,
             recurrence: Some(RRuleOptions {
                 freq: RRuleFrequenzy::Daily,
@@ -200,13 +447,73 @@ mod test {
             account_id: Default::default(),
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             created: Default::default(),
             updated: Default::default(),
+            version: Default::default(),
         };
 
         let oc = event.expand(None, &settings);
         assert_eq!(oc.len(), 3);
+        assert!(oc
+            .iter()
+            .all(|instance| instance.event_id.as_ref() == Some(&event.id)));
+    }
+
+    #[test]
+    fn splits_recurring_event_at_instance() {
+        let settings = CalendarSettings {
+            timezone: UTC,
+            week_start: 0,
+        };
+        let day = 1000 * 60 * 60 * 24;
+        let mut event = CalendarEvent {
+            id: Default::default(),
+            start_ts: 0,
+            busy: false,
+            status: EventStatus::Free,
+            duration: 1000 * 60 * 60,
+            recurrence: Some(RRuleOptions {
+                freq: RRuleFrequenzy::Daily,
+                interval: 1,
+                count: Some(5),
+                ..Default::default()
+            }),
+            end_ts: 0,
+            exdates: vec![day, 3 * day],
+            calendar_id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: Some("external".into()),
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+        };
+
+        let following = event.split_at(2 * day, &settings);
+
+        let starts = |e: &CalendarEvent| {
+            e.expand(None, &settings)
+                .into_iter()
+                .map(|instance| instance.start_ts)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(&event), vec![0]);
+        assert_eq!(event.exdates, vec![day]);
+        assert_eq!(event.end_ts, 1000 * 60 * 60);
+        assert_ne!(following.id, event.id);
+        assert_eq!(following.start_ts, 2 * day);
+        assert_eq!(following.recurrence.as_ref().unwrap().count, Some(3));
+        assert_eq!(following.exdates, vec![3 * day]);
+        assert_eq!(starts(&following), vec![2 * day, 4 * day]);
+        assert_eq!(following.end_ts, 4 * day + 1000 * 60 * 60);
+        assert!(following.external_id.is_none());
     }
 
     #[test]
@@ -219,6 +526,7 @@ mod test {
             id: Default::default(),
             start_ts: 1521317491239,
             busy: false,
+            status: EventStatus::Free,
             duration: 1000 * 60 * 60,
             recurrence: None,
             end_ts: 2521317491239,
@@ -228,9 +536,12 @@ mod test {
             account_id: Default::default(),
             reminder: None,
             is_service: false,
+            service_id: None,
             metadata: Default::default(),
+            external_id: None,
             created: Default::default(),
             updated: Default::default(),
+            version: Default::default(),
         };
 
         let oc = event.expand(None, &settings);
@@ -263,11 +574,39 @@ mod test {
             freq: RRuleFrequenzy::Monthly,
             ..Default::default()
         });
+        invalid_rrules.push(RRuleOptions {
+            bymonthday: Some(vec![32]),
+            freq: RRuleFrequenzy::Monthly,
+            ..Default::default()
+        });
+        invalid_rrules.push(RRuleOptions {
+            bymonthday: Some(vec![0]),
+            freq: RRuleFrequenzy::Monthly,
+            ..Default::default()
+        });
+        invalid_rrules.push(RRuleOptions {
+            // There is no day of the month in a weekly rule
+            bymonthday: Some(vec![15]),
+            freq: RRuleFrequenzy::Weekly,
+            ..Default::default()
+        });
+        invalid_rrules.push(RRuleOptions {
+            // No month has a sixth Tuesday
+            byweekday: Some(vec![WeekDay::new_nth(1, 6).unwrap()]),
+            freq: RRuleFrequenzy::Monthly,
+            ..Default::default()
+        });
+        invalid_rrules.push(RRuleOptions {
+            byweekday: Some(vec![WeekDay::new_nth(1, 2).unwrap()]),
+            freq: RRuleFrequenzy::Weekly,
+            ..Default::default()
+        });
         for rrule in invalid_rrules {
             let mut event = CalendarEvent {
                 id: Default::default(),
                 start_ts: 1521317491239,
                 busy: false,
+                status: EventStatus::Free,
                 duration: 1000 * 60 * 60,
                 end_ts: 2521317491239,
                 exdates: vec![],
@@ -277,9 +616,12 @@ mod test {
                 recurrence: None,
                 reminder: None,
                 is_service: false,
+                service_id: None,
                 metadata: Default::default(),
+                external_id: None,
                 created: Default::default(),
                 updated: Default::default(),
+                version: Default::default(),
             };
 
             assert!(!event.set_recurrence(rrule, &settings, true));
@@ -312,11 +654,22 @@ mod test {
             freq: RRuleFrequenzy::Monthly,
             ..Default::default()
         });
+        valid_rrules.push(RRuleOptions {
+            bymonthday: Some(vec![15, -1]),
+            freq: RRuleFrequenzy::Monthly,
+            ..Default::default()
+        });
+        valid_rrules.push(RRuleOptions {
+            byweekday: Some(vec![WeekDay::new_nth(4, -1).unwrap()]),
+            freq: RRuleFrequenzy::Monthly,
+            ..Default::default()
+        });
         for rrule in valid_rrules {
             let mut event = CalendarEvent {
                 id: Default::default(),
                 start_ts: start_ts as i64,
                 busy: false,
+                status: EventStatus::Free,
                 duration: 1000 * 60 * 60,
                 end_ts: 2521317491239,
                 exdates: vec![],
@@ -326,12 +679,251 @@ mod test {
                 recurrence: None,
                 reminder: None,
                 is_service: false,
+                service_id: None,
                 metadata: Default::default(),
+                external_id: None,
                 created: Default::default(),
                 updated: Default::default(),
+                version: Default::default(),
             };
 
             assert!(event.set_recurrence(rrule, &settings, true));
         }
     }
+
+    fn monthly_event(start_ts: i64, recurrence: RRuleOptions) -> CalendarEvent {
+        CalendarEvent {
+            id: Default::default(),
+            start_ts,
+            busy: false,
+            status: EventStatus::Free,
+            duration: 1000 * 60 * 60,
+            recurrence: Some(RRuleOptions {
+                freq: RRuleFrequenzy::Monthly,
+                ..recurrence
+            }),
+            end_ts: 0,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+        }
+    }
+
+    fn instance_starts(event: &CalendarEvent) -> Vec<i64> {
+        let settings = CalendarSettings {
+            timezone: UTC,
+            week_start: 0,
+        };
+        event
+            .expand(None, &settings)
+            .iter()
+            .map(|instance| instance.start_ts)
+            .collect()
+    }
+
+    #[test]
+    fn monthly_recurrence_by_day_of_month_skips_short_months() {
+        // Sun Jan 31 2021 09:00:00 UTC
+        let event = monthly_event(
+            1612083600000,
+            RRuleOptions {
+                count: Some(4),
+                bymonthday: Some(vec![31]),
+                ..Default::default()
+            },
+        );
+        // Jan 31, Mar 31, May 31 and Jul 31
+        assert_eq!(
+            instance_starts(&event),
+            vec![1612083600000, 1617181200000, 1622451600000, 1627722000000]
+        );
+
+        // Counted from the end of the month instead, which every month has
+        let event = monthly_event(
+            1612083600000,
+            RRuleOptions {
+                count: Some(3),
+                bymonthday: Some(vec![-1]),
+                ..Default::default()
+            },
+        );
+        // Jan 31, Feb 28 and Mar 31
+        assert_eq!(
+            instance_starts(&event),
+            vec![1612083600000, 1614502800000, 1617181200000]
+        );
+    }
+
+    #[test]
+    fn monthly_recurrence_by_nth_weekday() {
+        // Fri Jan 29 2021 09:00:00 UTC, the last Friday of January
+        let event = monthly_event(
+            1611910800000,
+            RRuleOptions {
+                count: Some(3),
+                byweekday: Some(vec![WeekDay::new_nth(4, -1).unwrap()]),
+                ..Default::default()
+            },
+        );
+        // Jan 29, Feb 26 and Mar 26
+        assert_eq!(
+            instance_starts(&event),
+            vec![1611910800000, 1614330000000, 1616749200000]
+        );
+    }
+
+    fn biweekly_tuesday_and_sunday_event() -> CalendarEvent {
+        let start_ts = 870771600000; // Tue Aug 05 1997 09:00:00 UTC
+        CalendarEvent {
+            id: Default::default(),
+            start_ts,
+            busy: false,
+            status: EventStatus::Free,
+            duration: 1000 * 60 * 60,
+            recurrence: Some(RRuleOptions {
+                freq: RRuleFrequenzy::Weekly,
+                interval: 2,
+                count: Some(4),
+                byweekday: Some(vec![WeekDay::new(1).unwrap(), WeekDay::new(6).unwrap()]),
+                ..Default::default()
+            }),
+            end_ts: 0,
+            exdates: vec![],
+            calendar_id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            reminder: None,
+            is_service: false,
+            service_id: None,
+            metadata: Default::default(),
+            external_id: None,
+            created: Default::default(),
+            updated: Default::default(),
+            version: Default::default(),
+        }
+    }
+
+    #[test]
+    fn weekly_recurrence_respects_week_start() {
+        // Example from RFC 5545 on how WKST changes the instances of a rule
+        let event = biweekly_tuesday_and_sunday_event();
+        let monday_start = CalendarSettings {
+            timezone: UTC,
+            week_start: 0,
+        };
+        let sunday_start = CalendarSettings {
+            timezone: UTC,
+            week_start: 6,
+        };
+
+        let starts = |settings: &CalendarSettings| {
+            event
+                .expand(None, settings)
+                .iter()
+                .map(|instance| instance.start_ts)
+                .collect::<Vec<_>>()
+        };
+        // Aug 5, 10, 19 and 24
+        assert_eq!(
+            starts(&monday_start),
+            vec![870771600000, 871203600000, 871981200000, 872413200000]
+        );
+        // Aug 5, 17, 19 and 31
+        assert_eq!(
+            starts(&sunday_start),
+            vec![870771600000, 871808400000, 871981200000, 873018000000]
+        );
+    }
+
+    #[test]
+    fn week_start_changes_instances_in_queried_week() {
+        let event = biweekly_tuesday_and_sunday_event();
+        let day = 1000 * 60 * 60 * 24;
+        // Wed Aug 06 1997 to Sat Aug 16 1997
+        let timespan = TimeSpan::new(870825600000, 870825600000 + 10 * day);
+
+        let monday_start = CalendarSettings {
+            timezone: UTC,
+            week_start: 0,
+        };
+        let instances = event.expand(Some(&timespan), &monday_start);
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].start_ts, 871203600000);
+
+        let sunday_start = CalendarSettings {
+            timezone: UTC,
+            week_start: 6,
+        };
+        assert!(event.expand(Some(&timespan), &sunday_start).is_empty());
+    }
+
+    fn daily_availability_event(start_ts: i64, settings: &CalendarSettings) -> CalendarEvent {
+        let calendar = Calendar::new(&Default::default(), &Default::default());
+        let mut event = CalendarEvent::new(&calendar, start_ts, 1000 * 60 * 105, EventStatus::Free);
+        assert!(event.set_recurrence(Default::default(), settings, true));
+        event
+    }
+
+    #[test]
+    fn daily_availability_windows_span_the_event_duration() {
+        let settings = CalendarSettings {
+            timezone: UTC,
+            week_start: 0,
+        };
+        let hour = 1000 * 60 * 60;
+        let day = 24 * hour;
+        // Available from 04:00 to 05:45 every day, like in the service booking slot tests
+        let event = daily_availability_event(4 * hour, &settings);
+
+        let windows = event.preview_instances(0, 5, &settings);
+        assert_eq!(windows.len(), 5);
+        for (i, window) in windows.iter().enumerate() {
+            assert_eq!(window.start_ts, i as i64 * day + 4 * hour);
+            assert_eq!(window.duration(), 1000 * 60 * 105);
+        }
+
+        // The window that has already started is not previewed
+        let windows = event.preview_instances(2 * day + 5 * hour, 1, &settings);
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].start_ts, 3 * day + 4 * hour);
+
+        // Far enough ahead that the timespan has to be widened
+        let windows = event.preview_instances(0, 100, &settings);
+        assert_eq!(windows.len(), 100);
+        assert_eq!(windows[99].start_ts, 99 * day + 4 * hour);
+    }
+
+    #[test]
+    fn daily_availability_windows_keep_local_time_across_dst() {
+        let settings = CalendarSettings {
+            timezone: chrono_tz::Europe::Oslo,
+            week_start: 0,
+        };
+        // Fri Mar 26 2021 09:00 in Oslo, the clocks are moved forward on Sunday
+        let event = daily_availability_event(1616745600000, &settings);
+
+        let windows = event.preview_instances(event.start_ts, 4, &settings);
+        assert_eq!(windows.len(), 4);
+        for window in &windows {
+            let start = settings.timezone.timestamp_millis(window.start_ts);
+            let end = settings.timezone.timestamp_millis(window.end_ts);
+            assert_eq!((start.hour(), start.minute()), (9, 0));
+            assert_eq!((end.hour(), end.minute()), (10, 45));
+            assert_eq!(window.duration(), 1000 * 60 * 105);
+        }
+        // Only 23 hours between the windows around the change to summer time
+        assert_eq!(
+            windows[2].start_ts - windows[1].start_ts,
+            1000 * 60 * 60 * 23
+        );
+    }
 }
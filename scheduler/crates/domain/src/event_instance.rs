@@ -1,28 +1,117 @@
-use crate::CalendarEvent;
-use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use crate::{shared::entity::ID, CalendarEvent};
+use serde::{Deserialize, Serialize, Serializer};
+use std::{cmp::Ordering, collections::VecDeque};
 
 /// Occurence of a `CalendarEvent`
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EventInstance {
     pub start_ts: i64,
     pub end_ts: i64,
     pub busy: bool,
+    /// The `CalendarEvent` this is an occurence of. It is `None` when the instance
+    /// does not originate from a single `CalendarEvent`, like merged instances of
+    /// different `CalendarEvent`s or free instances from a `Schedule`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<ID>,
+}
+
+/// The serialized form of an `EventInstance`, which also has the `duration` so that
+/// clients do not have to compute it. It is ignored when deserializing.
+#[derive(Serialize)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+struct SerializedEventInstance<'a> {
+    start_ts: i64,
+    end_ts: i64,
+    /// `end_ts - start_ts`
+    duration: i64,
+    busy: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    event_id: Option<&'a ID>,
+}
+
+/// Described by the serialized form, so that the schema has the `duration` as well
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for EventInstance {
+    fn schema_name() -> String {
+        "EventInstance".into()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        <SerializedEventInstance as schemars::JsonSchema>::json_schema(gen)
+    }
+}
+
+impl Serialize for EventInstance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedEventInstance {
+            start_ts: self.start_ts,
+            end_ts: self.end_ts,
+            duration: self.duration(),
+            busy: self.busy,
+            event_id: self.event_id.as_ref(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// `EventInstance`s are ordered by lowest `start_ts` first and then by lowest `end_ts`.
+/// `busy` and `event_id` are only used as tiebreakers to stay consistent with `Eq`.
+impl Ord for EventInstance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.start_ts, self.end_ts, self.busy, &self.event_id).cmp(&(
+            other.start_ts,
+            other.end_ts,
+            other.busy,
+            &other.event_id,
+        ))
+    }
+}
+
+impl PartialOrd for EventInstance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 /// This type contains a list of `EventInstance`s that are guaranteed to be
 /// compatible and sorted by lowest `start_ts` first.
 /// Two `EventInstance`s are compatible if they do not overlap.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct CompatibleInstances {
     events: VecDeque<EventInstance>,
 }
 
 impl CompatibleInstances {
-    pub fn new(mut events: Vec<EventInstance>) -> Self {
-        // sort with least start_ts first
-        events.sort_by(|i1, i2| i1.start_ts.cmp(&i2.start_ts));
+    /// Overlapping `EventInstance`s with the same `busy` flag are merged. Where a free
+    /// and a busy instance overlap the busy one takes precedence, so the free instance
+    /// is cut down to the parts outside of it.
+    pub fn new(events: Vec<EventInstance>) -> Self {
+        let (free_instances, busy_instances) = seperate_free_busy_events(events);
+        let busy_instances = Self::merge_overlapping(busy_instances);
+        if free_instances.is_empty() {
+            return busy_instances;
+        }
+        let mut free_instances = Self::merge_overlapping(free_instances);
+        if busy_instances.is_empty() {
+            return free_instances;
+        }
+
+        free_instances.remove_intances(&busy_instances, 0);
+        let mut events = Vec::from(free_instances.events);
+        events.extend(busy_instances.events);
+        events.sort();
+        let instances = Self {
+            events: events.into(),
+        };
+        instances.assert_invariants();
+        instances
+    }
+
+    /// Merges the overlapping instances, which all need to have the same `busy` flag
+    fn merge_overlapping(mut events: Vec<EventInstance>) -> Self {
+        events.sort();
 
         let mut compatible_events: VecDeque<EventInstance> = Default::default();
 
@@ -42,9 +131,26 @@ impl CompatibleInstances {
             }
         }
 
-        Self {
+        let instances = Self {
             events: compatible_events,
-        }
+        };
+        instances.assert_invariants();
+        instances
+    }
+
+    /// Checks that the `EventInstance`s are sorted and do not overlap, which every
+    /// mutating method should uphold. It only runs in debug builds, so that a violation
+    /// surfaces in the tests instead of as a wrong freebusy in production.
+    fn assert_invariants(&self) {
+        debug_assert!(
+            self.events
+                .iter()
+                .zip(self.events.iter().skip(1))
+                .all(|(instance, next)| instance.start_ts <= instance.end_ts
+                    && instance.end_ts <= next.start_ts),
+            "CompatibleInstances are not sorted and compatible: {:?}",
+            self.events
+        );
     }
 
     pub fn remove_intances(&mut self, instances: &CompatibleInstances, skip: usize) {
@@ -53,9 +159,12 @@ impl CompatibleInstances {
             .iter()
             .map(|free_instance| free_instance.remove_instances(instances, skip).inner())
             .flatten()
-            .collect()
+            .collect();
+        self.assert_invariants();
     }
 
+    /// Adds the `EventInstance` first, unless it overlaps with the current first
+    /// instance in which case it is not added and `false` is returned
     pub fn push_front(&mut self, instance: EventInstance) -> bool {
         if let Some(first_instance) = self.events.get(0) {
             // There is overlap, so cannot be added
@@ -64,9 +173,12 @@ impl CompatibleInstances {
             }
         }
         self.events.push_front(instance);
+        self.assert_invariants();
         true
     }
 
+    /// Adds the `EventInstance` last, unless it overlaps with the current last
+    /// instance in which case it is not added and `false` is returned
     pub fn push_back(&mut self, instance: EventInstance) -> bool {
         if !self.events.is_empty() {
             if let Some(last_instance) = self.events.get(self.events.len() - 1) {
@@ -77,13 +189,63 @@ impl CompatibleInstances {
             }
         }
         self.events.push_back(instance);
+        self.assert_invariants();
         true
     }
 
+    /// Adds all the given `EventInstance`s. Instances overlapping with the existing
+    /// ones are merged the same way as in `new`, so that no time is lost.
     pub fn extend(&mut self, instances: CompatibleInstances) {
-        for instance in instances.inner() {
-            self.push_back(instance);
+        let mut events = Vec::from(std::mem::take(&mut self.events));
+        events.extend(instances.inner());
+        *self = Self::new(events);
+    }
+
+    /// Returns the gaps within the window from `window_start` to `window_end` that
+    /// are not covered by any of the `EventInstance`s, as free instances
+    pub fn complement(&self, window_start: i64, window_end: i64) -> CompatibleInstances {
+        let mut gaps = VecDeque::new();
+        let mut cursor = window_start;
+        for instance in &self.events {
+            if instance.start_ts >= window_end {
+                break;
+            }
+            if instance.start_ts > cursor {
+                gaps.push_back(EventInstance {
+                    start_ts: cursor,
+                    end_ts: instance.start_ts,
+                    busy: false,
+                    event_id: None,
+                });
+            }
+            cursor = std::cmp::max(cursor, instance.end_ts);
         }
+        if cursor < window_end {
+            gaps.push_back(EventInstance {
+                start_ts: cursor,
+                end_ts: window_end,
+                busy: false,
+                event_id: None,
+            });
+        }
+
+        let gaps = Self { events: gaps };
+        gaps.assert_invariants();
+        gaps
+    }
+
+    /// Moves every `EventInstance` by `delta_ms`. A constant shift keeps the instances
+    /// sorted and non-overlapping, so they are still compatible afterwards.
+    pub fn shift(self, delta_ms: i64) -> CompatibleInstances {
+        let shifted = Self {
+            events: self
+                .events
+                .into_iter()
+                .map(|instance| instance.shift(delta_ms))
+                .collect(),
+        };
+        shifted.assert_invariants();
+        shifted
     }
 
     pub fn inner(self) -> VecDeque<EventInstance> {
@@ -101,6 +263,24 @@ impl CompatibleInstances {
     pub fn is_empty(&self) -> bool {
         self.events.is_empty()
     }
+
+    /// Returns the index of the first `EventInstance` with an `end_ts` greater than
+    /// the given timestamp, or `len()` if there is none.
+    /// Because the instances are sorted and do not overlap, `end_ts` is increasing
+    /// as well and a binary search can be used.
+    pub(crate) fn find_first_ending_after(&self, timestamp: i64) -> usize {
+        let mut low = 0;
+        let mut high = self.events.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.events[mid].end_ts > timestamp {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        low
+    }
 }
 
 impl AsRef<VecDeque<EventInstance>> for CompatibleInstances {
@@ -124,6 +304,21 @@ pub enum SubtractInstanceResult {
 }
 
 impl EventInstance {
+    /// Length of the `EventInstance` in milliseconds
+    pub fn duration(&self) -> i64 {
+        self.end_ts - self.start_ts
+    }
+
+    /// Moves both the start and the end of the `EventInstance` by `delta_ms`, saturating
+    /// at the bounds of `i64` instead of overflowing
+    pub fn shift(self, delta_ms: i64) -> EventInstance {
+        Self {
+            start_ts: self.start_ts.saturating_add(delta_ms),
+            end_ts: self.end_ts.saturating_add(delta_ms),
+            ..self
+        }
+    }
+
     pub fn has_overlap(instance1: &Self, instance2: &Self) -> bool {
         instance1.start_ts <= instance2.end_ts && instance1.end_ts >= instance2.start_ts
     }
@@ -133,15 +328,22 @@ impl EventInstance {
     }
 
     /// Merges two `EventInstance`s into a new `EventInstance` if they overlap.
+    /// The `event_id` is only kept if both are instances of the same `CalendarEvent`.
     pub fn merge(instance1: &Self, instance2: &Self) -> Option<Self> {
         if !Self::can_merge(instance1, instance2) {
             return None;
         }
 
+        let event_id = if instance1.event_id == instance2.event_id {
+            instance1.event_id.clone()
+        } else {
+            None
+        };
         Some(Self {
             start_ts: std::cmp::min(instance1.start_ts, instance2.start_ts),
             end_ts: std::cmp::max(instance1.end_ts, instance2.end_ts),
             busy: instance1.busy,
+            event_id,
         })
     }
 
@@ -160,11 +362,13 @@ impl EventInstance {
                 start_ts: free_instance.start_ts,
                 end_ts: instance.start_ts,
                 busy: false,
+                event_id: free_instance.event_id.clone(),
             };
             let free_instance_2 = Self {
                 start_ts: instance.end_ts,
                 end_ts: free_instance.end_ts,
                 busy: false,
+                event_id: free_instance.event_id.clone(),
             };
             let events = CompatibleInstances::new(vec![free_instance_1, free_instance_2]);
             return SubtractInstanceResult::Split(events);
@@ -175,6 +379,7 @@ impl EventInstance {
                 start_ts: instance.end_ts,
                 end_ts: free_instance.end_ts,
                 busy: false,
+                event_id: free_instance.event_id.clone(),
             }]);
             SubtractInstanceResult::OverlapBeginning(e)
         } else {
@@ -182,11 +387,15 @@ impl EventInstance {
                 start_ts: free_instance.start_ts,
                 end_ts: instance.start_ts,
                 busy: false,
+                event_id: free_instance.event_id.clone(),
             }]);
             SubtractInstanceResult::OverlapEnd(e)
         }
     }
 
+    /// Subtracts the given `CompatibleInstances` (starting at index `skip`) from this
+    /// `EventInstance` and returns the parts of it that are left.
+    /// The parts that are left keep the `event_id` of this `EventInstance`.
     pub fn remove_instances(
         &self,
         intances: &CompatibleInstances,
@@ -194,53 +403,34 @@ impl EventInstance {
     ) -> CompatibleInstances {
         let mut free_instances_without_conflict = CompatibleInstances::new(vec![]);
 
+        // Instances ending before this instance starts can never conflict, so skip them
+        let first = std::cmp::max(skip, intances.find_first_ending_after(self.start_ts));
         let mut conflict = false;
-        for (pos, instance) in intances.as_ref().iter().skip(skip).enumerate() {
+        let mut cursor = self.start_ts;
+        for instance in intances.as_ref().iter().skip(first) {
             if instance.start_ts >= self.end_ts {
                 break;
             }
-            let free_instances = match EventInstance::remove_instance(self, instance) {
-                SubtractInstanceResult::OverlapEnd(event) => {
-                    assert_eq!(event.len(), 1);
-                    conflict = true;
-                    Some(event)
-                }
-                SubtractInstanceResult::OverlapBeginning(mut event) => {
-                    assert_eq!(event.len(), 1);
-                    conflict = true;
-                    event.remove_intances(intances, pos + 1);
-
-                    Some(event)
-                }
-                SubtractInstanceResult::Split(events) => {
-                    assert_eq!(events.len(), 2);
-                    conflict = true;
-
-                    let mut events = events.inner();
-                    let last_event = events.pop_back().unwrap();
-                    let first_event = events.pop_front().unwrap();
-
-                    let mut events = CompatibleInstances::new(vec![last_event.clone()]);
-                    events.remove_intances(intances, pos + 1);
-                    events.push_front(first_event);
-
-                    Some(events)
-                }
-                SubtractInstanceResult::Empty => {
-                    conflict = true;
-                    None
-                }
-                SubtractInstanceResult::NoOverlap => {
-                    conflict = false;
-                    None
-                }
-            };
-            if let Some(new_free_instances) = free_instances {
-                free_instances_without_conflict.extend(new_free_instances);
+            conflict = true;
+            if instance.start_ts > cursor {
+                free_instances_without_conflict.push_back(Self {
+                    start_ts: cursor,
+                    end_ts: instance.start_ts,
+                    busy: false,
+                    event_id: self.event_id.clone(),
+                });
             }
+            cursor = std::cmp::max(cursor, instance.end_ts);
         }
         if !conflict {
             free_instances_without_conflict.push_back(self.clone());
+        } else if cursor < self.end_ts {
+            free_instances_without_conflict.push_back(Self {
+                start_ts: cursor,
+                end_ts: self.end_ts,
+                busy: false,
+                event_id: self.event_id.clone(),
+            });
         }
 
         free_instances_without_conflict
@@ -289,6 +479,78 @@ pub fn get_free_busy(instances: Vec<EventInstance>) -> FreeBusy {
     }
 }
 
+/// Two busy `EventInstance`s of different `CalendarEvent`s that overlap in time,
+/// like a double-booking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventConflict {
+    pub first_event_id: ID,
+    pub second_event_id: ID,
+    /// Start of the time that both `EventInstance`s cover
+    pub start_ts: i64,
+    /// End of the time that both `EventInstance`s cover
+    pub end_ts: i64,
+}
+
+/// Finds every pair of busy `EventInstance`s that overlap. Instances that only touch,
+/// where one ends at the moment the other starts, do not conflict.
+pub fn find_conflicts(instances: Vec<EventInstance>) -> Vec<EventConflict> {
+    let mut busy_instances = instances
+        .into_iter()
+        .filter(|instance| instance.busy)
+        .collect::<Vec<_>>();
+    busy_instances.sort();
+
+    let mut conflicts = vec![];
+    for (i, instance) in busy_instances.iter().enumerate() {
+        // Sorted by start, so only the following instances starting before this one
+        // ends can overlap with it
+        for other in busy_instances[i + 1..]
+            .iter()
+            .take_while(|other| other.start_ts < instance.end_ts)
+        {
+            if let (Some(first_event_id), Some(second_event_id)) =
+                (&instance.event_id, &other.event_id)
+            {
+                if first_event_id == second_event_id {
+                    continue;
+                }
+                conflicts.push(EventConflict {
+                    first_event_id: first_event_id.clone(),
+                    second_event_id: second_event_id.clone(),
+                    start_ts: other.start_ts,
+                    end_ts: std::cmp::min(instance.end_ts, other.end_ts),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Finds the busy `EventInstance`s that overlap with any of the proposed instances,
+/// sorted by their start. Like for `find_conflicts`, instances that only touch do not conflict.
+pub fn find_conflicting_instances(
+    proposed: &CompatibleInstances,
+    instances: Vec<EventInstance>,
+) -> Vec<EventInstance> {
+    let mut conflicting = instances
+        .into_iter()
+        .filter(|instance| instance.busy)
+        .filter(|instance| {
+            // The first proposed instance ending after this one starts is the only
+            // candidate, as every later proposed instance starts even later
+            let index = proposed.find_first_ending_after(instance.start_ts);
+            proposed
+                .get(index)
+                .map(|proposed| proposed.start_ts < instance.end_ts)
+                .unwrap_or(false)
+        })
+        .collect::<Vec<_>>();
+    conflicting.sort();
+    conflicting
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -303,12 +565,14 @@ mod test {
                 start_ts: 0,
                 end_ts: 4,
                 busy: false,
+                event_id: None,
             };
 
             let e2 = EventInstance {
                 start_ts: 5,
                 end_ts: 10,
                 busy: false,
+                event_id: None,
             };
 
             let res = EventInstance::merge(&e1, &e2);
@@ -321,12 +585,14 @@ mod test {
                 start_ts: 1,
                 end_ts: 10,
                 busy: false,
+                event_id: None,
             };
 
             let e2 = EventInstance {
                 start_ts: 5,
                 end_ts: 7,
                 busy: false,
+                event_id: None,
             };
 
             let res = EventInstance::merge(&e1, &e2);
@@ -340,12 +606,14 @@ mod test {
                 start_ts: 1,
                 end_ts: 10,
                 busy: false,
+                event_id: None,
             };
 
             let e2 = EventInstance {
                 start_ts: 5,
                 end_ts: 15,
                 busy: false,
+                event_id: None,
             };
 
             let res = EventInstance::merge(&e1, &e2);
@@ -355,7 +623,8 @@ mod test {
                 EventInstance {
                     start_ts: 1,
                     end_ts: 15,
-                    busy: false
+                    busy: false,
+                    event_id: None,
                 }
             );
         }
@@ -366,12 +635,14 @@ mod test {
                 start_ts: 0,
                 end_ts: 4,
                 busy: false,
+                event_id: None,
             };
 
             let e2 = EventInstance {
                 start_ts: 5,
                 end_ts: 10,
                 busy: true,
+                event_id: None,
             };
 
             let res = EventInstance::remove_instance(&e1, &e2);
@@ -384,12 +655,14 @@ mod test {
                 start_ts: 0,
                 end_ts: 4,
                 busy: false,
+                event_id: None,
             };
 
             let e2 = EventInstance {
                 start_ts: 0,
                 end_ts: 10,
                 busy: true,
+                event_id: None,
             };
 
             let res = EventInstance::remove_instance(&e1, &e2);
@@ -402,12 +675,14 @@ mod test {
                 start_ts: 0,
                 end_ts: 4,
                 busy: false,
+                event_id: None,
             };
 
             let mut e2 = EventInstance {
                 start_ts: 3,
                 end_ts: 10,
                 busy: true,
+                event_id: None,
             };
 
             let res = EventInstance::remove_instance(&e1, &e2);
@@ -415,6 +690,7 @@ mod test {
                 start_ts: 0,
                 end_ts: 3,
                 busy: false,
+                event_id: None,
             }]);
             let expected_res = SubtractInstanceResult::OverlapEnd(expected_e);
             assert_eq!(res, expected_res);
@@ -428,6 +704,7 @@ mod test {
                 start_ts: 4,
                 end_ts: 10,
                 busy: false,
+                event_id: None,
             }]);
             let expected_res = SubtractInstanceResult::OverlapBeginning(expected_e);
             assert_eq!(res, expected_res);
@@ -439,12 +716,14 @@ mod test {
                 start_ts: 2,
                 end_ts: 14,
                 busy: false,
+                event_id: None,
             };
 
             let mut e2 = EventInstance {
                 start_ts: 3,
                 end_ts: 10,
                 busy: true,
+                event_id: None,
             };
 
             let res = EventInstance::remove_instance(&e1, &e2);
@@ -453,11 +732,13 @@ mod test {
                     start_ts: 2,
                     end_ts: 3,
                     busy: false,
+                    event_id: None,
                 },
                 EventInstance {
                     start_ts: 10,
                     end_ts: 14,
                     busy: false,
+                    event_id: None,
                 },
             ]);
             let expected_res = SubtractInstanceResult::Split(expected_events);
@@ -478,6 +759,7 @@ mod test {
             start_ts: 5,
             end_ts: 100,
             busy: false,
+            event_id: None,
         };
         let mut free = CompatibleInstances::new(vec![free1]);
 
@@ -485,16 +767,19 @@ mod test {
             start_ts: 2,
             end_ts: 40,
             busy: false,
+            event_id: None,
         };
         let busy2 = EventInstance {
             start_ts: 50,
             end_ts: 70,
             busy: false,
+            event_id: None,
         };
         let busy3 = EventInstance {
             start_ts: 72,
             end_ts: 75,
             busy: false,
+            event_id: None,
         };
         let busy = CompatibleInstances::new(vec![busy1, busy2, busy3]);
         free.remove_intances(&busy, 0);
@@ -505,7 +790,8 @@ mod test {
             EventInstance {
                 start_ts: 40,
                 end_ts: 50,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -513,7 +799,8 @@ mod test {
             EventInstance {
                 start_ts: 70,
                 end_ts: 72,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -521,7 +808,8 @@ mod test {
             EventInstance {
                 start_ts: 75,
                 end_ts: 100,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
     }
@@ -532,16 +820,19 @@ mod test {
             start_ts: 0,
             end_ts: 71,
             busy: false,
+            event_id: None,
         };
         let free2 = EventInstance {
             start_ts: 72,
             end_ts: 74,
             busy: false,
+            event_id: None,
         };
         let free3 = EventInstance {
             start_ts: 100,
             end_ts: 140,
             busy: false,
+            event_id: None,
         };
         let mut free = CompatibleInstances::new(vec![free1, free2, free3]);
 
@@ -549,16 +840,19 @@ mod test {
             start_ts: 2,
             end_ts: 40,
             busy: false,
+            event_id: None,
         };
         let busy2 = EventInstance {
             start_ts: 50,
             end_ts: 70,
             busy: false,
+            event_id: None,
         };
         let busy3 = EventInstance {
             start_ts: 72,
             end_ts: 75,
             busy: false,
+            event_id: None,
         };
         let busy = CompatibleInstances::new(vec![busy1, busy2, busy3]);
         free.remove_intances(&busy, 0);
@@ -570,7 +864,8 @@ mod test {
             EventInstance {
                 start_ts: 0,
                 end_ts: 2,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -578,7 +873,8 @@ mod test {
             EventInstance {
                 start_ts: 40,
                 end_ts: 50,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -586,7 +882,8 @@ mod test {
             EventInstance {
                 start_ts: 70,
                 end_ts: 71,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -594,7 +891,8 @@ mod test {
             EventInstance {
                 start_ts: 100,
                 end_ts: 140,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
     }
@@ -610,6 +908,7 @@ mod test {
             start_ts: 0,
             end_ts: 2,
             busy: false,
+            event_id: None,
         };
         let c_events = CompatibleInstances::new(vec![e1.clone()]);
         let c_events = c_events.inner();
@@ -622,11 +921,13 @@ mod test {
             start_ts: 0,
             end_ts: 2,
             busy: false,
+            event_id: None,
         };
         let e2 = EventInstance {
             start_ts: 0,
             end_ts: 2,
             busy: false,
+            event_id: None,
         };
         let c_events = CompatibleInstances::new(vec![e1.clone(), e2.clone()]);
         let c_events = c_events.inner();
@@ -639,11 +940,13 @@ mod test {
             start_ts: 0,
             end_ts: 2,
             busy: false,
+            event_id: None,
         };
         let e2 = EventInstance {
             start_ts: 5,
             end_ts: 10,
             busy: false,
+            event_id: None,
         };
         let c_events = CompatibleInstances::new(vec![e1.clone(), e2.clone()]);
         let c_events = c_events.inner();
@@ -658,31 +961,37 @@ mod test {
             start_ts: 5,
             end_ts: 10,
             busy: false,
+            event_id: None,
         };
         let e2 = EventInstance {
             start_ts: 1,
             end_ts: 7,
             busy: false,
+            event_id: None,
         };
         let e3 = EventInstance {
             start_ts: 6,
             end_ts: 14,
             busy: false,
+            event_id: None,
         };
         let e4 = EventInstance {
             start_ts: 20,
             end_ts: 30,
             busy: false,
+            event_id: None,
         };
         let e5 = EventInstance {
             start_ts: 24,
             end_ts: 40,
             busy: false,
+            event_id: None,
         };
         let e6 = EventInstance {
             start_ts: 44,
             end_ts: 50,
             busy: false,
+            event_id: None,
         };
         let c_events = CompatibleInstances::new(vec![
             e1.clone(),
@@ -699,7 +1008,8 @@ mod test {
             EventInstance {
                 start_ts: 1,
                 end_ts: 14,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -707,7 +1017,8 @@ mod test {
             EventInstance {
                 start_ts: 20,
                 end_ts: 40,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(c_events[2], e6);
@@ -719,26 +1030,31 @@ mod test {
             start_ts: 5,
             end_ts: 10,
             busy: false,
+            event_id: None,
         };
         let e2 = EventInstance {
             start_ts: 1,
             end_ts: 7,
             busy: false,
+            event_id: None,
         };
         let e3 = EventInstance {
             start_ts: 6,
             end_ts: 14,
             busy: false,
+            event_id: None,
         };
         let e4 = EventInstance {
             start_ts: 20,
             end_ts: 30,
             busy: false,
+            event_id: None,
         };
         let e5 = EventInstance {
             start_ts: 24,
             end_ts: 40,
             busy: false,
+            event_id: None,
         };
         let c_events = CompatibleInstances::new(vec![
             e1.clone(),
@@ -754,7 +1070,8 @@ mod test {
             EventInstance {
                 start_ts: 1,
                 end_ts: 14,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -762,7 +1079,8 @@ mod test {
             EventInstance {
                 start_ts: 20,
                 end_ts: 40,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
     }
@@ -775,6 +1093,7 @@ mod test {
                     start_ts: i * 10 + 5,
                     end_ts: i * 10 + 8,
                     busy: false,
+                    event_id: None,
                 })
                 .collect(),
         );
@@ -784,6 +1103,7 @@ mod test {
                     start_ts: i * 10 + 6,
                     end_ts: i * 10 + 7,
                     busy: false,
+                    event_id: None,
                 })
                 .collect(),
         );
@@ -797,6 +1117,7 @@ mod test {
             start_ts: 0,
             end_ts: 10,
             busy: false,
+            event_id: None,
         };
 
         let instances = vec![e1.clone()];
@@ -811,6 +1132,7 @@ mod test {
             start_ts: 0,
             end_ts: 10,
             busy: true,
+            event_id: None,
         };
 
         let instances = vec![e1];
@@ -824,12 +1146,14 @@ mod test {
             start_ts: 0,
             end_ts: 10,
             busy: false,
+            event_id: None,
         };
 
         let e2 = EventInstance {
             start_ts: 3,
             end_ts: 5,
             busy: true,
+            event_id: None,
         };
 
         let instances = vec![e1, e2];
@@ -841,14 +1165,498 @@ mod test {
                 EventInstance {
                     start_ts: 0,
                     end_ts: 3,
-                    busy: false
+                    busy: false,
+                    event_id: None,
                 },
                 EventInstance {
                     start_ts: 5,
                     end_ts: 10,
-                    busy: false
+                    busy: false,
+                    event_id: None,
                 }
             ]
         )
     }
+
+    #[test]
+    fn sorts_instances_by_start_and_then_end() {
+        let instance = |start_ts: i64, end_ts: i64| EventInstance {
+            start_ts,
+            end_ts,
+            busy: false,
+            event_id: None,
+        };
+        let mut instances = vec![
+            instance(20, 30),
+            instance(0, 10),
+            instance(5, 6),
+            instance(0, 5),
+            instance(20, 25),
+            instance(5, 15),
+        ];
+        instances.sort();
+        assert_eq!(
+            instances,
+            vec![
+                instance(0, 5),
+                instance(0, 10),
+                instance(5, 6),
+                instance(5, 15),
+                instance(20, 25),
+                instance(20, 30),
+            ]
+        );
+    }
+
+    /// Generates `count` sorted instances with random gaps and lengths
+    fn generate_instances(count: usize, max_len: i64, busy: bool, seed: u64) -> Vec<EventInstance> {
+        let mut state = seed;
+        let mut next = |max: i64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 33) as i64) % max
+        };
+        let mut ts = 0;
+        (0..count)
+            .map(|_| {
+                let start_ts = ts + next(50);
+                let end_ts = start_ts + 1 + next(max_len);
+                ts = end_ts;
+                EventInstance {
+                    start_ts,
+                    end_ts,
+                    busy,
+                    event_id: None,
+                }
+            })
+            .collect()
+    }
+
+    fn naive_remove_instances(
+        free: &CompatibleInstances,
+        busy: &CompatibleInstances,
+    ) -> Vec<EventInstance> {
+        let mut remaining = vec![];
+        for free_instance in free.as_ref() {
+            let mut pieces = vec![free_instance.clone()];
+            for busy_instance in busy.as_ref() {
+                pieces = pieces
+                    .into_iter()
+                    .flat_map(|piece| {
+                        if busy_instance.end_ts <= piece.start_ts
+                            || busy_instance.start_ts >= piece.end_ts
+                        {
+                            return vec![piece];
+                        }
+                        let mut left = vec![];
+                        if busy_instance.start_ts > piece.start_ts {
+                            left.push(EventInstance {
+                                start_ts: piece.start_ts,
+                                end_ts: busy_instance.start_ts,
+                                busy: false,
+                                event_id: None,
+                            });
+                        }
+                        if busy_instance.end_ts < piece.end_ts {
+                            left.push(EventInstance {
+                                start_ts: busy_instance.end_ts,
+                                end_ts: piece.end_ts,
+                                busy: false,
+                                event_id: None,
+                            });
+                        }
+                        left
+                    })
+                    .collect();
+            }
+            remaining.extend(pieces);
+        }
+        remaining
+    }
+
+    #[test]
+    fn remove_many_busy_from_many_free_matches_naive_implementation() {
+        let free = CompatibleInstances::new(generate_instances(2000, 400, false, 1));
+        let busy = CompatibleInstances::new(generate_instances(3000, 100, true, 2));
+
+        let expected = naive_remove_instances(&free, &busy);
+
+        let mut actual = free;
+        actual.remove_intances(&busy, 0);
+        let actual = actual.inner().into_iter().collect::<Vec<_>>();
+
+        assert!(!expected.is_empty());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn finds_first_instance_ending_after_timestamp() {
+        let instances = CompatibleInstances::new(generate_instances(1000, 100, true, 3));
+        let last_end_ts = instances.as_ref().back().unwrap().end_ts;
+        for timestamp in (-10..last_end_ts + 10).step_by(7) {
+            let expected = instances
+                .as_ref()
+                .iter()
+                .position(|instance| instance.end_ts > timestamp)
+                .unwrap_or(instances.len());
+            assert_eq!(instances.find_first_ending_after(timestamp), expected);
+        }
+    }
+
+    #[test]
+    fn extend_merges_overlapping_instances() {
+        let mut instances = CompatibleInstances::new(vec![
+            EventInstance {
+                start_ts: 0,
+                end_ts: 10,
+                busy: false,
+                event_id: None,
+            },
+            EventInstance {
+                start_ts: 20,
+                end_ts: 30,
+                busy: false,
+                event_id: None,
+            },
+        ]);
+        instances.extend(CompatibleInstances::new(vec![
+            EventInstance {
+                start_ts: 5,
+                end_ts: 15,
+                busy: false,
+                event_id: None,
+            },
+            EventInstance {
+                start_ts: 25,
+                end_ts: 40,
+                busy: false,
+                event_id: None,
+            },
+            EventInstance {
+                start_ts: 50,
+                end_ts: 60,
+                busy: false,
+                event_id: None,
+            },
+        ]));
+
+        let instances = instances.inner().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            instances,
+            vec![
+                EventInstance {
+                    start_ts: 0,
+                    end_ts: 15,
+                    busy: false,
+                    event_id: None,
+                },
+                EventInstance {
+                    start_ts: 20,
+                    end_ts: 40,
+                    busy: false,
+                    event_id: None,
+                },
+                EventInstance {
+                    start_ts: 50,
+                    end_ts: 60,
+                    busy: false,
+                    event_id: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn instances_built_through_valid_paths_pass_invariant_check() {
+        let mut instances = CompatibleInstances::new(vec![
+            free_instance(20, 30),
+            free_instance(0, 10),
+            free_instance(5, 15),
+        ]);
+        instances.assert_invariants();
+        assert!(instances.push_back(free_instance(40, 50)));
+        assert!(!instances.push_back(free_instance(45, 60)));
+        assert!(instances.push_front(free_instance(-10, -5)));
+        assert!(!instances.push_front(free_instance(-20, -6)));
+        instances.extend(CompatibleInstances::new(vec![
+            free_instance(12, 22),
+            free_instance(70, 80),
+        ]));
+        instances.remove_intances(
+            &CompatibleInstances::new(vec![busy_instance(2, 4), busy_instance(45, 75)]),
+            0,
+        );
+        instances.assert_invariants();
+        instances.complement(-20, 100).assert_invariants();
+        instances.shift(1000).assert_invariants();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn overlapping_instances_fail_invariant_check() {
+        let instances = CompatibleInstances {
+            events: vec![free_instance(0, 10), free_instance(5, 15)].into(),
+        };
+        instances.assert_invariants();
+    }
+
+    fn busy_instance(start_ts: i64, end_ts: i64) -> EventInstance {
+        EventInstance {
+            start_ts,
+            end_ts,
+            busy: true,
+            event_id: None,
+        }
+    }
+
+    fn free_instance(start_ts: i64, end_ts: i64) -> EventInstance {
+        EventInstance {
+            start_ts,
+            end_ts,
+            busy: false,
+            event_id: None,
+        }
+    }
+
+    #[test]
+    fn complement_of_no_instances_is_the_whole_window() {
+        let instances = CompatibleInstances::new(vec![]);
+        let free = instances.complement(0, 100).inner();
+        assert_eq!(free, vec![free_instance(0, 100)]);
+    }
+
+    #[test]
+    fn complement_with_busy_instance_in_the_middle() {
+        let instances = CompatibleInstances::new(vec![busy_instance(40, 60)]);
+        let free = instances.complement(0, 100).inner();
+        assert_eq!(free, vec![free_instance(0, 40), free_instance(60, 100)]);
+    }
+
+    #[test]
+    fn complement_with_busy_instances_at_the_edges() {
+        let instances = CompatibleInstances::new(vec![
+            busy_instance(-20, 10),
+            busy_instance(30, 50),
+            busy_instance(90, 120),
+        ]);
+        let free = instances.complement(0, 100).inner();
+        assert_eq!(free, vec![free_instance(10, 30), free_instance(50, 90)]);
+
+        // Instances touching the window edges leave no empty gaps behind
+        let instances =
+            CompatibleInstances::new(vec![busy_instance(0, 10), busy_instance(90, 100)]);
+        let free = instances.complement(0, 100).inner();
+        assert_eq!(free, vec![free_instance(10, 90)]);
+    }
+
+    #[test]
+    fn complement_of_fully_covered_window_is_empty() {
+        let instances =
+            CompatibleInstances::new(vec![busy_instance(-10, 200), busy_instance(300, 400)]);
+        assert!(instances.complement(0, 100).is_empty());
+        assert!(instances.complement(50, 50).is_empty());
+    }
+
+    #[test]
+    fn shifts_instance_bounds() {
+        assert_eq!(busy_instance(10, 20).shift(5), busy_instance(15, 25));
+        assert_eq!(free_instance(10, 20).shift(-15), free_instance(-5, 5));
+        assert_eq!(
+            busy_instance(10, 20).shift(i64::MAX),
+            busy_instance(i64::MAX, i64::MAX)
+        );
+        assert_eq!(
+            busy_instance(-20, -10).shift(i64::MIN),
+            busy_instance(i64::MIN, i64::MIN)
+        );
+    }
+
+    #[test]
+    fn shifted_instances_are_still_compatible() {
+        let instances = CompatibleInstances::new(vec![
+            busy_instance(50, 60),
+            busy_instance(0, 10),
+            busy_instance(5, 20),
+            busy_instance(30, 40),
+        ]);
+        for delta in &[0, 7, -100, 1000 * 60 * 60] {
+            let expected = instances
+                .as_ref()
+                .iter()
+                .cloned()
+                .map(|instance| instance.shift(*delta))
+                .collect::<Vec<_>>();
+            let shifted = CompatibleInstances::new(instances.as_ref().iter().cloned().collect())
+                .shift(*delta);
+            // Building the instances again from the shifted ones changes nothing
+            assert_eq!(CompatibleInstances::new(expected.clone()), shifted);
+            assert_eq!(shifted.inner(), expected);
+        }
+
+        let shifted = instances.shift(-5);
+        assert_eq!(
+            shifted.inner(),
+            vec![
+                busy_instance(-5, 15),
+                busy_instance(25, 35),
+                busy_instance(45, 55)
+            ]
+        );
+    }
+
+    fn event_instance(start_ts: i64, end_ts: i64, event_id: &ID) -> EventInstance {
+        EventInstance {
+            event_id: Some(event_id.clone()),
+            ..busy_instance(start_ts, end_ts)
+        }
+    }
+
+    #[test]
+    fn merged_instances_of_same_event_keep_event_id() {
+        let event_id = ID::default();
+        let instances = CompatibleInstances::new(vec![
+            event_instance(0, 10, &event_id),
+            event_instance(5, 20, &event_id),
+            event_instance(30, 40, &event_id),
+        ]);
+        assert_eq!(
+            instances.inner(),
+            vec![
+                event_instance(0, 20, &event_id),
+                event_instance(30, 40, &event_id)
+            ]
+        );
+    }
+
+    #[test]
+    fn merged_instances_of_different_events_clear_event_id() {
+        let event_id = ID::default();
+        let other_event_id = ID::default();
+        let instances = CompatibleInstances::new(vec![
+            event_instance(0, 10, &event_id),
+            event_instance(5, 20, &other_event_id),
+            event_instance(30, 40, &other_event_id),
+            busy_instance(35, 50),
+        ]);
+        assert_eq!(
+            instances.inner(),
+            vec![busy_instance(0, 20), busy_instance(30, 50)]
+        );
+    }
+
+    #[test]
+    fn remaining_parts_of_instance_keep_event_id() {
+        let event_id = ID::default();
+        let instance = EventInstance {
+            busy: false,
+            ..event_instance(0, 100, &event_id)
+        };
+        let busy = CompatibleInstances::new(vec![busy_instance(20, 30), busy_instance(60, 70)]);
+        let parts = instance.remove_instances(&busy, 0).inner();
+        assert_eq!(parts.len(), 3);
+        assert!(parts
+            .iter()
+            .all(|part| part.event_id.as_ref() == Some(&event_id)));
+    }
+
+    #[test]
+    fn finds_overlapping_busy_instances_of_different_events() {
+        let event1 = ID::default();
+        let event2 = ID::default();
+        let event3 = ID::default();
+        let conflicts = find_conflicts(vec![
+            event_instance(20, 30, &event3),
+            event_instance(0, 10, &event1),
+            event_instance(5, 20, &event2),
+            // Free instances never conflict
+            EventInstance {
+                busy: false,
+                ..event_instance(0, 30, &ID::default())
+            },
+        ]);
+        assert_eq!(
+            conflicts,
+            vec![EventConflict {
+                first_event_id: event1,
+                second_event_id: event2,
+                start_ts: 5,
+                end_ts: 10,
+            }]
+        );
+    }
+
+    #[test]
+    fn touching_instances_do_not_conflict() {
+        let conflicts = find_conflicts(vec![
+            event_instance(0, 10, &ID::default()),
+            event_instance(10, 20, &ID::default()),
+        ]);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn busy_instances_take_precedence_over_overlapping_free_instances() {
+        let instances = CompatibleInstances::new(vec![
+            free_instance(0, 10),
+            busy_instance(5, 15),
+            free_instance(20, 50),
+            busy_instance(30, 40),
+            busy_instance(35, 45),
+            // Completely covered by a busy instance
+            free_instance(60, 65),
+            busy_instance(55, 70),
+        ]);
+        assert_eq!(
+            instances.inner(),
+            vec![
+                free_instance(0, 5),
+                busy_instance(5, 15),
+                free_instance(20, 30),
+                busy_instance(30, 45),
+                free_instance(45, 50),
+                busy_instance(55, 70),
+            ]
+        );
+    }
+
+    #[test]
+    fn mixed_instances_without_overlap_are_kept() {
+        let instances = CompatibleInstances::new(vec![
+            busy_instance(10, 20),
+            free_instance(0, 6),
+            free_instance(4, 10),
+            free_instance(20, 30),
+        ]);
+        assert_eq!(
+            instances.inner(),
+            vec![
+                free_instance(0, 10),
+                busy_instance(10, 20),
+                free_instance(20, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn serializes_with_duration() {
+        let event_id = ID::default();
+        let instance = event_instance(100, 400, &event_id);
+        assert_eq!(instance.duration(), 300);
+
+        let json = serde_json::to_value(&instance).unwrap();
+        assert_eq!(json["startTs"], 100);
+        assert_eq!(json["endTs"], 400);
+        assert_eq!(json["duration"], 300);
+        assert_eq!(json["eventId"], event_id.to_string());
+        assert!(serde_json::to_value(&busy_instance(0, 10)).unwrap()["eventId"].is_null());
+
+        // The duration is derived, so it is neither required nor used when deserializing
+        let parsed: EventInstance = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, instance);
+        let parsed: EventInstance =
+            serde_json::from_str(r#"{ "startTs": 0, "endTs": 10, "busy": true }"#).unwrap();
+        assert_eq!(parsed, busy_instance(0, 10));
+    }
 }
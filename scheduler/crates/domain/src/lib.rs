@@ -11,17 +11,22 @@ mod shared;
 mod timespan;
 mod user;
 
-pub use account::{Account, AccountSettings, AccountWebhookSettings, PEMKey};
-pub use calendar::{Calendar, CalendarSettings};
-pub use event::{CalendarEvent, CalendarEventReminder};
+pub use account::{
+    Account, AccountSettings, AccountWebhookSettings, PEMKey, DEFAULT_MAX_CALENDARS_PER_USER,
+    MAX_CALENDARS_PER_USER_LIMIT,
+};
+pub use calendar::{Calendar, CalendarSettings, CALENDAR_VISIBILITY_KEY};
+pub use event::{CalendarEvent, CalendarEventReminder, EventEditMode, EventStatus};
 pub use event_instance::{
-    get_free_busy, CompatibleInstances, EventInstance, EventWithInstances, FreeBusy,
+    find_conflicting_instances, find_conflicts, get_free_busy, CompatibleInstances, EventConflict,
+    EventInstance, EventWithInstances, FreeBusy,
 };
 pub use reminder::{EventRemindersExpansionJob, Reminder};
 pub use schedule::{Schedule, ScheduleRule};
-pub use service::{Service, ServiceResource, TimePlan};
+pub use service::{BookingStrategy, Service, ServiceResource, TimePlan};
 pub use shared::entity::{Entity, ID};
-pub use shared::metadata::{Meta, Metadata};
-pub use shared::recurrence::{RRuleFrequenzy, RRuleOptions};
+pub use shared::metadata::{patch_metadata, Meta, Metadata, MetadataPatch};
+pub use shared::millis::Millis;
+pub use shared::recurrence::{RRuleFrequenzy, RRuleOptions, RRuleParseError};
 pub use timespan::TimeSpan;
 pub use user::User;
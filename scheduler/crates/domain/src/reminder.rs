@@ -22,6 +22,26 @@ pub struct Reminder {
     /// The job sending out the `Reminder`s is going to detect duplicate `Reminder`s
     /// and filter away the one with the lowest priority.
     pub priority: i64,
+    /// Timestamp of when the `Reminder` was sent to the `Account`, or `None` while it
+    /// is still pending. Sent `Reminder`s are kept for a while so that an `Account`
+    /// whose webhook receiver was down can backfill the `Reminder`s it missed.
+    pub sent_at: Option<i64>,
+    /// Timestamp of when sending the `Reminder` was given up, because the `Account` had no
+    /// webhook or its receiver did not accept the delivery. Failed `Reminder`s are kept
+    /// like the sent ones, so that the `Account` can still backfill them.
+    pub failed_at: Option<i64>,
+}
+
+impl Reminder {
+    /// Whether the `Reminder` is still waiting to be sent
+    pub fn is_pending(&self) -> bool {
+        self.sent_at.is_none() && self.failed_at.is_none()
+    }
+
+    /// When the `Reminder` was sent or failed to be sent
+    pub fn dispatched_at(&self) -> Option<i64> {
+        self.sent_at.or(self.failed_at)
+    }
 }
 
 impl Entity for Reminder {
@@ -5,7 +5,7 @@ use crate::{
     timespan::TimeSpan,
     CompatibleInstances,
 };
-use chrono::{prelude::*, Duration};
+use chrono::{prelude::*, Duration, LocalResult};
 use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, str::FromStr};
@@ -17,6 +17,8 @@ pub struct Schedule {
     pub account_id: ID,
     pub rules: Vec<ScheduleRule>,
     pub timezone: Tz,
+    pub created: i64,
+    pub updated: i64,
 }
 
 impl Schedule {
@@ -27,10 +29,19 @@ impl Schedule {
             account_id,
             rules: ScheduleRule::default_rules(),
             timezone: timezone.to_owned(),
+            created: Default::default(),
+            updated: Default::default(),
         }
     }
 
-    pub fn set_rules(&mut self, rules: &Vec<ScheduleRule>) {
+    /// Sets the rules of the `Schedule`. Returns false and leaves the rules unchanged
+    /// if the intervals of the rules for the same weekday or date overlap, as that
+    /// would count the same availability twice.
+    pub fn set_rules(&mut self, rules: &Vec<ScheduleRule>) -> bool {
+        if has_overlapping_intervals(rules) {
+            return false;
+        }
+
         let now = Utc::now();
         let min_date = self.timezone.ymd(now.year(), now.month(), now.day()) - Duration::days(2);
         let max_date = self.timezone.ymd(min_date.year() + 5, 1, 1);
@@ -53,7 +64,33 @@ impl Schedule {
             })
             .collect();
         self.rules = allowed_rules;
+        true
+    }
+}
+
+/// Intervals that only touch each other, e.g. 9:00 - 12:00 and 12:00 - 15:00,
+/// are not overlapping. The part of an interval running past midnight is only
+/// compared with the other intervals of the day it starts on.
+fn has_overlapping_intervals(rules: &[ScheduleRule]) -> bool {
+    let mut day_intervals: HashMap<String, Vec<&ScheduleRuleInterval>> = HashMap::new();
+    for rule in rules {
+        let day = match &rule.variant {
+            ScheduleRuleVariant::WDay(wday) => wday.num_days_from_monday().to_string(),
+            ScheduleRuleVariant::Date(date) => date.clone(),
+        };
+        day_intervals
+            .entry(day)
+            .or_default()
+            .extend(rule.intervals.iter());
     }
+
+    day_intervals.values_mut().any(|intervals| {
+        // earliest start first
+        intervals.sort_by(|i1, i2| i1.start.partial_cmp(&i2.start).unwrap());
+        intervals
+            .windows(2)
+            .any(|pair| pair[1].start.minutes_of_day() < pair[0].end_minutes())
+    })
 }
 
 impl Entity for Schedule {
@@ -75,6 +112,38 @@ struct Time {
     pub minutes: i64,
 }
 
+impl Time {
+    fn minutes_of_day(&self) -> i64 {
+        self.hours * 60 + self.minutes
+    }
+
+    /// Converts this local time on the given `Day` in the given timezone to a
+    /// timestamp in millis.
+    ///
+    /// Local times that do not exist because the clock skips ahead, like 02:30 on
+    /// a spring-forward day, are moved forward by the length of the gap, so 02:30
+    /// becomes 03:30 when the clock jumps from 02:00 to 03:00. Local times that
+    /// occur twice because the clock is set back resolve to the earliest of them.
+    fn timestamp_millis(&self, day: &Day, tzid: &Tz) -> i64 {
+        let local = NaiveDate::from_ymd(day.year, day.month, day.day).and_hms(
+            self.hours as u32,
+            self.minutes as u32,
+            0,
+        );
+        match tzid.from_local_datetime(&local) {
+            LocalResult::Single(datetime) => datetime.timestamp_millis(),
+            LocalResult::Ambiguous(earliest, _) => earliest.timestamp_millis(),
+            LocalResult::None => {
+                // Use the offset that was in effect before the gap
+                let offset = tzid
+                    .offset_from_utc_datetime(&(local - Duration::days(1)))
+                    .fix();
+                (local - offset).timestamp_millis()
+            }
+        }
+    }
+}
+
 impl std::cmp::PartialOrd for Time {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match self.hours.cmp(&other.hours) {
@@ -94,18 +163,63 @@ pub struct ScheduleRuleInterval {
 }
 
 impl ScheduleRuleInterval {
-    pub fn to_event(&self, day: &Day, tzid: &Tz) -> EventInstance {
-        EventInstance {
-            busy: false,
-            start_ts: tzid
-                .ymd(day.year, day.month, day.day)
-                .and_hms(self.start.hours as u32, self.start.minutes as u32, 0)
-                .timestamp_millis(),
-            end_ts: tzid
-                .ymd(day.year, day.month, day.day)
-                .and_hms(self.end.hours as u32, self.end.minutes as u32, 0)
-                .timestamp_millis(),
+    /// An interval ending before it starts, like 22:00 - 02:00, runs past midnight
+    /// into the next day
+    pub fn wraps_midnight(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Minutes from the start of the day the interval starts on until it ends
+    fn end_minutes(&self) -> i64 {
+        if self.wraps_midnight() {
+            self.end.minutes_of_day() + 24 * 60
+        } else {
+            self.end.minutes_of_day()
+        }
+    }
+
+    /// The free instances of this interval on the given `Day`.
+    ///
+    /// An interval always belongs to the day it starts on. When it wraps past midnight
+    /// it is split into two instances on consecutive local days, so a Friday rule of
+    /// 22:00 - 02:00 is free from 22:00 until midnight on the Friday and from midnight
+    /// until 02:00 on the Saturday, regardless of the rules for that Saturday.
+    pub fn to_events(&self, day: &Day, tzid: &Tz) -> Vec<EventInstance> {
+        let start_ts = self.start.timestamp_millis(day, tzid);
+        if !self.wraps_midnight() {
+            return vec![EventInstance {
+                busy: false,
+                start_ts,
+                end_ts: self.end.timestamp_millis(day, tzid),
+                event_id: None,
+            }];
+        }
+
+        let mut next_day = Day {
+            year: day.year,
+            month: day.month,
+            day: day.day,
+        };
+        next_day.inc();
+        let midnight = Time {
+            hours: 0,
+            minutes: 0,
         }
+        .timestamp_millis(&next_day, tzid);
+        vec![
+            EventInstance {
+                busy: false,
+                start_ts,
+                end_ts: midnight,
+                event_id: None,
+            },
+            EventInstance {
+                busy: false,
+                start_ts: midnight,
+                end_ts: self.end.timestamp_millis(&next_day, tzid),
+                event_id: None,
+            },
+        ]
     }
 }
 
@@ -151,9 +265,6 @@ impl ScheduleRule {
         self.intervals
             .sort_by(|i1, i2| i1.start.partial_cmp(&i2.start).unwrap());
 
-        self.intervals
-            .retain(|interval| interval.start <= interval.end);
-
         let mut remove_intervals = HashMap::new();
 
         for i in 0..self.intervals.len() {
@@ -164,10 +275,8 @@ impl ScheduleRule {
                 if remove_intervals.get(&j).is_some() {
                     continue;
                 }
-                if self.intervals[j].start == self.intervals[i].start
-                    || self.intervals[j].start <= self.intervals[i].end
-                {
-                    if self.intervals[j].end > self.intervals[i].end {
+                if self.intervals[j].start.minutes_of_day() <= self.intervals[i].end_minutes() {
+                    if self.intervals[j].end_minutes() > self.intervals[i].end_minutes() {
                         self.intervals[i].end = self.intervals[j].end.clone();
                     }
                     remove_intervals.insert(j, true);
@@ -219,8 +328,9 @@ impl Day {
         }
     }
 
-    pub fn weekday(&self, tzid: &Tz) -> Weekday {
-        self.date(tzid).weekday()
+    pub fn weekday(&self) -> Weekday {
+        // Unlike `date` this does not fail in timezones where midnight is skipped
+        NaiveDate::from_ymd(self.year, self.month, self.day).weekday()
     }
 
     pub fn date(&self, tzid: &Tz) -> Date<Tz> {
@@ -270,11 +380,18 @@ impl Schedule {
 
         let mut free_instances = CompatibleInstances::new(vec![]);
 
-        let mut day_cursor = Day {
+        let first_day = Day {
             year: start.year(),
             month: start.month(),
             day: start.day(),
         };
+        // Intervals of the day before can run past midnight into the timespan
+        let day_before = start - Duration::days(1);
+        let mut day_cursor = Day {
+            year: day_before.year(),
+            month: day_before.month(),
+            day: day_before.day(),
+        };
         let last_day = Day {
             year: end.year(),
             month: end.month(),
@@ -287,14 +404,22 @@ impl Schedule {
                 Some(intervals) => Some(intervals),
                 None => {
                     // check if weekday rule exists
-                    let weekday = day_cursor.weekday(&self.timezone);
+                    let weekday = day_cursor.weekday();
                     weekday_lookup.get(&weekday)
                 }
             };
             if let Some(intervals) = intervals {
                 for interval in intervals.iter() {
-                    let event = interval.to_event(&day_cursor, &self.timezone);
-                    free_instances.push_back(event);
+                    for event in interval.to_events(&day_cursor, &self.timezone) {
+                        if day_cursor < first_day && event.end_ts <= timespan.start() {
+                            continue;
+                        }
+                        // The part of an interval after midnight can overlap with
+                        // the intervals of the next day
+                        if !free_instances.push_back(event.clone()) {
+                            free_instances.extend(CompatibleInstances::new(vec![event]));
+                        }
+                    }
                 }
             }
             day_cursor.inc();
@@ -373,6 +498,8 @@ mod test {
             user_id: Default::default(),
             account_id: Default::default(),
             timezone: chrono_tz::UTC,
+            created: Default::default(),
+            updated: Default::default(),
             rules: vec![
                 ScheduleRule {
                     variant: ScheduleRuleVariant::WDay(Weekday::Mon),
@@ -412,7 +539,8 @@ mod test {
             EventInstance {
                 start_ts: 374400000,
                 end_ts: 383400000,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         // Check that Date variant ovverides wday variant
@@ -421,7 +549,8 @@ mod test {
             EventInstance {
                 start_ts: 982800000,
                 end_ts: 995400000,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -429,7 +558,8 @@ mod test {
             EventInstance {
                 start_ts: 1584000000,
                 end_ts: 1593000000,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
         assert_eq!(
@@ -437,7 +567,8 @@ mod test {
             EventInstance {
                 start_ts: 2188800000,
                 end_ts: 2197800000,
-                busy: false
+                busy: false,
+                event_id: None,
             }
         );
     }
@@ -535,4 +666,221 @@ mod test {
         println!("Free: {:?}", free);
         // assert!(!free.is_empty());
     }
+
+    fn interval(start_hours: i64, end_hours: i64) -> ScheduleRuleInterval {
+        ScheduleRuleInterval {
+            start: Time {
+                hours: start_hours,
+                minutes: 0,
+            },
+            end: Time {
+                hours: end_hours,
+                minutes: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn rejects_overlapping_rules_for_same_weekday() {
+        let mut schedule = Schedule::new(Default::default(), Default::default(), &chrono_tz::UTC);
+        let default_rules = schedule.rules.clone();
+
+        let rules = vec![
+            ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Mon),
+                intervals: vec![interval(9, 12)],
+            },
+            ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Mon),
+                intervals: vec![interval(11, 15)],
+            },
+        ];
+        assert!(!schedule.set_rules(&rules));
+        assert_eq!(schedule.rules.len(), default_rules.len());
+
+        let rules = vec![ScheduleRule {
+            variant: ScheduleRuleVariant::WDay(Weekday::Mon),
+            intervals: vec![interval(9, 12), interval(8, 10)],
+        }];
+        assert!(!schedule.set_rules(&rules));
+    }
+
+    #[test]
+    fn accepts_adjacent_rules_for_same_weekday() {
+        let mut schedule = Schedule::new(Default::default(), Default::default(), &chrono_tz::UTC);
+
+        let rules = vec![
+            ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Mon),
+                intervals: vec![interval(9, 12)],
+            },
+            ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Mon),
+                intervals: vec![interval(12, 15)],
+            },
+            // Same hours on another weekday do not overlap
+            ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Tue),
+                intervals: vec![interval(10, 14)],
+            },
+        ];
+        assert!(schedule.set_rules(&rules));
+        assert_eq!(schedule.rules.len(), 3);
+    }
+
+    fn working_hours_schedule(timezone: Tz) -> Schedule {
+        Schedule {
+            id: Default::default(),
+            user_id: Default::default(),
+            account_id: Default::default(),
+            timezone,
+            created: Default::default(),
+            updated: Default::default(),
+            rules: vec![ScheduleRule {
+                variant: ScheduleRuleVariant::WDay(Weekday::Sun),
+                intervals: vec![ScheduleRuleInterval {
+                    start: Time {
+                        hours: 1,
+                        minutes: 30,
+                    },
+                    end: Time {
+                        hours: 17,
+                        minutes: 0,
+                    },
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn it_handles_spring_forward_gap() {
+        // The clocks in New York jump from 02:00 to 03:00 on 2021-03-14
+        let tz = chrono_tz::America::New_York;
+        let start = Time {
+            hours: 2,
+            minutes: 30,
+        };
+        let day = Day {
+            year: 2021,
+            month: 3,
+            day: 14,
+        };
+        assert_eq!(
+            start.timestamp_millis(&day, &tz),
+            tz.ymd(2021, 3, 14).and_hms(3, 30, 0).timestamp_millis()
+        );
+
+        let schedule = working_hours_schedule(tz);
+        let timespan = TimeSpan::new(
+            tz.ymd(2021, 3, 14).and_hms(0, 0, 0).timestamp_millis(),
+            tz.ymd(2021, 3, 14).and_hms(23, 0, 0).timestamp_millis(),
+        );
+        let free = schedule.freebusy(&timespan).inner();
+        assert_eq!(free.len(), 1);
+        assert_eq!(
+            free[0].start_ts,
+            tz.ymd(2021, 3, 14).and_hms(1, 30, 0).timestamp_millis()
+        );
+        assert_eq!(
+            free[0].end_ts,
+            tz.ymd(2021, 3, 14).and_hms(17, 0, 0).timestamp_millis()
+        );
+        // The local hour that does not exist is not counted as available
+        assert_eq!(
+            free[0].end_ts - free[0].start_ts,
+            1000 * 60 * 60 * 31 / 2 - 1000 * 60 * 60
+        );
+    }
+
+    #[test]
+    fn it_handles_fall_back_overlap() {
+        // The clocks in New York are set back from 02:00 to 01:00 on 2021-11-07,
+        // so 01:30 happens first at 05:30 UTC and then at 06:30 UTC
+        let tz = chrono_tz::America::New_York;
+        let schedule = working_hours_schedule(tz);
+        let timespan = TimeSpan::new(
+            tz.ymd(2021, 11, 7).and_hms(0, 0, 0).timestamp_millis(),
+            tz.ymd(2021, 11, 7).and_hms(23, 0, 0).timestamp_millis(),
+        );
+        let free = schedule.freebusy(&timespan).inner();
+        assert_eq!(free.len(), 1);
+        assert_eq!(
+            free[0].start_ts,
+            Utc.ymd(2021, 11, 7).and_hms(5, 30, 0).timestamp_millis()
+        );
+        assert_eq!(
+            free[0].end_ts,
+            Utc.ymd(2021, 11, 7).and_hms(22, 0, 0).timestamp_millis()
+        );
+        // The repeated local hour is counted as available
+        assert_eq!(
+            free[0].end_ts - free[0].start_ts,
+            1000 * 60 * 60 * 31 / 2 + 1000 * 60 * 60
+        );
+    }
+
+    fn friday_night_schedule() -> Schedule {
+        let mut schedule = Schedule::new(Default::default(), Default::default(), &chrono_tz::UTC);
+        let rules = vec![ScheduleRule {
+            variant: ScheduleRuleVariant::WDay(Weekday::Fri),
+            intervals: vec![interval(22, 2)],
+        }];
+        assert!(schedule.set_rules(&rules));
+        schedule
+    }
+
+    #[test]
+    fn splits_interval_wrapping_past_midnight_into_consecutive_days() {
+        let schedule = friday_night_schedule();
+        assert!(schedule.rules[0].intervals[0].wraps_midnight());
+
+        // 2021-01-08 is a Friday
+        let friday = Day {
+            year: 2021,
+            month: 1,
+            day: 8,
+        };
+        let friday_night = EventInstance {
+            busy: false,
+            start_ts: Utc.ymd(2021, 1, 8).and_hms(22, 0, 0).timestamp_millis(),
+            end_ts: Utc.ymd(2021, 1, 9).and_hms(0, 0, 0).timestamp_millis(),
+            event_id: None,
+        };
+        let saturday_morning = EventInstance {
+            busy: false,
+            start_ts: Utc.ymd(2021, 1, 9).and_hms(0, 0, 0).timestamp_millis(),
+            end_ts: Utc.ymd(2021, 1, 9).and_hms(2, 0, 0).timestamp_millis(),
+            event_id: None,
+        };
+        assert_eq!(
+            schedule.rules[0].intervals[0].to_events(&friday, &chrono_tz::UTC),
+            vec![friday_night.clone(), saturday_morning.clone()]
+        );
+
+        let timespan = TimeSpan::new(
+            Utc.ymd(2021, 1, 8).and_hms(0, 0, 0).timestamp_millis(),
+            Utc.ymd(2021, 1, 9).and_hms(12, 0, 0).timestamp_millis(),
+        );
+        assert_eq!(
+            schedule.freebusy(&timespan).inner(),
+            vec![friday_night, saturday_morning.clone()]
+        );
+
+        // The Saturday morning belongs to the Friday rule, even when only Saturday is queried
+        let timespan = TimeSpan::new(
+            Utc.ymd(2021, 1, 9).and_hms(0, 0, 0).timestamp_millis(),
+            Utc.ymd(2021, 1, 9).and_hms(12, 0, 0).timestamp_millis(),
+        );
+        assert_eq!(schedule.freebusy(&timespan).inner(), vec![saturday_morning]);
+    }
+
+    #[test]
+    fn rejects_intervals_overlapping_with_an_interval_wrapping_past_midnight() {
+        let mut schedule = friday_night_schedule();
+        let rules = vec![ScheduleRule {
+            variant: ScheduleRuleVariant::WDay(Weekday::Fri),
+            intervals: vec![interval(20, 23), interval(22, 2)],
+        }];
+        assert!(!schedule.set_rules(&rules));
+    }
 }
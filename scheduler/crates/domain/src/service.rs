@@ -1,8 +1,9 @@
 use crate::{
     shared::entity::{Entity, ID},
-    Meta, Metadata,
+    Meta, Metadata, Millis,
 };
-use serde::{Deserialize, Serialize};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize, Serializer};
 
 /// A type that describes a time plan and is either a `Calendar` or a `Schedule`
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -18,6 +19,7 @@ pub enum TimePlan {
 
 /// A bookable `User` registered on a `Service`
 #[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ServiceResource {
     pub id: ID,
     /// Id of the `User` registered on this `Service`
@@ -46,6 +48,19 @@ pub struct ServiceResource {
     /// time T then this `ServiceResource` will not have any availaible
     /// bookingslots after T + `furthest_booking_time`
     pub furthest_booking_time: Option<i64>,
+    /// Timezone used when displaying the availibility of this `ServiceResource`.
+    /// The availibility itself is always computed in the timezone of the
+    /// `Calendar` or `Schedule` used as its `TimePlan`, and bookingslots are
+    /// grouped by the timezone of the requester.
+    #[serde(serialize_with = "serialize_timezone")]
+    pub timezone: Option<Tz>,
+}
+
+fn serialize_timezone<S: Serializer>(
+    timezone: &Option<Tz>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    timezone.map(|tz| tz.name()).serialize(serializer)
 }
 
 impl ServiceResource {
@@ -58,6 +73,17 @@ impl ServiceResource {
             buffer: 0,
             closest_booking_time: 0,
             furthest_booking_time: None,
+            timezone: None,
+        }
+    }
+
+    pub fn set_timezone(&mut self, timezone: &str) -> bool {
+        match timezone.parse::<Tz>() {
+            Ok(tzid) => {
+                self.timezone = Some(tzid);
+                true
+            }
+            Err(_) => false,
         }
     }
 
@@ -132,6 +158,24 @@ impl ServiceResource {
     }
 }
 
+/// Describes how the `ServiceResource`s of a `Service` are combined
+/// when generating bookingslots
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum BookingStrategy {
+    /// A booking is assigned to one of the users, so a slot is available
+    /// when at least one of the users is free
+    RoundRobin,
+    /// A booking requires all the users, so a slot is only available
+    /// when every user is free
+    Collective,
+}
+
+impl Default for BookingStrategy {
+    fn default() -> Self {
+        Self::RoundRobin
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Service {
     pub id: ID,
@@ -139,6 +183,22 @@ pub struct Service {
     // interval: usize,
     // allow_more_booking_requests_in_queue_than_resources
     pub users: Vec<ServiceResource>,
+    pub booking_strategy: BookingStrategy,
+    /// `Schedule` inherited as the availibility of the `ServiceResource`s that
+    /// do not have a `TimePlan` of their own
+    pub default_schedule_id: Option<ID>,
+    /// Booking slots starting sooner than this from now are not offered,
+    /// regardless of the `closest_booking_time` of the users
+    pub min_lead_time: Millis,
+    /// The durations in milliseconds that bookingslots can be requested for.
+    /// When empty, any duration is allowed.
+    pub allowed_durations: Vec<i64>,
+    /// When set, booking slots where every `User` is already booked are still offered,
+    /// and are marked as overbooked
+    pub allow_overbooking: bool,
+    /// Timezone of the business offering this `Service`. Bookingslots queries without
+    /// a timezone are grouped by the days in this timezone.
+    pub timezone: Option<Tz>,
     pub metadata: Metadata,
 }
 
@@ -163,10 +223,48 @@ impl Service {
             id: Default::default(),
             account_id,
             users: Default::default(),
+            booking_strategy: Default::default(),
+            default_schedule_id: None,
+            min_lead_time: Millis(0),
+            allowed_durations: Default::default(),
+            allow_overbooking: false,
+            timezone: None,
             metadata: Default::default(),
         }
     }
 
+    pub fn set_timezone(&mut self, timezone: &str) -> bool {
+        match timezone.parse::<Tz>() {
+            Ok(tzid) => {
+                self.timezone = Some(tzid);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn set_min_lead_time(&mut self, min_lead_time: Millis) -> bool {
+        if min_lead_time < Millis(0) {
+            return false;
+        }
+        self.min_lead_time = min_lead_time;
+        true
+    }
+
+    pub fn set_allowed_durations(&mut self, mut allowed_durations: Vec<i64>) -> bool {
+        if allowed_durations.iter().any(|duration| *duration <= 0) {
+            return false;
+        }
+        allowed_durations.sort_unstable();
+        allowed_durations.dedup();
+        self.allowed_durations = allowed_durations;
+        true
+    }
+
+    pub fn allows_duration(&self, duration: i64) -> bool {
+        self.allowed_durations.is_empty() || self.allowed_durations.contains(&duration)
+    }
+
     pub fn add_user(&mut self, user: ServiceResource) {
         self.users.push(user);
     }
@@ -187,4 +285,27 @@ impl Service {
     pub fn find_user_mut(&mut self, user_id: &ID) -> Option<&mut ServiceResource> {
         self.users.iter_mut().find(|u| u.user_id == *user_id)
     }
+
+    /// The `TimePlan` describing when the given `ServiceResource` is bookable,
+    /// which falls back to the default `Schedule` of this `Service`
+    pub fn get_availibility(&self, user: &ServiceResource) -> TimePlan {
+        match (&user.availibility, &self.default_schedule_id) {
+            (TimePlan::Empty, Some(schedule_id)) => TimePlan::Schedule(schedule_id.clone()),
+            (availibility, _) => availibility.clone(),
+        }
+    }
+
+    pub fn contains_schedule(&self, schedule_id: &ID) -> bool {
+        self.default_schedule_id.as_ref() == Some(schedule_id)
+            || self.users.iter().any(|u| u.contains_schedule(schedule_id))
+    }
+
+    pub fn remove_schedule(&mut self, schedule_id: &ID) {
+        if self.default_schedule_id.as_ref() == Some(schedule_id) {
+            self.default_schedule_id = None;
+        }
+        for user in &mut self.users {
+            user.remove_schedule(schedule_id);
+        }
+    }
 }
@@ -1,6 +1,13 @@
 use mongodb::bson::oid::ObjectId;
+#[cfg(feature = "json-schema")]
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{de::Visitor, Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
 use thiserror::Error;
 
 pub trait Entity {
@@ -62,9 +69,32 @@ impl FromStr for ID {
     }
 }
 
+// Equality, hashing and ordering are all based on the raw bytes of the id, so that
+// ids which only differ in the casing of their hex representation are the same id.
+// The ordering matches the ordering of the lowercase hex strings returned by `as_string`.
 impl PartialEq for ID {
     fn eq(&self, other: &Self) -> bool {
-        self.as_string() == other.as_string()
+        self.0.bytes() == other.0.bytes()
+    }
+}
+
+impl Eq for ID {}
+
+impl Hash for ID {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.bytes().hash(state);
+    }
+}
+
+impl PartialOrd for ID {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ID {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.bytes().cmp(&other.0.bytes())
     }
 }
 
@@ -104,3 +134,66 @@ impl<'de> Deserialize<'de> for ID {
         deserializer.deserialize_str(IDVisitor)
     }
 }
+
+/// Serialized as the hex string of the id
+#[cfg(feature = "json-schema")]
+impl JsonSchema for ID {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "ID".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{hash_map::DefaultHasher, HashMap};
+
+    fn hash(id: &ID) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn ids_differing_only_in_case_are_equal() {
+        let lower = "5fb3d5a3e1ac8a2a2c9e3a1f".parse::<ID>().unwrap();
+        let upper = "5FB3D5A3E1AC8A2A2C9E3A1F".parse::<ID>().unwrap();
+        assert_eq!(lower, upper);
+        assert_eq!(hash(&lower), hash(&upper));
+        assert_eq!(lower.cmp(&upper), Ordering::Equal);
+        // The representation is normalized to lowercase
+        assert_eq!(upper.as_string(), "5fb3d5a3e1ac8a2a2c9e3a1f");
+
+        let mut map = HashMap::new();
+        map.insert(lower, 1);
+        map.insert(upper.clone(), 2);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&upper), Some(&2));
+    }
+
+    #[test]
+    fn ids_are_ordered_like_their_string_representation() {
+        let a = "0fb3d5a3e1ac8a2a2c9e3a1f".parse::<ID>().unwrap();
+        let b = "AFB3D5A3E1AC8A2A2C9E3A1F".parse::<ID>().unwrap();
+        let c = "bfb3d5a3e1ac8a2a2c9e3a1f".parse::<ID>().unwrap();
+        let mut ids = vec![c.clone(), a.clone(), b.clone()];
+        ids.sort();
+        assert_eq!(ids, vec![a, b, c]);
+        assert_ne!(hash(&ids[0]), hash(&ids[1]));
+    }
+
+    #[test]
+    fn rejects_malformed_ids() {
+        assert!("".parse::<ID>().is_err());
+        assert!("5fb3d5a3e1ac8a2a2c9e3a1".parse::<ID>().is_err());
+        assert!("5fb3d5a3e1ac8a2a2c9e3a1g".parse::<ID>().is_err());
+    }
+}
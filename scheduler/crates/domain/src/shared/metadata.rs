@@ -4,6 +4,23 @@ use crate::{Entity, ID};
 
 pub type Metadata = HashMap<String, String>;
 
+/// A partial update of `Metadata`. Keys with a value are upserted and
+/// keys set to `None` are removed, all other keys are left untouched.
+pub type MetadataPatch = HashMap<String, Option<String>>;
+
+pub fn patch_metadata(metadata: &mut Metadata, patch: &MetadataPatch) {
+    for (key, value) in patch {
+        match value {
+            Some(value) => {
+                metadata.insert(key.clone(), value.clone());
+            }
+            None => {
+                metadata.remove(key);
+            }
+        }
+    }
+}
+
 pub trait Meta: Entity {
     fn metadata(&self) -> &Metadata;
     /// Retrives the account_id associated with this entity, which
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+/// A length of time in milliseconds, like the duration of a booking slot or
+/// a lead time. It is serialized as the plain number of milliseconds.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Millis(pub i64);
+
+impl Millis {
+    pub const fn from_seconds(seconds: i64) -> Self {
+        Self(seconds * 1000)
+    }
+
+    pub const fn from_minutes(minutes: i64) -> Self {
+        Self::from_seconds(minutes * 60)
+    }
+
+    pub const fn from_hours(hours: i64) -> Self {
+        Self::from_minutes(hours * 60)
+    }
+}
+
+impl From<i64> for Millis {
+    fn from(millis: i64) -> Self {
+        Self(millis)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn constructors_give_millis() {
+        assert_eq!(Millis::from_seconds(2), Millis(2000));
+        assert_eq!(Millis::from_minutes(15), Millis(1000 * 60 * 15));
+        assert_eq!(Millis::from_hours(2), Millis(1000 * 60 * 60 * 2));
+        assert_eq!(Millis::from_hours(1), Millis::from_minutes(60));
+        assert_eq!(Millis::from(500), Millis(500));
+    }
+
+    #[test]
+    fn serializes_as_plain_number() {
+        let millis = Millis::from_minutes(30);
+        assert_eq!(serde_json::to_string(&millis).unwrap(), "1800000");
+        assert_eq!(serde_json::from_str::<Millis>("1800000").unwrap(), millis);
+        assert!(serde_json::from_str::<Millis>("{ \"0\": 1800000 }").is_err());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod entity;
 pub mod metadata;
+pub mod millis;
 pub mod recurrence;
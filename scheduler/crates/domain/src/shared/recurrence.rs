@@ -1,11 +1,14 @@
 use crate::CalendarSettings;
 use chrono::prelude::*;
 use rrule::{Frequenzy, ParsedOptions};
+#[cfg(feature = "json-schema")]
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{de::Visitor, Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 use thiserror::Error;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum RRuleFrequenzy {
     Yearly,
@@ -14,7 +17,8 @@ pub enum RRuleFrequenzy {
     Daily,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct RRuleOptions {
     pub freq: RRuleFrequenzy,
@@ -42,6 +46,53 @@ fn is_none_or_empty<T>(v: &Option<Vec<T>>) -> bool {
     !matches!(v, Some(v) if !v.is_empty())
 }
 
+/// Format of `UNTIL` in iCalendar rules, which is always written in UTC
+const RRULE_UNTIL_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Error, Debug)]
+pub enum RRuleParseError {
+    #[error("The rule does not specify FREQ")]
+    MissingFreq,
+    #[error("Malformed rule part: {0}")]
+    Malformed(String),
+    #[error("Unsupported rule part: {0}")]
+    Unsupported(String),
+    #[error("Invalid value for {0}: {1}")]
+    InvalidValue(String, String),
+    #[error(transparent)]
+    InvalidWeekDay(#[from] InvalidWeekDayError),
+}
+
+fn parse_rrule_value<T: FromStr>(key: &str, value: &str) -> Result<T, RRuleParseError> {
+    value
+        .parse::<T>()
+        .map_err(|_| RRuleParseError::InvalidValue(key.into(), value.into()))
+}
+
+fn parse_rrule_list<T: FromStr>(key: &str, value: &str) -> Result<Vec<T>, RRuleParseError> {
+    value
+        .split(',')
+        .map(|item| parse_rrule_value(key, item))
+        .collect()
+}
+
+fn parse_rrule_until(value: &str) -> Result<i64, RRuleParseError> {
+    // Dates without a time and times without a timezone are both interpreted as UTC
+    let datetime = NaiveDateTime::parse_from_str(value, RRULE_UNTIL_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S"))
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d").map(|date| date.and_hms(0, 0, 0)))
+        .map_err(|_| RRuleParseError::InvalidValue("UNTIL".into(), value.into()))?;
+    Ok(datetime.timestamp_millis())
+}
+
+fn join_rrule_list<T: Display>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 impl RRuleOptions {
     pub fn is_valid(&self, start_ts: i64) -> bool {
         if let Some(count) = self.count {
@@ -70,6 +121,32 @@ impl RRuleOptions {
             }
         }
 
+        if let Some(bymonthday) = &self.bymonthday {
+            // Negative days count from the end of the month, so -1 is the last day
+            if self.freq == RRuleFrequenzy::Weekly
+                || bymonthday.iter().any(|day| *day == 0 || day.abs() > 31)
+            {
+                return false;
+            }
+        }
+
+        if let Some(byweekday) = &self.byweekday {
+            // Like "the second Tuesday" or "the last Friday", which only makes sense
+            // within a month or a year
+            let max_nth = match self.freq {
+                RRuleFrequenzy::Monthly => 5,
+                RRuleFrequenzy::Yearly => 53,
+                _ => 0,
+            };
+            if byweekday
+                .iter()
+                .filter_map(|wday| wday.nth())
+                .any(|n| n.abs() > max_nth)
+            {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -135,6 +212,109 @@ impl RRuleOptions {
     }
 }
 
+impl RRuleOptions {
+    /// Parses an iCalendar recurrence rule like `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE`,
+    /// with or without the `RRULE:` prefix
+    pub fn from_rrule_string(rule: &str) -> Result<Self, RRuleParseError> {
+        let rule = rule.trim();
+        let rule = match rule.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("RRULE:") => &rule[6..],
+            _ => rule,
+        };
+
+        let mut freq = None;
+        let mut options = Self::default();
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let mut key_value = part.splitn(2, '=');
+            let (key, value) = match (key_value.next(), key_value.next()) {
+                (Some(key), Some(value)) => (key.to_uppercase(), value),
+                _ => return Err(RRuleParseError::Malformed(part.into())),
+            };
+            match key.as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_uppercase().as_str() {
+                        "YEARLY" => RRuleFrequenzy::Yearly,
+                        "MONTHLY" => RRuleFrequenzy::Monthly,
+                        "WEEKLY" => RRuleFrequenzy::Weekly,
+                        "DAILY" => RRuleFrequenzy::Daily,
+                        _ => return Err(RRuleParseError::InvalidValue(key, value.into())),
+                    })
+                }
+                "INTERVAL" => options.interval = parse_rrule_value(&key, value)?,
+                "COUNT" => options.count = Some(parse_rrule_value(&key, value)?),
+                "UNTIL" => options.until = Some(parse_rrule_until(value)? as isize),
+                "BYDAY" => {
+                    options.byweekday = Some(
+                        value
+                            .split(',')
+                            .map(|day| day.parse::<WeekDay>())
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                "BYMONTH" => options.bymonth = Some(parse_rrule_list(&key, value)?),
+                "BYWEEKNO" => options.byweekno = Some(parse_rrule_list(&key, value)?),
+                "BYYEARDAY" => options.byyearday = Some(parse_rrule_list(&key, value)?),
+                "BYMONTHDAY" => options.bymonthday = Some(parse_rrule_list(&key, value)?),
+                "BYSETPOS" => options.bysetpos = Some(parse_rrule_list(&key, value)?),
+                // The week start is given by the `CalendarSettings` instead
+                "WKST" => (),
+                _ => return Err(RRuleParseError::Unsupported(part.into())),
+            }
+        }
+
+        match freq {
+            Some(freq) => Ok(Self { freq, ..options }),
+            None => Err(RRuleParseError::MissingFreq),
+        }
+    }
+
+    /// Writes the options as an iCalendar recurrence rule without the `RRULE:` prefix.
+    /// `UNTIL` is written in UTC with a precision of seconds, and is an error when it
+    /// is outside of the dates that can be represented.
+    pub fn to_rrule_string(&self) -> Result<String, RRuleParseError> {
+        let freq = match self.freq {
+            RRuleFrequenzy::Yearly => "YEARLY",
+            RRuleFrequenzy::Monthly => "MONTHLY",
+            RRuleFrequenzy::Weekly => "WEEKLY",
+            RRuleFrequenzy::Daily => "DAILY",
+        };
+        let mut parts = vec![format!("FREQ={}", freq)];
+        if self.interval != 1 {
+            parts.push(format!("INTERVAL={}", self.interval));
+        }
+        if let Some(count) = self.count {
+            parts.push(format!("COUNT={}", count));
+        }
+        if let Some(until) = self.until {
+            let until = Utc
+                .timestamp_millis_opt(until as i64)
+                .single()
+                .ok_or_else(|| RRuleParseError::InvalidValue("UNTIL".into(), until.to_string()))?;
+            parts.push(format!("UNTIL={}", until.format(RRULE_UNTIL_FORMAT)));
+        }
+        let lists = [
+            ("BYMONTH", self.bymonth.as_deref().map(join_rrule_list)),
+            ("BYWEEKNO", self.byweekno.as_deref().map(join_rrule_list)),
+            ("BYYEARDAY", self.byyearday.as_deref().map(join_rrule_list)),
+            (
+                "BYMONTHDAY",
+                self.bymonthday.as_deref().map(join_rrule_list),
+            ),
+            ("BYDAY", self.byweekday.as_deref().map(join_rrule_list)),
+            ("BYSETPOS", self.bysetpos.as_deref().map(join_rrule_list)),
+        ];
+        for (key, value) in lists.iter() {
+            if let Some(value) = value {
+                if !value.is_empty() {
+                    parts.push(format!("{}={}", key, value));
+                }
+            }
+        }
+
+        Ok(parts.join(";"))
+    }
+}
+
 impl Default for RRuleOptions {
     fn default() -> Self {
         Self {
@@ -300,6 +480,22 @@ impl<'de> Deserialize<'de> for WeekDay {
     }
 }
 
+/// Serialized as a string like `MO` or `-1FR`
+#[cfg(feature = "json-schema")]
+impl JsonSchema for WeekDay {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "WeekDay".into()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -354,6 +550,96 @@ mod test {
         assert!("+0mo".parse::<WeekDay>().is_err());
     }
 
+    #[test]
+    fn rrule_strings_round_trip() {
+        let rules = [
+            "FREQ=DAILY",
+            "FREQ=DAILY;COUNT=10",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE",
+            "FREQ=MONTHLY;UNTIL=20211231T230000Z;BYDAY=-1FR",
+            "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "FREQ=YEARLY;INTERVAL=3;COUNT=5;BYMONTH=1,7;BYMONTHDAY=1,-1",
+        ];
+        for rule in &rules {
+            let options = RRuleOptions::from_rrule_string(rule).unwrap();
+            assert_eq!(&options.to_rrule_string().unwrap(), rule);
+            assert_eq!(
+                RRuleOptions::from_rrule_string(&options.to_rrule_string().unwrap()).unwrap(),
+                options
+            );
+        }
+    }
+
+    #[test]
+    fn parses_rrule_string() {
+        let options =
+            RRuleOptions::from_rrule_string("RRULE:freq=weekly;byday=MO,2WE;interval=2;wkst=SU")
+                .unwrap();
+        assert_eq!(options.freq, RRuleFrequenzy::Weekly);
+        assert_eq!(options.interval, 2);
+        assert_eq!(
+            options.byweekday,
+            Some(vec![
+                WeekDay::new(0).unwrap(),
+                WeekDay::new_nth(2, 2).unwrap()
+            ])
+        );
+        assert_eq!(
+            options.to_rrule_string().unwrap(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,2WE"
+        );
+
+        let date_only = RRuleOptions::from_rrule_string("FREQ=DAILY;UNTIL=20210102").unwrap();
+        let with_time =
+            RRuleOptions::from_rrule_string("FREQ=DAILY;UNTIL=20210102T000000Z").unwrap();
+        assert_eq!(date_only.until, Some(1609545600000));
+        assert_eq!(date_only, with_time);
+    }
+
+    #[test]
+    fn rejects_invalid_rrule_string() {
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("INTERVAL=2"),
+            Err(RRuleParseError::MissingFreq)
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=HOURLY"),
+            Err(RRuleParseError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=DAILY;COUNT=many"),
+            Err(RRuleParseError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=DAILY;UNTIL=tomorrow"),
+            Err(RRuleParseError::InvalidValue(_, _))
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=DAILY;BYDAY=XY"),
+            Err(RRuleParseError::InvalidWeekDay(_))
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=DAILY;BYHOUR=10"),
+            Err(RRuleParseError::Unsupported(_))
+        ));
+        assert!(matches!(
+            RRuleOptions::from_rrule_string("FREQ=DAILY;COUNT"),
+            Err(RRuleParseError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_out_of_range_until_when_writing_rrule_string() {
+        let options = RRuleOptions {
+            until: Some(isize::MAX),
+            ..Default::default()
+        };
+        assert!(matches!(
+            options.to_rrule_string(),
+            Err(RRuleParseError::InvalidValue(_, _))
+        ));
+    }
+
     #[test]
     fn serializes_weekday() {
         assert_eq!(WeekDay::new(0).unwrap().to_string(), "MO");
@@ -0,0 +1,293 @@
+use nettu_scheduler_domain::{booking_slots::UserFreeEvents, Account, ID};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Identifies the computed free times of the `User`s in a `Service` on a given date
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceBookingSlotsCacheKey {
+    pub service_id: ID,
+    pub date: String,
+    /// The IANA timezone or the UTC offset the `date` is given in
+    pub timezone: String,
+    /// Sorted so that the order of the excluded `User`s in the query does not matter
+    pub exclude_user_ids: Vec<ID>,
+    /// The only `User`s that were considered, sorted like `exclude_user_ids`
+    pub user_ids: Option<Vec<ID>>,
+}
+
+impl ServiceBookingSlotsCacheKey {
+    pub fn new(
+        service_id: &ID,
+        date: &str,
+        timezone: &str,
+        exclude_user_ids: &[ID],
+        user_ids: Option<&[ID]>,
+    ) -> Self {
+        let sorted_ids = |ids: &[ID]| {
+            let mut ids = ids.to_vec();
+            ids.sort();
+            ids
+        };
+        Self {
+            service_id: service_id.clone(),
+            date: date.into(),
+            timezone: timezone.into(),
+            exclude_user_ids: sorted_ids(exclude_user_ids),
+            user_ids: user_ids.map(sorted_ids),
+        }
+    }
+}
+
+struct CacheEntry {
+    cached_at: i64,
+    /// The `User`s in the `Service` when the free times were computed. A change to
+    /// any of their `CalendarEvent`s, `Calendar`s or `Schedule`s invalidates the entry.
+    user_ids: Vec<ID>,
+    users_free_events: Vec<UserFreeEvents>,
+}
+
+/// Upper bound for how many invalidated `Service`s and `User`s are remembered
+const MAX_REMEMBERED_INVALIDATIONS: usize = 10_000;
+
+#[derive(Default)]
+struct ServiceBookingSlotsCacheState {
+    entries: HashMap<ServiceBookingSlotsCacheKey, CacheEntry>,
+    /// Number of invalidations so far, which is the current generation of the cache
+    generation: u64,
+    /// The generation in which each `Service` and `User` was last invalidated, so that
+    /// free times computed while one of them changed are not cached
+    invalidated_in: HashMap<ID, u64>,
+    /// Invalidations up to this generation are no longer remembered, so free times
+    /// computed before it are treated as invalidated
+    forgotten_until: u64,
+}
+
+impl ServiceBookingSlotsCacheState {
+    fn invalidate(&mut self, id: &ID) {
+        self.generation += 1;
+        self.invalidated_in.insert(id.clone(), self.generation);
+        if self.invalidated_in.len() > MAX_REMEMBERED_INVALIDATIONS {
+            self.forget_older_invalidations();
+        }
+    }
+
+    /// Forgets the older half of the invalidations, so that the ids of every
+    /// `Service` and `User` that ever changed do not pile up
+    fn forget_older_invalidations(&mut self) {
+        let mut generations = self.invalidated_in.values().copied().collect::<Vec<_>>();
+        generations.sort_unstable();
+        let cutoff = generations[generations.len() / 2];
+        self.invalidated_in
+            .retain(|_, generation| *generation > cutoff);
+        self.forgotten_until = cutoff;
+    }
+
+    fn invalidated_since(&self, id: &ID, generation: u64) -> bool {
+        if generation < self.forgotten_until {
+            return true;
+        }
+        match self.invalidated_in.get(id) {
+            Some(invalidated_in) => *invalidated_in > generation,
+            None => false,
+        }
+    }
+}
+
+/// Short lived cache for the free times of the `User`s in a `Service`, which the booking
+/// slots are generated from. Computing them requires expanding every `CalendarEvent`
+/// and `Schedule` of every `User` in the `Service`, which is expensive for services
+/// that are queried often. The booking windows of the `User`s and the lead time of
+/// the `Service` depend on the current time, so they are applied after the cache.
+pub struct ServiceBookingSlotsCache {
+    /// Time to live in millis for a cached entry
+    ttl: i64,
+    state: Mutex<ServiceBookingSlotsCacheState>,
+}
+
+impl ServiceBookingSlotsCache {
+    pub fn new(ttl: i64) -> Self {
+        Self {
+            ttl,
+            state: Mutex::new(Default::default()),
+        }
+    }
+
+    /// Returns the cached free times if they were computed less than `ttl` millis ago
+    pub fn get(&self, key: &ServiceBookingSlotsCacheKey, now: i64) -> Option<Vec<UserFreeEvents>> {
+        let mut state = self.state.lock().unwrap();
+        match state.entries.get(key) {
+            Some(entry) if now - entry.cached_at < self.ttl => {
+                Some(entry.users_free_events.clone())
+            }
+            Some(_) => {
+                state.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// The current generation of the cache, which has to be read before loading
+    /// anything that the free times later given to `insert` are computed from
+    pub fn generation(&self) -> u64 {
+        self.state.lock().unwrap().generation
+    }
+
+    /// Caches the free times unless the `Service` or any of the `User`s was
+    /// invalidated since `generation` was read, as they might then be outdated
+    pub fn insert(
+        &self,
+        key: ServiceBookingSlotsCacheKey,
+        user_ids: Vec<ID>,
+        users_free_events: Vec<UserFreeEvents>,
+        now: i64,
+        generation: u64,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let invalidated = std::iter::once(&key.service_id)
+            .chain(&user_ids)
+            .any(|id| state.invalidated_since(id, generation));
+        if invalidated {
+            return;
+        }
+        // Expired entries are only removed on lookup, so clean them up here
+        // to avoid keys that are never queried again from piling up
+        let ttl = self.ttl;
+        state.entries.retain(|_, entry| now - entry.cached_at < ttl);
+        state.entries.insert(
+            key,
+            CacheEntry {
+                cached_at: now,
+                user_ids,
+                users_free_events,
+            },
+        );
+    }
+
+    /// Removes all the cached free times of the given `Service`
+    pub fn invalidate_service(&self, service_id: &ID) {
+        let mut state = self.state.lock().unwrap();
+        state.invalidate(service_id);
+        state.entries.retain(|key, _| key.service_id != *service_id);
+    }
+
+    /// Removes all the cached free times of the `Service`s that the given `User` is part of
+    pub fn invalidate_user(&self, user_id: &ID) {
+        let mut state = self.state.lock().unwrap();
+        state.invalidate(user_id);
+        state
+            .entries
+            .retain(|_, entry| !entry.user_ids.contains(user_id));
+    }
+}
+
+/// How the `Account` of a request was looked up
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AccountCacheKey {
+    ApiKey(String),
+    AccountId(ID),
+}
+
+struct CachedAccount {
+    cached_at: i64,
+    account: Account,
+}
+
+/// Short lived cache for the `Account`s that requests are authenticated against,
+/// so that a burst of requests from the same client does not query the
+/// `Account` for every single request
+pub struct AccountCache {
+    /// Time to live in millis for a cached entry
+    ttl: i64,
+    entries: Mutex<HashMap<AccountCacheKey, CachedAccount>>,
+}
+
+impl AccountCache {
+    pub fn new(ttl: i64) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached `Account` if it was looked up less than `ttl` millis ago
+    pub fn get(&self, key: &AccountCacheKey, now: i64) -> Option<Account> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if now - entry.cached_at < self.ttl => Some(entry.account.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: AccountCacheKey, account: Account, now: i64) {
+        let mut entries = self.entries.lock().unwrap();
+        // Expired entries are only removed on lookup, so clean them up here
+        // to avoid keys that are never queried again from piling up
+        let ttl = self.ttl;
+        entries.retain(|_, entry| now - entry.cached_at < ttl);
+        entries.insert(
+            key,
+            CachedAccount {
+                cached_at: now,
+                account,
+            },
+        );
+    }
+
+    /// Removes the given `Account` from the cache, regardless of how it was looked up
+    pub fn invalidate_account(&self, account_id: &ID) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.account.id != *account_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_free_times_computed_while_a_user_was_invalidated() {
+        let cache = ServiceBookingSlotsCache::new(1000);
+        let service_id = ID::default();
+        let user_id = ID::default();
+        let key = ServiceBookingSlotsCacheKey::new(&service_id, "2010-1-1", "UTC", &[], None);
+
+        let generation = cache.generation();
+        cache.invalidate_user(&user_id);
+        cache.insert(key.clone(), vec![user_id.clone()], vec![], 0, generation);
+        assert!(cache.get(&key, 0).is_none());
+
+        // Invalidating an unrelated `User` does not prevent caching
+        let generation = cache.generation();
+        cache.invalidate_user(&ID::default());
+        cache.insert(key.clone(), vec![user_id.clone()], vec![], 0, generation);
+        assert!(cache.get(&key, 0).is_some());
+        assert!(cache.get(&key, 1000).is_none());
+    }
+
+    #[test]
+    fn forgets_old_invalidations() {
+        let cache = ServiceBookingSlotsCache::new(1000);
+        let key = ServiceBookingSlotsCacheKey::new(&ID::default(), "2010-1-1", "UTC", &[], None);
+
+        let old_generation = cache.generation();
+        for _ in 0..MAX_REMEMBERED_INVALIDATIONS + 1 {
+            cache.invalidate_user(&ID::default());
+        }
+        assert!(cache.state.lock().unwrap().invalidated_in.len() <= MAX_REMEMBERED_INVALIDATIONS);
+
+        // Free times computed before the forgotten invalidations are not cached
+        cache.insert(key.clone(), vec![], vec![], 0, old_generation);
+        assert!(cache.get(&key, 0).is_none());
+
+        let generation = cache.generation();
+        cache.insert(key.clone(), vec![], vec![], 0, generation);
+        assert!(cache.get(&key, 0).is_some());
+    }
+}
@@ -1,3 +1,4 @@
+use nettu_scheduler_domain::{booking_slots::DEFAULT_MAX_BOOKING_SLOTS, Millis};
 use nettu_scheduler_utils::create_random_secret;
 use tracing::{info, warn};
 
@@ -17,6 +18,29 @@ pub struct Config {
     /// timespan of several years which will take a lot of time to compute
     /// and is also not very useful information to query about anyways.
     pub booking_slots_query_duration_limit: i64,
+    /// Time to live in millis for cached `Service` booking slots. Cached slots
+    /// are invalidated when the `CalendarEvent`s, `Calendar`s or `Schedule`s
+    /// of the `Service` users change, so this only bounds how long slots can
+    /// stay stale relative to the current time.
+    pub booking_slots_cache_ttl: i64,
+    /// Time to live in millis for cached `Account`s that requests are authenticated
+    /// against. Changes to an `Account` made through the api invalidate it right away.
+    pub account_cache_ttl: i64,
+    /// Maximum number of `BookingSlot`s computed for a single `User` in a query.
+    /// Slots past this bound are dropped, which protects against queries
+    /// generating a huge amount of slots.
+    pub max_booking_slots: usize,
+    /// Length of the window that due `Reminder`s are batched in.
+    /// All `Reminder`s due within the same window are sent in a single
+    /// webhook call per `Account`, which reduces webhook volume for receivers
+    /// that can tolerate some slack. Defaults to one minute.
+    pub reminders_batch_window: Millis,
+    /// How many times a webhook delivery that failed is retried before it is
+    /// given up and dead-lettered
+    pub webhook_max_retries: usize,
+    /// Delay in millis before the first retry of a failed webhook delivery.
+    /// The delay is doubled for every following retry.
+    pub webhook_retry_backoff_ms: u64,
 }
 
 impl Config {
@@ -45,11 +69,56 @@ impl Config {
                 default_port.parse::<usize>().unwrap()
             }
         };
+        let default_batch_window = Millis::from_minutes(1).0;
+        let batch_window = std::env::var("REMINDERS_BATCH_WINDOW_MS")
+            .unwrap_or_else(|_| default_batch_window.to_string());
+        let reminders_batch_window_ms = match batch_window.parse::<i64>() {
+            Ok(window) if window >= default_batch_window => window,
+            _ => {
+                warn!(
+                    "The given REMINDERS_BATCH_WINDOW_MS: {} is not valid, it should be at least {} millis. Falling back to {}.",
+                    batch_window, default_batch_window, default_batch_window
+                );
+                default_batch_window
+            }
+        };
+        let default_max_retries = 3;
+        let max_retries = std::env::var("WEBHOOK_MAX_RETRIES")
+            .unwrap_or_else(|_| default_max_retries.to_string());
+        let webhook_max_retries = match max_retries.parse::<usize>() {
+            Ok(retries) => retries,
+            Err(_) => {
+                warn!(
+                    "The given WEBHOOK_MAX_RETRIES: {} is not valid, falling back to {}.",
+                    max_retries, default_max_retries
+                );
+                default_max_retries
+            }
+        };
+        let default_retry_backoff = 1000;
+        let retry_backoff = std::env::var("WEBHOOK_RETRY_BACKOFF_MS")
+            .unwrap_or_else(|_| default_retry_backoff.to_string());
+        let webhook_retry_backoff_ms = match retry_backoff.parse::<u64>() {
+            Ok(backoff) => backoff,
+            Err(_) => {
+                warn!(
+                    "The given WEBHOOK_RETRY_BACKOFF_MS: {} is not valid, falling back to {}.",
+                    retry_backoff, default_retry_backoff
+                );
+                default_retry_backoff
+            }
+        };
         Self {
             create_account_secret_code,
             port,
             event_instances_query_duration_limit: 1000 * 60 * 60 * 24 * 62, // 62 days
             booking_slots_query_duration_limit: 1000 * 60 * 60 * 24 * 7,    // 7 days
+            booking_slots_cache_ttl: 1000 * 30,                             // 30 seconds
+            account_cache_ttl: 1000 * 10,                                   // 10 seconds
+            max_booking_slots: DEFAULT_MAX_BOOKING_SLOTS,
+            reminders_batch_window: Millis(reminders_batch_window_ms),
+            webhook_max_retries,
+            webhook_retry_backoff_ms,
         }
     }
 }
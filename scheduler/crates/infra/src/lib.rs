@@ -1,11 +1,23 @@
+mod cache;
 mod config;
 mod repos;
+#[cfg(any(test, feature = "test-util"))]
+mod seed;
+mod shutdown;
 mod system;
 
+pub use cache::{
+    AccountCache, AccountCacheKey, ServiceBookingSlotsCache, ServiceBookingSlotsCacheKey,
+};
 pub use config::Config;
 pub use mongodb::bson::oid::ObjectId;
 use repos::Repos;
-pub use repos::{KVMetadata, MetadataFindQuery};
+pub use repos::{
+    DeleteResult, IServiceRepo, KVMetadata, MetadataFindAfterQuery, MetadataFindQuery, StoreBackend,
+};
+#[cfg(any(test, feature = "test-util"))]
+pub use seed::{ContextSeeder, SeededContext};
+pub use shutdown::{Shutdown, WorkGuard};
 use std::sync::Arc;
 pub use system::ISys;
 use system::RealSys;
@@ -16,6 +28,9 @@ pub struct NettuContext {
     pub repos: Repos,
     pub config: Config,
     pub sys: Arc<dyn ISys>,
+    pub booking_slots_cache: Arc<ServiceBookingSlotsCache>,
+    pub account_cache: Arc<AccountCache>,
+    pub shutdown: Arc<Shutdown>,
 }
 
 struct ContextParams {
@@ -24,11 +39,17 @@ struct ContextParams {
 }
 
 impl NettuContext {
-    fn create_inmemory() -> Self {
+    pub fn create_inmemory() -> Self {
+        let config = Config::new();
         Self {
             repos: Repos::create_inmemory(),
-            config: Config::new(),
+            booking_slots_cache: Arc::new(ServiceBookingSlotsCache::new(
+                config.booking_slots_cache_ttl,
+            )),
+            account_cache: Arc::new(AccountCache::new(config.account_cache_ttl)),
+            config,
             sys: Arc::new(RealSys {}),
+            shutdown: Arc::new(Shutdown::new()),
         }
     }
 
@@ -36,10 +57,16 @@ impl NettuContext {
         let repos = Repos::create_mongodb(&params.mongodb.0, &params.mongodb.1)
             .await
             .expect("Mongo db creds must be set and valid");
+        let config = Config::new();
         Self {
             repos,
-            config: Config::new(),
+            booking_slots_cache: Arc::new(ServiceBookingSlotsCache::new(
+                config.booking_slots_cache_ttl,
+            )),
+            account_cache: Arc::new(AccountCache::new(config.account_cache_ttl)),
+            config,
             sys: Arc::new(RealSys {}),
+            shutdown: Arc::new(Shutdown::new()),
         }
     }
 }
@@ -17,8 +17,7 @@ impl InMemoryAccountRepo {
 #[async_trait::async_trait]
 impl IAccountRepo for InMemoryAccountRepo {
     async fn insert(&self, account: &Account) -> anyhow::Result<()> {
-        insert(account, &self.accounts);
-        Ok(())
+        insert(account, &self.accounts)
     }
 
     async fn save(&self, account: &Account) -> anyhow::Result<()> {
@@ -30,8 +29,12 @@ impl IAccountRepo for InMemoryAccountRepo {
         find(account_id, &self.accounts)
     }
 
-    async fn delete(&self, account_id: &ID) -> Option<Account> {
-        delete(account_id, &self.accounts)
+    async fn count(&self) -> anyhow::Result<usize> {
+        Ok(count_by(&self.accounts, |_| true))
+    }
+
+    async fn delete(&self, account_id: &ID) -> anyhow::Result<Option<Account>> {
+        Ok(delete(account_id, &self.accounts))
     }
 
     async fn find_many(&self, account_ids: &[ID]) -> anyhow::Result<Vec<Account>> {
@@ -12,8 +12,9 @@ pub trait IAccountRepo: Send + Sync {
     async fn insert(&self, account: &Account) -> anyhow::Result<()>;
     async fn save(&self, account: &Account) -> anyhow::Result<()>;
     async fn find(&self, account_id: &ID) -> Option<Account>;
+    async fn count(&self) -> anyhow::Result<usize>;
     async fn find_many(&self, account_ids: &[ID]) -> anyhow::Result<Vec<Account>>;
-    async fn delete(&self, account_id: &ID) -> Option<Account>;
+    async fn delete(&self, account_id: &ID) -> anyhow::Result<Option<Account>>;
     async fn find_by_apikey(&self, api_key: &str) -> Option<Account>;
     async fn find_by_webhook_url(&self, url: &str) -> Option<Account>;
 }
@@ -56,10 +57,14 @@ mod tests {
             assert!(res.eq(&account));
 
             // Delete
-            let res = ctx.repos.account_repo.delete(&account.id).await;
+            let res = ctx.repos.account_repo.delete(&account.id).await.unwrap();
             assert!(res.is_some());
             assert!(res.unwrap().eq(&account));
 
+            // Deleting it again is not an error, but nothing is deleted
+            let res = ctx.repos.account_repo.delete(&account.id).await;
+            assert!(matches!(res, Ok(None)));
+
             // Find
             assert!(ctx.repos.account_repo.find(&account.id).await.is_none());
         }
@@ -78,18 +83,15 @@ mod tests {
 
             let pubkey = PEMKey::new(pubkey).unwrap();
             account.set_public_jwt_key(Some(pubkey));
+            assert!(account.settings.set_max_calendars_per_user(3));
 
             // Save
             assert!(ctx.repos.account_repo.save(&account).await.is_ok());
 
             // Find
-            assert!(ctx
-                .repos
-                .account_repo
-                .find(&account.id)
-                .await
-                .unwrap()
-                .eq(&account));
+            let res = ctx.repos.account_repo.find(&account.id).await.unwrap();
+            assert!(res.eq(&account));
+            assert_eq!(res.settings.max_calendars_per_user, 3);
         }
     }
 }
@@ -5,8 +5,11 @@ use mongodb::{
     bson::{doc, oid::ObjectId, Document},
     Collection, Database,
 };
-use nettu_scheduler_domain::{Account, AccountSettings, AccountWebhookSettings, PEMKey, ID};
+use nettu_scheduler_domain::{
+    Account, AccountSettings, AccountWebhookSettings, PEMKey, DEFAULT_MAX_CALENDARS_PER_USER, ID,
+};
 use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 
 pub struct MongoAccountRepo {
     collection: Collection,
@@ -35,6 +38,10 @@ impl IAccountRepo for MongoAccountRepo {
         mongo_repo::find::<_, AccountMongo>(&self.collection, &oid).await
     }
 
+    async fn count(&self) -> anyhow::Result<usize> {
+        mongo_repo::count_by(&self.collection, doc! {}).await
+    }
+
     async fn find_many(&self, accounts_ids: &[ID]) -> anyhow::Result<Vec<Account>> {
         let filter = doc! {
             "_id": {
@@ -69,7 +76,7 @@ impl IAccountRepo for MongoAccountRepo {
         mongo_repo::find_one_by::<_, AccountMongo>(&self.collection, filter).await
     }
 
-    async fn delete(&self, account_id: &ID) -> Option<Account> {
+    async fn delete(&self, account_id: &ID) -> anyhow::Result<Option<Account>> {
         let oid = account_id.inner_ref();
         mongo_repo::delete::<_, AccountMongo>(&self.collection, &oid).await
     }
@@ -93,6 +100,15 @@ struct AccountAttributeMongo {
 #[derive(Debug, Serialize, Deserialize)]
 struct AccountSettingsMongo {
     pub webhook: Option<AccountWebhookSettingsMongo>,
+    /// Stored as `i64` because bson has no unsigned integer type
+    #[serde(default = "default_max_calendars_per_user")]
+    pub max_calendars_per_user: i64,
+    #[serde(default)]
+    pub default_timezone: Option<String>,
+}
+
+fn default_max_calendars_per_user() -> i64 {
+    DEFAULT_MAX_CALENDARS_PER_USER as i64
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -103,7 +119,15 @@ struct AccountWebhookSettingsMongo {
 
 impl<'de> MongoDocument<Account> for AccountMongo {
     fn to_domain(self) -> Account {
-        let mut settings = AccountSettings { webhook: None };
+        let mut settings = AccountSettings {
+            webhook: None,
+            max_calendars_per_user: usize::try_from(self.settings.max_calendars_per_user)
+                .unwrap_or(DEFAULT_MAX_CALENDARS_PER_USER),
+            default_timezone: self
+                .settings
+                .default_timezone
+                .and_then(|tz| tz.parse().ok()),
+        };
         if let Some(webhook_settings) = self.settings.webhook.as_ref() {
             settings.webhook = Some(AccountWebhookSettings {
                 url: webhook_settings.url.to_owned(),
@@ -120,7 +144,12 @@ impl<'de> MongoDocument<Account> for AccountMongo {
     }
 
     fn from_domain(account: &Account) -> Self {
-        let mut settings = AccountSettingsMongo { webhook: None };
+        let mut settings = AccountSettingsMongo {
+            webhook: None,
+            max_calendars_per_user: i64::try_from(account.settings.max_calendars_per_user)
+                .unwrap_or(i64::MAX),
+            default_timezone: account.settings.default_timezone.map(|tz| tz.to_string()),
+        };
         let mut attributes = vec![AccountAttributeMongo {
             key: "secret_api_key".to_string(),
             value: account.secret_api_key.clone(),
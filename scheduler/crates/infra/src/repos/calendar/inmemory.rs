@@ -1,6 +1,8 @@
 use super::ICalendarRepo;
 use crate::repos::shared::{
-    inmemory_repo::*, query_structs::MetadataFindQuery, repo::DeleteResult,
+    inmemory_repo::*,
+    query_structs::{MetadataFindAfterQuery, MetadataFindQuery},
+    repo::DeleteResult,
 };
 use nettu_scheduler_domain::{Calendar, ID};
 
@@ -19,8 +21,7 @@ impl InMemoryCalendarRepo {
 #[async_trait::async_trait]
 impl ICalendarRepo for InMemoryCalendarRepo {
     async fn insert(&self, calendar: &Calendar) -> anyhow::Result<()> {
-        insert(calendar, &self.calendars);
-        Ok(())
+        insert(calendar, &self.calendars)
     }
 
     async fn save(&self, calendar: &Calendar) -> anyhow::Result<()> {
@@ -32,12 +33,28 @@ impl ICalendarRepo for InMemoryCalendarRepo {
         find(calendar_id, &self.calendars)
     }
 
+    async fn find_for_account(&self, calendar_id: &ID, account_id: &ID) -> Option<Calendar> {
+        find_by(&self.calendars, |cal| {
+            cal.id == *calendar_id && cal.account_id == *account_id
+        })
+        .into_iter()
+        .next()
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        Ok(count_by(&self.calendars, |_| true))
+    }
+
     async fn find_by_user(&self, user_id: &ID) -> Vec<Calendar> {
         find_by(&self.calendars, |cal| cal.user_id == *user_id)
     }
 
-    async fn delete(&self, calendar_id: &ID) -> Option<Calendar> {
-        delete(calendar_id, &self.calendars)
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize> {
+        Ok(count_by(&self.calendars, |cal| cal.user_id == *user_id))
+    }
+
+    async fn delete(&self, calendar_id: &ID) -> anyhow::Result<Option<Calendar>> {
+        Ok(delete(calendar_id, &self.calendars))
     }
 
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult> {
@@ -45,7 +62,16 @@ impl ICalendarRepo for InMemoryCalendarRepo {
         Ok(res)
     }
 
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.calendars, |cal| cal.account_id == *account_id);
+        Ok(res)
+    }
+
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Calendar> {
         find_by_metadata(&self.calendars, query)
     }
+
+    async fn find_by_metadata_after(&self, query: MetadataFindAfterQuery) -> Vec<Calendar> {
+        find_by_metadata_after(&self.calendars, query)
+    }
 }
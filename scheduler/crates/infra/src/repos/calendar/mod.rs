@@ -6,22 +6,28 @@ pub use inmemory::InMemoryCalendarRepo;
 pub use mongo::MongoCalendarRepo;
 use nettu_scheduler_domain::{Calendar, ID};
 
-use super::shared::query_structs::MetadataFindQuery;
+use super::shared::query_structs::{MetadataFindAfterQuery, MetadataFindQuery};
 
 #[async_trait::async_trait]
 pub trait ICalendarRepo: Send + Sync {
     async fn insert(&self, calendar: &Calendar) -> anyhow::Result<()>;
     async fn save(&self, calendar: &Calendar) -> anyhow::Result<()>;
     async fn find(&self, calendar_id: &ID) -> Option<Calendar>;
+    /// Finds the `Calendar` with the given id only if it belongs to the `Account`
+    async fn find_for_account(&self, calendar_id: &ID, account_id: &ID) -> Option<Calendar>;
+    async fn count(&self) -> anyhow::Result<usize>;
     async fn find_by_user(&self, user_id: &ID) -> Vec<Calendar>;
-    async fn delete(&self, calendar_id: &ID) -> Option<Calendar>;
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize>;
+    async fn delete(&self, calendar_id: &ID) -> anyhow::Result<Option<Calendar>>;
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult>;
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult>;
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Calendar>;
+    async fn find_by_metadata_after(&self, query: MetadataFindAfterQuery) -> Vec<Calendar>;
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{setup_context, NettuContext};
+    use crate::{setup_context, KVMetadata, MetadataFindAfterQuery, NettuContext};
     use nettu_scheduler_domain::{Calendar, Entity, ID};
 
     /// Creates inmemory and mongo context when mongo is running,
@@ -45,12 +51,18 @@ mod tests {
             assert!(res.eq(&calendar));
             let res = ctx.repos.calendar_repo.find_by_user(&user_id).await;
             assert!(res[0].eq(&calendar));
+            let count = ctx.repos.calendar_repo.count_by_user(&user_id).await;
+            assert_eq!(count.unwrap(), 1);
 
             // Delete
-            let res = ctx.repos.calendar_repo.delete(&calendar.id).await;
+            let res = ctx.repos.calendar_repo.delete(&calendar.id).await.unwrap();
             assert!(res.is_some());
             assert!(res.unwrap().eq(&calendar));
 
+            // Deleting it again is not an error, but nothing is deleted
+            let res = ctx.repos.calendar_repo.delete(&calendar.id).await;
+            assert!(matches!(res, Ok(None)));
+
             // Find
             assert!(ctx.repos.calendar_repo.find(&calendar.id).await.is_none());
         }
@@ -82,6 +94,42 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn stores_created_and_updated() {
+        for ctx in create_contexts().await {
+            let mut calendar = Calendar::new(&ID::default(), &ID::default());
+            calendar.created = 10;
+            calendar.updated = 10;
+            ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+            calendar.updated = 20;
+            ctx.repos.calendar_repo.save(&calendar).await.unwrap();
+
+            let res = ctx.repos.calendar_repo.find(&calendar.id).await.unwrap();
+            assert_eq!(res.created, 10);
+            assert_eq!(res.updated, 20);
+        }
+    }
+
+    #[tokio::test]
+    async fn save_replaces_the_stored_calendar() {
+        for ctx in create_contexts().await {
+            let mut calendar = Calendar::new(&ID::default(), &ID::default());
+            calendar
+                .metadata
+                .insert("group".to_string(), "a".to_string());
+            ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+
+            calendar.metadata.clear();
+            calendar.settings.week_start += 1;
+            ctx.repos.calendar_repo.save(&calendar).await.unwrap();
+
+            let res = ctx.repos.calendar_repo.find(&calendar.id).await.unwrap();
+            assert!(res.metadata.is_empty());
+            assert_eq!(res.settings.week_start, calendar.settings.week_start);
+        }
+    }
+
     #[tokio::test]
     async fn delete_by_user() {
         for ctx in create_contexts().await {
@@ -101,4 +149,97 @@ mod tests {
             assert!(ctx.repos.calendar_repo.find(&calendar.id).await.is_none());
         }
     }
+
+    #[tokio::test]
+    async fn find_by_metadata_after_pages_stably() {
+        for ctx in create_contexts().await {
+            let user_id = ID::default();
+            let account_id = ID::default();
+            let new_calendar = |value: &str| {
+                let mut calendar = Calendar::new(&user_id, &account_id);
+                calendar
+                    .metadata
+                    .insert("group".to_string(), value.to_string());
+                calendar
+            };
+            let mut expected = vec![];
+            for _ in 0..5 {
+                let calendar = new_calendar("a");
+                ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+                expected.push(calendar.id.as_string());
+            }
+            let other_group = new_calendar("b");
+            ctx.repos.calendar_repo.insert(&other_group).await.unwrap();
+
+            let query = |after_id: Option<ID>| MetadataFindAfterQuery {
+                metadata: KVMetadata {
+                    key: "group".into(),
+                    value: "a".into(),
+                },
+                after_id,
+                limit: 2,
+                account_id: account_id.clone(),
+            };
+
+            let mut found = vec![];
+            let mut after_id = None;
+            loop {
+                let page = ctx
+                    .repos
+                    .calendar_repo
+                    .find_by_metadata_after(query(after_id.clone()))
+                    .await;
+                if page.is_empty() {
+                    break;
+                }
+                assert!(page.len() <= 2);
+                // Inserting in the middle of the iteration does not shift the pages
+                if found.is_empty() {
+                    let calendar = new_calendar("a");
+                    ctx.repos.calendar_repo.insert(&calendar).await.unwrap();
+                    expected.push(calendar.id.as_string());
+                }
+                after_id = page.last().map(|c| c.id.clone());
+                found.extend(page.into_iter().map(|c| c.id.as_string()));
+            }
+
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn mongo_errors_describe_the_operation_and_id() {
+        use super::{ICalendarRepo, MongoCalendarRepo};
+        use mongodb::{options::ClientOptions, Client};
+
+        // Nothing is listening on this port, so every operation fails
+        let options = ClientOptions::parse("mongodb://127.0.0.1:1/?serverSelectionTimeoutMS=100")
+            .await
+            .unwrap();
+        let db = Client::with_options(options)
+            .unwrap()
+            .database("nettuscheduler");
+        let repo = MongoCalendarRepo::new(&db);
+        let calendar = Calendar::new(&ID::default(), &ID::default());
+
+        let err = repo.insert(&calendar).await.unwrap_err().to_string();
+        assert!(err.contains("Failed to insert"));
+        assert!(err.contains(&calendar.id.to_string()));
+
+        let err = repo.save(&calendar).await.unwrap_err().to_string();
+        assert!(err.contains("Failed to save"));
+        assert!(err.contains(&calendar.id.to_string()));
+
+        let err = repo
+            .count_by_user(&calendar.user_id)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Failed to count"));
+        assert!(err.contains(&calendar.user_id.to_string()));
+
+        let err = repo.delete(&calendar.id).await.unwrap_err().to_string();
+        assert!(err.contains("Failed to delete"));
+        assert!(err.contains(&calendar.id.to_string()));
+    }
 }
@@ -1,5 +1,9 @@
 use super::ICalendarRepo;
-use crate::repos::shared::{mongo_repo, query_structs::MetadataFindQuery, repo::DeleteResult};
+use crate::repos::shared::{
+    mongo_repo,
+    query_structs::{MetadataFindAfterQuery, MetadataFindQuery},
+    repo::DeleteResult,
+};
 use crate::KVMetadata;
 use mongo_repo::MongoDocument;
 use mongodb::{
@@ -36,6 +40,18 @@ impl ICalendarRepo for MongoCalendarRepo {
         mongo_repo::find::<_, CalendarMongo>(&self.collection, &oid).await
     }
 
+    async fn find_for_account(&self, calendar_id: &ID, account_id: &ID) -> Option<Calendar> {
+        let filter = doc! {
+            "_id": calendar_id.inner_ref(),
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::find_one_by::<_, CalendarMongo>(&self.collection, filter).await
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        mongo_repo::count_by(&self.collection, doc! {}).await
+    }
+
     async fn find_by_user(&self, user_id: &ID) -> Vec<Calendar> {
         let filter = doc! {
             "user_id": user_id.inner_ref()
@@ -46,7 +62,14 @@ impl ICalendarRepo for MongoCalendarRepo {
         }
     }
 
-    async fn delete(&self, calendar_id: &ID) -> Option<Calendar> {
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize> {
+        let filter = doc! {
+            "user_id": user_id.inner_ref()
+        };
+        mongo_repo::count_by(&self.collection, filter).await
+    }
+
+    async fn delete(&self, calendar_id: &ID) -> anyhow::Result<Option<Calendar>> {
         let oid = calendar_id.inner_ref();
         mongo_repo::delete::<_, CalendarMongo>(&self.collection, &oid).await
     }
@@ -58,9 +81,20 @@ impl ICalendarRepo for MongoCalendarRepo {
         mongo_repo::delete_many_by::<_, CalendarMongo>(&self.collection, filter).await
     }
 
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::delete_many_by::<_, CalendarMongo>(&self.collection, filter).await
+    }
+
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Calendar> {
         mongo_repo::find_by_metadata::<_, CalendarMongo>(&self.collection, query).await
     }
+
+    async fn find_by_metadata_after(&self, query: MetadataFindAfterQuery) -> Vec<Calendar> {
+        mongo_repo::find_by_metadata_after::<_, CalendarMongo>(&self.collection, query).await
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -70,6 +104,11 @@ struct CalendarMongo {
     account_id: ObjectId,
     settings: CalendarSettingsMongo,
     metadata: Vec<KVMetadata>,
+    /// Missing on calendars stored before the timestamps were introduced
+    #[serde(default)]
+    created: i64,
+    #[serde(default)]
+    updated: i64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -89,6 +128,8 @@ impl MongoDocument<Calendar> for CalendarMongo {
                 timezone: self.settings.timezone.parse().unwrap(),
             },
             metadata: KVMetadata::to_metadata(self.metadata),
+            created: self.created,
+            updated: self.updated,
         }
     }
 
@@ -102,6 +143,8 @@ impl MongoDocument<Calendar> for CalendarMongo {
                 timezone: calendar.settings.timezone.to_string(),
             },
             metadata: KVMetadata::new(calendar.metadata.clone()),
+            created: calendar.created,
+            updated: calendar.updated,
         }
     }
 
@@ -18,7 +18,22 @@ impl InMemoryEventRepo {
 #[async_trait::async_trait]
 impl IEventRepo for InMemoryEventRepo {
     async fn insert(&self, e: &CalendarEvent) -> anyhow::Result<()> {
-        insert(e, &self.calendar_events);
+        insert(e, &self.calendar_events)
+    }
+
+    async fn insert_many(&self, events: &[CalendarEvent]) -> anyhow::Result<()> {
+        // Extended under a single lock, so that no reader sees a part of the events
+        let mut calendar_events = self.calendar_events.lock().unwrap();
+        for (i, event) in events.iter().enumerate() {
+            if calendar_events
+                .iter()
+                .chain(&events[..i])
+                .any(|e| e.id == event.id)
+            {
+                anyhow::bail!("An entity with id: {} already exists", event.id);
+            }
+        }
+        calendar_events.extend_from_slice(events);
         Ok(())
     }
 
@@ -27,10 +42,44 @@ impl IEventRepo for InMemoryEventRepo {
         Ok(())
     }
 
+    async fn save_if_version(
+        &self,
+        e: &CalendarEvent,
+        expected_version: i64,
+    ) -> anyhow::Result<bool> {
+        Ok(save_if(e, &self.calendar_events, |stored| {
+            stored.version == expected_version
+        }))
+    }
+
     async fn find(&self, event_id: &ID) -> Option<CalendarEvent> {
         find(event_id, &self.calendar_events)
     }
 
+    async fn find_for_account(&self, event_id: &ID, account_id: &ID) -> Option<CalendarEvent> {
+        find_by(&self.calendar_events, |e| {
+            e.id == *event_id && e.account_id == *account_id
+        })
+        .into_iter()
+        .next()
+    }
+
+    async fn find_by_external_id(
+        &self,
+        account_id: &ID,
+        external_id: &str,
+    ) -> Option<CalendarEvent> {
+        find_by(&self.calendar_events, |e| {
+            e.account_id == *account_id && e.external_id.as_deref() == Some(external_id)
+        })
+        .into_iter()
+        .next()
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        Ok(count_by(&self.calendar_events, |_| true))
+    }
+
     async fn find_by_calendar(
         &self,
         calendar_id: &ID,
@@ -50,13 +99,80 @@ impl IEventRepo for InMemoryEventRepo {
         Ok(res)
     }
 
+    async fn count_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<usize> {
+        Ok(count_by(&self.calendar_events, |event| {
+            event.calendar_id == *calendar_id
+        }))
+    }
+
+    async fn find_last_updated_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<Option<i64>> {
+        let events = find_by(&self.calendar_events, |event| {
+            event.calendar_id == *calendar_id
+        });
+        Ok(events.iter().map(|event| event.updated).max())
+    }
+
+    async fn find_by_service_after(
+        &self,
+        service_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<CalendarEvent>> {
+        let res = find_by(&self.calendar_events, |event| {
+            event.service_id.as_ref() == Some(service_id) && event.end_ts > after
+        });
+        Ok(res)
+    }
+
+    async fn find_with_reminders(
+        &self,
+        account_id: Option<&ID>,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CalendarEvent>> {
+        let mut events = find_by(&self.calendar_events, |event| {
+            event.reminder.is_some() && account_id.map_or(true, |id| event.account_id == *id)
+        });
+        events.sort_by(|e1, e2| e1.id.cmp(&e2.id));
+        Ok(events.into_iter().skip(skip).take(limit).collect())
+    }
+
     async fn find_many(&self, event_ids: &[ID]) -> anyhow::Result<Vec<CalendarEvent>> {
         let res = find_by(&self.calendar_events, |event| event_ids.contains(&event.id));
         Ok(res)
     }
 
-    async fn delete(&self, event_id: &ID) -> Option<CalendarEvent> {
-        delete(event_id, &self.calendar_events)
+    async fn add_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>> {
+        let res = update(event_id, &self.calendar_events, |event| {
+            if !event.exdates.contains(&exdate) {
+                event.exdates.push(exdate);
+            }
+            event.updated = updated;
+            event.version += 1;
+        });
+        Ok(res)
+    }
+
+    async fn remove_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>> {
+        let res = update(event_id, &self.calendar_events, |event| {
+            event.exdates.retain(|ts| *ts != exdate);
+            event.updated = updated;
+            event.version += 1;
+        });
+        Ok(res)
+    }
+
+    async fn delete(&self, event_id: &ID) -> anyhow::Result<Option<CalendarEvent>> {
+        Ok(delete(event_id, &self.calendar_events))
     }
 
     async fn delete_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<DeleteResult> {
@@ -69,6 +185,11 @@ impl IEventRepo for InMemoryEventRepo {
         Ok(res)
     }
 
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.calendar_events, |e| e.account_id == *account_id);
+        Ok(res)
+    }
+
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<CalendarEvent> {
         find_by_metadata(&self.calendar_events, query)
     }
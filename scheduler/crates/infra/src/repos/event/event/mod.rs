@@ -9,24 +9,80 @@ use nettu_scheduler_domain::{CalendarEvent, TimeSpan, ID};
 #[async_trait::async_trait]
 pub trait IEventRepo: Send + Sync {
     async fn insert(&self, e: &CalendarEvent) -> anyhow::Result<()>;
+    /// Inserts all the `CalendarEvent`s, or none of them when any of them can not be inserted
+    async fn insert_many(&self, events: &[CalendarEvent]) -> anyhow::Result<()>;
     async fn save(&self, e: &CalendarEvent) -> anyhow::Result<()>;
+    /// Saves the `CalendarEvent` only if the stored one still has the expected `version`,
+    /// so that concurrent changes are not overwritten. Returns whether it was saved.
+    async fn save_if_version(
+        &self,
+        e: &CalendarEvent,
+        expected_version: i64,
+    ) -> anyhow::Result<bool>;
     async fn find(&self, event_id: &ID) -> Option<CalendarEvent>;
+    /// Finds the `CalendarEvent` with the given id only if it belongs to the `Account`
+    async fn find_for_account(&self, event_id: &ID, account_id: &ID) -> Option<CalendarEvent>;
+    /// Finds the `CalendarEvent` in the `Account` with the given external id
+    async fn find_by_external_id(
+        &self,
+        account_id: &ID,
+        external_id: &str,
+    ) -> Option<CalendarEvent>;
+    async fn count(&self) -> anyhow::Result<usize>;
     async fn find_many(&self, event_ids: &[ID]) -> anyhow::Result<Vec<CalendarEvent>>;
     async fn find_by_calendar(
         &self,
         calendar_id: &ID,
         timespan: Option<&TimeSpan>,
     ) -> anyhow::Result<Vec<CalendarEvent>>;
-    async fn delete(&self, event_id: &ID) -> Option<CalendarEvent>;
+    async fn count_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<usize>;
+    /// Finds the most recent `updated` timestamp across the `CalendarEvent`s in
+    /// the `Calendar`, or `None` if there are no events in it
+    async fn find_last_updated_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<Option<i64>>;
+    /// Finds the bookings of the `Service`, i.e. the `CalendarEvent`s referencing it,
+    /// that end after the given timestamp
+    async fn find_by_service_after(
+        &self,
+        service_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<CalendarEvent>>;
+    /// Finds the `CalendarEvent`s that have reminder settings, only within the
+    /// given `Account` when it is set. They are ordered by id so that paging
+    /// through them with `skip` is stable.
+    async fn find_with_reminders(
+        &self,
+        account_id: Option<&ID>,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CalendarEvent>>;
+    /// Atomically adds the exdate to the `CalendarEvent` and increments its `version`, so
+    /// that concurrent changes to the exdates are not lost. Returns the updated
+    /// `CalendarEvent` if it exists.
+    async fn add_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>>;
+    /// Atomically removes the exdate from the `CalendarEvent` and increments its `version`.
+    /// Returns the updated `CalendarEvent` if it exists.
+    async fn remove_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>>;
+    async fn delete(&self, event_id: &ID) -> anyhow::Result<Option<CalendarEvent>>;
     async fn delete_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<DeleteResult>;
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult>;
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult>;
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<CalendarEvent>;
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{setup_context, NettuContext};
-    use nettu_scheduler_domain::{CalendarEvent, Entity, TimeSpan, ID};
+    use nettu_scheduler_domain::{CalendarEvent, Entity, EventStatus, TimeSpan, ID};
 
     /// Creates inmemory and mongo context when mongo is running,
     /// otherwise it will create two inmemory
@@ -38,18 +94,22 @@ mod tests {
         CalendarEvent {
             account_id: Default::default(),
             busy: Default::default(),
+            status: EventStatus::Free,
             calendar_id: Default::default(),
             created: Default::default(),
             duration: Default::default(),
             end_ts: Default::default(),
             exdates: Default::default(),
+            external_id: None,
             id: Default::default(),
             is_service: Default::default(),
+            service_id: None,
             metadata: Default::default(),
             recurrence: Default::default(),
             reminder: Default::default(),
             start_ts: Default::default(),
             updated: Default::default(),
+            version: Default::default(),
             user_id: Default::default(),
         }
     }
@@ -79,14 +139,153 @@ mod tests {
                 .event_repo
                 .delete(&event.id)
                 .await
-                .expect("To delete event by id");
+                .expect("To delete event by id")
+                .expect("Event to exist");
             assert!(delete_res.eq(&event));
 
+            // Deleting it again is not an error, but nothing is deleted
+            let delete_res = ctx.repos.event_repo.delete(&event.id).await;
+            assert!(matches!(delete_res, Ok(None)));
+
             // Find
             assert!(ctx.repos.event_repo.find(&event.id).await.is_none());
         }
     }
 
+    #[tokio::test]
+    async fn save_if_version() {
+        for ctx in create_contexts().await {
+            let mut event = generate_default_event();
+            ctx.repos.event_repo.insert(&event).await.unwrap();
+
+            event.duration = 100;
+            event.version = 1;
+            // Another version than the stored one is not saved
+            assert!(!ctx
+                .repos
+                .event_repo
+                .save_if_version(&event, 1)
+                .await
+                .unwrap());
+            assert!(ctx
+                .repos
+                .event_repo
+                .save_if_version(&event, 0)
+                .await
+                .unwrap());
+            let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+            assert_eq!(stored.duration, 100);
+            assert_eq!(stored.version, 1);
+
+            // Changing the exdates is also a new version
+            let stored = ctx
+                .repos
+                .event_repo
+                .add_exdate(&event.id, 10, 20)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored.version, 2);
+            event.duration = 200;
+            assert!(!ctx
+                .repos
+                .event_repo
+                .save_if_version(&event, 1)
+                .await
+                .unwrap());
+            let stored = ctx.repos.event_repo.find(&event.id).await.unwrap();
+            assert_eq!(stored.duration, 100);
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_many() {
+        for ctx in create_contexts().await {
+            let events = vec![generate_default_event(), generate_default_event()];
+
+            assert!(ctx.repos.event_repo.insert_many(&events).await.is_ok());
+            let ids = events.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+            let res = ctx.repos.event_repo.find_many(&ids).await.unwrap();
+            assert_eq!(res.len(), 2);
+
+            // Inserting nothing is not an error
+            assert!(ctx.repos.event_repo.insert_many(&[]).await.is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn inserting_existing_id_errors() {
+        for ctx in create_contexts().await {
+            let mut event = generate_default_event();
+            assert!(ctx.repos.event_repo.insert(&event).await.is_ok());
+
+            // Neither insert overwrites the existing event
+            let start_ts = event.start_ts;
+            event.start_ts += 1000;
+            assert!(ctx.repos.event_repo.insert(&event).await.is_err());
+            assert!(ctx
+                .repos
+                .event_repo
+                .insert_many(&[event.clone()])
+                .await
+                .is_err());
+            let found = ctx.repos.event_repo.find(&event.id).await.unwrap();
+            assert_eq!(found.start_ts, start_ts);
+
+            // None of the events in the batch are inserted
+            let new_event = generate_default_event();
+            assert!(ctx
+                .repos
+                .event_repo
+                .insert_many(&[new_event.clone(), event.clone()])
+                .await
+                .is_err());
+            assert!(ctx.repos.event_repo.find(&new_event.id).await.is_none());
+            assert!(ctx
+                .repos
+                .event_repo
+                .insert_many(&[new_event.clone(), new_event.clone()])
+                .await
+                .is_err());
+            assert!(ctx.repos.event_repo.find(&new_event.id).await.is_none());
+
+            // Only `save` replaces it
+            assert!(ctx.repos.event_repo.save(&event).await.is_ok());
+            let found = ctx.repos.event_repo.find(&event.id).await.unwrap();
+            assert_eq!(found.start_ts, event.start_ts);
+        }
+    }
+
+    #[tokio::test]
+    async fn find_by_external_id() {
+        for ctx in create_contexts().await {
+            let mut event = generate_default_event();
+            event.external_id = Some("external".into());
+            // Another account can use the same external id
+            let mut other_event = generate_default_event();
+            other_event.account_id = ID::default();
+            other_event.external_id = event.external_id.clone();
+            assert!(ctx.repos.event_repo.insert(&event).await.is_ok());
+            assert!(ctx.repos.event_repo.insert(&other_event).await.is_ok());
+
+            let found = ctx
+                .repos
+                .event_repo
+                .find_by_external_id(&event.account_id, "external")
+                .await
+                .expect("To find event by external id");
+            assert!(found.eq(&event));
+            assert_eq!(found.external_id, event.external_id);
+
+            assert!(ctx
+                .repos
+                .event_repo
+                .find_by_external_id(&event.account_id, "missing")
+                .await
+                .is_none());
+        }
+    }
+
     #[tokio::test]
     async fn update() {
         for ctx in create_contexts().await {
@@ -111,6 +310,51 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn add_and_remove_exdate() {
+        for ctx in create_contexts().await {
+            let event = generate_default_event();
+            assert!(ctx.repos.event_repo.insert(&event).await.is_ok());
+
+            let updated_event = ctx
+                .repos
+                .event_repo
+                .add_exdate(&event.id, 100, 1)
+                .await
+                .expect("To add exdate")
+                .expect("Event to exist");
+            assert_eq!(updated_event.exdates, vec![100]);
+            assert_eq!(updated_event.updated, 1);
+
+            // Adding the same exdate again does not duplicate it
+            let updated_event = ctx
+                .repos
+                .event_repo
+                .add_exdate(&event.id, 100, 2)
+                .await
+                .expect("To add exdate")
+                .expect("Event to exist");
+            assert_eq!(updated_event.exdates, vec![100]);
+
+            let updated_event = ctx
+                .repos
+                .event_repo
+                .remove_exdate(&event.id, 100, 3)
+                .await
+                .expect("To remove exdate")
+                .expect("Event to exist");
+            assert!(updated_event.exdates.is_empty());
+            assert_eq!(updated_event.updated, 3);
+
+            let res = ctx
+                .repos
+                .event_repo
+                .add_exdate(&ID::default(), 100, 4)
+                .await;
+            assert!(matches!(res, Ok(None)));
+        }
+    }
+
     #[tokio::test]
     async fn delete_by_user() {
         for ctx in create_contexts().await {
@@ -250,4 +494,103 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn find_by_calendar_only_returns_events_in_calendar() {
+        for ctx in create_contexts().await {
+            let calendar_id = ID::default();
+            let other_calendar_id = ID::default();
+
+            let event_1 = generate_event_with_time(&calendar_id, 100, 200, &ctx).await;
+            let event_2 = generate_event_with_time(&calendar_id, 300, 400, &ctx).await;
+            generate_event_with_time(&calendar_id, 500, 600, &ctx).await;
+            generate_event_with_time(&other_calendar_id, 100, 200, &ctx).await;
+            generate_event_with_time(&other_calendar_id, 300, 400, &ctx).await;
+
+            let events = ctx
+                .repos
+                .event_repo
+                .find_by_calendar(&calendar_id, Some(&TimeSpan::new(150, 350)))
+                .await
+                .expect("To get events");
+
+            assert_eq!(events.len(), 2);
+            assert!(events.iter().all(|e| e.calendar_id == calendar_id));
+            assert!(events.iter().any(|e| e.id() == event_1.id()));
+            assert!(events.iter().any(|e| e.id() == event_2.id()));
+        }
+    }
+
+    #[tokio::test]
+    async fn find_by_service_after() {
+        for ctx in create_contexts().await {
+            let service_id = ID::default();
+            let other_service_id = ID::default();
+            let mut events = vec![];
+            for (start_ts, service_id) in &[
+                (100, Some(&service_id)),
+                (300, Some(&service_id)),
+                (300, Some(&other_service_id)),
+                (300, None),
+            ] {
+                let mut event = generate_default_event();
+                event.start_ts = *start_ts;
+                event.end_ts = start_ts + 100;
+                event.is_service = true;
+                event.service_id = service_id.cloned();
+                ctx.repos.event_repo.insert(&event).await.unwrap();
+                events.push(event);
+            }
+
+            let bookings = ctx
+                .repos
+                .event_repo
+                .find_by_service_after(&service_id, 250)
+                .await
+                .expect("To get events");
+            assert_eq!(bookings.len(), 1);
+            assert_eq!(bookings[0].id, events[1].id);
+            assert_eq!(bookings[0].service_id, Some(service_id));
+
+            let bookings = ctx
+                .repos
+                .event_repo
+                .find_by_service_after(&ID::default(), 0)
+                .await
+                .expect("To get events");
+            assert!(bookings.is_empty());
+        }
+    }
+
+    #[tokio::test]
+    async fn count_and_last_updated_by_calendar() {
+        for ctx in create_contexts().await {
+            let calendar_id = ID::default();
+            let event_repo = &ctx.repos.event_repo;
+            assert_eq!(
+                event_repo
+                    .find_last_updated_by_calendar(&calendar_id)
+                    .await
+                    .unwrap(),
+                None
+            );
+
+            for updated in &[5, 15, 10] {
+                let mut event = generate_default_event();
+                event.calendar_id = calendar_id.clone();
+                event.updated = *updated;
+                event_repo.insert(&event).await.unwrap();
+            }
+            event_repo.insert(&generate_default_event()).await.unwrap();
+
+            assert_eq!(event_repo.count_by_calendar(&calendar_id).await.unwrap(), 3);
+            assert_eq!(
+                event_repo
+                    .find_last_updated_by_calendar(&calendar_id)
+                    .await
+                    .unwrap(),
+                Some(15)
+            );
+        }
+    }
 }
@@ -3,13 +3,17 @@ use crate::repos::shared::mongo_repo;
 use crate::repos::shared::query_structs::MetadataFindQuery;
 use crate::repos::shared::repo::DeleteResult;
 use crate::KVMetadata;
+use anyhow::Context;
 use mongo_repo::MongoDocument;
 use mongodb::{
     bson::doc,
-    bson::{oid::ObjectId, Document},
+    bson::{oid::ObjectId, Bson, Document},
+    options::FindOneOptions,
     Collection, Database,
 };
-use nettu_scheduler_domain::{CalendarEvent, CalendarEventReminder, RRuleOptions, TimeSpan, ID};
+use nettu_scheduler_domain::{
+    CalendarEvent, CalendarEventReminder, EventStatus, RRuleOptions, TimeSpan, ID,
+};
 use serde::{Deserialize, Serialize};
 
 pub struct MongoEventRepo {
@@ -30,15 +34,86 @@ impl IEventRepo for MongoEventRepo {
         mongo_repo::insert::<_, CalendarEventMongo>(&self.collection, e).await
     }
 
+    async fn insert_many(&self, events: &[CalendarEvent]) -> anyhow::Result<()> {
+        let ids = events
+            .iter()
+            .map(|e| e.id.inner_ref().clone())
+            .collect::<Vec<_>>();
+        let filter = doc! {
+            "_id": {
+                "$in": ids
+            }
+        };
+        // Checked up front, so that removing the events of a failed insert below
+        // never removes events that were already stored
+        if mongo_repo::count_by(&self.collection, filter.clone()).await? > 0 {
+            anyhow::bail!("An event with one of the ids already exists");
+        }
+
+        // The insert is ordered and stops at the first event that can not be inserted,
+        // so the events inserted before it are removed to not store only a part of them
+        if let Err(e) =
+            mongo_repo::bulk_insert::<_, CalendarEventMongo>(&self.collection, events).await
+        {
+            mongo_repo::delete_many_by::<_, CalendarEventMongo>(&self.collection, filter)
+                .await
+                .context("Failed to remove the events of a failed insert")?;
+            return Err(e);
+        }
+        Ok(())
+    }
+
     async fn save(&self, e: &CalendarEvent) -> anyhow::Result<()> {
         mongo_repo::save::<_, CalendarEventMongo>(&self.collection, e).await
     }
 
+    async fn save_if_version(
+        &self,
+        e: &CalendarEvent,
+        expected_version: i64,
+    ) -> anyhow::Result<bool> {
+        // Events stored before `version` was introduced do not have it, which is the same as 0
+        let condition = if expected_version == 0 {
+            doc! {
+                "version": { "$in": [0_i64, Bson::Null] }
+            }
+        } else {
+            doc! {
+                "version": expected_version
+            }
+        };
+        mongo_repo::save_if::<_, CalendarEventMongo>(&self.collection, e, condition).await
+    }
+
     async fn find(&self, event_id: &ID) -> Option<CalendarEvent> {
         let oid = event_id.inner_ref();
         mongo_repo::find::<_, CalendarEventMongo>(&self.collection, &oid).await
     }
 
+    async fn find_for_account(&self, event_id: &ID, account_id: &ID) -> Option<CalendarEvent> {
+        let filter = doc! {
+            "_id": event_id.inner_ref(),
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::find_one_by::<_, CalendarEventMongo>(&self.collection, filter).await
+    }
+
+    async fn find_by_external_id(
+        &self,
+        account_id: &ID,
+        external_id: &str,
+    ) -> Option<CalendarEvent> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref(),
+            "external_id": external_id,
+        };
+        mongo_repo::find_one_by::<_, CalendarEventMongo>(&self.collection, filter).await
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        mongo_repo::count_by(&self.collection, doc! {}).await
+    }
+
     async fn find_by_calendar(
         &self,
         calendar_id: &ID,
@@ -78,7 +153,121 @@ impl IEventRepo for MongoEventRepo {
         mongo_repo::find_many_by::<_, CalendarEventMongo>(&self.collection, filter).await
     }
 
-    async fn delete(&self, event_id: &ID) -> Option<CalendarEvent> {
+    async fn count_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<usize> {
+        let filter = doc! {
+            "calendar_id": calendar_id.inner_ref()
+        };
+        mongo_repo::count_by(&self.collection, filter).await
+    }
+
+    async fn find_last_updated_by_calendar(&self, calendar_id: &ID) -> anyhow::Result<Option<i64>> {
+        let filter = doc! {
+            "calendar_id": calendar_id.inner_ref()
+        };
+        let mut find_options = FindOneOptions::builder().build();
+        find_options.sort = Some(doc! { "updated": -1 });
+        find_options.projection = Some(doc! { "updated": 1 });
+
+        let doc = self
+            .collection
+            .find_one(filter, find_options)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to find the last updated event in calendar {}",
+                    calendar_id
+                )
+            })?;
+        match doc {
+            Some(doc) => Ok(Some(doc.get_i64("updated")?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_by_service_after(
+        &self,
+        service_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<CalendarEvent>> {
+        let filter = doc! {
+            "service_id": service_id.inner_ref(),
+            "end_ts": {
+                "$gt": after
+            }
+        };
+
+        mongo_repo::find_many_by::<_, CalendarEventMongo>(&self.collection, filter).await
+    }
+
+    async fn find_with_reminders(
+        &self,
+        account_id: Option<&ID>,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<CalendarEvent>> {
+        let mut filter = doc! {
+            "reminder": {
+                "$ne": null
+            }
+        };
+        if let Some(account_id) = account_id {
+            filter.insert("account_id", account_id.inner_ref());
+        }
+
+        mongo_repo::find_many_by_paginated::<_, CalendarEventMongo>(
+            &self.collection,
+            filter,
+            skip,
+            limit,
+        )
+        .await
+    }
+
+    async fn add_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>> {
+        let oid = event_id.inner_ref();
+        let update = doc! {
+            "$addToSet": {
+                "exdates": exdate
+            },
+            "$set": {
+                "updated": updated
+            },
+            "$inc": {
+                "version": 1_i64
+            }
+        };
+        mongo_repo::find_one_and_update::<_, CalendarEventMongo>(&self.collection, &oid, update)
+            .await
+    }
+
+    async fn remove_exdate(
+        &self,
+        event_id: &ID,
+        exdate: i64,
+        updated: i64,
+    ) -> anyhow::Result<Option<CalendarEvent>> {
+        let oid = event_id.inner_ref();
+        let update = doc! {
+            "$pull": {
+                "exdates": exdate
+            },
+            "$set": {
+                "updated": updated
+            },
+            "$inc": {
+                "version": 1_i64
+            }
+        };
+        mongo_repo::find_one_and_update::<_, CalendarEventMongo>(&self.collection, &oid, update)
+            .await
+    }
+
+    async fn delete(&self, event_id: &ID) -> anyhow::Result<Option<CalendarEvent>> {
         let oid = event_id.inner_ref();
         mongo_repo::delete::<_, CalendarEventMongo>(&self.collection, &oid).await
     }
@@ -87,13 +276,7 @@ impl IEventRepo for MongoEventRepo {
         let filter = doc! {
             "calendar_id": calendar_id.inner_ref()
         };
-        self.collection
-            .delete_many(filter, None)
-            .await
-            .map(|res| DeleteResult {
-                deleted_count: res.deleted_count,
-            })
-            .map_err(anyhow::Error::new)
+        mongo_repo::delete_many_by::<_, CalendarEventMongo>(&self.collection, filter).await
     }
 
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult> {
@@ -103,6 +286,13 @@ impl IEventRepo for MongoEventRepo {
         mongo_repo::delete_many_by::<_, CalendarEventMongo>(&self.collection, filter).await
     }
 
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::delete_many_by::<_, CalendarEventMongo>(&self.collection, filter).await
+    }
+
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<CalendarEvent> {
         mongo_repo::find_by_metadata::<_, CalendarEventMongo>(&self.collection, query).await
     }
@@ -116,7 +306,13 @@ struct CalendarEventMongo {
     end_ts: i64,
     pub created: i64,
     pub updated: i64,
+    /// Missing on events stored before `version` was introduced
+    #[serde(default)]
+    version: i64,
     busy: bool,
+    /// Missing on events stored before `status` was introduced
+    #[serde(default)]
+    status: Option<EventStatus>,
     user_id: ObjectId,
     exdates: Vec<i64>,
     calendar_id: ObjectId,
@@ -124,7 +320,11 @@ struct CalendarEventMongo {
     recurrence: Option<RRuleOptions>,
     reminder: Option<CalendarEventReminder>,
     is_service: bool,
+    #[serde(default)]
+    service_id: Option<ObjectId>,
     metadata: Vec<KVMetadata>,
+    #[serde(default)]
+    external_id: Option<String>,
 }
 
 impl MongoDocument<CalendarEvent> for CalendarEventMongo {
@@ -135,8 +335,12 @@ impl MongoDocument<CalendarEvent> for CalendarEventMongo {
             duration: self.duration,
             end_ts: self.end_ts,
             busy: self.busy,
+            status: self
+                .status
+                .unwrap_or_else(|| EventStatus::from_busy(self.busy)),
             created: self.created,
             updated: self.updated,
+            version: self.version,
             user_id: ID::from(self.user_id),
             account_id: ID::from(self.account_id),
             calendar_id: ID::from(self.calendar_id),
@@ -144,7 +348,9 @@ impl MongoDocument<CalendarEvent> for CalendarEventMongo {
             recurrence: self.recurrence,
             reminder: self.reminder,
             is_service: self.is_service,
+            service_id: self.service_id.map(ID::from),
             metadata: KVMetadata::to_metadata(self.metadata),
+            external_id: self.external_id,
         }
     }
 
@@ -155,8 +361,10 @@ impl MongoDocument<CalendarEvent> for CalendarEventMongo {
             duration: event.duration,
             end_ts: event.end_ts,
             busy: event.busy,
+            status: Some(event.status),
             created: event.created,
             updated: event.updated,
+            version: event.version,
             user_id: event.user_id.inner_ref().clone(),
             account_id: event.account_id.inner_ref().clone(),
             calendar_id: event.calendar_id.inner_ref().clone(),
@@ -164,7 +372,9 @@ impl MongoDocument<CalendarEvent> for CalendarEventMongo {
             recurrence: event.recurrence.clone(),
             reminder: event.reminder.clone(),
             is_service: event.is_service,
+            service_id: event.service_id.as_ref().map(|id| id.inner_ref().clone()),
             metadata: KVMetadata::new(event.metadata.clone()),
+            external_id: event.external_id.clone(),
         }
     }
 
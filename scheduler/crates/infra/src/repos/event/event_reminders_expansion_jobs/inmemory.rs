@@ -19,7 +19,7 @@ impl InMemoryEventRemindersExpansionJobsRepo {
 impl IEventRemindersExpansionJobsRepo for InMemoryEventRemindersExpansionJobsRepo {
     async fn bulk_insert(&self, jobs: &[EventRemindersExpansionJob]) -> anyhow::Result<()> {
         for job in jobs {
-            insert(job, &self.jobs);
+            insert(job, &self.jobs)?;
         }
         Ok(())
     }
@@ -62,13 +62,8 @@ impl IEventRemindersExpansionJobsRepo for MongoEventRemindersExpansionsJobRepo {
         let filter = doc! {
             "event_id": event_id.inner_ref()
         };
-        self.collection
-            .delete_many(filter, None)
+        mongo_repo::delete_many_by::<_, EventRemindersExpansionJobMongo>(&self.collection, filter)
             .await
-            .map(|res| DeleteResult {
-                deleted_count: res.deleted_count,
-            })
-            .map_err(anyhow::Error::new)
     }
 }
 
@@ -19,14 +19,14 @@ impl InMemoryReminderRepo {
 impl IReminderRepo for InMemoryReminderRepo {
     async fn bulk_insert(&self, reminders: &[Reminder]) -> anyhow::Result<()> {
         for reminder in reminders {
-            insert(reminder, &self.reminders);
+            insert(reminder, &self.reminders)?;
         }
         Ok(())
     }
 
     async fn find_by_event_and_priority(&self, event_id: &ID, priority: i64) -> Option<Reminder> {
         let reminders = find_by(&self.reminders, |reminder| {
-            reminder.event_id == *event_id && reminder.priority == priority
+            reminder.event_id == *event_id && reminder.priority == priority && reminder.is_pending()
         });
         if reminders.is_empty() {
             return None;
@@ -35,7 +35,48 @@ impl IReminderRepo for InMemoryReminderRepo {
     }
 
     async fn delete_all_before(&self, before: i64) -> Vec<Reminder> {
-        find_and_delete_by(&self.reminders, |reminder| reminder.remind_at <= before)
+        find_and_delete_by(&self.reminders, |reminder| {
+            reminder.remind_at <= before && reminder.is_pending()
+        })
+    }
+
+    async fn find_by_account_before(
+        &self,
+        account_id: &ID,
+        before_inc: i64,
+    ) -> anyhow::Result<Vec<Reminder>> {
+        let res = find_by(&self.reminders, |reminder| {
+            reminder.account_id == *account_id
+                && reminder.remind_at <= before_inc
+                && reminder.is_pending()
+        });
+        Ok(res)
+    }
+
+    async fn find_dispatched_by_account_after(
+        &self,
+        account_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<Reminder>> {
+        let res = find_by(&self.reminders, |reminder| {
+            reminder.account_id == *account_id
+                && reminder.dispatched_at().map(|ts| ts > after) == Some(true)
+        });
+        Ok(res)
+    }
+
+    async fn find_sent_by_events(&self, event_ids: &[ID]) -> anyhow::Result<Vec<Reminder>> {
+        let res = find_by(&self.reminders, |reminder| {
+            reminder.sent_at.is_some() && event_ids.contains(&reminder.event_id)
+        });
+        Ok(res)
+    }
+
+    async fn delete_dispatched_before(&self, before_inc: i64) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.reminders, |reminder| {
+            reminder.dispatched_at().map(|ts| ts <= before_inc) == Some(true)
+        });
+        Ok(res)
     }
 
     async fn delete_by_events(&self, event_ids: &[ID]) -> anyhow::Result<DeleteResult> {
@@ -44,4 +85,11 @@ impl IReminderRepo for InMemoryReminderRepo {
         });
         Ok(res)
     }
+
+    async fn delete_pending(&self, account_id: Option<&ID>) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.reminders, |reminder| {
+            reminder.is_pending() && account_id.map_or(true, |id| reminder.account_id == *id)
+        });
+        Ok(res)
+    }
 }
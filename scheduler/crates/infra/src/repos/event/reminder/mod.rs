@@ -10,7 +10,50 @@ use crate::repos::shared::repo::DeleteResult;
 #[async_trait::async_trait]
 pub trait IReminderRepo: Send + Sync {
     async fn bulk_insert(&self, reminders: &[Reminder]) -> anyhow::Result<()>;
+    /// Finds a pending `Reminder` of the `CalendarEvent` with the given priority
     async fn find_by_event_and_priority(&self, event_id: &ID, priority: i64) -> Option<Reminder>;
+    /// Deletes and returns the pending `Reminder`s that are due at or before the given timestamp
     async fn delete_all_before(&self, before: i64) -> Vec<Reminder>;
+    /// Finds the pending `Reminder`s of the `Account` that are due at or before the given timestamp
+    /// without removing them
+    async fn find_by_account_before(
+        &self,
+        account_id: &ID,
+        before_inc: i64,
+    ) -> anyhow::Result<Vec<Reminder>>;
+    /// Finds the `Reminder`s of the `Account` that were sent, or failed to be sent,
+    /// after the given timestamp
+    async fn find_dispatched_by_account_after(
+        &self,
+        account_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<Reminder>>;
+    /// Finds the `Reminder`s of the `CalendarEvent`s that have been sent
+    async fn find_sent_by_events(&self, event_ids: &[ID]) -> anyhow::Result<Vec<Reminder>>;
+    /// Deletes the `Reminder`s that were sent, or failed to be sent, at or before the given timestamp
+    async fn delete_dispatched_before(&self, before_inc: i64) -> anyhow::Result<DeleteResult>;
     async fn delete_by_events(&self, event_ids: &[ID]) -> anyhow::Result<DeleteResult>;
+    /// Deletes the `Reminder`s that have not been sent yet, only within the
+    /// given `Account` when it is set
+    async fn delete_pending(&self, account_id: Option<&ID>) -> anyhow::Result<DeleteResult>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IReminderRepo, MongoReminderRepo};
+    use mongodb::{options::ClientOptions, Client};
+
+    #[tokio::test]
+    async fn bulk_inserting_no_reminders_is_not_an_error() {
+        // Nothing is listening on this port, so the driver must not be called
+        let options = ClientOptions::parse("mongodb://127.0.0.1:1/?serverSelectionTimeoutMS=100")
+            .await
+            .unwrap();
+        let db = Client::with_options(options)
+            .unwrap()
+            .database("nettuscheduler");
+        let repo = MongoReminderRepo::new(&db);
+
+        assert!(repo.bulk_insert(&[]).await.is_ok());
+    }
 }
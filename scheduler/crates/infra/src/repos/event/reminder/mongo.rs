@@ -33,6 +33,8 @@ impl IReminderRepo for MongoReminderRepo {
         let filter = doc! {
             "event_id": event_id.inner_ref(),
             "priority": priority,
+            "sent_at": null,
+            "failed_at": null,
         };
 
         mongo_repo::find_one_by::<_, ReminderMongo>(&self.collection, filter.clone()).await
@@ -42,7 +44,9 @@ impl IReminderRepo for MongoReminderRepo {
         let filter = doc! {
             "remind_at": {
                 "$lte": before_inc
-            }
+            },
+            "sent_at": null,
+            "failed_at": null,
         };
 
         // Find before deleting
@@ -65,19 +69,79 @@ impl IReminderRepo for MongoReminderRepo {
         docs
     }
 
+    async fn find_by_account_before(
+        &self,
+        account_id: &ID,
+        before_inc: i64,
+    ) -> anyhow::Result<Vec<Reminder>> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref(),
+            "remind_at": {
+                "$lte": before_inc
+            },
+            "sent_at": null,
+            "failed_at": null,
+        };
+
+        mongo_repo::find_many_by::<_, ReminderMongo>(&self.collection, filter).await
+    }
+
+    async fn find_dispatched_by_account_after(
+        &self,
+        account_id: &ID,
+        after: i64,
+    ) -> anyhow::Result<Vec<Reminder>> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref(),
+            "$or": [
+                { "sent_at": { "$gt": after } },
+                { "failed_at": { "$gt": after } },
+            ]
+        };
+
+        mongo_repo::find_many_by::<_, ReminderMongo>(&self.collection, filter).await
+    }
+
+    async fn find_sent_by_events(&self, event_ids: &[ID]) -> anyhow::Result<Vec<Reminder>> {
+        let filter = doc! {
+            "event_id": {
+                "$in": event_ids.iter().map(|e| e.inner_ref()).collect::<Vec<_>>()
+            },
+            "sent_at": {
+                "$ne": null
+            }
+        };
+        mongo_repo::find_many_by::<_, ReminderMongo>(&self.collection, filter).await
+    }
+
+    async fn delete_dispatched_before(&self, before_inc: i64) -> anyhow::Result<DeleteResult> {
+        let filter = doc! {
+            "$or": [
+                { "sent_at": { "$lte": before_inc } },
+                { "failed_at": { "$lte": before_inc } },
+            ]
+        };
+        mongo_repo::delete_many_by::<_, ReminderMongo>(&self.collection, filter).await
+    }
+
     async fn delete_by_events(&self, event_ids: &[ID]) -> anyhow::Result<DeleteResult> {
         let filter = doc! {
             "event_id": {
                 "$in": event_ids.iter().map(|e| e.inner_ref()).collect::<Vec<_>>()
             }
         };
-        self.collection
-            .delete_many(filter, None)
-            .await
-            .map(|res| DeleteResult {
-                deleted_count: res.deleted_count,
-            })
-            .map_err(anyhow::Error::new)
+        mongo_repo::delete_many_by::<_, ReminderMongo>(&self.collection, filter).await
+    }
+
+    async fn delete_pending(&self, account_id: Option<&ID>) -> anyhow::Result<DeleteResult> {
+        let mut filter = doc! {
+            "sent_at": null,
+            "failed_at": null,
+        };
+        if let Some(account_id) = account_id {
+            filter.insert("account_id", account_id.inner_ref());
+        }
+        mongo_repo::delete_many_by::<_, ReminderMongo>(&self.collection, filter).await
     }
 }
 
@@ -88,6 +152,10 @@ struct ReminderMongo {
     event_id: ObjectId,
     account_id: ObjectId,
     priority: i64,
+    #[serde(default)]
+    sent_at: Option<i64>,
+    #[serde(default)]
+    failed_at: Option<i64>,
 }
 
 impl MongoDocument<Reminder> for ReminderMongo {
@@ -98,6 +166,8 @@ impl MongoDocument<Reminder> for ReminderMongo {
             event_id: ID::from(self.event_id),
             account_id: ID::from(self.account_id),
             priority: self.priority,
+            sent_at: self.sent_at,
+            failed_at: self.failed_at,
         }
     }
 
@@ -108,6 +178,8 @@ impl MongoDocument<Reminder> for ReminderMongo {
             account_id: event.account_id.inner_ref().clone(),
             remind_at: event.remind_at,
             priority: event.priority,
+            sent_at: event.sent_at,
+            failed_at: event.failed_at,
         }
     }
 
@@ -15,16 +15,35 @@ use event::{
 };
 use mongodb::{options::ClientOptions, Client};
 use schedule::{IScheduleRepo, InMemoryScheduleRepo, MongoScheduleRepo};
-use service::{IServiceRepo, InMemoryServiceRepo, MongoServiceRepo};
+use service::{InMemoryServiceRepo, MongoServiceRepo};
 use std::sync::Arc;
 use tracing::info;
 use user::{IUserRepo, InMemoryUserRepo, MongoUserRepo};
 
 pub use mongodb::bson::oid::ObjectId;
+pub use service::IServiceRepo;
 pub use shared::query_structs::*;
+pub use shared::repo::DeleteResult;
+
+/// The kind of store that the `Repos` are backed by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreBackend {
+    InMemory,
+    MongoDB,
+}
+
+impl std::fmt::Display for StoreBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InMemory => write!(f, "inmemory"),
+            Self::MongoDB => write!(f, "mongodb"),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Repos {
+    pub backend: StoreBackend,
     pub event_repo: Arc<dyn IEventRepo>,
     pub calendar_repo: Arc<dyn ICalendarRepo>,
     pub account_repo: Arc<dyn IAccountRepo>,
@@ -56,6 +75,7 @@ impl Repos {
             .await?;
         info!("DB CHECKING CONNECTION ... [done]");
         Ok(Self {
+            backend: StoreBackend::MongoDB,
             event_repo: Arc::new(MongoEventRepo::new(&db)),
             calendar_repo: Arc::new(MongoCalendarRepo::new(&db)),
             account_repo: Arc::new(MongoAccountRepo::new(&db)),
@@ -71,6 +91,7 @@ impl Repos {
 
     pub fn create_inmemory() -> Self {
         Self {
+            backend: StoreBackend::InMemory,
             event_repo: Arc::new(InMemoryEventRepo::new()),
             calendar_repo: Arc::new(InMemoryCalendarRepo::new()),
             account_repo: Arc::new(InMemoryAccountRepo::new()),
@@ -84,3 +105,57 @@ impl Repos {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{setup_context, NettuContext, SeededContext};
+
+    async fn seed(ctx: &NettuContext) -> SeededContext {
+        NettuContext::seeded()
+            .with_calendar_events(&[(0, 100), (200, 300)])
+            .with_schedule()
+            .with_service()
+            .seed(ctx.clone())
+            .await
+    }
+
+    #[tokio::test]
+    async fn delete_by_account() {
+        for ctx in vec![NettuContext::create_inmemory(), setup_context().await] {
+            let seeded = seed(&ctx).await;
+            let other = seed(&ctx).await;
+            let account_id = seeded.account.unwrap().id;
+
+            let repos = &ctx.repos;
+            let deleted_calendars = repos.calendar_repo.delete_by_account(&account_id).await;
+            assert_eq!(deleted_calendars.unwrap().deleted_count, 1);
+            let deleted_events = repos.event_repo.delete_by_account(&account_id).await;
+            assert_eq!(deleted_events.unwrap().deleted_count, 2);
+            let deleted_schedules = repos.schedule_repo.delete_by_account(&account_id).await;
+            assert_eq!(deleted_schedules.unwrap().deleted_count, 1);
+            let deleted_services = repos.service_repo.delete_by_account(&account_id).await;
+            assert_eq!(deleted_services.unwrap().deleted_count, 1);
+
+            let calendar = seeded.calendar.unwrap();
+            assert!(repos.calendar_repo.find(&calendar.id).await.is_none());
+            for event in &seeded.events {
+                assert!(repos.event_repo.find(&event.id).await.is_none());
+            }
+            let schedule = seeded.schedule.unwrap();
+            assert!(repos.schedule_repo.find(&schedule.id).await.is_none());
+            let service = seeded.service.unwrap();
+            assert!(repos.service_repo.find(&service.id).await.is_none());
+
+            // The entities of other accounts are kept
+            let calendar = other.calendar.unwrap();
+            assert!(repos.calendar_repo.find(&calendar.id).await.is_some());
+            for event in &other.events {
+                assert!(repos.event_repo.find(&event.id).await.is_some());
+            }
+            let schedule = other.schedule.unwrap();
+            assert!(repos.schedule_repo.find(&schedule.id).await.is_some());
+            let service = other.service.unwrap();
+            assert!(repos.service_repo.find(&service.id).await.is_some());
+        }
+    }
+}
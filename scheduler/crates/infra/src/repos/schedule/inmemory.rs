@@ -17,8 +17,7 @@ impl InMemoryScheduleRepo {
 #[async_trait::async_trait]
 impl IScheduleRepo for InMemoryScheduleRepo {
     async fn insert(&self, schedule: &Schedule) -> anyhow::Result<()> {
-        insert(schedule, &self.schedules);
-        Ok(())
+        insert(schedule, &self.schedules)
     }
 
     async fn save(&self, schedule: &Schedule) -> anyhow::Result<()> {
@@ -30,22 +29,43 @@ impl IScheduleRepo for InMemoryScheduleRepo {
         find(schedule_id, &self.schedules)
     }
 
+    async fn find_for_account(&self, schedule_id: &ID, account_id: &ID) -> Option<Schedule> {
+        find_by(&self.schedules, |schedule| {
+            schedule.id == *schedule_id && schedule.account_id == *account_id
+        })
+        .into_iter()
+        .next()
+    }
+
     async fn find_by_user(&self, user_id: &ID) -> Vec<Schedule> {
         find_by(&self.schedules, |schedule| schedule.user_id == *user_id)
     }
 
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize> {
+        Ok(count_by(&self.schedules, |schedule| {
+            schedule.user_id == *user_id
+        }))
+    }
+
     async fn find_many(&self, schedule_ids: &[ID]) -> Vec<Schedule> {
         find_by(&self.schedules, |schedule| {
             schedule_ids.contains(&schedule.id)
         })
     }
 
-    async fn delete(&self, schedule_id: &ID) -> Option<Schedule> {
-        delete(schedule_id, &self.schedules)
+    async fn delete(&self, schedule_id: &ID) -> anyhow::Result<Option<Schedule>> {
+        Ok(delete(schedule_id, &self.schedules))
     }
 
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult> {
         let res = delete_by(&self.schedules, |schedule| schedule.user_id == *user_id);
         Ok(res)
     }
+
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.schedules, |schedule| {
+            schedule.account_id == *account_id
+        });
+        Ok(res)
+    }
 }
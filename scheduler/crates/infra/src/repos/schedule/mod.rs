@@ -11,10 +11,14 @@ pub trait IScheduleRepo: Send + Sync {
     async fn insert(&self, schedule: &Schedule) -> anyhow::Result<()>;
     async fn save(&self, schedule: &Schedule) -> anyhow::Result<()>;
     async fn find(&self, schedule_id: &ID) -> Option<Schedule>;
+    /// Finds the `Schedule` with the given id only if it belongs to the `Account`
+    async fn find_for_account(&self, schedule_id: &ID, account_id: &ID) -> Option<Schedule>;
     async fn find_many(&self, schedule_ids: &[ID]) -> Vec<Schedule>;
     async fn find_by_user(&self, user_id: &ID) -> Vec<Schedule>;
-    async fn delete(&self, schedule_id: &ID) -> Option<Schedule>;
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize>;
+    async fn delete(&self, schedule_id: &ID) -> anyhow::Result<Option<Schedule>>;
     async fn delete_by_user(&self, user_id: &ID) -> anyhow::Result<DeleteResult>;
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult>;
 }
 
 #[cfg(test)]
@@ -51,12 +55,22 @@ mod tests {
                 .await;
             assert_eq!(res.len(), 1);
             assert!(res[0].eq(&schedule));
+            let count = ctx
+                .repos
+                .schedule_repo
+                .count_by_user(&schedule.user_id)
+                .await;
+            assert_eq!(count.unwrap(), 1);
 
             // Delete
-            let res = ctx.repos.schedule_repo.delete(&schedule.id).await;
+            let res = ctx.repos.schedule_repo.delete(&schedule.id).await.unwrap();
             assert!(res.is_some());
             assert!(res.unwrap().eq(&schedule));
 
+            // Deleting it again is not an error, but nothing is deleted
+            let res = ctx.repos.schedule_repo.delete(&schedule.id).await;
+            assert!(matches!(res, Ok(None)));
+
             // Find
             assert!(ctx.repos.schedule_repo.find(&schedule.id).await.is_none());
 
@@ -99,4 +113,21 @@ mod tests {
                 .is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn stores_created_and_updated() {
+        for ctx in create_contexts().await {
+            let mut schedule = Schedule::new(ID::default(), ID::default(), &Pacific);
+            schedule.created = 10;
+            schedule.updated = 10;
+            ctx.repos.schedule_repo.insert(&schedule).await.unwrap();
+
+            schedule.updated = 20;
+            ctx.repos.schedule_repo.save(&schedule).await.unwrap();
+
+            let res = ctx.repos.schedule_repo.find(&schedule.id).await.unwrap();
+            assert_eq!(res.created, 10);
+            assert_eq!(res.updated, 20);
+        }
+    }
 }
@@ -38,6 +38,14 @@ impl IScheduleRepo for MongoScheduleRepo {
         mongo_repo::find::<_, ScheduleMongo>(&self.collection, &oid).await
     }
 
+    async fn find_for_account(&self, schedule_id: &ID, account_id: &ID) -> Option<Schedule> {
+        let filter = doc! {
+            "_id": schedule_id.inner_ref(),
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::find_one_by::<_, ScheduleMongo>(&self.collection, filter).await
+    }
+
     async fn find_by_user(&self, user_id: &ID) -> Vec<Schedule> {
         let filter = doc! {
             "user_id": user_id.inner_ref()
@@ -48,6 +56,13 @@ impl IScheduleRepo for MongoScheduleRepo {
         }
     }
 
+    async fn count_by_user(&self, user_id: &ID) -> anyhow::Result<usize> {
+        let filter = doc! {
+            "user_id": user_id.inner_ref()
+        };
+        mongo_repo::count_by(&self.collection, filter).await
+    }
+
     async fn find_many(&self, schedule_ids: &[ID]) -> Vec<Schedule> {
         let filter = doc! {
             "_id": {
@@ -60,7 +75,7 @@ impl IScheduleRepo for MongoScheduleRepo {
         }
     }
 
-    async fn delete(&self, schedule_id: &ID) -> Option<Schedule> {
+    async fn delete(&self, schedule_id: &ID) -> anyhow::Result<Option<Schedule>> {
         let oid = schedule_id.inner_ref();
         mongo_repo::delete::<_, ScheduleMongo>(&self.collection, &oid).await
     }
@@ -71,6 +86,13 @@ impl IScheduleRepo for MongoScheduleRepo {
         };
         mongo_repo::delete_many_by::<_, ScheduleMongo>(&self.collection, filter).await
     }
+
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::delete_many_by::<_, ScheduleMongo>(&self.collection, filter).await
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,6 +102,11 @@ struct ScheduleMongo {
     account_id: ObjectId,
     rules: Vec<ScheduleRule>,
     timezone: String,
+    /// Missing on schedules stored before the timestamps were introduced
+    #[serde(default)]
+    created: i64,
+    #[serde(default)]
+    updated: i64,
 }
 
 impl MongoDocument<Schedule> for ScheduleMongo {
@@ -90,6 +117,8 @@ impl MongoDocument<Schedule> for ScheduleMongo {
             account_id: ID::from(self.account_id),
             rules: self.rules,
             timezone: self.timezone.parse().unwrap(),
+            created: self.created,
+            updated: self.updated,
         }
     }
 
@@ -100,6 +129,8 @@ impl MongoDocument<Schedule> for ScheduleMongo {
             account_id: schedule.account_id.inner_ref().clone(),
             rules: schedule.rules.to_owned(),
             timezone: schedule.timezone.to_string(),
+            created: schedule.created,
+            updated: schedule.updated,
         }
     }
 
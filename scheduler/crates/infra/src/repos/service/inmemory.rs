@@ -1,5 +1,7 @@
 use super::IServiceRepo;
-use crate::repos::shared::{inmemory_repo::*, query_structs::MetadataFindQuery};
+use crate::repos::shared::{
+    inmemory_repo::*, query_structs::MetadataFindQuery, repo::DeleteResult,
+};
 use nettu_scheduler_domain::{Service, ID};
 
 pub struct InMemoryServiceRepo {
@@ -17,8 +19,7 @@ impl InMemoryServiceRepo {
 #[async_trait::async_trait]
 impl IServiceRepo for InMemoryServiceRepo {
     async fn insert(&self, service: &Service) -> anyhow::Result<()> {
-        insert(service, &self.services);
-        Ok(())
+        insert(service, &self.services)
     }
 
     async fn save(&self, service: &Service) -> anyhow::Result<()> {
@@ -30,8 +31,25 @@ impl IServiceRepo for InMemoryServiceRepo {
         find(service_id, &self.services)
     }
 
-    async fn delete(&self, service_id: &ID) -> Option<Service> {
-        delete(service_id, &self.services)
+    async fn find_for_account(&self, service_id: &ID, account_id: &ID) -> Option<Service> {
+        find_by(&self.services, |service| {
+            service.id == *service_id && service.account_id == *account_id
+        })
+        .into_iter()
+        .next()
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        Ok(count_by(&self.services, |_| true))
+    }
+
+    async fn delete(&self, service_id: &ID) -> anyhow::Result<Option<Service>> {
+        Ok(delete(service_id, &self.services))
+    }
+
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let res = delete_by(&self.services, |service| service.account_id == *account_id);
+        Ok(res)
     }
 
     async fn remove_calendar_from_services(&self, calendar_id: &ID) -> anyhow::Result<()> {
@@ -57,19 +75,8 @@ impl IServiceRepo for InMemoryServiceRepo {
     async fn remove_schedule_from_services(&self, schedule_id: &ID) -> anyhow::Result<()> {
         update_many(
             &self.services,
-            |service| {
-                for user in &service.users {
-                    if user.contains_schedule(&schedule_id) {
-                        return true;
-                    }
-                }
-                false
-            },
-            |service| {
-                for user in &mut service.users {
-                    user.remove_schedule(&schedule_id);
-                }
-            },
+            |service| service.contains_schedule(&schedule_id),
+            |service| service.remove_schedule(&schedule_id),
         );
         Ok(())
     }
@@ -88,4 +95,15 @@ impl IServiceRepo for InMemoryServiceRepo {
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Service> {
         find_by_metadata(&self.services, query)
     }
+
+    async fn find_by_account(
+        &self,
+        account_id: &ID,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Service>> {
+        let mut services = find_by(&self.services, |s| s.account_id == *account_id);
+        services.sort_by(|s1, s2| s1.id.cmp(&s2.id));
+        Ok(services.into_iter().skip(skip).take(limit).collect())
+    }
 }
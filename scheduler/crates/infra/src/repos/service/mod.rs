@@ -5,18 +5,30 @@ pub use inmemory::InMemoryServiceRepo;
 pub use mongo::MongoServiceRepo;
 use nettu_scheduler_domain::{Service, ID};
 
-use super::shared::query_structs::MetadataFindQuery;
+use super::shared::{query_structs::MetadataFindQuery, repo::DeleteResult};
 
 #[async_trait::async_trait]
 pub trait IServiceRepo: Send + Sync {
     async fn insert(&self, service: &Service) -> anyhow::Result<()>;
     async fn save(&self, service: &Service) -> anyhow::Result<()>;
     async fn find(&self, service_id: &ID) -> Option<Service>;
-    async fn delete(&self, service_id: &ID) -> Option<Service>;
+    /// Finds the `Service` with the given id only if it belongs to the `Account`
+    async fn find_for_account(&self, service_id: &ID, account_id: &ID) -> Option<Service>;
+    async fn count(&self) -> anyhow::Result<usize>;
+    async fn delete(&self, service_id: &ID) -> anyhow::Result<Option<Service>>;
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult>;
     async fn remove_calendar_from_services(&self, calendar_id: &ID) -> anyhow::Result<()>;
     async fn remove_schedule_from_services(&self, schedule_id: &ID) -> anyhow::Result<()>;
     async fn remove_user_from_services(&self, user_id: &ID) -> anyhow::Result<()>;
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Service>;
+    /// Finds the `Service`s of the `Account` ordered by id, which is the order
+    /// they were created in, so that paging through them with `skip` is stable
+    async fn find_by_account(
+        &self,
+        account_id: &ID,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Service>>;
 }
 
 #[cfg(test)]
@@ -128,9 +140,103 @@ mod tests {
                 .service_repo
                 .delete(&service.id)
                 .await
-                .expect("To delete service");
+                .expect("To delete service")
+                .expect("Service to exist");
 
             assert!(ctx.repos.service_repo.find(&service.id).await.is_none());
+            let res = ctx.repos.service_repo.delete(&service.id).await;
+            assert!(matches!(res, Ok(None)));
+        }
+    }
+
+    #[tokio::test]
+    async fn remove_default_schedule_from_services() {
+        for ctx in create_contexts().await {
+            let schedule_id = ID::default();
+            let mut service = Service::new(ID::default());
+            service.default_schedule_id = Some(schedule_id.clone());
+            service.add_user(ServiceResource::new(
+                ID::default(),
+                TimePlan::Schedule(schedule_id.clone()),
+                vec![],
+            ));
+            ctx.repos
+                .service_repo
+                .insert(&service)
+                .await
+                .expect("To insert service");
+
+            ctx.repos
+                .service_repo
+                .remove_schedule_from_services(&schedule_id)
+                .await
+                .expect("To remove schedule from services");
+
+            let service = ctx
+                .repos
+                .service_repo
+                .find(&service.id)
+                .await
+                .expect("To get service");
+            assert!(service.default_schedule_id.is_none());
+            assert_eq!(service.users[0].availibility, TimePlan::Empty);
+        }
+    }
+
+    #[tokio::test]
+    async fn find_for_account() {
+        for ctx in create_contexts().await {
+            let service = Service::new(ID::default());
+            ctx.repos.service_repo.insert(&service).await.unwrap();
+
+            let found = ctx
+                .repos
+                .service_repo
+                .find_for_account(&service.id, &service.account_id)
+                .await
+                .expect("To find service");
+            assert_eq!(found.id, service.id);
+
+            // A service id from another account is not found
+            assert!(ctx
+                .repos
+                .service_repo
+                .find_for_account(&service.id, &ID::default())
+                .await
+                .is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn find_by_account_pages() {
+        for ctx in create_contexts().await {
+            let account_id = ID::default();
+            let service1 = Service::new(account_id.clone());
+            let service2 = Service::new(account_id.clone());
+            ctx.repos.service_repo.insert(&service1).await.unwrap();
+            ctx.repos.service_repo.insert(&service2).await.unwrap();
+
+            let page = |skip, limit| {
+                let ctx = ctx.clone();
+                let account_id = account_id.clone();
+                async move {
+                    ctx.repos
+                        .service_repo
+                        .find_by_account(&account_id, skip, limit)
+                        .await
+                        .expect("To find services")
+                        .into_iter()
+                        .map(|s| s.id)
+                        .collect::<Vec<_>>()
+                }
+            };
+            assert_eq!(
+                page(0, 10).await,
+                vec![service1.id.clone(), service2.id.clone()]
+            );
+            assert_eq!(page(1, 10).await, vec![service2.id.clone()]);
+            // A limit of 0 gives an empty page on every repo
+            assert!(page(0, 0).await.is_empty());
         }
     }
 }
@@ -3,6 +3,7 @@ use crate::{
     repos::shared::{
         mongo_repo::{self},
         query_structs::MetadataFindQuery,
+        repo::DeleteResult,
     },
     KVMetadata,
 };
@@ -11,7 +12,7 @@ use mongodb::{
     bson::{doc, oid::ObjectId, Document},
     Collection, Database,
 };
-use nettu_scheduler_domain::{Service, ServiceResource, TimePlan, ID};
+use nettu_scheduler_domain::{BookingStrategy, Millis, Service, ServiceResource, TimePlan, ID};
 use serde::{Deserialize, Serialize};
 
 pub struct MongoServiceRepo {
@@ -41,11 +42,30 @@ impl IServiceRepo for MongoServiceRepo {
         mongo_repo::find::<_, ServiceMongo>(&self.collection, &oid).await
     }
 
-    async fn delete(&self, service_id: &ID) -> Option<Service> {
+    async fn find_for_account(&self, service_id: &ID, account_id: &ID) -> Option<Service> {
+        let filter = doc! {
+            "_id": service_id.inner_ref(),
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::find_one_by::<_, ServiceMongo>(&self.collection, filter).await
+    }
+
+    async fn count(&self) -> anyhow::Result<usize> {
+        mongo_repo::count_by(&self.collection, doc! {}).await
+    }
+
+    async fn delete(&self, service_id: &ID) -> anyhow::Result<Option<Service>> {
         let oid = service_id.inner_ref();
         mongo_repo::delete::<_, ServiceMongo>(&self.collection, &oid).await
     }
 
+    async fn delete_by_account(&self, account_id: &ID) -> anyhow::Result<DeleteResult> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::delete_many_by::<_, ServiceMongo>(&self.collection, filter).await
+    }
+
     async fn remove_calendar_from_services(&self, calendar_id: &ID) -> anyhow::Result<()> {
         let filter = doc! {
             "ids": &calendar_id.inner_ref()
@@ -75,13 +95,7 @@ impl IServiceRepo for MongoServiceRepo {
         let mut services =
             mongo_repo::find_many_by::<_, ServiceMongo>(&self.collection, filter).await?;
         for service in &mut services {
-            for user in &mut service.users {
-                if let TimePlan::Schedule(id) = &user.availibility {
-                    if id == schedule_id {
-                        user.availibility = TimePlan::Empty;
-                    }
-                }
-            }
+            service.remove_schedule(schedule_id);
             mongo_repo::save::<_, ServiceMongo>(&self.collection, service).await?;
         }
         Ok(())
@@ -107,6 +121,19 @@ impl IServiceRepo for MongoServiceRepo {
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<Service> {
         mongo_repo::find_by_metadata::<_, ServiceMongo>(&self.collection, query).await
     }
+
+    async fn find_by_account(
+        &self,
+        account_id: &ID,
+        skip: usize,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Service>> {
+        let filter = doc! {
+            "account_id": account_id.inner_ref()
+        };
+        mongo_repo::find_many_by_paginated::<_, ServiceMongo>(&self.collection, filter, skip, limit)
+            .await
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,6 +145,8 @@ struct ServiceResourceMongo {
     pub buffer: i64,
     pub closest_booking_time: i64,
     pub furthest_booking_time: Option<i64>,
+    #[serde(default)]
+    pub timezone: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -126,6 +155,18 @@ struct ServiceMongo {
     pub account_id: ObjectId,
     pub users: Vec<ServiceResourceMongo>,
     pub ids: Vec<ObjectId>,
+    #[serde(default)]
+    pub booking_strategy: BookingStrategy,
+    #[serde(default)]
+    pub default_schedule_id: Option<ObjectId>,
+    #[serde(default)]
+    pub min_lead_time_ms: i64,
+    #[serde(default)]
+    pub allowed_durations: Vec<i64>,
+    #[serde(default)]
+    pub allow_overbooking: bool,
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub metadata: Vec<KVMetadata>,
 }
 
@@ -145,8 +186,15 @@ impl MongoDocument<Service> for ServiceMongo {
                     buffer: user.buffer,
                     closest_booking_time: user.closest_booking_time,
                     furthest_booking_time: user.furthest_booking_time,
+                    timezone: user.timezone.and_then(|tz| tz.parse().ok()),
                 })
                 .collect(),
+            booking_strategy: self.booking_strategy,
+            default_schedule_id: self.default_schedule_id.map(ID::from),
+            min_lead_time: Millis(self.min_lead_time_ms),
+            allowed_durations: self.allowed_durations,
+            allow_overbooking: self.allow_overbooking,
+            timezone: self.timezone.and_then(|tz| tz.parse().ok()),
             metadata: KVMetadata::to_metadata(self.metadata),
         }
     }
@@ -166,8 +214,18 @@ impl MongoDocument<Service> for ServiceMongo {
                     buffer: user.buffer,
                     closest_booking_time: user.closest_booking_time,
                     furthest_booking_time: user.furthest_booking_time,
+                    timezone: user.timezone.map(|tz| tz.to_string()),
                 })
                 .collect(),
+            booking_strategy: service.booking_strategy.clone(),
+            default_schedule_id: service
+                .default_schedule_id
+                .as_ref()
+                .map(|id| id.inner_ref().clone()),
+            min_lead_time_ms: service.min_lead_time.0,
+            allowed_durations: service.allowed_durations.clone(),
+            allow_overbooking: service.allow_overbooking,
+            timezone: service.timezone.map(|tz| tz.to_string()),
             metadata: KVMetadata::new(service.metadata.clone()),
             ids: service
                 .users
@@ -188,6 +246,12 @@ impl MongoDocument<Service> for ServiceMongo {
                     ids
                 })
                 .flatten()
+                .chain(
+                    service
+                        .default_schedule_id
+                        .iter()
+                        .map(|id| id.inner_ref().clone()),
+                )
                 .collect(),
         }
     }
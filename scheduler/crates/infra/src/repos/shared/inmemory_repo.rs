@@ -2,13 +2,19 @@ use crate::repos::shared::repo::DeleteResult;
 use nettu_scheduler_domain::{Entity, Meta, ID};
 use std::sync::Mutex;
 
-use super::query_structs::MetadataFindQuery;
+use super::query_structs::{MetadataFindAfterQuery, MetadataFindQuery};
 
 /// Useful functions for creating inmemory repositories
 
-pub fn insert<T: Clone>(val: &T, collection: &Mutex<Vec<T>>) {
+/// Fails when the collection already has an entity with the same id, just like
+/// mongo rejects a duplicate `_id`, instead of silently keeping both
+pub fn insert<T: Clone + Entity>(val: &T, collection: &Mutex<Vec<T>>) -> anyhow::Result<()> {
     let mut collection = collection.lock().unwrap();
+    if collection.iter().any(|item| item.id() == val.id()) {
+        anyhow::bail!("An entity with id: {} already exists", val.id());
+    }
     collection.push(val.clone());
+    Ok(())
 }
 
 pub fn save<T: Clone + Entity + std::fmt::Debug>(val: &T, collection: &Mutex<Vec<T>>) {
@@ -20,6 +26,26 @@ pub fn save<T: Clone + Entity + std::fmt::Debug>(val: &T, collection: &Mutex<Vec
     }
 }
 
+/// Replaces the stored value only if the predicate holds for it, under a single lock.
+/// Returns whether it was replaced.
+pub fn save_if<T: Clone + Entity>(
+    val: &T,
+    collection: &Mutex<Vec<T>>,
+    predicate: impl Fn(&T) -> bool,
+) -> bool {
+    let mut collection = collection.lock().unwrap();
+    match collection
+        .iter_mut()
+        .find(|stored| stored.id() == val.id() && predicate(stored))
+    {
+        Some(stored) => {
+            *stored = val.clone();
+            true
+        }
+        None => false,
+    }
+}
+
 pub fn find<T: Clone + Entity>(val_id: &ID, collection: &Mutex<Vec<T>>) -> Option<T> {
     let collection = collection.lock().unwrap();
     for i in 0..collection.len() {
@@ -44,6 +70,23 @@ pub fn find_by<T: Clone + Entity, F: FnMut(&T) -> bool>(
     items
 }
 
+pub fn count_by<T, F: Fn(&T) -> bool>(collection: &Mutex<Vec<T>>, compare: F) -> usize {
+    let collection = collection.lock().unwrap();
+    collection.iter().filter(|item| compare(item)).count()
+}
+
+/// Applies the update to the entity while holding the lock and returns the updated entity
+pub fn update<T: Clone + Entity, F: FnOnce(&mut T)>(
+    val_id: &ID,
+    collection: &Mutex<Vec<T>>,
+    update: F,
+) -> Option<T> {
+    let mut collection = collection.lock().unwrap();
+    let item = collection.iter_mut().find(|item| item.id() == val_id)?;
+    update(item);
+    Some(item.clone())
+}
+
 pub fn delete<T: Clone + Entity>(val_id: &ID, collection: &Mutex<Vec<T>>) -> Option<T> {
     let mut collection = collection.lock().unwrap();
     for i in 0..collection.len() {
@@ -125,3 +168,23 @@ pub fn find_by_metadata<T: Clone + Entity + Meta>(
         }
     })
 }
+
+pub fn find_by_metadata_after<T: Clone + Entity + Meta>(
+    collection: &Mutex<Vec<T>>,
+    query: MetadataFindAfterQuery,
+) -> Vec<T> {
+    // Ids are hex encoded `ObjectId`s of the same length, so they sort the same as strings
+    let after_id = query.after_id.map(|id| id.as_string());
+    let mut items = find_by(collection, |e| {
+        let after_cursor = match &after_id {
+            Some(after_id) => e.id().as_string() > *after_id,
+            None => true,
+        };
+        after_cursor
+            && *e.account_id() == query.account_id
+            && e.metadata().get(&query.metadata.key) == Some(&query.metadata.value)
+    });
+    items.sort_by_key(|e| e.id().as_string());
+    items.truncate(query.limit);
+    items
+}
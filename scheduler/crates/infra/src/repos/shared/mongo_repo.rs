@@ -1,11 +1,14 @@
 use std::cmp::max;
 
-use super::{query_structs::MetadataFindQuery, repo::DeleteResult};
-use anyhow::Result;
+use super::{
+    query_structs::{MetadataFindAfterQuery, MetadataFindQuery},
+    repo::DeleteResult,
+};
+use anyhow::{Context, Result};
 use futures::stream::StreamExt;
 use mongodb::{
     bson::{self, doc, oid::ObjectId, to_bson, Document},
-    options::FindOptions,
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument},
     Collection, Cursor,
 };
 
@@ -42,8 +45,15 @@ fn doc_to_persistence<E, D: MongoDocument<E>>(raw: &D) -> Document {
 }
 
 pub async fn insert<E, D: MongoDocument<E>>(collection: &Collection, entity: &E) -> Result<()> {
-    let doc = entity_to_persistence::<E, D>(entity);
-    let _res = collection.insert_one(doc, None).await;
+    let raw = D::from_domain(entity);
+    let doc = doc_to_persistence(&raw);
+    collection.insert_one(doc, None).await.with_context(|| {
+        format!(
+            "Failed to insert {} into {}",
+            raw.get_id_filter(),
+            collection.name()
+        )
+    })?;
     Ok(())
 }
 
@@ -51,11 +61,21 @@ pub async fn bulk_insert<E, D: MongoDocument<E>>(
     collection: &Collection,
     entities: &[E],
 ) -> Result<()> {
+    // The driver refuses to insert an empty list of documents
+    if entities.is_empty() {
+        return Ok(());
+    }
     let docs = entities
         .iter()
         .map(|e| entity_to_persistence::<E, D>(e))
         .collect::<Vec<_>>();
-    let _res = collection.insert_many(docs, None).await;
+    collection.insert_many(docs, None).await.with_context(|| {
+        format!(
+            "Failed to insert {} documents into {}",
+            entities.len(),
+            collection.name()
+        )
+    })?;
     Ok(())
 }
 
@@ -63,20 +83,47 @@ pub async fn save<E, D: MongoDocument<E>>(collection: &Collection, entity: &E) -
     let raw = D::from_domain(entity);
     let filter = raw.get_id_filter();
     let doc = doc_to_persistence(&raw);
-    let _res = collection.update_one(filter, doc, None).await;
+    collection
+        .replace_one(filter.clone(), doc, None)
+        .await
+        .with_context(|| format!("Failed to save {} in {}", filter, collection.name()))?;
     Ok(())
 }
 
+/// Replaces the stored entity only if it also matches the given condition, in a single
+/// operation. Returns whether it was replaced.
+pub async fn save_if<E, D: MongoDocument<E>>(
+    collection: &Collection,
+    entity: &E,
+    condition: Document,
+) -> Result<bool> {
+    let raw = D::from_domain(entity);
+    let mut filter = raw.get_id_filter();
+    filter.extend(condition);
+    let doc = doc_to_persistence(&raw);
+    let res = collection
+        .replace_one(filter.clone(), doc, None)
+        .await
+        .with_context(|| format!("Failed to save {} in {}", filter, collection.name()))?;
+    Ok(res.matched_count == 1)
+}
+
 pub async fn update_many<E, D: MongoDocument<E>>(
     collection: &Collection,
     filter: Document,
     update: Document,
 ) -> Result<()> {
-    let coll = collection;
-    coll.update_many(filter, update, None)
+    collection
+        .update_many(filter.clone(), update, None)
         .await
-        .map(|_| ())
-        .map_err(anyhow::Error::new)
+        .with_context(|| {
+            format!(
+                "Failed to update documents matching {} in {}",
+                filter,
+                collection.name()
+            )
+        })?;
+    Ok(())
 }
 
 pub async fn find<E, D: MongoDocument<E>>(collection: &Collection, id: &ObjectId) -> Option<E> {
@@ -103,32 +150,106 @@ pub async fn find_many_by<E, D: MongoDocument<E>>(
     collection: &Collection,
     filter: Document,
 ) -> Result<Vec<E>> {
-    let coll = collection;
-    let res = coll.find(filter, None).await;
+    let cursor = collection
+        .find(filter.clone(), None)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to find documents matching {} in {}",
+                filter,
+                collection.name()
+            )
+        })?;
+    Ok(consume_cursor::<E, D>(cursor).await)
+}
 
-    match res {
-        Ok(cursor) => Ok(consume_cursor::<E, D>(cursor).await),
-        Err(err) => Err(anyhow::Error::new(err)),
+/// Finds a page of the documents matching the filter. The documents are sorted by
+/// `_id` so that paging through them with `skip` is stable. A `limit` of 0 gives
+/// an empty page, like the inmemory repos, instead of every document.
+pub async fn find_many_by_paginated<E, D: MongoDocument<E>>(
+    collection: &Collection,
+    filter: Document,
+    skip: usize,
+    limit: usize,
+) -> Result<Vec<E>> {
+    if limit == 0 {
+        return Ok(vec![]);
     }
+    let mut find_options = FindOptions::builder().build();
+    find_options.sort = Some(doc! { "_id": 1 });
+    find_options.skip = Some(skip as i64);
+    find_options.limit = Some(limit as i64);
+
+    let cursor = collection
+        .find(filter.clone(), find_options)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to find documents matching {} in {}",
+                filter,
+                collection.name()
+            )
+        })?;
+    Ok(consume_cursor::<E, D>(cursor).await)
 }
 
-pub async fn delete<E, D: MongoDocument<E>>(collection: &Collection, id: &ObjectId) -> Option<E> {
+pub async fn count_by(collection: &Collection, filter: Document) -> Result<usize> {
+    let count = collection
+        .count_documents(filter.clone(), None)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to count documents matching {} in {}",
+                filter,
+                collection.name()
+            )
+        })?;
+    Ok(count as usize)
+}
+
+/// Applies the update atomically and returns the updated entity
+pub async fn find_one_and_update<E, D: MongoDocument<E>>(
+    collection: &Collection,
+    id: &ObjectId,
+    update: Document,
+) -> Result<Option<E>> {
     let filter = get_id_filter(id);
-    let res = collection.find_one_and_delete(filter, None).await;
-    match res {
-        Ok(doc) if doc.is_some() => {
-            let event = persistence_to_entity::<E, D>(doc.unwrap());
-            Some(event)
-        }
-        _ => None,
-    }
+    let options = FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build();
+    let doc = collection
+        .find_one_and_update(filter.clone(), update, options)
+        .await
+        .with_context(|| format!("Failed to update {} in {}", filter, collection.name()))?;
+    Ok(doc.map(persistence_to_entity::<E, D>))
+}
+
+pub async fn delete<E, D: MongoDocument<E>>(
+    collection: &Collection,
+    id: &ObjectId,
+) -> Result<Option<E>> {
+    let filter = get_id_filter(id);
+    let doc = collection
+        .find_one_and_delete(filter.clone(), None)
+        .await
+        .with_context(|| format!("Failed to delete {} from {}", filter, collection.name()))?;
+    Ok(doc.map(persistence_to_entity::<E, D>))
 }
 
 pub async fn delete_many_by<E, D: MongoDocument<E>>(
     collection: &Collection,
     filter: Document,
 ) -> Result<DeleteResult> {
-    let res = collection.delete_many(filter, None).await?;
+    let res = collection
+        .delete_many(filter.clone(), None)
+        .await
+        .with_context(|| {
+            format!(
+                "Failed to delete documents matching {} from {}",
+                filter,
+                collection.name()
+            )
+        })?;
     Ok(DeleteResult {
         deleted_count: res.deleted_count,
     })
@@ -176,3 +297,34 @@ pub async fn find_by_metadata<E, D: MongoDocument<E>>(
         Err(_) => vec![],
     }
 }
+
+pub async fn find_by_metadata_after<E, D: MongoDocument<E>>(
+    collection: &Collection,
+    query: MetadataFindAfterQuery,
+) -> Vec<E> {
+    // Mongo treats a limit of 0 as no limit, but the inmemory repos give an empty page
+    if query.limit == 0 {
+        return vec![];
+    }
+    let mut filter = doc! {
+        "metadata": {
+            "$elemMatch": {
+                "key": query.metadata.key,
+                "value": query.metadata.value
+            }
+        },
+        "account_id": query.account_id.inner()
+    };
+    if let Some(after_id) = query.after_id {
+        filter.insert("_id", doc! { "$gt": after_id.inner() });
+    }
+
+    let mut find_options = FindOptions::builder().build();
+    find_options.sort = Some(doc! { "_id": 1 });
+    find_options.limit = Some(query.limit as i64);
+
+    match collection.find(filter, find_options).await {
+        Ok(cursor) => consume_cursor::<E, D>(cursor).await,
+        Err(_) => vec![],
+    }
+}
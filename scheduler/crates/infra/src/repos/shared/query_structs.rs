@@ -10,6 +10,17 @@ pub struct MetadataFindQuery {
     pub account_id: ID,
 }
 
+/// Cursor based variant of `MetadataFindQuery`. Entities are returned sorted by id,
+/// starting right after `after_id`, so that pages do not drift when entities are
+/// inserted or deleted between the queries.
+#[derive(Debug, Clone)]
+pub struct MetadataFindAfterQuery {
+    pub metadata: KVMetadata,
+    pub after_id: Option<ID>,
+    pub limit: usize,
+    pub account_id: ID,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KVMetadata {
     pub key: String,
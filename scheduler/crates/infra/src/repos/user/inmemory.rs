@@ -17,8 +17,7 @@ impl InMemoryUserRepo {
 #[async_trait::async_trait]
 impl IUserRepo for InMemoryUserRepo {
     async fn insert(&self, user: &User) -> anyhow::Result<()> {
-        insert(user, &self.users);
-        Ok(())
+        insert(user, &self.users)
     }
 
     async fn save(&self, user: &User) -> anyhow::Result<()> {
@@ -26,20 +25,24 @@ impl IUserRepo for InMemoryUserRepo {
         Ok(())
     }
 
-    async fn delete(&self, user_id: &ID) -> Option<User> {
-        delete(user_id, &self.users)
+    async fn delete(&self, user_id: &ID) -> anyhow::Result<Option<User>> {
+        Ok(delete(user_id, &self.users))
     }
 
     async fn find(&self, user_id: &ID) -> Option<User> {
         find(user_id, &self.users)
     }
 
+    async fn count(&self) -> anyhow::Result<usize> {
+        Ok(count_by(&self.users, |_| true))
+    }
+
     /// Ignores skip and limit as this is just used for testing
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<User> {
         find_by_metadata(&self.users, query)
     }
 
-    async fn find_by_account_id(&self, user_id: &ID, account_id: &ID) -> Option<User> {
+    async fn find_for_account(&self, user_id: &ID, account_id: &ID) -> Option<User> {
         let mut user = find_by(&self.users, |u| {
             u.id == *user_id && u.account_id == *account_id
         });
@@ -11,9 +11,11 @@ use super::shared::query_structs::MetadataFindQuery;
 pub trait IUserRepo: Send + Sync {
     async fn insert(&self, user: &User) -> anyhow::Result<()>;
     async fn save(&self, user: &User) -> anyhow::Result<()>;
-    async fn delete(&self, user_id: &ID) -> Option<User>;
+    async fn delete(&self, user_id: &ID) -> anyhow::Result<Option<User>>;
     async fn find(&self, user_id: &ID) -> Option<User>;
-    async fn find_by_account_id(&self, user_id: &ID, account_id: &ID) -> Option<User>;
+    async fn count(&self) -> anyhow::Result<usize>;
+    /// Finds the `User` with the given id only if it belongs to the `Account`
+    async fn find_for_account(&self, user_id: &ID, account_id: &ID) -> Option<User>;
     async fn find_by_metadata(&self, query: MetadataFindQuery) -> Vec<User>;
 }
 
@@ -77,4 +79,28 @@ mod tests {
             assert!(ctx.repos.user_repo.find_by_metadata(query).await.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn find_for_account() {
+        for ctx in create_contexts().await {
+            let user = User::new(ID::default());
+            ctx.repos.user_repo.insert(&user).await.unwrap();
+
+            let found = ctx
+                .repos
+                .user_repo
+                .find_for_account(&user.id, &user.account_id)
+                .await
+                .expect("To find user");
+            assert_eq!(found.id, user.id);
+
+            // A user id from another account is not found
+            assert!(ctx
+                .repos
+                .user_repo
+                .find_for_account(&user.id, &ID::default())
+                .await
+                .is_none());
+        }
+    }
 }
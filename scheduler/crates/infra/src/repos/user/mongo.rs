@@ -40,12 +40,16 @@ impl IUserRepo for MongoUserRepo {
         mongo_repo::find::<_, UserMongo>(&self.collection, &oid).await
     }
 
-    async fn delete(&self, user_id: &ID) -> Option<User> {
+    async fn count(&self) -> anyhow::Result<usize> {
+        mongo_repo::count_by(&self.collection, doc! {}).await
+    }
+
+    async fn delete(&self, user_id: &ID) -> anyhow::Result<Option<User>> {
         let oid = user_id.inner_ref();
         mongo_repo::delete::<_, UserMongo>(&self.collection, &oid).await
     }
 
-    async fn find_by_account_id(&self, user_id: &ID, account_id: &ID) -> Option<User> {
+    async fn find_for_account(&self, user_id: &ID, account_id: &ID) -> Option<User> {
         let filter = doc! {
             "_id": user_id.inner_ref(),
             "account_id": account_id.inner_ref()
@@ -0,0 +1,212 @@
+use crate::NettuContext;
+use nettu_scheduler_domain::{
+    Account, Calendar, CalendarEvent, EventStatus, Schedule, Service, User,
+};
+
+/// Builds a `NettuContext` with an `Account`, a `User`, a `Calendar` with
+/// `CalendarEvent`s, a `Schedule` and a `Service`, which most use case tests need
+/// as a fixture. Each step also creates the entities it depends on, so
+/// `with_calendar_events` alone creates the `Account`, the `User` and the `Calendar`.
+#[derive(Default)]
+pub struct ContextSeeder {
+    account: Option<Account>,
+    user: Option<User>,
+    calendar: Option<Calendar>,
+    events: Vec<CalendarEvent>,
+    schedule: Option<Schedule>,
+    service: Option<Service>,
+}
+
+/// The seeded `NettuContext` together with the entities that were stored in it
+pub struct SeededContext {
+    pub ctx: NettuContext,
+    pub account: Option<Account>,
+    pub user: Option<User>,
+    pub calendar: Option<Calendar>,
+    pub events: Vec<CalendarEvent>,
+    pub schedule: Option<Schedule>,
+    pub service: Option<Service>,
+}
+
+impl ContextSeeder {
+    pub fn with_account(mut self) -> Self {
+        self.account.get_or_insert_with(Account::default);
+        self
+    }
+
+    pub fn with_user(mut self) -> Self {
+        if self.user.is_none() {
+            self = self.with_account();
+            let account_id = self.account.as_ref().unwrap().id.clone();
+            self.user = Some(User::new(account_id));
+        }
+        self
+    }
+
+    pub fn with_calendar(mut self) -> Self {
+        if self.calendar.is_none() {
+            self = self.with_user();
+            let user = self.user.as_ref().unwrap();
+            self.calendar = Some(Calendar::new(&user.id, &user.account_id));
+        }
+        self
+    }
+
+    /// Adds a busy non-recurring `CalendarEvent` to the `Calendar` for each of the
+    /// given `(start_ts, end_ts)` timespans
+    pub fn with_calendar_events(self, timespans: &[(i64, i64)]) -> Self {
+        self.with_events(timespans, EventStatus::Busy)
+    }
+
+    /// Adds a free non-recurring `CalendarEvent` to the `Calendar` for each of the
+    /// given `(start_ts, end_ts)` timespans, e.g. for a `Calendar` used as availability
+    pub fn with_free_events(self, timespans: &[(i64, i64)]) -> Self {
+        self.with_events(timespans, EventStatus::Free)
+    }
+
+    fn with_events(mut self, timespans: &[(i64, i64)], status: EventStatus) -> Self {
+        self = self.with_calendar();
+        let calendar = self.calendar.as_ref().unwrap();
+        for (start_ts, end_ts) in timespans {
+            let event = CalendarEvent::new(calendar, *start_ts, end_ts - start_ts, status);
+            self.events.push(event);
+        }
+        self
+    }
+
+    /// Adds a `Schedule` in UTC with the default rules for the `User`
+    pub fn with_schedule(mut self) -> Self {
+        if self.schedule.is_none() {
+            self = self.with_user();
+            let user = self.user.as_ref().unwrap();
+            let schedule = Schedule::new(
+                user.id.clone(),
+                user.account_id.clone(),
+                &"UTC".parse().unwrap(),
+            );
+            self.schedule = Some(schedule);
+        }
+        self
+    }
+
+    /// Adds a `Service` without any `User`s to the `Account`
+    pub fn with_service(mut self) -> Self {
+        if self.service.is_none() {
+            self = self.with_account();
+            let account_id = self.account.as_ref().unwrap().id.clone();
+            self.service = Some(Service::new(account_id));
+        }
+        self
+    }
+
+    /// Stores the entities in a new inmemory `NettuContext`
+    pub async fn build(self) -> SeededContext {
+        self.seed(NettuContext::create_inmemory()).await
+    }
+
+    /// Stores the entities in the given `NettuContext`, which can be shared by
+    /// several seeders or be backed by MongoDB
+    pub async fn seed(self, ctx: NettuContext) -> SeededContext {
+        if let Some(account) = &self.account {
+            ctx.repos.account_repo.insert(account).await.unwrap();
+        }
+        if let Some(user) = &self.user {
+            ctx.repos.user_repo.insert(user).await.unwrap();
+        }
+        if let Some(calendar) = &self.calendar {
+            ctx.repos.calendar_repo.insert(calendar).await.unwrap();
+        }
+        for event in &self.events {
+            ctx.repos.event_repo.insert(event).await.unwrap();
+        }
+        if let Some(schedule) = &self.schedule {
+            ctx.repos.schedule_repo.insert(schedule).await.unwrap();
+        }
+        if let Some(service) = &self.service {
+            ctx.repos.service_repo.insert(service).await.unwrap();
+        }
+
+        SeededContext {
+            ctx,
+            account: self.account,
+            user: self.user,
+            calendar: self.calendar,
+            events: self.events,
+            schedule: self.schedule,
+            service: self.service,
+        }
+    }
+}
+
+impl NettuContext {
+    /// Starts building a seeded inmemory `NettuContext` for tests
+    pub fn seeded() -> ContextSeeder {
+        ContextSeeder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn seeded_entities_are_stored() {
+        let seeded = NettuContext::seeded()
+            .with_account()
+            .with_user()
+            .with_calendar_events(&[(0, 100), (200, 300)])
+            .with_free_events(&[(400, 500)])
+            .with_schedule()
+            .with_service()
+            .build()
+            .await;
+        let repos = &seeded.ctx.repos;
+
+        let account = seeded.account.unwrap();
+        let user = seeded.user.unwrap();
+        let calendar = seeded.calendar.unwrap();
+        assert!(repos.account_repo.find(&account.id).await.is_some());
+        assert_eq!(
+            repos.user_repo.find(&user.id).await.unwrap().account_id,
+            account.id
+        );
+        assert_eq!(repos.calendar_repo.find_by_user(&user.id).await.len(), 1);
+        let events = repos
+            .event_repo
+            .find_by_calendar(&calendar.id, None)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 3);
+        for event in &seeded.events {
+            assert!(events
+                .iter()
+                .any(|e| e.id == event.id && e.busy == (e.start_ts < 400)));
+        }
+        let schedule = seeded.schedule.unwrap();
+        assert_eq!(
+            repos
+                .schedule_repo
+                .find(&schedule.id)
+                .await
+                .unwrap()
+                .user_id,
+            user.id
+        );
+        let service = seeded.service.unwrap();
+        assert_eq!(
+            repos
+                .service_repo
+                .find(&service.id)
+                .await
+                .unwrap()
+                .account_id,
+            account.id
+        );
+
+        // Only the requested entities are seeded
+        let seeded = NettuContext::seeded().with_account().build().await;
+        assert!(seeded.account.is_some());
+        assert!(seeded.user.is_none());
+        assert_eq!(seeded.ctx.repos.user_repo.count().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::sync::Notify;
+
+/// Coordinates the shutdown of the background jobs. The jobs stop picking up new work
+/// once shutdown is triggered, and the work they have in progress can be drained
+/// before the process exits.
+#[derive(Default)]
+pub struct Shutdown {
+    triggered: AtomicBool,
+    triggered_notify: Notify,
+    in_progress: AtomicUsize,
+    drained_notify: Notify,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Tells the jobs to stop after their current iteration
+    pub fn trigger(&self) {
+        self.triggered.store(true, Ordering::SeqCst);
+        self.triggered_notify.notify_waiters();
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::SeqCst)
+    }
+
+    /// Completes when shutdown is triggered, or right away if it already has been
+    pub async fn triggered(&self) {
+        // Created before checking the flag, so that a trigger in between is not missed
+        let notified = self.triggered_notify.notified();
+        if self.is_triggered() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Marks that a job has work in progress until the returned guard is dropped
+    pub fn start_work(self: &Arc<Self>) -> WorkGuard {
+        self.in_progress.fetch_add(1, Ordering::SeqCst);
+        WorkGuard {
+            shutdown: self.clone(),
+        }
+    }
+
+    /// Completes when no job has work in progress anymore
+    pub async fn drained(&self) {
+        loop {
+            let notified = self.drained_notify.notified();
+            if self.in_progress.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Work in progress of a job, see `Shutdown::start_work`
+pub struct WorkGuard {
+    shutdown: Arc<Shutdown>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        if self.shutdown.in_progress.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.drained_notify.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn triggered_completes_once_triggered() {
+        let shutdown = Shutdown::new();
+        assert!(shutdown.triggered().now_or_never().is_none());
+        shutdown.trigger();
+        assert!(shutdown.is_triggered());
+        assert!(shutdown.triggered().now_or_never().is_some());
+    }
+
+    #[test]
+    fn drained_waits_for_work_in_progress() {
+        let shutdown = Arc::new(Shutdown::new());
+        assert!(shutdown.drained().now_or_never().is_some());
+
+        let work1 = shutdown.start_work();
+        let work2 = shutdown.start_work();
+        assert!(shutdown.drained().now_or_never().is_none());
+        drop(work1);
+        assert!(shutdown.drained().now_or_never().is_none());
+        drop(work2);
+        assert!(shutdown.drained().now_or_never().is_some());
+    }
+}
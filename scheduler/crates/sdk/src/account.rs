@@ -50,4 +50,52 @@ impl AccountClient {
             .put(body, "account/pubkey".into(), StatusCode::OK)
             .await
     }
+
+    pub async fn set_default_timezone(
+        &self,
+        timezone: Option<String>,
+    ) -> APIResponse<set_account_default_timezone::APIResponse> {
+        let body = set_account_default_timezone::RequestBody {
+            default_timezone: timezone,
+        };
+        self.base
+            .put(body, "account/timezone".into(), StatusCode::OK)
+            .await
+    }
+
+    pub async fn set_calendar_limit(
+        &self,
+        max_calendars_per_user: usize,
+    ) -> APIResponse<set_account_calendar_limit::APIResponse> {
+        let body = set_account_calendar_limit::RequestBody {
+            max_calendars_per_user,
+        };
+        self.base
+            .put(body, "account/calendar-limit".into(), StatusCode::OK)
+            .await
+    }
+
+    pub async fn get_upcoming_reminders(
+        &self,
+        within_ms: i64,
+    ) -> APIResponse<get_account_upcoming_reminders::APIResponse> {
+        self.base
+            .get(
+                format!("reminders/upcoming?withinMs={}", within_ms),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn get_sent_reminders(
+        &self,
+        since_ts: i64,
+    ) -> APIResponse<get_sent_reminders::APIResponse> {
+        self.base
+            .get(
+                format!("reminders/sent?sinceTs={}", since_ts),
+                StatusCode::OK,
+            )
+            .await
+    }
 }
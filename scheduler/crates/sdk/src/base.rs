@@ -42,6 +42,7 @@ impl BaseClient {
             Method::GET => client.get(&url),
             Method::POST => client.post(&url),
             Method::PUT => client.put(&url),
+            Method::PATCH => client.patch(&url),
             Method::DELETE => client.delete(&url),
             _ => unimplemented!(),
         };
@@ -139,6 +140,24 @@ impl BaseClient {
         self.handle_api_response(res, expected_status_code).await
     }
 
+    pub async fn patch<T: for<'de> Deserialize<'de>, S: Serialize>(
+        &self,
+        body: S,
+        path: String,
+        expected_status_code: StatusCode,
+    ) -> APIResponse<T> {
+        let res = match self
+            .get_client(Method::PATCH, path)
+            .json(&body)
+            .send()
+            .await
+        {
+            Ok(res) => res,
+            Err(_) => return Err(self.network_error()),
+        };
+        self.handle_api_response(res, expected_status_code).await
+    }
+
     pub async fn post<T: for<'de> Deserialize<'de>, S: Serialize>(
         &self,
         body: S,
@@ -30,6 +30,25 @@ pub struct GetCalendarEventsInput {
     pub end_ts: i64,
 }
 
+pub struct GetCalendarConflictsInput {
+    pub calendar_id: ID,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+pub struct GetUserCalendarsInput {
+    pub user_id: ID,
+    pub skip: usize,
+    pub limit: usize,
+}
+
+pub struct BlockCalendarTimeInput {
+    pub calendar_id: ID,
+    pub start_ts: i64,
+    pub duration: i64,
+    pub reason: Option<String>,
+}
+
 pub struct DeleteCalendarInput {
     pub calendar_id: ID,
 }
@@ -103,6 +122,54 @@ impl CalendarClient {
             .await
     }
 
+    pub async fn get_conflicts(
+        &self,
+        input: GetCalendarConflictsInput,
+    ) -> APIResponse<get_calendar_conflicts::APIResponse> {
+        self.base
+            .get(
+                format!(
+                    "user/calendar/{}/conflicts?startTs={}&endTs={}",
+                    input.calendar_id, input.start_ts, input.end_ts
+                ),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn get_by_user(
+        &self,
+        input: GetUserCalendarsInput,
+    ) -> APIResponse<get_user_calendars::APIResponse> {
+        self.base
+            .get(
+                format!(
+                    "user/{}/calendars?skip={}&limit={}",
+                    input.user_id, input.skip, input.limit
+                ),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn block_time(
+        &self,
+        input: BlockCalendarTimeInput,
+    ) -> APIResponse<block_calendar_time::APIResponse> {
+        let body = block_calendar_time::RequestBody {
+            start_ts: input.start_ts,
+            duration: input.duration,
+            reason: input.reason,
+        };
+        self.base
+            .post(
+                body,
+                format!("user/calendar/{}/block", input.calendar_id),
+                StatusCode::CREATED,
+            )
+            .await
+    }
+
     pub async fn get_by_meta(
         &self,
         input: MetadataFindInput,
@@ -1,7 +1,7 @@
 use crate::{shared::MetadataFindInput, APIResponse, BaseClient};
-use crate::{CalendarEventReminder, RRuleOptions, ID};
+use crate::{CalendarEventReminder, EventEditMode, EventStatus, RRuleOptions, SlotStart, ID};
 use nettu_scheduler_api_structs::*;
-use nettu_scheduler_domain::Metadata;
+use nettu_scheduler_domain::{Metadata, MetadataPatch};
 use reqwest::StatusCode;
 use std::sync::Arc;
 
@@ -22,6 +22,19 @@ pub struct GetEventsInstancesInput {
     pub end_ts: i64,
 }
 
+pub struct GetEventRescheduleSlotsInput {
+    pub event_id: ID,
+    pub iana_tz: Option<String>,
+    /// Alternative to `iana_tz` for clients that only know their offset from UTC
+    pub utc_offset_minutes: Option<i32>,
+    pub duration: i64,
+    /// Defaults to the `duration` when not set
+    pub interval: Option<i64>,
+    /// Defaults to `SlotStart::Interval` when not set
+    pub slot_start: Option<SlotStart>,
+    pub date: String,
+}
+
 pub struct DeleteEventInput {
     pub event_id: ID,
 }
@@ -30,14 +43,38 @@ pub struct UpdateEventInput {
     pub event_id: ID,
     pub start_ts: Option<i64>,
     pub duration: Option<i64>,
+    pub end_ts: Option<i64>,
     pub busy: Option<bool>,
-    pub reminder: Option<CalendarEventReminder>,
+    pub status: Option<EventStatus>,
+    /// Removes the reminder when set to `Some(None)`
+    pub reminder: Option<Option<CalendarEventReminder>>,
     pub rrule_options: Option<RRuleOptions>,
     pub is_service: Option<bool>,
     pub exdates: Option<Vec<i64>>,
     pub metadata: Option<Metadata>,
+    pub metadata_patch: Option<MetadataPatch>,
+    pub edit_mode: Option<EventEditMode>,
+    pub instance_start_ts: Option<i64>,
+}
+
+pub struct ReplaceEventInput {
+    pub event_id: ID,
+    pub event: replace_event::RequestBody,
+}
+
+pub struct AddEventExdateInput {
+    pub event_id: ID,
+    pub ts: i64,
 }
 
+pub struct RemoveEventExdateInput {
+    pub event_id: ID,
+    pub ts: i64,
+}
+
+pub type ValidateRecurrenceInput = validate_recurrence::RequestBody;
+pub type ValidateRecurrenceCalendarSettings = validate_recurrence::CalendarSettingsBody;
+
 impl CalendarEventClient {
     pub(crate) fn new(base: Arc<BaseClient>) -> Self {
         Self { base }
@@ -55,6 +92,18 @@ impl CalendarEventClient {
             .await
     }
 
+    pub async fn get_by_external_id(
+        &self,
+        external_id: String,
+    ) -> APIResponse<get_event_by_external_id::APIResponse> {
+        self.base
+            .get(
+                format!("user/events/external/{}", external_id),
+                StatusCode::OK,
+            )
+            .await
+    }
+
     pub async fn get_instances(
         &self,
         input: GetEventsInstancesInput,
@@ -70,6 +119,34 @@ impl CalendarEventClient {
             .await
     }
 
+    pub async fn get_reschedule_slots(
+        &self,
+        input: GetEventRescheduleSlotsInput,
+    ) -> APIResponse<get_event_reschedule_slots::APIResponse> {
+        let mut query_string = format!("duration={}&date={}", input.duration, input.date);
+        if let Some(interval) = input.interval {
+            query_string = format!("{}&interval={}", query_string, interval);
+        }
+        if let Some(slot_start) = input.slot_start {
+            query_string = format!("{}&slotStart={}", query_string, slot_start);
+        }
+        if let Some(timezone) = input.iana_tz {
+            query_string = format!("{}&ianaTz={}", query_string, timezone);
+        }
+        if let Some(offset) = input.utc_offset_minutes {
+            query_string = format!("{}&utcOffsetMinutes={}", query_string, offset);
+        }
+        self.base
+            .get(
+                format!(
+                    "user/events/{}/reschedule-slots?{}",
+                    input.event_id, query_string
+                ),
+                StatusCode::OK,
+            )
+            .await
+    }
+
     pub async fn create(
         &self,
         user_id: ID,
@@ -84,6 +161,21 @@ impl CalendarEventClient {
             .await
     }
 
+    pub async fn create_batch(
+        &self,
+        user_id: ID,
+        events: Vec<CreateEventInput>,
+    ) -> APIResponse<create_events_batch::APIResponse> {
+        let body = create_events_batch::RequestBody { events };
+        self.base
+            .post(
+                body,
+                format!("user/{}/events/batch", user_id),
+                StatusCode::CREATED,
+            )
+            .await
+    }
+
     pub async fn get_by_meta(
         &self,
         input: MetadataFindInput,
@@ -100,16 +192,69 @@ impl CalendarEventClient {
         let event_id = input.event_id.clone();
         let body = update_event::RequestBody {
             busy: input.busy,
+            status: input.status,
             duration: input.duration,
+            end_ts: input.end_ts,
             exdates: input.exdates,
             recurrence: input.rrule_options,
             reminder: input.reminder,
             is_service: input.is_service,
             start_ts: input.start_ts,
             metadata: input.metadata,
+            metadata_patch: input.metadata_patch,
+            edit_mode: input.edit_mode,
+            instance_start_ts: input.instance_start_ts,
         };
         self.base
-            .put(body, format!("user/events/{}", event_id), StatusCode::OK)
+            .patch(body, format!("user/events/{}", event_id), StatusCode::OK)
+            .await
+    }
+
+    pub async fn replace(
+        &self,
+        input: ReplaceEventInput,
+    ) -> APIResponse<replace_event::APIResponse> {
+        self.base
+            .put(
+                input.event,
+                format!("user/events/{}/replace", input.event_id),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn add_exdate(
+        &self,
+        input: AddEventExdateInput,
+    ) -> APIResponse<add_event_exdate::APIResponse> {
+        let body = add_event_exdate::RequestBody { ts: input.ts };
+        self.base
+            .post(
+                body,
+                format!("user/events/{}/exdates", input.event_id),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn remove_exdate(
+        &self,
+        input: RemoveEventExdateInput,
+    ) -> APIResponse<remove_event_exdate::APIResponse> {
+        self.base
+            .delete(
+                format!("user/events/{}/exdates/{}", input.event_id, input.ts),
+                StatusCode::OK,
+            )
+            .await
+    }
+
+    pub async fn validate_recurrence(
+        &self,
+        input: ValidateRecurrenceInput,
+    ) -> APIResponse<validate_recurrence::APIResponse> {
+        self.base
+            .post(input, "recurrence/validate".into(), StatusCode::OK)
             .await
     }
 }
@@ -20,17 +20,24 @@ use user::UserClient;
 pub(crate) use base::{APIResponse, BaseClient};
 
 pub use calendar::{
-    CreateCalendarInput, DeleteCalendarInput, GetCalendarEventsInput, GetCalendarInput,
-    UpdateCalendarInput,
+    BlockCalendarTimeInput, CreateCalendarInput, DeleteCalendarInput, GetCalendarConflictsInput,
+    GetCalendarEventsInput, GetCalendarInput, GetUserCalendarsInput, UpdateCalendarInput,
 };
 pub use event::{
-    CreateEventInput, DeleteEventInput, GetEventInput, GetEventsInstancesInput, UpdateEventInput,
+    AddEventExdateInput, CreateEventInput, DeleteEventInput, GetEventInput,
+    GetEventRescheduleSlotsInput, GetEventsInstancesInput, RemoveEventExdateInput,
+    ReplaceEventInput, UpdateEventInput, ValidateRecurrenceCalendarSettings,
+    ValidateRecurrenceInput,
+};
+pub use nettu_scheduler_domain::booking_slots::SlotStart;
+pub use nettu_scheduler_domain::{
+    BookingStrategy, CalendarEventReminder, EventEditMode, EventStatus, Millis, RRuleOptions,
+    ScheduleRule, TimePlan, ID,
 };
-pub use nettu_scheduler_domain::{CalendarEventReminder, RRuleOptions, ScheduleRule, TimePlan, ID};
 pub use schedule::{CreateScheduleInput, UpdateScheduleInput};
 pub use service::{
-    AddServiceUserInput, GetSerivceBookingSlotsInput, RemoveServiceUserInput,
-    UpdateServiceUserInput,
+    AddServiceUserInput, CancelBookingInput, GetSerivceBookingSlotsInput,
+    GetServiceAvailabilityInput, RemoveServiceUserInput, UpdateServiceUserInput,
 };
 pub use shared::{KVMetadata, MetadataFindInput};
 pub use user::CreateUserInput;
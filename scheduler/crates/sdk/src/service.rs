@@ -1,6 +1,8 @@
-use crate::{shared::MetadataFindInput, APIResponse, BaseClient, TimePlan, ID};
+use crate::{
+    shared::MetadataFindInput, APIResponse, BaseClient, BookingStrategy, SlotStart, TimePlan, ID,
+};
 use nettu_scheduler_api_structs::*;
-use nettu_scheduler_domain::Metadata;
+use nettu_scheduler_domain::{Metadata, Millis};
 use reqwest::StatusCode;
 use serde::Serialize;
 use std::sync::Arc;
@@ -18,6 +20,7 @@ pub struct AddServiceUserInput {
     pub buffer: Option<i64>,
     pub closest_booking_time: Option<i64>,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 pub struct UpdateServiceUserInput {
@@ -28,6 +31,7 @@ pub struct UpdateServiceUserInput {
     pub buffer: Option<i64>,
     pub closest_booking_time: Option<i64>,
     pub furthest_booking_time: Option<i64>,
+    pub timezone: Option<String>,
 }
 
 pub struct RemoveServiceUserInput {
@@ -38,13 +42,41 @@ pub struct RemoveServiceUserInput {
 pub struct GetSerivceBookingSlotsInput {
     pub service_id: ID,
     pub iana_tz: Option<String>,
+    /// Alternative to `iana_tz` for clients that only know their offset from UTC
+    pub utc_offset_minutes: Option<i32>,
     pub duration: i64,
-    pub interval: i64,
+    /// Defaults to the `duration` when not set
+    pub interval: Option<i64>,
+    /// Defaults to `SlotStart::Interval` when not set
+    pub slot_start: Option<SlotStart>,
     pub date: String,
+    pub exclude_user_ids: Option<Vec<ID>>,
+    pub user_ids: Option<Vec<ID>>,
+}
+
+pub struct GetServiceAvailabilityInput {
+    pub service_id: ID,
+    pub iana_tz: Option<String>,
+    /// Alternative to `iana_tz` for clients that only know their offset from UTC
+    pub utc_offset_minutes: Option<i32>,
+    pub date: String,
+    pub exclude_user_ids: Option<Vec<ID>>,
+    pub user_ids: Option<Vec<ID>>,
+}
+
+pub struct CancelBookingInput {
+    pub service_id: ID,
+    pub event_id: ID,
 }
 
 pub struct UpdateServiceInput {
     pub service_id: ID,
+    pub booking_strategy: Option<BookingStrategy>,
+    pub default_schedule_id: Option<ID>,
+    pub min_lead_time_ms: Option<Millis>,
+    pub allowed_durations: Option<Vec<i64>>,
+    pub allow_overbooking: Option<bool>,
+    pub timezone: Option<String>,
     pub metadata: Option<Metadata>,
 }
 
@@ -62,17 +94,48 @@ impl ServiceClient {
             .await
     }
 
+    pub async fn list(&self, skip: usize, limit: usize) -> APIResponse<get_services::APIResponse> {
+        self.base
+            .get(
+                format!("service?skip={}&limit={}", skip, limit),
+                StatusCode::OK,
+            )
+            .await
+    }
+
     pub async fn bookingslots(
         &self,
         input: GetSerivceBookingSlotsInput,
     ) -> APIResponse<get_service_bookingslots::APIResponse> {
-        let mut query_string = format!(
-            "duration={}&interval={}&date={}",
-            input.duration, input.interval, input.date
-        );
+        let mut query_string = format!("duration={}&date={}", input.duration, input.date);
+        if let Some(interval) = input.interval {
+            query_string = format!("{}&interval={}", query_string, interval);
+        }
+        if let Some(slot_start) = input.slot_start {
+            query_string = format!("{}&slotStart={}", query_string, slot_start);
+        }
         if let Some(timezone) = input.iana_tz {
             query_string = format!("{}&ianaTz={}", query_string, timezone);
         }
+        if let Some(offset) = input.utc_offset_minutes {
+            query_string = format!("{}&utcOffsetMinutes={}", query_string, offset);
+        }
+        if let Some(user_ids) = input.exclude_user_ids {
+            let user_ids = user_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            query_string = format!("{}&excludeUserIds={}", query_string, user_ids);
+        }
+        if let Some(user_ids) = input.user_ids {
+            let user_ids = user_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            query_string = format!("{}&userIds={}", query_string, user_ids);
+        }
 
         self.base
             .get(
@@ -82,12 +145,57 @@ impl ServiceClient {
             .await
     }
 
+    /// The free intervals of the `Service` on the date, instead of booking slots
+    pub async fn availability(
+        &self,
+        input: GetServiceAvailabilityInput,
+    ) -> APIResponse<get_service_availability::APIResponse> {
+        let mut query_string = format!("date={}", input.date);
+        if let Some(timezone) = input.iana_tz {
+            query_string = format!("{}&ianaTz={}", query_string, timezone);
+        }
+        if let Some(offset) = input.utc_offset_minutes {
+            query_string = format!("{}&utcOffsetMinutes={}", query_string, offset);
+        }
+        if let Some(user_ids) = input.exclude_user_ids {
+            let user_ids = user_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            query_string = format!("{}&excludeUserIds={}", query_string, user_ids);
+        }
+        if let Some(user_ids) = input.user_ids {
+            let user_ids = user_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            query_string = format!("{}&userIds={}", query_string, user_ids);
+        }
+
+        self.base
+            .get(
+                format!("service/{}/availability?{}", input.service_id, query_string),
+                StatusCode::OK,
+            )
+            .await
+    }
+
     pub async fn delete(&self, service_id: ID) -> APIResponse<delete_service::APIResponse> {
         self.base
             .delete(format!("service/{}", service_id), StatusCode::OK)
             .await
     }
 
+    /// Deletes the `Service` together with its upcoming bookings, which would
+    /// otherwise make the deletion fail with a conflict
+    pub async fn force_delete(&self, service_id: ID) -> APIResponse<delete_service::APIResponse> {
+        self.base
+            .delete(format!("service/{}?force=true", service_id), StatusCode::OK)
+            .await
+    }
+
     pub async fn create(&self) -> APIResponse<create_service::APIResponse> {
         let body = Empty {};
         self.base
@@ -100,6 +208,12 @@ impl ServiceClient {
         input: UpdateServiceInput,
     ) -> APIResponse<update_service::APIResponse> {
         let body = update_service::RequestBody {
+            booking_strategy: input.booking_strategy,
+            default_schedule_id: input.default_schedule_id,
+            min_lead_time_ms: input.min_lead_time_ms,
+            allowed_durations: input.allowed_durations,
+            allow_overbooking: input.allow_overbooking,
+            timezone: input.timezone,
             metadata: input.metadata,
         };
         self.base
@@ -147,6 +261,7 @@ impl ServiceClient {
             busy: input.busy,
             closest_booking_time: input.closest_booking_time,
             furthest_booking_time: input.furthest_booking_time,
+            timezone: input.timezone,
         };
 
         self.base
@@ -170,6 +285,7 @@ impl ServiceClient {
             busy: input.busy,
             closest_booking_time: input.closest_booking_time,
             furthest_booking_time: input.furthest_booking_time,
+            timezone: input.timezone,
         };
 
         self.base
@@ -180,4 +296,16 @@ impl ServiceClient {
             )
             .await
     }
+
+    pub async fn cancel_booking(
+        &self,
+        input: CancelBookingInput,
+    ) -> APIResponse<cancel_booking::APIResponse> {
+        self.base
+            .delete(
+                format!("service/{}/booking/{}", input.service_id, input.event_id),
+                StatusCode::OK,
+            )
+            .await
+    }
 }
@@ -8,8 +8,8 @@ use nettu_scheduler_sdk::{
     AddServiceUserInput, CreateCalendarInput, CreateEventInput, CreateScheduleInput,
     CreateUserInput, DeleteCalendarInput, DeleteEventInput, GetCalendarEventsInput,
     GetCalendarInput, GetEventInput, GetEventsInstancesInput, GetSerivceBookingSlotsInput,
-    KVMetadata, MetadataFindInput, NettuSDK, RemoveServiceUserInput, UpdateCalendarInput,
-    UpdateEventInput, UpdateScheduleInput, UpdateServiceUserInput,
+    KVMetadata, MetadataFindInput, NettuSDK, RRuleOptions, RemoveServiceUserInput,
+    UpdateCalendarInput, UpdateEventInput, UpdateScheduleInput, UpdateServiceUserInput,
 };
 
 #[actix_web::main]
@@ -384,12 +384,16 @@ async fn test_crud_events() {
             CreateEventInput {
                 calendar_id: calendar.id.clone(),
                 busy: None,
-                duration: 1000 * 60 * 60,
+                status: None,
+                duration: Some(1000 * 60 * 60),
+                end_ts: None,
                 reminder: None,
                 recurrence: None,
                 is_service: None,
+                service_id: None,
                 start_ts: 0,
                 metadata: None,
+                external_id: None,
             },
         )
         .await
@@ -423,12 +427,17 @@ async fn test_crud_events() {
             event_id: event.id.clone(),
             exdates: Some(vec![0]),
             busy: None,
+            status: None,
             duration: None,
+            end_ts: None,
             reminder: None,
             rrule_options: None,
             is_service: None,
             start_ts: None,
             metadata: None,
+            metadata_patch: None,
+            edit_mode: None,
+            instance_start_ts: None,
         })
         .await
         .is_ok());
@@ -492,6 +501,7 @@ async fn test_crud_service() {
             busy: None,
             closest_booking_time: None,
             furthest_booking_time: None,
+            timezone: None,
         })
         .await
         .unwrap()
@@ -509,6 +519,7 @@ async fn test_crud_service() {
             busy: None,
             closest_booking_time: Some(new_closest_booking_time),
             furthest_booking_time: None,
+            timezone: None,
         })
         .await
         .unwrap()
@@ -535,8 +546,12 @@ async fn test_crud_service() {
             date: "2020-1-1".to_string(),
             duration: 1000 * 60 * 30,
             iana_tz: Some("UTC".to_string()),
-            interval: 1000 * 60 * 15,
+            utc_offset_minutes: None,
+            interval: Some(1000 * 60 * 15),
+            slot_start: None,
             service_id: service.id.clone(),
+            exclude_user_ids: None,
+            user_ids: None,
         })
         .await
         .unwrap()
@@ -553,3 +568,94 @@ async fn test_crud_service() {
     // Get now returns 404
     assert!(admin_client.service.get(service.id.clone()).await.is_err());
 }
+
+#[actix_web::main]
+#[test]
+async fn test_large_responses_are_compressed() {
+    let (app, sdk, address) = spawn_app().await;
+    let res = sdk
+        .account
+        .create(&app.config.create_account_secret_code)
+        .await
+        .expect("Expected to create account");
+    let api_key = res.secret_api_key.clone();
+    let admin_client = NettuSDK::new(address.clone(), res.secret_api_key);
+    let user = admin_client
+        .user
+        .create(CreateUserInput { metadata: None })
+        .await
+        .unwrap()
+        .user;
+    let calendar = admin_client
+        .calendar
+        .create(CreateCalendarInput {
+            user_id: user.id.clone(),
+            timezone: "UTC".into(),
+            week_start: 0,
+            metadata: None,
+        })
+        .await
+        .unwrap()
+        .calendar;
+    admin_client
+        .event
+        .create(
+            user.id.clone(),
+            CreateEventInput {
+                calendar_id: calendar.id.clone(),
+                busy: None,
+                status: None,
+                duration: Some(1000 * 60 * 60),
+                end_ts: None,
+                reminder: None,
+                recurrence: Some(RRuleOptions::default()),
+                is_service: None,
+                service_id: None,
+                start_ts: 0,
+                metadata: None,
+                external_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let client = actix_web::client::Client::default();
+    let events_url = format!(
+        "{}/api/v1/user/calendar/{}/events?startTs=0&endTs={}",
+        address,
+        calendar.id,
+        1000 * 60 * 60 * 24 * 30
+    );
+    let res = client
+        .get(&events_url)
+        .header("x-api-key", api_key.clone())
+        .header("accept-encoding", "gzip")
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get("content-encoding").unwrap(), "gzip");
+
+    // Not compressed when the client does not support it
+    let res = client
+        .get(&events_url)
+        .header("x-api-key", api_key)
+        .header("accept-encoding", "identity")
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+    assert!(res.headers().get("content-encoding").is_none());
+
+    // Small responses are not worth compressing
+    let res = client
+        .get(format!("{}/api/v1/", address))
+        .header("accept-encoding", "gzip")
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert!(res.headers().get("content-encoding").is_none());
+}